@@ -0,0 +1,10 @@
+CREATE TABLE pattern_collaborators_tb303 (
+    pattern_id uuid NOT NULL REFERENCES patterns_tb303(pattern_id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('editor', 'viewer')),
+    updated_at timestamptz NOT NULL DEFAULT NOW(),
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (pattern_id, user_id)
+);
+
+CREATE INDEX idx_pattern_collaborators_tb303_user ON pattern_collaborators_tb303(user_id);
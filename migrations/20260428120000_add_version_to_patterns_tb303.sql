@@ -0,0 +1 @@
+ALTER TABLE patterns_tb303 ADD COLUMN version BIGINT NOT NULL DEFAULT 0;
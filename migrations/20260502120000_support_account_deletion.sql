@@ -0,0 +1,21 @@
+-- Patterns of deleted accounts can be handed over to this archive user.
+INSERT INTO users (user_id, username)
+VALUES ('00000000-0000-0000-0000-000000000000', 'anonymous')
+ON CONFLICT (user_id) DO NOTHING;
+
+ALTER TABLE patterns_tb303
+    DROP CONSTRAINT patterns_tb303_user_id_fkey,
+    ADD CONSTRAINT patterns_tb303_user_id_fkey
+        FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE;
+
+CREATE TABLE account_deletions (
+    deletion_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    pattern_disposition TEXT NOT NULL CHECK (pattern_disposition IN ('delete', 'reassign')),
+    deleted_patterns INTEGER NOT NULL,
+    reassigned_patterns INTEGER NOT NULL,
+    deleted_files INTEGER NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_deletions_user_id ON account_deletions(user_id);
@@ -0,0 +1,21 @@
+-- Every presigned upload is recorded so the object can be verified when a
+-- profile claims it, and swept if nobody ever does.
+CREATE TABLE uploads (
+    key TEXT PRIMARY KEY,
+    user_id UUID NOT NULL,
+    upload_type TEXT NOT NULL CHECK (upload_type IN ('avatar', 'banner')),
+    content_type TEXT NOT NULL,
+    content_length BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    claimed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_uploads_user_id ON uploads(user_id);
+CREATE INDEX idx_uploads_unclaimed ON uploads(created_at) WHERE claimed_at IS NULL;
+
+-- Keys already on profiles were accepted before uploads were tracked.
+INSERT INTO uploads (key, user_id, upload_type, content_type, content_length, claimed_at)
+SELECT avatar_key, user_id, 'avatar', '', 0, NOW() FROM users WHERE avatar_key IS NOT NULL
+UNION ALL
+SELECT banner_key, user_id, 'banner', '', 0, NOW() FROM users WHERE banner_key IS NOT NULL
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,3 @@
+-- Sizes of the processed WebP variants stored next to a claimed upload.
+-- Empty for uploads claimed before images were processed.
+ALTER TABLE uploads ADD COLUMN variant_sizes INTEGER[] NOT NULL DEFAULT '{}';
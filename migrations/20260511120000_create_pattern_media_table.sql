@@ -0,0 +1,15 @@
+ALTER TABLE uploads DROP CONSTRAINT uploads_upload_type_check;
+ALTER TABLE uploads ADD CONSTRAINT uploads_upload_type_check
+    CHECK (upload_type IN ('avatar', 'banner', 'pattern_audio'));
+
+-- Files attached to a pattern, at most one of each kind. The objects
+-- themselves are tracked in uploads like every other upload.
+CREATE TABLE pattern_media (
+    pattern_id UUID NOT NULL REFERENCES patterns_tb303(pattern_id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('audio')),
+    key TEXT NOT NULL UNIQUE,
+    content_type TEXT NOT NULL,
+    duration_ms BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (pattern_id, kind)
+);
@@ -0,0 +1,7 @@
+ALTER TABLE uploads DROP CONSTRAINT uploads_upload_type_check;
+ALTER TABLE uploads ADD CONSTRAINT uploads_upload_type_check
+    CHECK (upload_type IN ('avatar', 'banner', 'pattern_audio', 'pattern_cover'));
+
+ALTER TABLE pattern_media DROP CONSTRAINT pattern_media_kind_check;
+ALTER TABLE pattern_media ADD CONSTRAINT pattern_media_kind_check
+    CHECK (kind IN ('audio', 'cover'));
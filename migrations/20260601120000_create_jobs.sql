@@ -0,0 +1,25 @@
+-- Deferred work, run by the job workers. Jobs are written in the same
+-- transaction as the change that needs them. Finished jobs are deleted; jobs
+-- that ran out of attempts stay behind as 'dead' for inspection.
+CREATE TABLE jobs (
+    job_id UUID PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    died_at TIMESTAMPTZ,
+    CONSTRAINT job_status_check CHECK (status IN ('pending', 'dead'))
+);
+
+CREATE INDEX idx_jobs_due ON jobs(run_at) WHERE status = 'pending';
+
+-- When each recurring job is next due. Workers on every instance race to
+-- move next_run_at forward; only the winner enqueues the job.
+CREATE TABLE job_schedules (
+    name TEXT PRIMARY KEY,
+    next_run_at TIMESTAMPTZ NOT NULL
+);
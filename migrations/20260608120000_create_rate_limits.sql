@@ -0,0 +1,11 @@
+-- Request counts of the Postgres rate limit backend, one fixed window per
+-- budget and client. Expired windows are pruned by a scheduled job.
+CREATE TABLE rate_limits (
+    budget TEXT NOT NULL,
+    client TEXT NOT NULL,
+    window_end TIMESTAMPTZ NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (budget, client)
+);
+
+CREATE INDEX idx_rate_limits_window_end ON rate_limits(window_end);
@@ -0,0 +1,15 @@
+-- Responses to requests sent with an Idempotency-Key, replayed when the
+-- client retries. A row without a response is still being processed. Rows
+-- are kept for a day and then pruned by a scheduled job.
+CREATE TABLE idempotency (
+    user_id UUID NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash BYTEA NOT NULL,
+    response_status_code SMALLINT,
+    response_content_type TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_created_at ON idempotency(created_at);
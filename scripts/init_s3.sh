@@ -0,0 +1,39 @@
+#!/usr/bin/env bash
+set -x
+set -eo pipefail
+
+# Starts a local S3-compatible store (MinIO) so uploads and image processing
+# work without AWS. Point the API at it with:
+#     APP_S3__ENDPOINT_URL=http://localhost:9000
+#     AWS_ACCESS_KEY_ID=minioadmin AWS_SECRET_ACCESS_KEY=minioadmin
+
+S3_PORT="${S3_PORT:=9000}"
+S3_USER="${S3_USER:=minioadmin}"
+S3_PASSWORD="${S3_PASSWORD:=minioadmin}"
+S3_BUCKET="${S3_BUCKET:=acidarchive}"
+
+RUNNING_MINIO_CONTAINER=$(docker ps --filter 'name=minio' --format '{{.ID}}')
+if [[ -n $RUNNING_MINIO_CONTAINER ]]; then
+  echo >&2 "there is a minio container already running, kill it with"
+  echo >&2 "    docker kill ${RUNNING_MINIO_CONTAINER}"
+  exit 1
+fi
+CONTAINER_NAME="minio_$(date '+%s')"
+
+docker run \
+    --env MINIO_ROOT_USER=${S3_USER} \
+    --env MINIO_ROOT_PASSWORD=${S3_PASSWORD} \
+    --publish "${S3_PORT}":9000 \
+    --detach \
+    --name "${CONTAINER_NAME}" \
+    minio/minio server /data
+
+until docker exec "${CONTAINER_NAME}" mc alias set local http://localhost:9000 "${S3_USER}" "${S3_PASSWORD}" > /dev/null 2>&1; do
+  >&2 echo "MinIO is still unavailable - sleeping"
+  sleep 1
+done
+
+docker exec "${CONTAINER_NAME}" mc mb --ignore-existing "local/${S3_BUCKET}"
+docker exec "${CONTAINER_NAME}" mc anonymous set download "local/${S3_BUCKET}"
+
+>&2 echo "MinIO is up and running on port ${S3_PORT} with bucket ${S3_BUCKET}!"
@@ -0,0 +1,67 @@
+mod patterns;
+mod stats;
+mod users;
+
+pub use patterns::*;
+pub use stats::*;
+pub use users::*;
+
+use crate::utils::error_chain_fmt;
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum AdminError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("No user named or with the ID {0}")]
+    UserNotFound(String),
+    #[error("The username {0} is already taken")]
+    UsernameTaken(String),
+    #[error("A user with the ID {0} already exists")]
+    UserExists(Uuid),
+    #[error("Pattern with ID {0} not found")]
+    PatternNotFound(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AdminError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+#[tracing::instrument(name = "Running migrations", skip(pool))]
+pub async fn run_migrations(pool: &PgPool) -> Result<(), AdminError> {
+    let mut migrator = sqlx::migrate!("./migrations");
+    // The canonical patterns used to be seeded by a migration that has since
+    // moved to `fixtures/archive.yaml`.
+    migrator.set_ignore_missing(true);
+    migrator
+        .run(pool)
+        .await
+        .context("Failed to run migrations.")?;
+    Ok(())
+}
+
+/// Finds a user by ID or by username, whichever `user` looks like.
+#[tracing::instrument(name = "Resolving user", skip(pool))]
+pub async fn resolve_user(pool: &PgPool, user: &str) -> Result<Uuid, AdminError> {
+    let user_id = match Uuid::parse_str(user) {
+        Ok(user_id) => {
+            sqlx::query_scalar!(r#"SELECT user_id FROM users WHERE user_id = $1"#, user_id)
+                .fetch_optional(pool)
+                .await
+        }
+        Err(_) => {
+            sqlx::query_scalar!(r#"SELECT user_id FROM users WHERE username = $1"#, user)
+                .fetch_optional(pool)
+                .await
+        }
+    }
+    .context("Failed to fetch user.")?;
+
+    user_id.ok_or_else(|| AdminError::UserNotFound(user.to_string()))
+}
@@ -0,0 +1,212 @@
+use crate::admin::{resolve_user, AdminError};
+use crate::api::models::archive::{
+    ImportTB303Archive, ImportTB303Response, TB303PatternArchive, ARCHIVE_FORMAT, ARCHIVE_VERSION,
+};
+use crate::domain::WebhookEvent;
+use crate::routes::patterns::{
+    delete_pattern as delete_pattern_in_transaction, fetch_pattern_by_id, import_archive,
+    ImportPatternError,
+};
+use crate::storage::ObjectStorage;
+use crate::webhooks::enqueue_pattern_event;
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+async fn lock_pattern(
+    transaction: &mut Transaction<'_, Postgres>,
+    pattern_id: Uuid,
+) -> Result<(Uuid, bool), AdminError> {
+    sqlx::query!(
+        r#"SELECT user_id, is_public FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE"#,
+        pattern_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to fetch pattern.")?
+    .map(|row| (row.user_id, row.is_public.unwrap_or(false)))
+    .ok_or(AdminError::PatternNotFound(pattern_id))
+}
+
+/// Imports an archive, as written by `export_patterns` or
+/// `GET /v1/users/me/export`, into the library of `user`.
+#[tracing::instrument(name = "Importing patterns", skip(pool, storage, archive))]
+pub async fn import_patterns(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    user: &str,
+    archive: &str,
+    dry_run: bool,
+) -> Result<ImportTB303Response, AdminError> {
+    let user_id = resolve_user(pool, user).await?;
+    let archive: ImportTB303Archive = serde_json::from_str(archive)
+        .map_err(|e| AdminError::ValidationError(format!("Invalid archive: {e}")))?;
+
+    import_archive(pool, storage, user_id.into(), archive, dry_run)
+        .await
+        .map_err(|e| match e {
+            ImportPatternError::UnexpectedError(e) => AdminError::UnexpectedError(e),
+            e => AdminError::ValidationError(e.to_string()),
+        })
+}
+
+/// Exports the patterns of `user`, or of everyone, in the archive format.
+#[tracing::instrument(name = "Exporting patterns", skip(pool, storage))]
+pub async fn export_patterns(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    user: Option<&str>,
+) -> Result<TB303PatternArchive, AdminError> {
+    let user_id = match user {
+        Some(user) => Some(resolve_user(pool, user).await?),
+        None => None,
+    };
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT pattern_id, user_id FROM patterns_tb303
+        WHERE $1::uuid IS NULL OR user_id = $1
+        ORDER BY created_at, pattern_id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to fetch patterns to export.")?;
+
+    let mut patterns = Vec::with_capacity(rows.len());
+    for row in rows {
+        let pattern = fetch_pattern_by_id(pool, storage, row.pattern_id, Some(row.user_id.into()))
+            .await
+            .context("Failed to fetch pattern to export.")?;
+        patterns.push(pattern);
+    }
+
+    Ok(TB303PatternArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        patterns,
+    })
+}
+
+/// Hands the pattern over to `user`. If they were a collaborator on it,
+/// that role is dropped; others keep theirs.
+#[tracing::instrument(name = "Reassigning pattern", skip(pool))]
+pub async fn reassign_pattern(
+    pool: &PgPool,
+    pattern_id: Uuid,
+    user: &str,
+) -> Result<(), AdminError> {
+    let user_id = resolve_user(pool, user).await?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction to reassign a pattern.")?;
+
+    let (owner_id, was_public) = lock_pattern(&mut transaction, pattern_id).await?;
+    if owner_id == user_id {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"DELETE FROM pattern_collaborators_tb303 WHERE pattern_id = $1 AND user_id = $2"#,
+        pattern_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to remove the new owner as collaborator.")?;
+
+    sqlx::query!(
+        r#"
+        UPDATE patterns_tb303 SET user_id = $2, updated_at = NOW(), version = version + 1
+        WHERE pattern_id = $1
+        "#,
+        pattern_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to reassign pattern.")?;
+
+    enqueue_pattern_event(
+        &mut transaction,
+        WebhookEvent::PatternUpdated,
+        pattern_id,
+        was_public,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the reassigned pattern.")?;
+
+    Ok(())
+}
+
+/// Publishes or unpublishes the pattern. Returns false if it already had
+/// that visibility.
+#[tracing::instrument(name = "Setting pattern visibility", skip(pool))]
+pub async fn set_pattern_visibility(
+    pool: &PgPool,
+    pattern_id: Uuid,
+    is_public: bool,
+) -> Result<bool, AdminError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction to change visibility.")?;
+
+    let (_, was_public) = lock_pattern(&mut transaction, pattern_id).await?;
+    if was_public == is_public {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE patterns_tb303 SET is_public = $2, updated_at = NOW(), version = version + 1
+        WHERE pattern_id = $1
+        "#,
+        pattern_id,
+        is_public
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to change pattern visibility.")?;
+
+    let event = match is_public {
+        true => WebhookEvent::PatternPublished,
+        false => WebhookEvent::PatternUnpublished,
+    };
+    enqueue_pattern_event(&mut transaction, event, pattern_id, was_public).await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the visibility change.")?;
+
+    Ok(true)
+}
+
+/// Deletes the pattern like its owner would, attached files included.
+#[tracing::instrument(name = "Deleting pattern", skip(pool))]
+pub async fn delete_pattern(pool: &PgPool, pattern_id: Uuid) -> Result<(), AdminError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction to delete a pattern.")?;
+
+    lock_pattern(&mut transaction, pattern_id).await?;
+    delete_pattern_in_transaction(&mut transaction, pattern_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the deleted pattern.")?;
+
+    Ok(())
+}
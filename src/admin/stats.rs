@@ -0,0 +1,45 @@
+use crate::admin::AdminError;
+use anyhow::Context;
+use serde::Serialize;
+use sqlx::PgPool;
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveStats {
+    pub users: i64,
+    pub patterns: i64,
+    pub public_patterns: i64,
+    pub private_patterns: i64,
+    pub patterns_last_30_days: i64,
+    pub unclaimed_uploads: i64,
+    pub webhooks: i64,
+    pub pending_webhook_deliveries: i64,
+    pub pending_jobs: i64,
+    pub dead_jobs: i64,
+}
+
+#[tracing::instrument(name = "Collecting archive statistics", skip(pool))]
+pub async fn archive_stats(pool: &PgPool) -> Result<ArchiveStats, AdminError> {
+    let stats = sqlx::query_as!(
+        ArchiveStats,
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM users) AS "users!",
+            (SELECT COUNT(*) FROM patterns_tb303) AS "patterns!",
+            (SELECT COUNT(*) FROM patterns_tb303 WHERE is_public) AS "public_patterns!",
+            (SELECT COUNT(*) FROM patterns_tb303 WHERE NOT is_public) AS "private_patterns!",
+            (SELECT COUNT(*) FROM patterns_tb303
+             WHERE created_at > NOW() - INTERVAL '30 days') AS "patterns_last_30_days!",
+            (SELECT COUNT(*) FROM uploads WHERE claimed_at IS NULL) AS "unclaimed_uploads!",
+            (SELECT COUNT(*) FROM webhooks) AS "webhooks!",
+            (SELECT COUNT(*) FROM webhook_deliveries
+             WHERE status = 'pending') AS "pending_webhook_deliveries!",
+            (SELECT COUNT(*) FROM jobs WHERE status = 'pending') AS "pending_jobs!",
+            (SELECT COUNT(*) FROM jobs WHERE status = 'dead') AS "dead_jobs!"
+        "#
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to collect archive statistics.")?;
+
+    Ok(stats)
+}
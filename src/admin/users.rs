@@ -0,0 +1,132 @@
+use crate::admin::{resolve_user, AdminError};
+use crate::domain::Username;
+use crate::webhooks::enqueue_profile_event;
+use anyhow::Context;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Usernames are unique regardless of case, so feed URLs cannot be confused.
+async fn ensure_username_is_free(
+    transaction: &mut Transaction<'_, Postgres>,
+    username: &Username,
+    user_id: Uuid,
+) -> Result<(), AdminError> {
+    // There is no unique index to fall back on, so concurrent renames take
+    // turns until their transactions end.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('users.username'))")
+        .execute(&mut **transaction)
+        .await
+        .context("Failed to lock usernames.")?;
+
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT user_id FROM users
+        WHERE lower(username) = lower($1) AND user_id <> $2
+        "#,
+        username.as_ref(),
+        user_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to check username.")?
+    .is_some();
+
+    if taken {
+        return Err(AdminError::UsernameTaken(username.as_ref().to_string()));
+    }
+    Ok(())
+}
+
+/// Creates a user. Users normally come from the identity provider, so pass
+/// their ID there as `user_id` to link the two; a random ID is used if none
+/// is given.
+#[tracing::instrument(name = "Creating user", skip(pool))]
+pub async fn create_user(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    username: String,
+    is_admin: bool,
+) -> Result<Uuid, AdminError> {
+    let username = Username::parse(username).map_err(AdminError::ValidationError)?;
+    let user_id = user_id.unwrap_or_else(Uuid::new_v4);
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction for a new user.")?;
+
+    ensure_username_is_free(&mut transaction, &username, user_id).await?;
+
+    let created = sqlx::query!(
+        r#"
+        INSERT INTO users (user_id, username, is_admin) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) DO NOTHING
+        "#,
+        user_id,
+        username.as_ref(),
+        is_admin
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to insert user.")?
+    .rows_affected()
+        > 0;
+    if !created {
+        return Err(AdminError::UserExists(user_id));
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the new user.")?;
+
+    Ok(user_id)
+}
+
+#[tracing::instrument(name = "Renaming user", skip(pool))]
+pub async fn set_username(pool: &PgPool, user: &str, username: String) -> Result<(), AdminError> {
+    let username = Username::parse(username).map_err(AdminError::ValidationError)?;
+    let user_id = resolve_user(pool, user).await?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction to rename a user.")?;
+
+    ensure_username_is_free(&mut transaction, &username, user_id).await?;
+
+    sqlx::query!(
+        r#"UPDATE users SET username = $2, updated_at = NOW() WHERE user_id = $1"#,
+        user_id,
+        username.as_ref()
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to update username.")?;
+
+    enqueue_profile_event(&mut transaction, user_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the new username.")?;
+
+    Ok(())
+}
+
+/// Admins may register global webhooks.
+#[tracing::instrument(name = "Setting admin flag", skip(pool))]
+pub async fn set_admin(pool: &PgPool, user: &str, is_admin: bool) -> Result<(), AdminError> {
+    let user_id = resolve_user(pool, user).await?;
+
+    sqlx::query!(
+        r#"UPDATE users SET is_admin = $2 WHERE user_id = $1"#,
+        user_id,
+        is_admin
+    )
+    .execute(pool)
+    .await
+    .context("Failed to update admin flag.")?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::api::models::tb303::{CreateTB303Pattern, TB303Pattern};
+use crate::problem::FieldError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+pub const ARCHIVE_FORMAT: &str = "acidarchive.patterns";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Serialize, ToSchema)]
+pub struct TB303PatternArchive {
+    #[schema(example = "acidarchive.patterns")]
+    pub format: String,
+    #[schema(example = 1)]
+    pub version: u32,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub exported_at: DateTime<Utc>,
+    pub patterns: Vec<TB303Pattern>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportTB303Archive {
+    #[schema(example = 1)]
+    pub version: u32,
+    /// Patterns in the same shape as `GET /v1/patterns/tb303/{pattern_id}`.
+    /// Each entry is parsed on its own so one malformed pattern does not hide
+    /// problems with the others.
+    #[schema(value_type = Vec<TB303Pattern>)]
+    pub patterns: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportTB303Pattern {
+    pub id: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub pattern: CreateTB303Pattern,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct ImportTB303Params {
+    /// Validate the archive and report what would be imported without
+    /// writing anything.
+    #[param(default = false, example = true)]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportTB303Status {
+    Created,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ImportTB303Result {
+    #[schema(example = 0)]
+    pub index: usize,
+    #[schema(example = "First pattern")]
+    pub name: Option<String>,
+    #[schema(example = "created")]
+    pub status: ImportTB303Status,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub pattern_id: Option<Uuid>,
+    #[schema(example = "Bar 1 must contain at least one step.")]
+    pub error: Option<String>,
+    /// Why the pattern is invalid, pointing into the archive.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ImportTB303Response {
+    #[schema(example = "success")]
+    pub status: String,
+    #[schema(example = false)]
+    pub dry_run: bool,
+    #[schema(example = 3)]
+    pub created: usize,
+    #[schema(example = 1)]
+    pub duplicates: usize,
+    #[schema(example = 0)]
+    pub invalid: usize,
+    pub results: Vec<ImportTB303Result>,
+}
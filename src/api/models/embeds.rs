@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, Debug, IntoParams)]
+pub struct OEmbedParams {
+    /// Share page or player URL of a public pattern.
+    #[param(
+        example = "https://api.example.com/patterns/tb303/123e4567-e89b-12d3-a456-426614174000"
+    )]
+    pub url: String,
+    #[param(minimum = 1, example = 640)]
+    pub maxwidth: Option<u32>,
+    #[param(minimum = 1, example = 240)]
+    pub maxheight: Option<u32>,
+    /// Only `json` is supported.
+    #[param(example = "json")]
+    pub format: Option<String>,
+}
+
+/// A `rich` oEmbed response whose HTML is an iframe of the pattern player.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct OEmbedResponse {
+    #[serde(rename = "type")]
+    #[schema(example = "rich")]
+    pub kind: String,
+    #[schema(example = "1.0")]
+    pub version: String,
+    #[schema(example = "First pattern")]
+    pub title: String,
+    #[schema(example = "username")]
+    pub author_name: String,
+    #[schema(example = "acidarchive")]
+    pub provider_name: String,
+    #[schema(example = "https://api.example.com")]
+    pub provider_url: String,
+    #[schema(
+        example = "<iframe src=\"https://api.example.com/embed/patterns/tb303/123e4567-e89b-12d3-a456-426614174000\" width=\"640\" height=\"240\" frameborder=\"0\" loading=\"lazy\"></iframe>"
+    )]
+    pub html: String,
+    #[schema(example = 640)]
+    pub width: u32,
+    #[schema(example = 240)]
+    pub height: u32,
+    #[schema(
+        example = "https://api.example.com/v1/patterns/tb303/123e4567-e89b-12d3-a456-426614174000/preview.svg?v=1696161600"
+    )]
+    pub thumbnail_url: String,
+    #[schema(example = 384)]
+    pub thumbnail_width: u32,
+    #[schema(example = 123)]
+    pub thumbnail_height: u32,
+}
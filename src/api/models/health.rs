@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct LivenessResponse {
+    #[schema(example = "ok")]
+    pub status: HealthStatus,
+}
+
+/// Why a dependency is unavailable is only logged, as the reason can name
+/// hosts, buckets or the schema.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct DependencyHealth {
+    #[schema(example = "ok")]
+    pub status: HealthStatus,
+    /// How long the check took, including when it timed out.
+    #[schema(example = 4)]
+    pub latency_ms: u64,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ReadinessChecks {
+    /// Postgres is reachable and has every migration this build expects.
+    pub database: DependencyHealth,
+    /// The bucket, or the local storage directory, accepts objects.
+    pub storage: DependencyHealth,
+    /// The signing keys of the Cognito user pool can be fetched.
+    pub jwks: DependencyHealth,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct ReadinessResponse {
+    /// `ok` only when every check is.
+    #[schema(example = "ok")]
+    pub status: HealthStatus,
+    pub checks: ReadinessChecks,
+}
@@ -0,0 +1,89 @@
+use crate::api::models::tb303::{CreateTB303Step, TB303Step};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveKnob {
+    Tuning,
+    CutOffFreq,
+    Resonance,
+    EnvMod,
+    Decay,
+    Accent,
+}
+
+impl LiveKnob {
+    pub fn column(&self) -> &'static str {
+        match self {
+            LiveKnob::Tuning => "tuning",
+            LiveKnob::CutOffFreq => "cut_off_freq",
+            LiveKnob::Resonance => "resonance",
+            LiveKnob::EnvMod => "env_mod",
+            LiveKnob::Decay => "decay",
+            LiveKnob::Accent => "accent",
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveClientMessage {
+    StepEdit {
+        base_version: i64,
+        bar: i32,
+        step: CreateTB303Step,
+    },
+    KnobEdit {
+        base_version: i64,
+        knob: LiveKnob,
+        value: i32,
+    },
+    Presence {
+        bar: Option<i32>,
+    },
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct LivePresence {
+    pub connection_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub bar: Option<i32>,
+    pub can_edit: bool,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveServerMessage {
+    Welcome {
+        connection_id: Uuid,
+        version: i64,
+        can_edit: bool,
+        users: Vec<LivePresence>,
+    },
+    Presence {
+        users: Vec<LivePresence>,
+    },
+    StepEdited {
+        version: i64,
+        user_id: Uuid,
+        bar: i32,
+        step: TB303Step,
+    },
+    KnobEdited {
+        version: i64,
+        user_id: Uuid,
+        knob: LiveKnob,
+        value: i32,
+    },
+    Conflict {
+        base_version: i64,
+        version: i64,
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
@@ -1,5 +1,10 @@
+pub mod archive;
+pub mod embeds;
+pub mod health;
+pub mod live;
 pub mod pagination;
 pub mod sort;
 pub mod tb303;
 pub mod uploads;
 pub mod users;
+pub mod webhooks;
@@ -1,10 +1,11 @@
-use crate::domain::{Note, Time, Transpose, Waveform};
+use crate::api::models::uploads::ImageVariant;
+use crate::domain::{CollaboratorRole, Note, Time, Transpose, Waveform};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Serialize, ToSchema, Debug, Deserialize)]
+#[derive(Serialize, ToSchema, Debug, Deserialize, Clone)]
 pub struct TB303Pattern {
     #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
     pub id: Option<Uuid>,
@@ -40,6 +41,11 @@ pub struct TB303Pattern {
     pub created_at: Option<DateTime<Utc>>,
     #[schema(example = "2023-10-01T12:00:00Z")]
     pub updated_at: Option<DateTime<Utc>>,
+    /// Reference clip of the pattern, e.g. a snippet of the transcribed record.
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/pattern-audio/user-id/upload-id")]
+    pub audio_url: Option<String>,
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/pattern-covers/user-id/upload-id")]
+    pub cover_url: Option<String>,
     pub bars: Vec<TB303Bar>,
 }
 
@@ -76,14 +82,14 @@ pub struct CreateTB303Pattern {
     pub bars: Vec<CreateTB303Bar>,
 }
 
-#[derive(Serialize, ToSchema, Debug, Deserialize)]
+#[derive(Serialize, ToSchema, Debug, Deserialize, Clone)]
 pub struct TB303Bar {
     pub id: Uuid,
     pub number: i32,
     pub steps: Vec<TB303Step>,
 }
 
-#[derive(Serialize, ToSchema, Debug, Deserialize)]
+#[derive(Serialize, ToSchema, Debug, Deserialize, Clone)]
 pub struct TB303Step {
     #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
     pub id: Uuid,
@@ -158,6 +164,17 @@ pub struct PublicTB303PatternSummary {
     pub username: String,
     #[schema(example = "https://bucket.s3.region.amazonaws.com/avatars/user-id")]
     pub avatar_url: Option<String>,
+    /// Resized WebP versions of the avatar, smallest first.
+    pub avatar_variants: Vec<ImageVariant>,
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/pattern-covers/user-id/upload-id")]
+    pub cover_url: Option<String>,
+    /// Resized WebP versions of the cover, smallest first.
+    pub cover_variants: Vec<ImageVariant>,
+    /// SVG step grid of the pattern, always available.
+    #[schema(
+        example = "https://api.example.com/v1/patterns/tb303/123e4567-e89b-12d3-a456-426614174000/preview.svg?v=1696161600"
+    )]
+    pub preview_url: String,
     #[schema(example = "2023-10-01T12:00:00Z")]
     pub created_at: DateTime<Utc>,
     #[schema(example = "2023-10-01T12:00:00Z")]
@@ -171,3 +188,79 @@ pub struct PaginatedPublicTB303PatternSummary {
     pub limit: i64,
     pub offset: i64,
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct SharedTB303PatternSummary {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub pattern_id: Uuid,
+    #[schema(example = "First pattern")]
+    pub name: String,
+    #[schema(example = "Phuture")]
+    pub author: Option<String>,
+    #[schema(example = "Acid Trax")]
+    pub title: Option<String>,
+    #[schema(example = false)]
+    pub is_public: bool,
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "editor")]
+    pub role: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Deserialize)]
+pub struct AddTB303Collaborator {
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "editor")]
+    pub role: CollaboratorRole,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TB303Collaborator {
+    #[schema(example = "26f29224-6001-702f-25dc-6d5c1b750f51")]
+    pub user_id: Uuid,
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "editor")]
+    pub role: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema, Debug, Deserialize)]
+pub struct AttachTB303Audio {
+    #[schema(
+        example = "pattern-audio/550e8400-e29b-41d4-a716-446655440000/f47ac10b-58cc-4372-a567-0e02b2c3d479"
+    )]
+    pub audio_key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TB303PatternAudio {
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/pattern-audio/user-id/upload-id")]
+    pub url: String,
+    #[schema(example = "audio/mpeg")]
+    pub content_type: String,
+    #[schema(example = 12500)]
+    pub duration_ms: i64,
+}
+
+#[derive(Serialize, ToSchema, Debug, Deserialize)]
+pub struct AttachTB303Cover {
+    #[schema(
+        example = "pattern-covers/550e8400-e29b-41d4-a716-446655440000/f47ac10b-58cc-4372-a567-0e02b2c3d479"
+    )]
+    pub cover_key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TB303PatternCover {
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/pattern-covers/user-id/upload-id")]
+    pub url: String,
+    /// Resized WebP versions of the cover, smallest first.
+    pub variants: Vec<ImageVariant>,
+}
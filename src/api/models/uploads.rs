@@ -21,3 +21,13 @@ pub struct PresignResponse {
     )]
     pub key: String,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageVariant {
+    #[schema(example = 256)]
+    pub size: i32,
+    #[schema(
+        example = "https://bucket.s3.region.amazonaws.com/avatars/user-id/upload-id-256.webp"
+    )]
+    pub url: String,
+}
@@ -1,6 +1,8 @@
+use crate::api::models::tb303::TB303Pattern;
+use crate::api::models::uploads::ImageVariant;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -19,10 +21,133 @@ pub struct UserResponse {
     pub username: String,
     #[schema(example = "https://bucket.s3.region.amazonaws.com/avatars/user-id")]
     pub avatar_url: Option<String>,
+    /// Resized WebP versions of the avatar, smallest first.
+    pub avatar_variants: Vec<ImageVariant>,
     #[schema(example = "https://bucket.s3.region.amazonaws.com/banners/user-id")]
     pub banner_url: Option<String>,
+    /// Resized WebP versions of the banner, smallest first.
+    pub banner_variants: Vec<ImageVariant>,
     #[schema(example = "2023-10-01T12:00:00Z")]
     pub created_at: DateTime<Utc>,
     #[schema(example = "2023-10-01T12:00:00Z")]
     pub updated_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternDisposition {
+    /// Delete every pattern owned by the account.
+    #[default]
+    Delete,
+    /// Hand every pattern over to the anonymous archive user. Private ones
+    /// stay private, reachable only by their collaborators.
+    Reassign,
+}
+
+impl AsRef<str> for PatternDisposition {
+    fn as_ref(&self) -> &str {
+        match self {
+            PatternDisposition::Delete => "delete",
+            PatternDisposition::Reassign => "reassign",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteUserParams {
+    /// What happens to the user's patterns. Defaults to `delete`.
+    #[param(inline, example = "reassign")]
+    pub patterns: Option<PatternDisposition>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataExport {
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub exported_at: DateTime<Utc>,
+    pub profile: UserDataProfile,
+    pub files: Vec<UserDataFile>,
+    pub patterns: Vec<TB303Pattern>,
+    pub collaborations: Vec<UserDataCollaboration>,
+    pub webhooks: Vec<UserDataWebhook>,
+    /// Every delivery still logged for the user's webhooks, newest first.
+    pub webhook_deliveries: Vec<UserDataWebhookDelivery>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataProfile {
+    #[schema(example = "26f29224-6001-702f-25dc-6d5c1b750f51")]
+    pub user_id: Uuid,
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "avatars/550e8400-e29b-41d4-a716-446655440000/f47ac10b-58cc...")]
+    pub avatar_key: Option<String>,
+    #[schema(example = "banners/550e8400-e29b-41d4-a716-446655440000/f47ac10b-58cc...")]
+    pub banner_key: Option<String>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataFile {
+    #[schema(example = "avatars/550e8400-e29b-41d4-a716-446655440000/f47ac10b-58cc...")]
+    pub key: String,
+    #[schema(example = "https://bucket.s3.region.amazonaws.com/avatars/user-id")]
+    pub url: String,
+    #[schema(example = 1024)]
+    pub size: Option<i64>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataCollaboration {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub pattern_id: Uuid,
+    #[schema(example = "editor")]
+    pub role: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A registered webhook, without its signing secret.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataWebhook {
+    #[schema(example = "f47ac10b-58cc-4372-a567-0e02b2c3d479")]
+    pub id: Uuid,
+    #[schema(example = "https://hooks.example.com/acid")]
+    pub url: String,
+    #[schema(example = json!(["pattern.published", "pattern.unpublished"]))]
+    pub events: Vec<String>,
+    #[schema(example = false)]
+    pub is_global: bool,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserDataWebhookDelivery {
+    #[schema(example = "9b2f6c1e-8d4a-4f7e-b3c5-2a1d0e9f8c7b")]
+    pub id: Uuid,
+    #[schema(example = "f47ac10b-58cc-4372-a567-0e02b2c3d479")]
+    pub webhook_id: Uuid,
+    #[schema(example = "1c8a9e2f-3b4d-4e5f-a6b7-c8d9e0f1a2b3")]
+    pub event_id: Uuid,
+    #[schema(example = "pattern.published")]
+    pub event: String,
+    #[schema(example = "succeeded")]
+    pub status: String,
+    #[schema(example = 1)]
+    pub attempts: i32,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    #[schema(example = 200)]
+    pub last_status_code: Option<i32>,
+    #[schema(example = json!(null))]
+    pub last_error: Option<String>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
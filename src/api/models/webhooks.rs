@@ -0,0 +1,116 @@
+use crate::domain::WebhookEvent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhook {
+    #[schema(example = "https://hooks.example.com/acid")]
+    pub url: String,
+    /// Defaults to every event.
+    #[schema(example = json!(["pattern.published", "pattern.unpublished"]))]
+    pub events: Option<Vec<WebhookEvent>>,
+    /// Receive events about everyone's public patterns and profiles instead
+    /// of only your own. Admins only.
+    #[schema(example = false)]
+    pub is_global: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Webhook {
+    #[schema(example = "f47ac10b-58cc-4372-a567-0e02b2c3d479")]
+    pub id: Uuid,
+    #[schema(example = "https://hooks.example.com/acid")]
+    pub url: String,
+    #[schema(example = json!(["pattern.published", "pattern.unpublished"]))]
+    pub events: Vec<WebhookEvent>,
+    #[schema(example = false)]
+    pub is_global: bool,
+    /// Key of the HMAC-SHA256 signature in the `X-Acid-Signature` header.
+    #[schema(example = "5d41402abc4b2a76b9719d911017c592")]
+    pub secret: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    #[schema(example = "9b2f6c1e-8d4a-4f7e-b3c5-2a1d0e9f8c7b")]
+    pub id: Uuid,
+    /// The `id` of the payload, the same for redeliveries of an event.
+    #[schema(example = "1c8a9e2f-3b4d-4e5f-a6b7-c8d9e0f1a2b3")]
+    pub event_id: Uuid,
+    #[schema(example = "pattern.published")]
+    pub event: String,
+    /// `pending`, `succeeded` or `failed` once every attempt failed.
+    #[schema(example = "succeeded")]
+    pub status: String,
+    #[schema(example = 1)]
+    pub attempts: i32,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    #[schema(example = 200)]
+    pub last_status_code: Option<i32>,
+    #[schema(example = json!(null))]
+    pub last_error: Option<String>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// The JSON body POSTed to webhooks.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookPayload {
+    /// Deliveries are retried until they succeed, so receivers may see an
+    /// event more than once. Its id stays the same.
+    #[schema(example = "1c8a9e2f-3b4d-4e5f-a6b7-c8d9e0f1a2b3")]
+    pub id: Uuid,
+    pub event: WebhookEvent,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    pub data: WebhookEventData,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum WebhookEventData {
+    Pattern { pattern: WebhookPattern },
+    Profile { user: WebhookUser },
+}
+
+/// The pattern as it is after the event, or was before it was deleted.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookPattern {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub id: Uuid,
+    #[schema(example = "First pattern")]
+    pub name: String,
+    #[schema(example = "Phuture")]
+    pub author: Option<String>,
+    #[schema(example = "Acid Tracks")]
+    pub title: Option<String>,
+    #[schema(example = true)]
+    pub is_public: bool,
+    #[schema(example = "26f29224-6001-702f-25dc-6d5c1b750f51")]
+    pub user_id: Uuid,
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookUser {
+    #[schema(example = "26f29224-6001-702f-25dc-6d5c1b750f51")]
+    pub user_id: Uuid,
+    #[schema(example = "username")]
+    pub username: String,
+    #[schema(example = "2023-10-01T12:00:00Z")]
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,10 +1,29 @@
+use crate::api::models::archive::{
+    ImportTB303Archive, ImportTB303Response, ImportTB303Result, ImportTB303Status,
+    TB303PatternArchive,
+};
+use crate::api::models::embeds::OEmbedResponse;
+use crate::api::models::health::{
+    DependencyHealth, HealthStatus, LivenessResponse, ReadinessChecks, ReadinessResponse,
+};
+use crate::api::models::live::{LiveClientMessage, LiveKnob, LivePresence, LiveServerMessage};
 use crate::api::models::tb303::{
-    PaginatedPublicTB303PatternSummary, PublicTB303PatternSummary, TB303Bar, TB303Pattern,
-    TB303Step,
+    AddTB303Collaborator, AttachTB303Audio, AttachTB303Cover, PaginatedPublicTB303PatternSummary,
+    PublicTB303PatternSummary, SharedTB303PatternSummary, TB303Bar, TB303Collaborator,
+    TB303Pattern, TB303PatternAudio, TB303PatternCover, TB303Step,
+};
+use crate::api::models::uploads::{ImageVariant, PresignRequest, PresignResponse};
+use crate::api::models::users::{
+    PatternDisposition, UpdateUserRequest, UserDataCollaboration, UserDataExport, UserDataFile,
+    UserDataProfile, UserDataWebhook, UserDataWebhookDelivery, UserResponse,
+};
+use crate::api::models::webhooks::{
+    CreateWebhook, Webhook, WebhookDelivery, WebhookEventData, WebhookPattern, WebhookPayload,
+    WebhookUser,
 };
-use crate::api::models::uploads::{PresignRequest, PresignResponse};
-use crate::api::models::users::{UpdateUserRequest, UserResponse};
-use crate::routes::{patterns, uploads, users};
+use crate::domain::{CollaboratorRole, WebhookEvent};
+use crate::problem::{FieldError, FieldErrorCode, Problem, ProblemCode};
+use crate::routes::{embeds, feeds, health_check, patterns, uploads, users, webhooks};
 use utoipa::OpenApi;
 use utoipa::{
     openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
@@ -38,9 +57,34 @@ impl Modify for SecurityAddon {
         patterns::get_tb303_pattern,
         patterns::delete_tb303_pattern,
         patterns::update_tb303_pattern,
+        patterns::list_shared_tb303_patterns,
+        patterns::list_tb303_collaborators,
+        patterns::add_tb303_collaborator,
+        patterns::remove_tb303_collaborator,
+        patterns::put_tb303_audio,
+        patterns::delete_tb303_audio,
+        patterns::put_tb303_cover,
+        patterns::delete_tb303_cover,
+        patterns::get_tb303_preview,
+        patterns::live_tb303_pattern,
+        patterns::import_tb303_patterns,
         uploads::presign_upload,
         users::get_me,
         users::patch_me,
+        users::export_me,
+        users::delete_me,
+        users::get_me_data,
+        embeds::get_oembed,
+        feeds::get_patterns_feed,
+        feeds::get_user_patterns_feed,
+        feeds::get_author_patterns_feed,
+        webhooks::create_webhook,
+        webhooks::list_webhooks,
+        webhooks::delete_webhook,
+        webhooks::list_webhook_deliveries,
+        webhooks::redeliver_webhook_delivery,
+        health_check::health_live,
+        health_check::health_ready,
     ),
     components(
         schemas(
@@ -49,10 +93,53 @@ impl Modify for SecurityAddon {
             TB303Step,
             PaginatedPublicTB303PatternSummary,
             PublicTB303PatternSummary,
+            SharedTB303PatternSummary,
+            AddTB303Collaborator,
+            TB303Collaborator,
+            CollaboratorRole,
+            AttachTB303Audio,
+            TB303PatternAudio,
+            AttachTB303Cover,
+            TB303PatternCover,
+            LiveClientMessage,
+            LiveServerMessage,
+            LivePresence,
+            LiveKnob,
+            TB303PatternArchive,
+            ImportTB303Archive,
+            ImportTB303Response,
+            ImportTB303Result,
+            ImportTB303Status,
             PresignRequest,
             PresignResponse,
+            ImageVariant,
             UpdateUserRequest,
             UserResponse,
+            PatternDisposition,
+            UserDataExport,
+            UserDataProfile,
+            UserDataFile,
+            UserDataCollaboration,
+            UserDataWebhook,
+            UserDataWebhookDelivery,
+            OEmbedResponse,
+            CreateWebhook,
+            Webhook,
+            WebhookEvent,
+            WebhookDelivery,
+            WebhookPayload,
+            WebhookEventData,
+            WebhookPattern,
+            WebhookUser,
+            HealthStatus,
+            LivenessResponse,
+            DependencyHealth,
+            ReadinessChecks,
+            ReadinessResponse,
+            Problem,
+            ProblemCode,
+            FieldError,
+            FieldErrorCode,
         )
     ),
     modifiers(&SecurityAddon)
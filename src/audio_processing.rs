@@ -0,0 +1,289 @@
+use std::io::Cursor;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Clips are references for judging a transcription, not full tracks.
+pub const MAX_AUDIO_DURATION_MS: i64 = 60_000;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum AudioProcessingError {
+    #[error("Unsupported audio format")]
+    UnsupportedFormat,
+    #[error("File is {actual} but was uploaded as {declared}")]
+    ContentTypeMismatch { declared: String, actual: String },
+    #[error("Audio is {duration_ms} ms long, the maximum is {MAX_AUDIO_DURATION_MS} ms")]
+    TooLong { duration_ms: i64 },
+    #[error("Audio contains no sound")]
+    Empty,
+    #[error("Failed to decode audio: {0}")]
+    Decode(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AudioInfo {
+    pub duration_ms: i64,
+}
+
+/// Identifies the container from its magic bytes.
+fn detect_content_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        _ if is_mpeg_audio(bytes) => Some("audio/mpeg"),
+        [b'O', b'g', b'g', b'S', ..] => Some("audio/ogg"),
+        [b'f', b'L', b'a', b'C', ..] => Some("audio/flac"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some("audio/wav"),
+        _ => None,
+    }
+}
+
+/// MPEG audio starts with a frame, or with an ID3v2 tag right before one.
+fn is_mpeg_audio(bytes: &[u8]) -> bool {
+    is_mpeg_frame_header(bytes)
+        || id3_tag_len(bytes)
+            .and_then(|len| bytes.get(len..))
+            .is_some_and(is_mpeg_frame_header)
+}
+
+/// The sync bits alone turn up in plenty of other files, so the version,
+/// layer, bitrate and sample rate have to be valid too.
+fn is_mpeg_frame_header(bytes: &[u8]) -> bool {
+    let [0xFF, second, third, ..] = *bytes else {
+        return false;
+    };
+    let version = (second >> 3) & 0b11;
+    let layer = (second >> 1) & 0b11;
+    let bitrate = third >> 4;
+    let sample_rate = (third >> 2) & 0b11;
+    second & 0xE0 == 0xE0
+        && version != 0b01
+        && layer != 0b00
+        && bitrate != 0b0000
+        && bitrate != 0b1111
+        && sample_rate != 0b11
+}
+
+/// Length of the ID3v2 tag `bytes` start with, footer included.
+fn id3_tag_len(bytes: &[u8]) -> Option<usize> {
+    let [b'I', b'D', b'3', major, revision, flags, ref size @ ..] = *bytes else {
+        return None;
+    };
+    let size = size.get(..4)?;
+    if !(2..=4).contains(&major) || revision == 0xFF || size.iter().any(|b| b & 0x80 != 0) {
+        return None;
+    }
+    // Sizes are stored 7 bits to a byte.
+    let size = size
+        .iter()
+        .fold(0usize, |size, byte| (size << 7) | usize::from(*byte));
+    let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + size + footer)
+}
+
+/// Checks the magic bytes of an uploaded clip against the declared content
+/// type, then demuxes and decodes the whole stream to make sure it is playable
+/// and measure its duration. Ogg files have to contain Vorbis.
+pub fn probe_audio(
+    bytes: Vec<u8>,
+    declared_content_type: &str,
+) -> Result<AudioInfo, AudioProcessingError> {
+    let actual = detect_content_type(&bytes).ok_or(AudioProcessingError::UnsupportedFormat)?;
+    if actual != declared_content_type {
+        return Err(AudioProcessingError::ContentTypeMismatch {
+            declared: declared_content_type.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut hint = Hint::new();
+    hint.mime_type(actual);
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(decode_error)?
+        .format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(AudioProcessingError::Empty)?;
+    let track_id = track.id;
+    let time_base = track
+        .codec_params
+        .time_base
+        .or_else(|| {
+            track
+                .codec_params
+                .sample_rate
+                .map(|rate| symphonia::core::units::TimeBase::new(1, rate))
+        })
+        .ok_or_else(|| AudioProcessingError::Decode("Unknown sample rate".to_string()))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(decode_error)?;
+
+    let mut frames: u64 = 0;
+    let mut decoded_packets = 0;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(decode_error(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(_) => decoded_packets += 1,
+            // A damaged frame is skipped by players too.
+            Err(SymphoniaError::DecodeError(_)) => {}
+            Err(e) => return Err(decode_error(e)),
+        }
+        frames += packet.dur;
+    }
+
+    if decoded_packets == 0 || frames == 0 {
+        return Err(AudioProcessingError::Empty);
+    }
+
+    let time = time_base.calc_time(frames);
+    let duration_ms = (time.seconds as f64 * 1000.0 + time.frac * 1000.0).round() as i64;
+    if duration_ms > MAX_AUDIO_DURATION_MS {
+        return Err(AudioProcessingError::TooLong { duration_ms });
+    }
+
+    Ok(AudioInfo { duration_ms })
+}
+
+fn decode_error(e: SymphoniaError) -> AudioProcessingError {
+    match e {
+        SymphoniaError::Unsupported(_) => AudioProcessingError::UnsupportedFormat,
+        e => AudioProcessingError::Decode(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio_processing::{
+        detect_content_type, probe_audio, AudioInfo, AudioProcessingError,
+    };
+    use claims::{assert_err, assert_none, assert_ok};
+
+    /// MPEG-1 Layer III, 128 kbit/s, 44.1 kHz.
+    const MPEG_FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x64];
+
+    /// A mono 16-bit PCM WAV file of silence.
+    fn wav(sample_rate: u32, samples: u32) -> Vec<u8> {
+        let data_len = samples * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + data_len as usize, 0);
+        bytes
+    }
+
+    #[test]
+    fn wav_duration_is_measured() {
+        let info = assert_ok!(probe_audio(wav(8000, 12000), "audio/wav"));
+
+        assert_eq!(info, AudioInfo { duration_ms: 1500 });
+    }
+
+    #[test]
+    fn clips_longer_than_a_minute_are_rejected() {
+        let result = probe_audio(wav(8000, 8000 * 61), "audio/wav");
+
+        assert_eq!(
+            assert_err!(result),
+            AudioProcessingError::TooLong {
+                duration_ms: 61_000
+            }
+        );
+    }
+
+    #[test]
+    fn declared_content_type_must_match_the_file() {
+        let result = probe_audio(wav(8000, 800), "audio/mpeg");
+
+        assert_eq!(
+            assert_err!(result),
+            AudioProcessingError::ContentTypeMismatch {
+                declared: "audio/mpeg".to_string(),
+                actual: "audio/wav".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn silence_without_samples_is_empty() {
+        let result = probe_audio(wav(8000, 0), "audio/wav");
+
+        assert_eq!(assert_err!(result), AudioProcessingError::Empty);
+    }
+
+    #[test]
+    fn non_audio_files_are_rejected() {
+        let result = probe_audio(b"<html><script></script></html>".to_vec(), "audio/wav");
+
+        assert_eq!(assert_err!(result), AudioProcessingError::UnsupportedFormat);
+    }
+
+    #[test]
+    fn mpeg_frames_are_recognised_with_or_without_an_id3_tag() {
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x00\x02\x00\x00".to_vec();
+        tagged.extend_from_slice(&MPEG_FRAME_HEADER);
+
+        assert_eq!(detect_content_type(&MPEG_FRAME_HEADER), Some("audio/mpeg"));
+        assert_eq!(detect_content_type(&tagged), Some("audio/mpeg"));
+    }
+
+    #[test]
+    fn sync_bits_without_a_valid_frame_header_are_not_mpeg() {
+        for header in [
+            [0xFF, 0xE0, 0x90, 0x64], // reserved layer
+            [0xFF, 0xEB, 0x90, 0x64], // reserved version
+            [0xFF, 0xFB, 0x00, 0x64], // free bitrate
+            [0xFF, 0xFB, 0xF0, 0x64], // bad bitrate
+            [0xFF, 0xFB, 0x9C, 0x64], // reserved sample rate
+        ] {
+            assert_none!(detect_content_type(&header), "{header:02X?}");
+        }
+    }
+
+    #[test]
+    fn id3_tags_have_to_be_followed_by_a_frame() {
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x00\x00".to_vec();
+        tagged.extend_from_slice(b"<html></html>");
+
+        assert_none!(detect_content_type(&tagged));
+        assert_none!(detect_content_type(b"ID3"));
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let mut bytes = wav(8000, 800);
+        bytes.truncate(20);
+
+        assert_err!(probe_audio(bytes, "audio/wav"));
+    }
+}
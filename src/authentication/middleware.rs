@@ -1,12 +1,15 @@
 use crate::configuration::CognitoSettings;
+use crate::metrics::METRICS;
+use crate::problem::{Problem, ProblemCode};
 use actix_web::http::Method;
 use actix_web::{
-    body::MessageBody,
+    body::{BoxBody, MessageBody},
     dev::{ServiceRequest, ServiceResponse},
     error::InternalError,
     http::header::{HeaderMap, AUTHORIZATION},
+    http::StatusCode,
     middleware::Next,
-    web, HttpMessage, HttpResponse,
+    web, HttpMessage,
 };
 use anyhow::anyhow;
 use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
@@ -25,6 +28,14 @@ impl std::fmt::Display for UserId {
     }
 }
 
+/// For trusted callers, such as the admin tooling, that act on behalf of a
+/// user without a token.
+impl From<Uuid> for UserId {
+    fn from(user_id: Uuid) -> Self {
+        Self(user_id)
+    }
+}
+
 impl Deref for UserId {
     type Target = Uuid;
     fn deref(&self) -> &Self::Target {
@@ -44,7 +55,7 @@ struct CognitoClaims {
 }
 
 #[derive(Debug, Deserialize)]
-struct JwkSet {
+pub struct JwkSet {
     keys: Vec<Jwk>,
 }
 
@@ -55,11 +66,6 @@ struct Jwk {
     e: String,
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    message: String,
-}
-
 static JWKS_CACHE: Lazy<RwLock<HashMap<String, DecodingKey>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
@@ -79,27 +85,44 @@ fn extract_token_from_header(headers: &HeaderMap) -> Result<&str, anyhow::Error>
     Ok(&auth_header[7..])
 }
 
-async fn get_decoding_key(
-    kid: &str,
-    region: &str,
-    user_pool_id: &str,
-) -> Result<DecodingKey, anyhow::Error> {
-    if let Some(key) = JWKS_CACHE.read().unwrap().get(kid) {
-        return Ok(key.clone());
-    }
-
-    let jwks_url =
-        format!("https://cognito-idp.{region}.amazonaws.com/{user_pool_id}/.well-known/jwks.json");
+/// Fetches the signing keys of the user pool. Fails when the document is
+/// unreachable, malformed or holds no keys.
+pub async fn fetch_jwks(cognito: &CognitoSettings) -> Result<JwkSet, anyhow::Error> {
+    request_jwks(cognito)
+        .await
+        .inspect_err(|_| METRICS.record_jwks_fetch_failure())
+}
 
+async fn request_jwks(cognito: &CognitoSettings) -> Result<JwkSet, anyhow::Error> {
     let jwk_set: JwkSet = reqwest::Client::new()
-        .get(&jwks_url)
+        .get(cognito.jwks_url())
         .send()
         .await
+        .and_then(|response| response.error_for_status())
         .map_err(|e| anyhow!("Failed to fetch JWKs: {}", e))?
         .json()
         .await
         .map_err(|e| anyhow!("Failed to parse JWKs: {}", e))?;
 
+    if jwk_set.keys.is_empty() {
+        return Err(anyhow!("The JWK set holds no keys"));
+    }
+
+    Ok(jwk_set)
+}
+
+async fn get_decoding_key(
+    kid: &str,
+    cognito: &CognitoSettings,
+) -> Result<DecodingKey, anyhow::Error> {
+    if let Some(key) = JWKS_CACHE.read().unwrap().get(kid) {
+        METRICS.record_jwks_lookup(true);
+        return Ok(key.clone());
+    }
+    METRICS.record_jwks_lookup(false);
+
+    let jwk_set = fetch_jwks(cognito).await?;
+
     let jwk = jwk_set
         .keys
         .into_iter()
@@ -119,10 +142,12 @@ async fn get_decoding_key(
 
 pub async fn try_extract_user_id(headers: &HeaderMap, cognito: &CognitoSettings) -> Option<UserId> {
     let token = extract_token_from_header(headers).ok()?;
+    validate_token(token, cognito).await
+}
+
+pub async fn validate_token(token: &str, cognito: &CognitoSettings) -> Option<UserId> {
     let kid = decode_header(token).ok()?.kid?;
-    let decoding_key = get_decoding_key(&kid, &cognito.region, &cognito.user_pool_id)
-        .await
-        .ok()?;
+    let decoding_key = get_decoding_key(&kid, cognito).await.ok()?;
 
     let mut validation = Validation::new(Algorithm::RS256);
     validation.set_audience(std::slice::from_ref(&cognito.user_pool_client_id));
@@ -140,91 +165,27 @@ pub async fn try_extract_user_id(headers: &HeaderMap, cognito: &CognitoSettings)
     Uuid::parse_str(&token_data.claims.sub).ok().map(UserId)
 }
 
-fn create_unauthorized_response() -> HttpResponse {
-    let error_response = ErrorResponse {
-        message: "Unauthorized access".to_string(),
-    };
-
-    HttpResponse::Unauthorized()
-        .content_type("application/json")
-        .insert_header(("Access-Control-Allow-Origin", "*"))
-        .insert_header((
-            "Access-Control-Allow-Methods",
-            "GET, POST, PUT, DELETE, OPTIONS",
-        ))
-        .insert_header((
-            "Access-Control-Allow-Headers",
-            "Authorization, Content-Type",
-        ))
-        .json(error_response)
-}
-
-pub async fn reject_unauthorized_users(
-    req: ServiceRequest,
-    next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
-    if req.method() == Method::OPTIONS {
-        return next.call(req).await;
-    }
-    let cognito_settings = req
-        .app_data::<web::Data<CognitoSettings>>()
-        .ok_or_else(|| {
-            InternalError::from_response(
-                anyhow!("Cognito configuration not found in app data"),
-                HttpResponse::InternalServerError()
-                    .content_type("application/json")
-                    .json(ErrorResponse {
-                        message: "Internal server error".to_string(),
-                    }),
-            )
-        })?;
-
+/// Why the request is not authenticated, for the logs only: clients are all
+/// told the same.
+async fn authenticate(
+    headers: &HeaderMap,
+    cognito_settings: &CognitoSettings,
+) -> Result<UserId, anyhow::Error> {
     let region = &cognito_settings.region;
     let user_pool_id = &cognito_settings.user_pool_id;
     let client_id = &cognito_settings.user_pool_client_id;
 
-    let token = match extract_token_from_header(req.headers()) {
-        Ok(token) => token,
-        Err(e) => {
-            // Log the specific error internally but return generic response
-            let error_msg = format!("Authentication failed: {e}");
-            return Err(InternalError::from_response(
-                anyhow!(error_msg),
-                create_unauthorized_response(),
-            )
-            .into());
-        }
-    };
+    let token =
+        extract_token_from_header(headers).map_err(|e| anyhow!("Authentication failed: {e}"))?;
 
-    let token_header = match decode_header(token) {
-        Ok(header) => header,
-        Err(e) => {
-            let error_msg = format!("Failed to decode token header: {e}");
-            return Err(InternalError::from_response(
-                anyhow!(error_msg),
-                create_unauthorized_response(),
-            )
-            .into());
-        }
-    };
+    let token_header =
+        decode_header(token).map_err(|e| anyhow!("Failed to decode token header: {e}"))?;
 
-    let kid = match token_header.kid {
-        Some(kid) => kid,
-        None => {
-            return Err(InternalError::from_response(
-                anyhow!("No 'kid' found in token header"),
-                create_unauthorized_response(),
-            )
-            .into())
-        }
-    };
+    let kid = token_header
+        .kid
+        .ok_or_else(|| anyhow!("No 'kid' found in token header"))?;
 
-    let decoding_key = match get_decoding_key(&kid, region, user_pool_id).await {
-        Ok(key) => key,
-        Err(e) => {
-            return Err(InternalError::from_response(e, create_unauthorized_response()).into())
-        }
-    };
+    let decoding_key = get_decoding_key(&kid, cognito_settings).await?;
 
     let mut validation = Validation::new(Algorithm::RS256);
     validation.set_audience(std::slice::from_ref(client_id));
@@ -232,36 +193,57 @@ pub async fn reject_unauthorized_users(
         "https://cognito-idp.{region}.amazonaws.com/{user_pool_id}"
     )]);
 
-    let token_data = match decode::<CognitoClaims>(token, &decoding_key, &validation) {
-        Ok(data) => data,
-        Err(e) => {
-            return Err(InternalError::from_response(
-                anyhow!("Invalid token: {}", e),
-                create_unauthorized_response(),
-            )
-            .into())
-        }
-    };
+    let token_data = decode::<CognitoClaims>(token, &decoding_key, &validation)
+        .map_err(|e| anyhow!("Invalid token: {}", e))?;
 
     if token_data.claims.token_use != "id" {
-        return Err(InternalError::from_response(
-            anyhow!("Token is not an ID token"),
-            create_unauthorized_response(),
-        )
-        .into());
+        return Err(anyhow!("Token is not an ID token"));
     }
 
-    let user_id = match Uuid::parse_str(&token_data.claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return Err(InternalError::from_response(
-                anyhow!("Invalid user ID in token"),
-                create_unauthorized_response(),
-            )
-            .into())
-        }
+    let user_id =
+        Uuid::parse_str(&token_data.claims.sub).map_err(|_| anyhow!("Invalid user ID in token"))?;
+
+    Ok(UserId(user_id))
+}
+
+/// Rejections are sent as responses rather than errors, so the CORS
+/// middleware still adds its headers and browsers can read them.
+pub async fn reject_unauthorized_users(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if req.method() == Method::OPTIONS {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_boxed_body);
+    }
+    let Some(cognito_settings) = req.app_data::<web::Data<CognitoSettings>>() else {
+        let problem = Problem::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ProblemCode::InternalError,
+            "Internal server error",
+        );
+        return Ok(req.error_response(InternalError::from_response(
+            anyhow!("Cognito configuration not found in app data"),
+            problem.into(),
+        )));
     };
 
-    req.extensions_mut().insert(UserId(user_id));
-    next.call(req).await
+    match authenticate(req.headers(), cognito_settings).await {
+        Ok(user_id) => {
+            req.extensions_mut().insert(user_id);
+            next.call(req)
+                .await
+                .map(ServiceResponse::map_into_boxed_body)
+        }
+        Err(e) => {
+            let problem = Problem::new(
+                StatusCode::UNAUTHORIZED,
+                ProblemCode::Unauthorized,
+                "Unauthorized access",
+            );
+            Ok(req.error_response(InternalError::from_response(e, problem.into())))
+        }
+    }
 }
@@ -1,3 +1,7 @@
 mod middleware;
+mod query_token;
 
-pub use middleware::{reject_unauthorized_users, try_extract_user_id, UserId};
+pub use middleware::{
+    fetch_jwks, reject_unauthorized_users, try_extract_user_id, validate_token, JwkSet, UserId,
+};
+pub use query_token::{redact_query_token, QueryToken};
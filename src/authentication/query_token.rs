@@ -0,0 +1,79 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::uri::{PathAndQuery, Uri};
+use actix_web::middleware::Next;
+use actix_web::HttpMessage;
+use secrecy::Secret;
+use std::borrow::Cow;
+
+/// An ID token sent as `?token=`, for clients that cannot set the
+/// `Authorization` header, such as browsers opening a WebSocket.
+#[derive(Clone)]
+pub struct QueryToken(pub Secret<String>);
+
+/// Takes `?token=` out of the request URI before the request is logged,
+/// leaving `token=REDACTED` in its place. Handlers that accept it read
+/// [`QueryToken`] from the request extensions instead. Wrap it around the
+/// request logger.
+pub async fn redact_query_token(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if let Some((token, uri)) = strip_token(req.uri()) {
+        req.head_mut().uri = uri;
+        req.extensions_mut().insert(QueryToken(Secret::new(token)));
+    }
+    next.call(req).await
+}
+
+fn strip_token(uri: &Uri) -> Option<(String, Uri)> {
+    let query = uri.query()?;
+    let mut token = None;
+    let redacted = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(
+            url::form_urlencoded::parse(query.as_bytes()).map(|(name, value)| {
+                if name == "token" {
+                    token = Some(value.into_owned());
+                    (name, Cow::Borrowed("REDACTED"))
+                } else {
+                    (name, value)
+                }
+            }),
+        )
+        .finish();
+    let token = token?;
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query =
+        Some(PathAndQuery::try_from(format!("{}?{}", uri.path(), redacted)).ok()?);
+    Some((token, Uri::from_parts(parts).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::authentication::query_token::strip_token;
+    use actix_web::http::Uri;
+    use claims::assert_none;
+
+    #[test]
+    fn tokens_are_taken_out_of_the_uri() {
+        let uri: Uri = "/v1/patterns/tb303/1/live?token=eyJ.secret&bar=2"
+            .parse()
+            .unwrap();
+
+        let (token, uri) = strip_token(&uri).unwrap();
+
+        assert_eq!(token, "eyJ.secret");
+        assert_eq!(
+            uri.path_and_query().unwrap().as_str(),
+            "/v1/patterns/tb303/1/live?token=REDACTED&bar=2"
+        );
+    }
+
+    #[test]
+    fn uris_without_a_token_are_left_alone() {
+        for uri in ["/v1/patterns/tb303/public?limit=2", "/health/live"] {
+            assert_none!(strip_token(&uri.parse().unwrap()));
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use acid::admin;
+use acid::configuration::get_configuration;
+use acid::fixtures::load_fixture_file;
+use acid::startup::get_connection_pool;
+use acid::telemetry::{get_subscriber, init_subscriber};
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use dotenvy::dotenv;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Maintenance tasks for the archive. Changes go through the same validation
+/// and webhooks as the API.
+#[derive(Parser)]
+#[command(name = "acidctl", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply pending database migrations
+    Migrate,
+    /// Manage users, given by ID or by username
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Manage patterns
+    #[command(subcommand)]
+    Patterns(PatternsCommand),
+    /// Manage the canonical patterns of the archive
+    #[command(subcommand)]
+    Fixtures(FixturesCommand),
+    /// Print archive statistics
+    Stats {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum UsersCommand {
+    /// Create a user
+    Create {
+        username: String,
+        /// ID of the user at the identity provider
+        #[arg(long)]
+        id: Option<Uuid>,
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Change a username
+    Rename { user: String, username: String },
+    /// Grant or revoke admin rights
+    Admin {
+        user: String,
+        #[arg(long)]
+        revoke: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PatternsCommand {
+    /// Import an archive into the library of a user
+    Import {
+        user: String,
+        file: PathBuf,
+        /// Validate the archive without saving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Export patterns to an archive
+    Export {
+        file: PathBuf,
+        /// Only export the patterns of this user
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Hand a pattern over to another user
+    Reassign { pattern_id: Uuid, user: String },
+    /// Make a pattern public
+    Publish { pattern_id: Uuid },
+    /// Make a pattern private
+    Unpublish { pattern_id: Uuid },
+    /// Delete a pattern and its attached files
+    Delete {
+        pattern_id: Uuid,
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FixturesCommand {
+    /// Create or update the fixture users and patterns
+    Load {
+        /// Defaults to `fixtures.path` in the configuration
+        file: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+    let subscriber = get_subscriber("acidctl".into(), "warn".into(), std::io::stderr, None);
+    init_subscriber(subscriber);
+
+    let cli = Cli::parse();
+    let configuration = get_configuration()?;
+    let pool = get_connection_pool(&configuration.database);
+
+    match cli.command {
+        Command::Migrate => {
+            admin::run_migrations(&pool).await?;
+            println!("Migrations applied");
+        }
+        Command::Users(UsersCommand::Create {
+            username,
+            id,
+            admin,
+        }) => {
+            let user_id = admin::create_user(&pool, id, username, admin).await?;
+            println!("{user_id}");
+        }
+        Command::Users(UsersCommand::Rename { user, username }) => {
+            admin::set_username(&pool, &user, username).await?;
+        }
+        Command::Users(UsersCommand::Admin { user, revoke }) => {
+            admin::set_admin(&pool, &user, !revoke).await?;
+        }
+        Command::Patterns(PatternsCommand::Import {
+            user,
+            file,
+            dry_run,
+        }) => {
+            let archive = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let storage = configuration.storage().await;
+            let response =
+                admin::import_patterns(&pool, storage.as_ref(), &user, &archive, dry_run).await?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+            if response.invalid > 0 {
+                anyhow::bail!("{} patterns are invalid", response.invalid);
+            }
+        }
+        Command::Patterns(PatternsCommand::Export { file, user }) => {
+            let storage = configuration.storage().await;
+            let archive = admin::export_patterns(&pool, storage.as_ref(), user.as_deref()).await?;
+            std::fs::write(&file, serde_json::to_vec_pretty(&archive)?)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            println!(
+                "Exported {} patterns to {}",
+                archive.patterns.len(),
+                file.display()
+            );
+        }
+        Command::Patterns(PatternsCommand::Reassign { pattern_id, user }) => {
+            admin::reassign_pattern(&pool, pattern_id, &user).await?;
+        }
+        Command::Patterns(PatternsCommand::Publish { pattern_id }) => {
+            if !admin::set_pattern_visibility(&pool, pattern_id, true).await? {
+                println!("Pattern {pattern_id} is already public");
+            }
+        }
+        Command::Patterns(PatternsCommand::Unpublish { pattern_id }) => {
+            if !admin::set_pattern_visibility(&pool, pattern_id, false).await? {
+                println!("Pattern {pattern_id} is already private");
+            }
+        }
+        Command::Patterns(PatternsCommand::Delete { pattern_id, yes }) => {
+            if !yes {
+                anyhow::bail!("Deleting a pattern cannot be undone. Pass --yes to confirm.");
+            }
+            admin::delete_pattern(&pool, pattern_id).await?;
+        }
+        Command::Fixtures(FixturesCommand::Load { file }) => {
+            let path = file.unwrap_or(configuration.fixtures.path.clone());
+            let storage = configuration.storage().await;
+            let report = load_fixture_file(&pool, storage.as_ref(), &path).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::Stats { json } => {
+            let stats = admin::archive_stats(&pool).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("Users:                      {}", stats.users);
+                println!("Patterns:                   {}", stats.patterns);
+                println!("  public:                   {}", stats.public_patterns);
+                println!("  private:                  {}", stats.private_patterns);
+                println!(
+                    "  last 30 days:             {}",
+                    stats.patterns_last_30_days
+                );
+                println!("Unclaimed uploads:          {}", stats.unclaimed_uploads);
+                println!("Webhooks:                   {}", stats.webhooks);
+                println!(
+                    "Pending webhook deliveries: {}",
+                    stats.pending_webhook_deliveries
+                );
+                println!("Pending jobs:               {}", stats.pending_jobs);
+                println!("Dead jobs:                  {}", stats.dead_jobs);
+            }
+        }
+    }
+
+    Ok(())
+}
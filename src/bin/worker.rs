@@ -0,0 +1,41 @@
+use acid::configuration::get_configuration;
+use acid::jobs::run_worker_until_stopped;
+use acid::telemetry::{get_subscriber, get_tracer, get_tracer_provider, init_subscriber};
+use acid::utils::shutdown_signal;
+use anyhow::anyhow;
+use dotenvy::dotenv;
+use tokio_util::sync::CancellationToken;
+
+/// Runs the job workers without the API, for deployments that set
+/// `jobs.run_in_process` to false. On SIGTERM or SIGINT, running jobs get
+/// `application.shutdown_timeout_secs` to finish.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+    let configuration = get_configuration()?;
+
+    let tracer_provider = get_tracer_provider("worker", &configuration.tracing)?;
+    let tracer = get_tracer(tracer_provider.as_ref(), "worker");
+    let subscriber = get_subscriber("worker".into(), "info".into(), std::io::stdout, tracer);
+    init_subscriber(subscriber);
+
+    let shutdown_timeout = configuration.application.shutdown_timeout();
+    let shutdown = CancellationToken::new();
+    let worker = run_worker_until_stopped(configuration, shutdown.clone());
+    tokio::pin!(worker);
+    let outcome = tokio::select! {
+        outcome = &mut worker => outcome,
+        () = shutdown_signal() => {
+            shutdown.cancel();
+            tokio::time::timeout(shutdown_timeout, worker)
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("Running jobs did not finish in time.")))
+        }
+    };
+
+    if let Some(provider) = tracer_provider {
+        provider.shutdown()?;
+    }
+
+    outcome
+}
@@ -1,8 +1,13 @@
-use crate::s3_client::S3Client;
+use crate::cors::OriginPattern;
+use crate::storage::{LocalStorage, ObjectStorage, S3Storage};
+use crate::utils::error_chain_fmt;
 use secrecy::{ExposeSecret, Secret};
-use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
 use std::convert::{TryFrom, TryInto};
+use std::sync::Arc;
 
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct Settings {
@@ -10,6 +15,18 @@ pub struct Settings {
     pub application: ApplicationSettings,
     pub cognito: CognitoSettings,
     pub s3: S3Settings,
+    pub storage: StorageSettings,
+    pub uploads: UploadSettings,
+    pub webhooks: WebhookSettings,
+    pub jobs: JobSettings,
+    pub fixtures: FixtureSettings,
+    pub health: HealthSettings,
+    #[serde(default)]
+    pub tracing: TracingSettings,
+    pub rate_limits: RateLimitSettings,
+    pub cors: CorsSettings,
+    pub pattern_cache: PatternCacheSettings,
+    pub metrics: MetricsSettings,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -17,6 +34,48 @@ pub struct ApplicationSettings {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
+    /// Where clients reach the API. Defaults to `http://{host}:{port}`.
+    pub base_url: Option<String>,
+    /// Signing secrets of webhooks are derived from it.
+    pub hmac_secret: Secret<String>,
+    /// Threads serving requests. Defaults to one per physical CPU.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub workers: Option<usize>,
+    /// How long idle connections are kept open. 0 closes them after each
+    /// response.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub keep_alive_secs: u64,
+    /// How long clients have to send the head of a request. 0 waits forever.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub request_timeout_secs: u64,
+    /// How long requests in flight, and the jobs and webhook deliveries
+    /// being worked on, may take to finish once a shutdown starts, before
+    /// they are dropped.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub shutdown_timeout_secs: u64,
+    /// Largest JSON body accepted. Imports have a larger limit of their own.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub json_limit_bytes: usize,
+}
+
+impl ApplicationSettings {
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{}:{}", self.host, self.port))
+    }
+
+    pub fn keep_alive(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.keep_alive_secs)
+    }
+
+    pub fn request_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn shutdown_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.shutdown_timeout_secs)
+    }
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -28,6 +87,13 @@ pub struct DatabaseSettings {
     pub host: String,
     pub database_name: String,
     pub require_ssl: bool,
+    /// Connections each pool opens at most. The API, the webhook dispatcher
+    /// and in-process workers have a pool each.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_connections: u32,
+    /// How long a query waits for a free connection before failing.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub acquire_timeout_secs: u64,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -35,6 +101,21 @@ pub struct CognitoSettings {
     pub region: String,
     pub user_pool_id: String,
     pub user_pool_client_id: String,
+    /// Where the signing keys of the user pool are fetched from. Defaults to
+    /// the pool's public JWKS document.
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+}
+
+impl CognitoSettings {
+    pub fn jwks_url(&self) -> String {
+        self.jwks_url.clone().unwrap_or_else(|| {
+            format!(
+                "https://cognito-idp.{}.amazonaws.com/{}/.well-known/jwks.json",
+                self.region, self.user_pool_id
+            )
+        })
+    }
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -42,17 +123,281 @@ pub struct S3Settings {
     pub region: String,
     pub bucket: String,
     pub endpoint_url: Option<String>,
+    /// Defaults to on when `endpoint_url` is set.
+    pub force_path_style: Option<bool>,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    S3,
+    Local,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct StorageSettings {
+    pub backend: StorageBackend,
+    /// Serves files from a CDN or custom domain instead of the backend.
+    pub public_base_url: Option<String>,
+    pub local: LocalStorageSettings,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct LocalStorageSettings {
+    pub root: String,
+    /// Signs upload URLs handed out by the API.
+    pub signing_key: Secret<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct UploadSettings {
+    /// Unclaimed uploads older than this are deleted by the sweep job.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub orphan_ttl_secs: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sweep_interval_secs: u64,
+}
+
+impl UploadSettings {
+    pub fn orphan_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.orphan_ttl_secs)
+    }
+
+    pub fn sweep_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.sweep_interval_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct WebhookSettings {
+    /// Lets webhooks reach loopback and private addresses, for local
+    /// receivers during development.
+    pub allow_private_networks: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_secs: u64,
+    /// How long the dispatcher waits when no delivery is due.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_secs: u64,
+}
+
+impl WebhookSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_interval_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct JobSettings {
+    /// Runs workers inside the API process. Turn it off when jobs are run by
+    /// the separate `worker` binary instead.
+    pub run_in_process: bool,
+    /// How many jobs one process runs at the same time. Each one holds a
+    /// connection for its locked row and another for its own queries, so
+    /// it has to stay below half of `database.max_connections`.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub concurrency: usize,
+    /// How long a worker waits when no job is due.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_secs: u64,
+}
+
+impl JobSettings {
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_interval_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct FixtureSettings {
+    /// Loads the canonical patterns when the API starts.
+    pub load_on_startup: bool,
+    pub path: String,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct HealthSettings {
+    /// How long each readiness check may take before the dependency is
+    /// reported as unavailable.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_secs: u64,
+}
+
+impl HealthSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// Counts per process. Each instance gets the whole budget.
+    Memory,
+    /// Counts in the database, shared by every instance.
+    Postgres,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct RateLimitSettings {
+    pub backend: RateLimitBackend,
+    /// How many proxies in front of the API append to `X-Forwarded-For`.
+    /// The client IP of unauthenticated requests is the entry that many
+    /// places from the right, since everything left of it is whatever the
+    /// client sent. With 0 the header is ignored and the peer address used.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub trusted_proxy_hops: usize,
+    /// Creating and updating patterns.
+    pub writes: RateLimit,
+    /// Presigned upload URLs.
+    pub presigns: RateLimit,
+    /// The random pattern and the public listing.
+    pub expensive_reads: RateLimit,
+}
+
+/// At most `requests` per client in every window of `period_secs`.
+#[derive(serde::Deserialize, Clone, Copy, Debug)]
+pub struct RateLimit {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub requests: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub period_secs: u64,
+}
+
+impl RateLimit {
+    pub fn period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.period_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct TracingSettings {
+    /// OTLP/HTTP endpoint spans are exported to, e.g.
+    /// `http://localhost:4318/v1/traces`. Spans are only logged when unset.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct PatternCacheSettings {
+    /// Public patterns kept in memory. 0 turns the cache off.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub capacity: usize,
+    /// Cached patterns are checked against the database on every read, but
+    /// media attached through other instances only show after this long.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub ttl_secs: u64,
 }
 
-impl S3Settings {
-    pub async fn client(&self) -> S3Client {
-        S3Client::new(
-            self.region.clone(),
-            self.bucket.clone(),
-            self.endpoint_url.clone(),
+impl PatternCacheSettings {
+    pub fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl_secs)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct MetricsSettings {
+    /// Scrapers send it as `Authorization: Bearer {token}`.
+    pub token: Secret<String>,
+    /// How often the archive totals are counted. Scrapes read the last
+    /// count rather than querying the database.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub refresh_interval_secs: u64,
+}
+
+impl MetricsSettings {
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.refresh_interval_secs)
+    }
+}
+
+/// Lists can also be set from the environment, separated by commas, e.g.
+/// `APP_CORS__ALLOWED_ORIGINS=https://acidarchive.com,https://*.acidarchive.com`.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct CorsSettings {
+    /// `*` allows any origin, `https://*.acidarchive.com` any subdomain.
+    #[serde(deserialize_with = "deserialize_list_from_string")]
+    pub allowed_origins: Vec<String>,
+    /// `*` allows any method.
+    #[serde(deserialize_with = "deserialize_list_from_string")]
+    pub allowed_methods: Vec<String>,
+    /// `*` allows any request header.
+    #[serde(deserialize_with = "deserialize_list_from_string")]
+    pub allowed_headers: Vec<String>,
+    /// Lets browsers send cookies. Needs explicit origins.
+    pub allow_credentials: bool,
+    /// How long browsers may cache the answer to a preflight request.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_age_secs: usize,
+}
+
+impl CorsSettings {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    pub fn origin_patterns(&self) -> Result<Vec<OriginPattern>, String> {
+        self.allowed_origins
+            .iter()
+            .map(|origin| OriginPattern::parse(origin))
+            .collect()
+    }
+}
+
+fn deserialize_list_from_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Separated(String),
+        Items(Vec<String>),
+    }
+
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        List::Separated(items) => items
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect(),
+        List::Items(items) => items,
+    })
+}
+
+impl Settings {
+    pub async fn storage(&self) -> Arc<dyn ObjectStorage> {
+        match self.storage.backend {
+            StorageBackend::S3 => Arc::new(self.s3_storage().await),
+            StorageBackend::Local => Arc::new(self.local_storage()),
+        }
+    }
+
+    pub async fn s3_storage(&self) -> S3Storage {
+        S3Storage::new(
+            self.s3.region.clone(),
+            self.s3.bucket.clone(),
+            self.s3.endpoint_url.clone(),
+            self.s3
+                .force_path_style
+                .unwrap_or(self.s3.endpoint_url.is_some()),
+            self.storage.public_base_url.clone(),
         )
         .await
     }
+
+    pub fn local_storage(&self) -> LocalStorage {
+        LocalStorage::new(
+            self.storage.local.root.clone().into(),
+            self.application.base_url(),
+            self.storage.public_base_url.clone(),
+            self.storage.local.signing_key.clone(),
+        )
+    }
 }
 
 impl DatabaseSettings {
@@ -70,16 +415,38 @@ impl DatabaseSettings {
             .ssl_mode(ssl_mode)
             .database(&self.database_name)
     }
+
+    pub fn acquire_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.acquire_timeout_secs)
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum ConfigurationError {
+    #[error("Failed to determine the current directory.")]
+    CurrentDirectory(#[source] std::io::Error),
+    #[error("Failed to parse APP_ENVIRONMENT: {0}")]
+    UnknownEnvironment(String),
+    #[error("Failed to read the configuration.")]
+    Unreadable(#[from] config::ConfigError),
+    #[error("Invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
 }
 
-pub fn get_configuration() -> Result<Settings, config::ConfigError> {
-    let base_path = std::env::current_dir().expect("Failed to determine the current directory");
+impl std::fmt::Debug for ConfigurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+pub fn get_configuration() -> Result<Settings, ConfigurationError> {
+    let base_path = std::env::current_dir().map_err(ConfigurationError::CurrentDirectory)?;
     let configuration_directory = base_path.join("configuration");
 
     let environment: Environment = std::env::var("APP_ENVIRONMENT")
         .unwrap_or_else(|_| "local".into())
         .try_into()
-        .expect("Failed to parse APP_ENVIRONMENT.");
+        .map_err(ConfigurationError::UnknownEnvironment)?;
 
     let environment_filename = format!("{}.yaml", environment.as_str());
     let settings = config::Config::builder()
@@ -96,9 +463,135 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
         )
         .build()?;
 
-    settings.try_deserialize::<Settings>()
+    let settings = settings.try_deserialize::<Settings>()?;
+    settings.validate(environment)?;
+    Ok(settings)
 }
 
+impl Settings {
+    /// Checks what deserializing cannot, naming every setting that is off so
+    /// they can all be fixed before the next start. Placeholder secrets are
+    /// only accepted locally.
+    pub fn validate(&self, environment: Environment) -> Result<(), ConfigurationError> {
+        let mut problems = Vec::new();
+        let mut require = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+
+        require(
+            self.application.workers != Some(0),
+            "application.workers must be at least 1.",
+        );
+        require(
+            self.application.shutdown_timeout_secs > 0,
+            "application.shutdown_timeout_secs must be at least 1.",
+        );
+        require(
+            self.application.json_limit_bytes > 0,
+            "application.json_limit_bytes must be at least 1.",
+        );
+        if let Some(ref base_url) = self.application.base_url {
+            require(
+                url::Url::parse(base_url).is_ok(),
+                "application.base_url must be an absolute URL.",
+            );
+        }
+        require(
+            self.database.max_connections > 0,
+            "database.max_connections must be at least 1.",
+        );
+        require(
+            self.database.acquire_timeout_secs > 0,
+            "database.acquire_timeout_secs must be at least 1.",
+        );
+        require(
+            self.jobs.concurrency > 0,
+            "jobs.concurrency must be at least 1.",
+        );
+        require(
+            2 * self.jobs.concurrency < self.database.max_connections as usize,
+            "jobs.concurrency must be less than half of database.max_connections.",
+        );
+        require(
+            self.health.timeout_secs > 0,
+            "health.timeout_secs must be at least 1.",
+        );
+        require(
+            self.metrics.refresh_interval_secs > 0,
+            "metrics.refresh_interval_secs must be at least 1.",
+        );
+        require(
+            self.webhooks.timeout_secs > 0,
+            "webhooks.timeout_secs must be at least 1.",
+        );
+        for (name, limit) in [
+            ("writes", self.rate_limits.writes),
+            ("presigns", self.rate_limits.presigns),
+            ("expensive_reads", self.rate_limits.expensive_reads),
+        ] {
+            require(
+                limit.requests > 0 && limit.period_secs > 0,
+                &format!("rate_limits.{name}.requests and period_secs must be at least 1."),
+            );
+        }
+
+        if environment != Environment::Local {
+            require(
+                !is_placeholder(&self.application.hmac_secret),
+                "application.hmac_secret must be set to a random value.",
+            );
+            require(
+                !is_placeholder(&self.metrics.token),
+                "metrics.token must be set to a random value.",
+            );
+            if self.storage.backend == StorageBackend::Local {
+                require(
+                    !is_placeholder(&self.storage.local.signing_key),
+                    "storage.local.signing_key must be set to a random value.",
+                );
+            }
+        }
+
+        if !self.cors.allows_any_origin() {
+            if let Err(e) = self.cors.origin_patterns() {
+                problems.push(format!("cors.allowed_origins: {e}"));
+            }
+        }
+        if self.cors.allow_credentials && self.cors.allows_any_origin() {
+            problems.push("cors.allow_credentials needs explicit cors.allowed_origins.".into());
+        }
+        for method in &self.cors.allowed_methods {
+            if method != "*" && method.parse::<actix_web::http::Method>().is_err() {
+                problems.push(format!("cors.allowed_methods: {method} is not a method."));
+            }
+        }
+        for header in &self.cors.allowed_headers {
+            if header != "*"
+                && header
+                    .parse::<actix_web::http::header::HeaderName>()
+                    .is_err()
+            {
+                problems.push(format!("cors.allowed_headers: {header} is not a header."));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigurationError::Invalid(problems))
+        }
+    }
+}
+
+/// Secrets left as shipped in `base.yaml`, which anyone can read.
+fn is_placeholder(secret: &Secret<String>) -> bool {
+    let secret = secret.expose_secret().trim();
+    secret.is_empty() || secret == "CHANGE_ME"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Environment {
     Local,
     Production,
@@ -125,3 +618,134 @@ impl TryFrom<String> for Environment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::{ConfigurationError, Environment, Settings, StorageBackend};
+    use claims::{assert_err, assert_ok};
+
+    fn configuration(environment: &str) -> config::ConfigBuilder<config::builder::DefaultState> {
+        config::Config::builder()
+            .add_source(config::File::with_name("configuration/base.yaml"))
+            .add_source(config::File::with_name(&format!(
+                "configuration/{environment}.yaml"
+            )))
+    }
+
+    fn local_settings() -> Settings {
+        configuration("local")
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn the_shipped_configuration_is_valid() {
+        assert_ok!(local_settings().validate(Environment::Local));
+
+        // Deployments set their secrets from the environment.
+        let production: Settings = configuration("production")
+            .set_override("application.hmac_secret", "f1c3a2b4d5e6")
+            .unwrap()
+            .set_override("metrics.token", "9a8b7c6d5e4f")
+            .unwrap()
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        assert_ok!(production.validate(Environment::Production));
+        // Deployments say how many proxies they run behind.
+        assert_eq!(production.rate_limits.trusted_proxy_hops, 0);
+    }
+
+    #[test]
+    fn placeholder_secrets_are_only_accepted_locally() {
+        let mut settings = local_settings();
+        settings.storage.backend = StorageBackend::Local;
+
+        let error = assert_err!(settings.validate(Environment::Production));
+
+        let ConfigurationError::Invalid(problems) = &error else {
+            panic!("Expected invalid settings, got {error:?}");
+        };
+        assert_eq!(
+            problems,
+            &[
+                "application.hmac_secret must be set to a random value.",
+                "metrics.token must be set to a random value.",
+                "storage.local.signing_key must be set to a random value.",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_invalid_setting_is_reported() {
+        let mut settings = local_settings();
+        settings.application.workers = Some(0);
+        settings.application.base_url = Some("acidarchive.com".to_string());
+        settings.database.max_connections = 0;
+        settings.rate_limits.presigns.period_secs = 0;
+
+        let error = assert_err!(settings.validate(Environment::Local));
+
+        let ConfigurationError::Invalid(problems) = &error else {
+            panic!("Expected invalid settings, got {error:?}");
+        };
+        assert_eq!(
+            problems,
+            &[
+                "application.workers must be at least 1.",
+                "application.base_url must be an absolute URL.",
+                "database.max_connections must be at least 1.",
+                "jobs.concurrency must be less than half of database.max_connections.",
+                "rate_limits.presigns.requests and period_secs must be at least 1.",
+            ]
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Invalid configuration:\n  application.workers"));
+    }
+
+    #[test]
+    fn job_workers_leave_connections_for_their_jobs() {
+        let mut settings = local_settings();
+        settings.database.max_connections = 10;
+
+        settings.jobs.concurrency = 5;
+        assert_err!(settings.validate(Environment::Local));
+
+        settings.jobs.concurrency = 4;
+        assert_ok!(settings.validate(Environment::Local));
+    }
+
+    #[test]
+    fn credentials_need_explicit_origins() {
+        let mut settings = local_settings();
+        settings.cors.allow_credentials = true;
+
+        assert_err!(settings.validate(Environment::Local));
+
+        settings.cors.allowed_origins = vec!["https://*.acidarchive.com".into()];
+        assert_ok!(settings.validate(Environment::Local));
+    }
+
+    #[test]
+    fn cors_lists_can_be_separated_by_commas() {
+        let settings: Settings = configuration("local")
+            .set_override(
+                "cors.allowed_origins",
+                "https://acidarchive.com, https://*.acidarchive.com",
+            )
+            .unwrap()
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(
+            settings.cors.allowed_origins,
+            ["https://acidarchive.com", "https://*.acidarchive.com"]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::configuration::CorsSettings;
+use crate::rate_limiting::RATE_LIMIT_HEADERS;
+use actix_cors::Cors;
+use actix_web::http::{header::HeaderName, Method};
+
+/// An origin allowed to call the API, such as `https://acidarchive.com`.
+/// `https://*.acidarchive.com` matches every subdomain, but not the domain
+/// itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OriginPattern {
+    Exact(String),
+    Subdomains { scheme: String, domain: String },
+}
+
+impl OriginPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim().to_ascii_lowercase();
+        let Some((scheme, host)) = pattern.split_once("://").filter(|(scheme, host)| {
+            matches!(*scheme, "http" | "https") && !host.is_empty() && !host.contains('/')
+        }) else {
+            return Err(format!(
+                "{pattern} is not an origin, e.g. https://acidarchive.com."
+            ));
+        };
+        match host.strip_prefix("*.") {
+            Some(domain) if !domain.is_empty() && !domain.contains('*') => Ok(Self::Subdomains {
+                scheme: scheme.to_string(),
+                domain: domain.to_string(),
+            }),
+            None if !host.contains('*') => Ok(Self::Exact(pattern)),
+            _ => Err(format!(
+                "{pattern} may only start its host with a wildcard, e.g. https://*.acidarchive.com."
+            )),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            Self::Exact(allowed) => allowed.eq_ignore_ascii_case(origin),
+            Self::Subdomains { scheme, domain } => {
+                let origin = origin.to_ascii_lowercase();
+                let Some(host) = origin
+                    .strip_prefix(scheme.as_str())
+                    .and_then(|rest| rest.strip_prefix("://"))
+                else {
+                    return false;
+                };
+                host.strip_suffix(domain.as_str())
+                    .and_then(|subdomain| subdomain.strip_suffix('.'))
+                    .is_some_and(|subdomain| {
+                        !subdomain.is_empty()
+                            && subdomain
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                    })
+            }
+        }
+    }
+}
+
+/// Builds the CORS middleware from settings already checked by
+/// `Settings::validate`.
+pub fn cors(settings: &CorsSettings) -> Cors {
+    let mut cors = Cors::default()
+        .expose_headers(RATE_LIMIT_HEADERS)
+        .max_age(settings.max_age_secs);
+
+    if settings.allows_any_origin() {
+        cors = cors.allow_any_origin();
+    } else {
+        let patterns = settings.origin_patterns().unwrap_or_default();
+        cors = cors.allowed_origin_fn(move |origin, _req| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+        });
+    }
+
+    if settings.allowed_methods.iter().any(|method| method == "*") {
+        cors = cors.allow_any_method();
+    } else {
+        cors = cors.allowed_methods(
+            settings
+                .allowed_methods
+                .iter()
+                .filter_map(|method| method.parse::<Method>().ok()),
+        );
+    }
+
+    if settings.allowed_headers.iter().any(|header| header == "*") {
+        cors = cors.allow_any_header();
+    } else {
+        cors = cors.allowed_headers(
+            settings
+                .allowed_headers
+                .iter()
+                .filter_map(|header| header.parse::<HeaderName>().ok()),
+        );
+    }
+
+    if settings.allow_credentials {
+        cors = cors.supports_credentials();
+    }
+
+    cors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cors::OriginPattern;
+    use claims::assert_err;
+
+    #[test]
+    fn exact_origins_match_only_themselves() {
+        let pattern = OriginPattern::parse("https://acidarchive.com").unwrap();
+
+        assert!(pattern.matches("https://acidarchive.com"));
+        assert!(pattern.matches("HTTPS://AcidArchive.com"));
+        assert!(!pattern.matches("http://acidarchive.com"));
+        assert!(!pattern.matches("https://acidarchive.com.evil.com"));
+    }
+
+    #[test]
+    fn wildcards_match_subdomains_only() {
+        let pattern = OriginPattern::parse("https://*.acidarchive.com").unwrap();
+
+        assert!(pattern.matches("https://embed.acidarchive.com"));
+        assert!(pattern.matches("https://pr-12.preview.acidarchive.com"));
+        assert!(!pattern.matches("https://acidarchive.com"));
+        assert!(!pattern.matches("https://evilacidarchive.com"));
+        assert!(!pattern.matches("https://evil.com/.acidarchive.com"));
+        assert!(!pattern.matches("http://embed.acidarchive.com"));
+    }
+
+    #[test]
+    fn malformed_patterns_are_rejected() {
+        for pattern in [
+            "acidarchive.com",
+            "ftp://acidarchive.com",
+            "https://acidarchive.com/app",
+            "https://embed.*.com",
+            "https://*.",
+        ] {
+            assert_err!(OriginPattern::parse(pattern), "{pattern}");
+        }
+    }
+}
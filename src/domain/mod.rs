@@ -1,5 +1,9 @@
 mod patterns;
 mod uploads;
+mod username;
+mod webhooks;
 
 pub use patterns::*;
 pub use uploads::*;
+pub use username::Username;
+pub use webhooks::*;
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CollaboratorRole {
+    Editor,
+    Viewer,
+}
+
+impl CollaboratorRole {
+    pub fn parse(s: &str) -> Result<CollaboratorRole, String> {
+        match s {
+            "editor" => Ok(Self::Editor),
+            "viewer" => Ok(Self::Viewer),
+            other => Err(format!("{other} is not a valid collaborator role.")),
+        }
+    }
+
+    pub fn can_edit(&self) -> bool {
+        matches!(self, Self::Editor)
+    }
+}
+
+impl AsRef<str> for CollaboratorRole {
+    fn as_ref(&self) -> &str {
+        match self {
+            CollaboratorRole::Editor => "editor",
+            CollaboratorRole::Viewer => "viewer",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::CollaboratorRole;
+    use claims::{assert_err, assert_ok};
+    use serde_json;
+
+    #[test]
+    fn collaborator_role_serialization() {
+        let roles = vec![CollaboratorRole::Editor, CollaboratorRole::Viewer];
+
+        for role in roles {
+            let json = serde_json::to_string(&role).unwrap();
+            let deserialized: CollaboratorRole = serde_json::from_str(&json).unwrap();
+            assert_eq!(role, deserialized);
+        }
+    }
+
+    #[test]
+    fn a_stored_role_is_parsed_successfully() {
+        assert_ok!(CollaboratorRole::parse("editor"));
+        assert_ok!(CollaboratorRole::parse("viewer"));
+    }
+
+    #[test]
+    fn an_unknown_role_is_rejected() {
+        assert_err!(CollaboratorRole::parse("owner"));
+    }
+}
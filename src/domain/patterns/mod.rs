@@ -1,4 +1,5 @@
 mod author;
+mod collaborator_role;
 mod description;
 mod knob;
 mod name;
@@ -14,6 +15,7 @@ mod transpose;
 mod waveform;
 
 pub use author::Author;
+pub use collaborator_role::CollaboratorRole;
 pub use description::Description;
 pub use knob::Knob;
 pub use name::Name;
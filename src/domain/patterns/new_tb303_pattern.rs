@@ -17,3 +17,108 @@ pub struct NewTB303Pattern {
     pub is_public: Option<bool>,
     pub bars: Vec<NewTB303Bar>,
 }
+
+impl NewTB303Pattern {
+    /// A canonical representation of the musical content of the pattern,
+    /// used to detect duplicates. Visibility is not part of the content.
+    pub fn fingerprint(&self) -> String {
+        fn text<T: AsRef<str>>(value: &Option<T>) -> &str {
+            value.as_ref().map(|v| v.as_ref()).unwrap_or("")
+        }
+        fn number<T: AsRef<i32>>(value: &Option<T>) -> i32 {
+            value.as_ref().map(|v| *v.as_ref()).unwrap_or(0)
+        }
+
+        let mut parts = vec![
+            self.name.as_ref().to_string(),
+            text(&self.author).to_string(),
+            text(&self.title).to_string(),
+            text(&self.description).to_string(),
+            text(&self.waveform).to_string(),
+            self.triplets.unwrap_or(false).to_string(),
+            number(&self.tempo).to_string(),
+        ];
+        for knob in [
+            &self.tuning,
+            &self.cut_off_freq,
+            &self.resonance,
+            &self.env_mod,
+            &self.decay,
+            &self.accent,
+        ] {
+            parts.push(number(knob).to_string());
+        }
+
+        let mut bars: Vec<&NewTB303Bar> = self.bars.iter().collect();
+        bars.sort_by_key(|bar| bar.number);
+        for bar in bars {
+            let mut steps: Vec<_> = bar.steps.iter().collect();
+            steps.sort_by_key(|step| *step.number.as_ref());
+            for step in steps {
+                parts.push(format!(
+                    "{}:{}:{}:{}:{}:{}:{}",
+                    bar.number,
+                    step.number.as_ref(),
+                    text(&step.note),
+                    text(&step.transpose),
+                    step.time.as_ref(),
+                    step.accent.unwrap_or(false),
+                    step.slide.unwrap_or(false)
+                ));
+            }
+        }
+
+        parts.join("|")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{Name, NewTB303Bar, NewTB303Pattern, NewTB303Step, Note, StepNumber, Time};
+
+    fn pattern(is_public: bool, note: Note) -> NewTB303Pattern {
+        NewTB303Pattern {
+            name: Name::parse("Pattern".to_string()).unwrap(),
+            author: None,
+            title: None,
+            description: None,
+            waveform: None,
+            triplets: None,
+            tempo: None,
+            tuning: None,
+            cut_off_freq: None,
+            resonance: None,
+            env_mod: None,
+            decay: None,
+            accent: None,
+            is_public: Some(is_public),
+            bars: vec![NewTB303Bar {
+                number: 1,
+                steps: vec![NewTB303Step {
+                    number: StepNumber::parse(1).unwrap(),
+                    note: Some(note),
+                    transpose: None,
+                    time: Time::Note,
+                    accent: None,
+                    slide: None,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_visibility() {
+        assert_eq!(
+            pattern(true, Note::C).fingerprint(),
+            pattern(false, Note::C).fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_changes_with_steps() {
+        assert_ne!(
+            pattern(true, Note::C).fingerprint(),
+            pattern(true, Note::D).fingerprint()
+        );
+    }
+}
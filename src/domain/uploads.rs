@@ -6,20 +6,51 @@ use utoipa::ToSchema;
 pub enum UploadType {
     Avatar,
     Banner,
+    PatternAudio,
+    PatternCover,
 }
 
 impl UploadType {
+    pub const ALL: [UploadType; 4] = [
+        Self::Avatar,
+        Self::Banner,
+        Self::PatternAudio,
+        Self::PatternCover,
+    ];
+
     pub fn s3_prefix(&self) -> &'static str {
         match self {
             Self::Avatar => "avatars",
             Self::Banner => "banners",
+            Self::PatternAudio => "pattern-audio",
+            Self::PatternCover => "pattern-covers",
+        }
+    }
+
+    /// Sizes of the processed WebP variants: the square edge for avatars and
+    /// covers, the width for banners.
+    pub fn variant_sizes(&self) -> &'static [u32] {
+        match self {
+            Self::Avatar => &[64, 256, 1024],
+            Self::Banner => &[640, 1280, 1920],
+            Self::PatternAudio => &[],
+            Self::PatternCover => &[256, 512, 1024],
         }
     }
 
     pub fn max_size_bytes(&self) -> u64 {
         match self {
-            Self::Avatar => 2 * 1024 * 1024, // 2MB
-            Self::Banner => 5 * 1024 * 1024, // 5MB
+            Self::Avatar => 2 * 1024 * 1024,        // 2MB
+            Self::Banner => 5 * 1024 * 1024,        // 5MB
+            Self::PatternAudio => 10 * 1024 * 1024, // 10MB
+            Self::PatternCover => 5 * 1024 * 1024,  // 5MB
+        }
+    }
+
+    pub fn accepts(&self, content_type: ContentType) -> bool {
+        match self {
+            Self::Avatar | Self::Banner | Self::PatternCover => content_type.is_image(),
+            Self::PatternAudio => content_type.is_audio(),
         }
     }
 }
@@ -34,6 +65,35 @@ pub enum ContentType {
     Webp,
     #[serde(rename = "image/gif")]
     Gif,
+    #[serde(rename = "audio/mpeg")]
+    Mp3,
+    #[serde(rename = "audio/ogg")]
+    Ogg,
+    #[serde(rename = "audio/wav")]
+    Wav,
+    #[serde(rename = "audio/flac")]
+    Flac,
+}
+
+impl ContentType {
+    pub fn is_image(&self) -> bool {
+        matches!(self, Self::Jpeg | Self::Png | Self::Webp | Self::Gif)
+    }
+
+    pub fn is_audio(&self) -> bool {
+        matches!(self, Self::Mp3 | Self::Ogg | Self::Wav | Self::Flac)
+    }
+}
+
+impl AsRef<str> for UploadType {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Avatar => "avatar",
+            Self::Banner => "banner",
+            Self::PatternAudio => "pattern_audio",
+            Self::PatternCover => "pattern_cover",
+        }
+    }
 }
 
 impl AsRef<str> for ContentType {
@@ -43,6 +103,10 @@ impl AsRef<str> for ContentType {
             Self::Png => "image/png",
             Self::Webp => "image/webp",
             Self::Gif => "image/gif",
+            Self::Mp3 => "audio/mpeg",
+            Self::Ogg => "audio/ogg",
+            Self::Wav => "audio/wav",
+            Self::Flac => "audio/flac",
         }
     }
 }
@@ -0,0 +1,71 @@
+const MAX_LENGTH: usize = 40;
+const MIN_LENGTH: usize = 3;
+
+/// Usernames appear in URLs such as `/v1/feeds/users/{username}/...`, so
+/// they are limited to characters that never need escaping.
+#[derive(Debug, Clone)]
+pub struct Username(String);
+
+impl Username {
+    pub fn parse(s: String) -> Result<Username, String> {
+        let has_valid_length = (MIN_LENGTH..=MAX_LENGTH).contains(&s.len());
+        let has_valid_characters = s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        let starts_with_alphanumeric = s.starts_with(|c: char| c.is_ascii_alphanumeric());
+
+        if has_valid_length && has_valid_characters && starts_with_alphanumeric {
+            Ok(Self(s))
+        } else {
+            Err(format!(
+                "{s} is not a valid username. Use {MIN_LENGTH} to {MAX_LENGTH} letters, digits, \
+                 '_', '-' or '.', starting with a letter or digit."
+            ))
+        }
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::Username;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_valid_username_is_parsed_successfully() {
+        assert_ok!(Username::parse("acid_303.fan-1".to_string()));
+    }
+
+    #[test]
+    fn a_uuid_is_a_valid_username() {
+        assert_ok!(Username::parse(uuid::Uuid::new_v4().to_string()));
+    }
+
+    #[test]
+    fn short_usernames_are_rejected() {
+        assert_err!(Username::parse("ab".to_string()));
+    }
+
+    #[test]
+    fn a_41_character_username_is_rejected() {
+        assert_err!(Username::parse("a".repeat(41)));
+    }
+
+    #[test]
+    fn usernames_with_spaces_or_slashes_are_rejected() {
+        for username in ["acid fan", "acid/fan", "ácid", "acid?"] {
+            assert_err!(Username::parse(username.to_string()));
+        }
+    }
+
+    #[test]
+    fn usernames_must_start_with_a_letter_or_digit() {
+        assert_err!(Username::parse(".acid".to_string()));
+        assert_err!(Username::parse("-acid".to_string()));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::routes::embeds::{escape_html, song_credit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+    Json,
+}
+
+impl FeedFormat {
+    /// Parses the extension of the feed path, e.g. `atom` in `patterns.atom`.
+    pub fn parse(extension: &str) -> Option<FeedFormat> {
+        match extension {
+            "atom" => Some(Self::Atom),
+            "rss" => Some(Self::Rss),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+            Self::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+}
+
+pub struct Feed {
+    pub title: String,
+    /// Where people read what the feed is about.
+    pub home_page_url: String,
+    /// The feed itself, in the format being rendered.
+    pub feed_url: String,
+    /// Newest first.
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Clone)]
+pub struct FeedItem {
+    pub id: uuid::Uuid,
+    pub url: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub username: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeedItem {
+    fn summary(&self) -> String {
+        match song_credit(self.title.as_deref(), self.author.as_deref()) {
+            Some(song) => format!("{song}, shared by {}", self.username),
+            None => format!("Shared by {}", self.username),
+        }
+    }
+}
+
+impl Feed {
+    /// When anything in the feed last changed, `None` for empty feeds.
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.items.iter().map(|item| item.updated_at).max()
+    }
+
+    pub fn render(&self, format: FeedFormat) -> String {
+        match format {
+            FeedFormat::Atom => self.render_atom(),
+            FeedFormat::Rss => self.render_rss(),
+            FeedFormat::Json => self.render_json(),
+        }
+    }
+
+    fn render_atom(&self) -> String {
+        let updated = self.updated_at().unwrap_or(DateTime::UNIX_EPOCH);
+        let mut xml = String::new();
+
+        // Writing to a String cannot fail.
+        let _ = write!(
+            xml,
+            r#"<?xml version="1.0" encoding="utf-8"?><feed xmlns="http://www.w3.org/2005/Atom"><id>{}</id><title>{}</title><updated>{}</updated><link rel="self" href="{}"/><link rel="alternate" type="text/html" href="{}"/>"#,
+            escape_html(&self.feed_url),
+            escape_html(&self.title),
+            rfc3339(updated),
+            escape_html(&self.feed_url),
+            escape_html(&self.home_page_url)
+        );
+        for item in &self.items {
+            let _ = write!(
+                xml,
+                r#"<entry><id>urn:uuid:{}</id><title>{}</title><link rel="alternate" type="text/html" href="{}"/><published>{}</published><updated>{}</updated><author><name>{}</name></author><summary>{}</summary>"#,
+                item.id,
+                escape_html(&item.name),
+                escape_html(&item.url),
+                rfc3339(item.created_at),
+                rfc3339(item.updated_at),
+                escape_html(&item.username),
+                escape_html(&item.summary())
+            );
+            if let Some(ref description) = item.description {
+                let _ = write!(
+                    xml,
+                    r#"<content type="text">{}</content>"#,
+                    escape_html(description)
+                );
+            }
+            xml.push_str("</entry>");
+        }
+        xml.push_str("</feed>");
+        xml
+    }
+
+    fn render_rss(&self) -> String {
+        let mut xml = String::new();
+
+        let _ = write!(
+            xml,
+            r#"<?xml version="1.0" encoding="utf-8"?><rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel><title>{}</title><link>{}</link><description>{}</description><atom:link rel="self" type="application/rss+xml" href="{}"/>"#,
+            escape_html(&self.title),
+            escape_html(&self.home_page_url),
+            escape_html(&self.title),
+            escape_html(&self.feed_url)
+        );
+        if let Some(updated) = self.updated_at() {
+            let _ = write!(
+                xml,
+                "<lastBuildDate>{}</lastBuildDate>",
+                updated.to_rfc2822()
+            );
+        }
+        for item in &self.items {
+            let description = match item.description {
+                Some(ref description) => format!("{}\n\n{}", item.summary(), description),
+                None => item.summary(),
+            };
+            let _ = write!(
+                xml,
+                r#"<item><title>{}</title><link>{}</link><guid isPermaLink="false">urn:uuid:{}</guid><pubDate>{}</pubDate><dc:creator>{}</dc:creator><description>{}</description></item>"#,
+                escape_html(&item.name),
+                escape_html(&item.url),
+                item.id,
+                item.created_at.to_rfc2822(),
+                escape_html(&item.username),
+                escape_html(&description)
+            );
+        }
+        xml.push_str("</channel></rss>");
+        xml
+    }
+
+    fn render_json(&self) -> String {
+        let feed = JsonFeed {
+            version: "https://jsonfeed.org/version/1.1",
+            title: &self.title,
+            home_page_url: &self.home_page_url,
+            feed_url: &self.feed_url,
+            items: self
+                .items
+                .iter()
+                .map(|item| JsonFeedItem {
+                    id: item.id.to_string(),
+                    url: &item.url,
+                    title: &item.name,
+                    summary: item.summary(),
+                    content_text: item.description.clone().unwrap_or_else(|| item.summary()),
+                    date_published: rfc3339(item.created_at),
+                    date_modified: rfc3339(item.updated_at),
+                    authors: vec![JsonFeedAuthor {
+                        name: &item.username,
+                    }],
+                    pattern: JsonFeedPattern {
+                        title: item.title.as_deref(),
+                        author: item.author.as_deref(),
+                    },
+                })
+                .collect(),
+        };
+        serde_json::to_string(&feed).expect("A JSON Feed can always be serialized")
+    }
+}
+
+fn rfc3339(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: &'a str,
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: String,
+    url: &'a str,
+    title: &'a str,
+    summary: String,
+    content_text: String,
+    date_published: String,
+    date_modified: String,
+    authors: Vec<JsonFeedAuthor<'a>>,
+    /// JSON Feed extensions start with an underscore.
+    #[serde(rename = "_acidarchive")]
+    pattern: JsonFeedPattern<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor<'a> {
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonFeedPattern<'a> {
+    title: Option<&'a str>,
+    author: Option<&'a str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feeds::{Feed, FeedFormat, FeedItem};
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn feed(items: Vec<FeedItem>) -> Feed {
+        Feed {
+            title: "acidarchive: new patterns".to_string(),
+            home_page_url: "https://api.example.com".to_string(),
+            feed_url: "https://api.example.com/v1/feeds/patterns.atom".to_string(),
+            items,
+        }
+    }
+
+    fn item(name: &str, day: u32) -> FeedItem {
+        FeedItem {
+            id: Uuid::new_v4(),
+            url: "https://api.example.com/patterns/tb303/1".to_string(),
+            name: name.to_string(),
+            title: Some("Acid Trax".to_string()),
+            author: Some("Phuture".to_string()),
+            username: "acid".to_string(),
+            description: None,
+            created_at: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2024, 2, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn formats_are_parsed_from_extensions() {
+        assert_eq!(FeedFormat::parse("atom"), Some(FeedFormat::Atom));
+        assert_eq!(FeedFormat::parse("rss"), Some(FeedFormat::Rss));
+        assert_eq!(FeedFormat::parse("json"), Some(FeedFormat::Json));
+        assert_eq!(FeedFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn feeds_are_updated_with_their_latest_item() {
+        let feed = feed(vec![item("a", 3), item("b", 9), item("c", 5)]);
+
+        assert_eq!(
+            feed.updated_at(),
+            Some(Utc.with_ymd_and_hms(2024, 2, 9, 12, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn atom_entries_carry_the_pattern() {
+        let atom = feed(vec![item("First pattern", 1)]).render(FeedFormat::Atom);
+
+        assert!(atom.contains("<title>First pattern</title>"));
+        assert!(atom.contains("<published>2024-01-01T12:00:00Z</published>"));
+        assert!(atom.contains("<author><name>acid</name></author>"));
+        assert!(atom.contains("<summary>Acid Trax by Phuture, shared by acid</summary>"));
+    }
+
+    #[test]
+    fn rss_items_use_rfc_2822_dates() {
+        let rss = feed(vec![item("First pattern", 1)]).render(FeedFormat::Rss);
+
+        assert!(rss.contains("<pubDate>Mon, 1 Jan 2024 12:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<dc:creator>acid</dc:creator>"));
+    }
+
+    #[test]
+    fn json_feed_items_carry_the_pattern() {
+        let json = feed(vec![item("First pattern", 1)]).render(FeedFormat::Json);
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        let item = &json["items"][0];
+        assert_eq!(item["title"], "First pattern");
+        assert_eq!(item["date_published"], "2024-01-01T12:00:00Z");
+        assert_eq!(item["authors"][0]["name"], "acid");
+        assert_eq!(item["_acidarchive"]["author"], "Phuture");
+    }
+
+    #[test]
+    fn markup_in_patterns_is_escaped() {
+        let mut item = item("<b>bold</b>", 1);
+        item.description = Some("a & b".to_string());
+
+        for format in [FeedFormat::Atom, FeedFormat::Rss] {
+            let xml = feed(vec![item.clone()]).render(format);
+
+            assert!(!xml.contains("<b>"));
+            assert!(xml.contains("&lt;b&gt;bold&lt;/b&gt;"));
+            assert!(xml.contains("a &amp; b"));
+        }
+    }
+}
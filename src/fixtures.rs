@@ -0,0 +1,328 @@
+use crate::api::models::tb303::CreateTB303Pattern;
+use crate::domain::{NewTB303Pattern, Username};
+use crate::routes::patterns::{
+    fetch_stored_pattern, insert_bars_tb303, insert_pattern_with_metadata, replace_pattern_content,
+};
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use anyhow::Context;
+use chrono::Utc;
+use config::{Config, File, FileFormat};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum FixtureError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for FixtureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// The canonical content of the archive, as kept in `fixtures/archive.yaml`.
+#[derive(Deserialize)]
+pub struct Fixtures {
+    #[serde(default)]
+    pub users: Vec<UserFixture>,
+    #[serde(default)]
+    pub patterns: Vec<PatternFixture>,
+}
+
+#[derive(Deserialize)]
+pub struct UserFixture {
+    pub id: Uuid,
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+pub struct PatternFixture {
+    /// Stable across environments; a pattern is updated in place when its
+    /// fixture changes.
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(flatten)]
+    pub pattern: CreateTB303Pattern,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct FixtureReport {
+    pub users_created: usize,
+    pub patterns_created: usize,
+    pub patterns_updated: usize,
+    pub patterns_unchanged: usize,
+}
+
+impl Fixtures {
+    pub fn from_file(path: &str) -> Result<Self, FixtureError> {
+        Self::build(File::new(path, FileFormat::Yaml))
+    }
+
+    pub fn parse(yaml: &str) -> Result<Self, FixtureError> {
+        Self::build(File::from_str(yaml, FileFormat::Yaml))
+    }
+
+    fn build<S>(source: S) -> Result<Self, FixtureError>
+    where
+        S: config::Source + Send + Sync + 'static,
+    {
+        Config::builder()
+            .add_source(source)
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .map_err(|e| FixtureError::ValidationError(format!("Invalid fixtures: {e}")))
+    }
+}
+
+/// Checks every fixture before anything is written, so a broken file never
+/// leaves the archive half loaded.
+fn validate(fixtures: Fixtures) -> Result<Vec<(Uuid, Uuid, NewTB303Pattern)>, FixtureError> {
+    let mut user_ids = HashSet::new();
+    for user in &fixtures.users {
+        Username::parse(user.username.clone()).map_err(FixtureError::ValidationError)?;
+        if !user_ids.insert(user.id) {
+            return Err(FixtureError::ValidationError(format!(
+                "User {} appears more than once",
+                user.id
+            )));
+        }
+    }
+
+    let mut pattern_ids = HashSet::new();
+    let mut patterns = Vec::with_capacity(fixtures.patterns.len());
+    for fixture in fixtures.patterns {
+        if !pattern_ids.insert(fixture.id) {
+            return Err(FixtureError::ValidationError(format!(
+                "Pattern {} appears more than once",
+                fixture.id
+            )));
+        }
+        if !user_ids.contains(&fixture.user_id) {
+            return Err(FixtureError::ValidationError(format!(
+                "Pattern {} belongs to {}, which is not among the fixture users",
+                fixture.id, fixture.user_id
+            )));
+        }
+        let new_pattern: NewTB303Pattern = fixture
+            .pattern
+            .try_into()
+            .map_err(|e| FixtureError::ValidationError(format!("Pattern {}: {e}", fixture.id)))?;
+        patterns.push((fixture.id, fixture.user_id, new_pattern));
+    }
+
+    Ok(patterns)
+}
+
+/// Creates missing fixtures and brings changed patterns in line with their
+/// fixture. Users are only created; their profile is theirs to edit. Loading
+/// is not an edit by anyone, so no webhook events are sent.
+#[tracing::instrument(name = "Loading fixtures", skip(pool, storage, fixtures))]
+pub async fn load_fixtures(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    fixtures: Fixtures,
+) -> Result<FixtureReport, FixtureError> {
+    let users: Vec<(Uuid, String)> = fixtures
+        .users
+        .iter()
+        .map(|user| (user.id, user.username.clone()))
+        .collect();
+    let patterns = validate(fixtures)?;
+    let mut report = FixtureReport::default();
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to start a transaction to load fixtures.")?;
+
+    // Instances starting together would otherwise insert the same patterns.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('fixtures'))")
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to lock fixtures.")?;
+
+    for (user_id, username) in users {
+        report.users_created += sqlx::query!(
+            r#"
+            INSERT INTO users (user_id, username) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO NOTHING
+            "#,
+            user_id,
+            username
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to insert fixture user.")?
+        .rows_affected() as usize;
+    }
+
+    for (pattern_id, user_id, new_pattern) in patterns {
+        let owner_id = sqlx::query_scalar!(
+            r#"SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE"#,
+            pattern_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .context("Failed to fetch fixture pattern.")?;
+
+        let Some(owner_id) = owner_id else {
+            let now = Utc::now();
+            insert_pattern_with_metadata(
+                &mut transaction,
+                pattern_id,
+                &new_pattern,
+                &user_id.into(),
+                now,
+                now,
+            )
+            .await
+            .context("Failed to insert fixture pattern.")?;
+            insert_bars_tb303(&mut transaction, pattern_id, &new_pattern.bars)
+                .await
+                .context("Failed to insert fixture bars.")?;
+            report.patterns_created += 1;
+            continue;
+        };
+
+        let is_public = new_pattern.is_public.unwrap_or(false);
+        let stored = fetch_stored_pattern(pool, storage, pattern_id, owner_id.into()).await?;
+        let is_unchanged = owner_id == user_id
+            && stored.is_some_and(|stored| {
+                stored.fingerprint() == new_pattern.fingerprint()
+                    && stored.is_public.unwrap_or(false) == is_public
+            });
+        if is_unchanged {
+            report.patterns_unchanged += 1;
+            continue;
+        }
+
+        replace_pattern_content(&mut transaction, pattern_id, &new_pattern, is_public).await?;
+        if owner_id != user_id {
+            sqlx::query!(
+                r#"UPDATE patterns_tb303 SET user_id = $2 WHERE pattern_id = $1"#,
+                pattern_id,
+                user_id
+            )
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to update the owner of a fixture pattern.")?;
+        }
+        report.patterns_updated += 1;
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit fixtures.")?;
+
+    Ok(report)
+}
+
+pub async fn load_fixture_file(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    path: &str,
+) -> Result<FixtureReport, FixtureError> {
+    load_fixtures(pool, storage, Fixtures::from_file(path)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::{validate, FixtureError, Fixtures};
+    use claims::assert_ok;
+
+    const USER_ID: &str = "26f29224-6001-702f-25dc-6d5c1b750f51";
+
+    fn fixtures(pattern: &str) -> String {
+        format!(
+            r#"
+users:
+  - id: {USER_ID}
+    username: acid
+patterns:
+  - id: a8fcebd7-10cf-4bfb-a828-bbd27109e15e
+    user_id: {USER_ID}
+{pattern}
+"#
+        )
+    }
+
+    #[test]
+    fn the_archive_fixtures_are_valid() {
+        let fixtures = assert_ok!(Fixtures::from_file("fixtures/archive.yaml"));
+        let patterns = assert_ok!(validate(fixtures));
+        assert!(!patterns.is_empty());
+    }
+
+    #[test]
+    fn a_pattern_fixture_is_parsed_into_a_pattern() {
+        let yaml = fixtures(
+            r#"    name: "Where's your child?"
+    waveform: square
+    bars:
+      - number: 1
+        steps:
+          - { number: 1, note: "F#", transpose: up, time: note, slide: true }
+          - { number: 2, time: rest }"#,
+        );
+
+        let patterns = assert_ok!(validate(assert_ok!(Fixtures::parse(&yaml))));
+
+        let (_, _, pattern) = &patterns[0];
+        assert_eq!(pattern.name.as_ref(), "Where's your child?");
+        assert_eq!(pattern.bars[0].steps.len(), 2);
+        assert_eq!(pattern.bars[0].steps[0].slide, Some(true));
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let yaml = fixtures(
+            r#"    name: "Too fast"
+    tempo: 1000
+    bars: []"#,
+        );
+
+        let result = validate(assert_ok!(Fixtures::parse(&yaml)));
+
+        assert!(matches!(result, Err(FixtureError::ValidationError(_))));
+    }
+
+    #[test]
+    fn patterns_of_unknown_users_are_rejected() {
+        let yaml = fixtures(
+            r#"    name: "Orphan"
+    bars: []"#,
+        )
+        .replacen(
+            &format!("user_id: {USER_ID}"),
+            "user_id: 00000000-0000-0000-0000-000000000001",
+            1,
+        );
+
+        let result = validate(assert_ok!(Fixtures::parse(&yaml)));
+
+        assert!(matches!(result, Err(FixtureError::ValidationError(_))));
+    }
+
+    #[test]
+    fn duplicate_pattern_ids_are_rejected() {
+        let pattern = r#"    name: "Twice"
+    bars: []"#;
+        let yaml = fixtures(pattern)
+            + "  - id: a8fcebd7-10cf-4bfb-a828-bbd27109e15e\n"
+            + &format!("    user_id: {USER_ID}\n")
+            + pattern
+            + "\n";
+
+        let result = validate(assert_ok!(Fixtures::parse(&yaml)));
+
+        assert!(matches!(result, Err(FixtureError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::http::header::{
+    CacheControl, ETag, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, SubsecRound, Utc};
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+/// Sends `body`, or `304 Not Modified` when the client's copy is current.
+/// The `ETag` is a hash of the body, so anything that changes the body
+/// changes it; `Last-Modified` only moves with `last_modified`. The tag is
+/// weak because the body may be sent compressed.
+pub fn conditional_response(
+    req: &HttpRequest,
+    body: impl Into<Vec<u8>>,
+    content_type: &str,
+    last_modified: Option<DateTime<Utc>>,
+    cache_control: CacheControl,
+) -> HttpResponse {
+    let body = body.into();
+    let etag = EntityTag::new_weak(hex::encode(Sha256::digest(&body)));
+    // HTTP dates have no fractional seconds to compare with.
+    let last_modified = last_modified
+        .map(|last_modified| HttpDate::from(SystemTime::from(last_modified.trunc_subsecs(0))));
+
+    // If-None-Match wins over If-Modified-Since when both are sent.
+    let not_modified = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => match (req.get_header::<IfModifiedSince>(), last_modified) {
+            (Some(IfModifiedSince(since)), Some(last_modified)) => last_modified <= since,
+            _ => false,
+        },
+    };
+
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header(ETag(etag))
+        .insert_header(cache_control);
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(last_modified));
+    }
+
+    if not_modified {
+        response.finish()
+    } else {
+        response.content_type(content_type).body(body)
+    }
+}
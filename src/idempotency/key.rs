@@ -0,0 +1,56 @@
+const MAX_LENGTH: usize = 255;
+
+/// Chosen by the client, usually a UUID, and unique among its requests.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn parse(s: String) -> Result<IdempotencyKey, String> {
+        let has_valid_length = (1..=MAX_LENGTH).contains(&s.len());
+        let has_valid_characters = s.chars().all(|c| c.is_ascii_graphic());
+
+        if has_valid_length && has_valid_characters {
+            Ok(Self(s))
+        } else {
+            Err(format!(
+                "The Idempotency-Key header must be 1 to {MAX_LENGTH} printable ASCII \
+                 characters without spaces."
+            ))
+        }
+    }
+}
+
+impl AsRef<str> for IdempotencyKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::idempotency::IdempotencyKey;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_uuid_is_a_valid_key() {
+        assert_ok!(IdempotencyKey::parse(uuid::Uuid::new_v4().to_string()));
+    }
+
+    #[test]
+    fn empty_keys_are_rejected() {
+        assert_err!(IdempotencyKey::parse(String::new()));
+    }
+
+    #[test]
+    fn a_256_character_key_is_rejected() {
+        assert_ok!(IdempotencyKey::parse("a".repeat(255)));
+        assert_err!(IdempotencyKey::parse("a".repeat(256)));
+    }
+
+    #[test]
+    fn keys_with_spaces_or_non_ascii_characters_are_rejected() {
+        for key in ["retry 1", "clé", "tab\tkey"] {
+            assert_err!(IdempotencyKey::parse(key.to_string()));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::authentication::UserId;
+use crate::idempotency::{
+    release_key, save_response, try_processing, IdempotencyKey, NextAction, SavedResponse,
+};
+use crate::problem::{Problem, ProblemCode};
+use crate::utils::e500;
+use actix_web::{
+    body::{to_bytes, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderName, CONTENT_TYPE},
+        StatusCode,
+    },
+    middleware::Next,
+    web, HttpMessage, HttpResponse,
+};
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// How large a body is held in memory to be hashed. Set it to the JSON limit
+/// of the routes behind the middleware, so bodies they would refuse are
+/// refused before being read in full.
+#[derive(Clone, Copy, Debug)]
+pub struct IdempotentBodyLimit(pub usize);
+
+/// Used when no `IdempotentBodyLimit` is set; the JSON extractor's default.
+const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Answers retries of a request sent with an `Idempotency-Key` with the
+/// response to the first one, for a day. Requests without the header are
+/// handled as usual. Server errors are not saved, so a retry gets another go.
+/// Nothing is saved either when the client goes away mid-request; the key is
+/// then claimed again by a retry once its lease runs out.
+pub async fn idempotent(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(header) = req.headers().get(IDEMPOTENCY_KEY).cloned() else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_boxed_body);
+    };
+    let key = match header
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|key| IdempotencyKey::parse(key.to_owned()))
+    {
+        Ok(key) => key,
+        Err(e) => {
+            return Ok(reject(
+                req,
+                StatusCode::BAD_REQUEST,
+                ProblemCode::InvalidIdempotencyKey,
+                e,
+            ))
+        }
+    };
+    // Only mounted behind authentication, but a missing user must not make
+    // every caller share keys.
+    let Some(user_id) = req.extensions().get::<UserId>().copied() else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_boxed_body);
+    };
+    let pool = req
+        .app_data::<web::Data<PgPool>>()
+        .cloned()
+        .ok_or_else(|| e500("The database pool is missing from the app data"))?;
+
+    let limit = req
+        .app_data::<IdempotentBodyLimit>()
+        .map_or(DEFAULT_BODY_LIMIT, |limit| limit.0);
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > limit {
+            return Ok(reject(
+                req,
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ProblemCode::PayloadTooLarge,
+                "The request body is too large",
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+    let request_hash = Sha256::new()
+        .chain_update(req.method().as_str())
+        .chain_update(b" ")
+        .chain_update(
+            req.uri()
+                .path_and_query()
+                .map_or(req.path(), |path| path.as_str()),
+        )
+        .chain_update(b"\n")
+        .chain_update(&body)
+        .finalize();
+    req.set_payload(Payload::from(body));
+
+    let claimed_at = match try_processing(&pool, *user_id, &key, &request_hash)
+        .await
+        .map_err(e500)?
+    {
+        NextAction::StartProcessing { claimed_at } => claimed_at,
+        NextAction::ReturnSavedResponse(saved) => {
+            return Ok(req.into_response(replay(saved)));
+        }
+        NextAction::InProgress => {
+            return Ok(reject(
+                req,
+                StatusCode::CONFLICT,
+                ProblemCode::IdempotencyKeyInUse,
+                "A request with this Idempotency-Key is still being processed",
+            ));
+        }
+        NextAction::Mismatch => {
+            return Ok(reject(
+                req,
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ProblemCode::IdempotencyKeyReused,
+                "This Idempotency-Key was already used for a different request",
+            ));
+        }
+    };
+
+    let response = match next.call(req).await {
+        Ok(response) if !response.status().is_server_error() => response,
+        outcome => {
+            if let Err(e) = release_key(&pool, *user_id, &key, claimed_at).await {
+                tracing::error!(error.cause_chain = ?e, "Failed to release the idempotency key");
+            }
+            return outcome.map(ServiceResponse::map_into_boxed_body);
+        }
+    };
+
+    let (request, response) = response.into_parts();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let status_code = response.status().as_u16();
+    let (response, body) = response.into_parts();
+    let body = to_bytes(body).await.map_err(|e| e500(e.into()))?;
+
+    let saved = SavedResponse {
+        status_code,
+        content_type,
+        body: body.to_vec(),
+    };
+    // The request went through, so its response is sent even if it cannot
+    // be saved. A retry then waits for the key to expire.
+    if let Err(e) = save_response(&pool, *user_id, &key, claimed_at, &saved).await {
+        tracing::error!(error.cause_chain = ?e, "Failed to save the response for replay");
+    }
+
+    let response = response.set_body(body).map_into_boxed_body();
+    Ok(ServiceResponse::new(request, response))
+}
+
+fn replay(saved: SavedResponse) -> HttpResponse {
+    let status = StatusCode::from_u16(saved.status_code).unwrap_or(StatusCode::OK);
+    let mut response = HttpResponse::build(status);
+    if let Some(content_type) = saved.content_type {
+        response.insert_header((CONTENT_TYPE, content_type));
+    }
+    response
+        .insert_header((IDEMPOTENT_REPLAYED, "true"))
+        .body(saved.body)
+}
+
+fn reject(
+    req: ServiceRequest,
+    status: StatusCode,
+    code: ProblemCode,
+    detail: impl Into<String>,
+) -> ServiceResponse<BoxBody> {
+    req.into_response(HttpResponse::from(Problem::new(status, code, detail)))
+}
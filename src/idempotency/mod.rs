@@ -0,0 +1,7 @@
+mod key;
+mod middleware;
+mod persistence;
+
+pub use key::IdempotencyKey;
+pub use middleware::{idempotent, IdempotentBodyLimit};
+pub use persistence::*;
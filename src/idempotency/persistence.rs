@@ -0,0 +1,181 @@
+use crate::idempotency::IdempotencyKey;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How long a response is replayed for. The key can be used again after.
+pub const IDEMPOTENCY_TTL_SECS: f64 = 24.0 * 60.0 * 60.0;
+
+/// How long the first request with a key has to answer. A key still without
+/// a response after that was abandoned, e.g. by a client that disconnected
+/// mid-request, and can be claimed again. Longer than any write takes.
+pub const IDEMPOTENCY_LEASE_SECS: f64 = 60.0;
+
+/// A response saved for replay.
+#[derive(Debug, Clone)]
+pub struct SavedResponse {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum NextAction {
+    /// The key is new: handle the request, then save the response. The claim
+    /// is told apart from later ones by when it was made.
+    StartProcessing { claimed_at: DateTime<Utc> },
+    /// The request was handled before: send the same response again.
+    ReturnSavedResponse(SavedResponse),
+    /// The first request with the key has not been answered yet.
+    InProgress,
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+/// Claims `key` for the request hashed to `request_hash`, or tells what
+/// became of the request that claimed it first.
+#[tracing::instrument(name = "Claiming idempotency key", skip(pool, request_hash))]
+pub async fn try_processing(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &IdempotencyKey,
+    request_hash: &[u8],
+) -> Result<NextAction, anyhow::Error> {
+    let saved = loop {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency
+            WHERE user_id = $1
+              AND idempotency_key = $2
+              AND (
+                created_at < NOW() - make_interval(secs => $3)
+                OR (response_status_code IS NULL AND created_at < NOW() - make_interval(secs => $4))
+              )
+            "#,
+            user_id,
+            key.as_ref(),
+            IDEMPOTENCY_TTL_SECS,
+            IDEMPOTENCY_LEASE_SECS
+        )
+        .execute(pool)
+        .await
+        .context("Failed to release an expired idempotency key.")?;
+
+        let claimed_at = sqlx::query_scalar!(
+            r#"
+            INSERT INTO idempotency (user_id, idempotency_key, request_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            RETURNING created_at
+            "#,
+            user_id,
+            key.as_ref(),
+            request_hash
+        )
+        .fetch_optional(pool)
+        .await
+        .context("Failed to claim the idempotency key.")?;
+        if let Some(claimed_at) = claimed_at {
+            return Ok(NextAction::StartProcessing { claimed_at });
+        }
+
+        // The key may have been released since the insert; claim it then.
+        let saved = sqlx::query!(
+            r#"
+            SELECT request_hash, response_status_code, response_content_type, response_body
+            FROM idempotency
+            WHERE user_id = $1 AND idempotency_key = $2
+            "#,
+            user_id,
+            key.as_ref()
+        )
+        .fetch_optional(pool)
+        .await
+        .context("Failed to fetch the saved response.")?;
+        if let Some(saved) = saved {
+            break saved;
+        }
+    };
+
+    if saved.request_hash != request_hash {
+        return Ok(NextAction::Mismatch);
+    }
+    match (saved.response_status_code, saved.response_body) {
+        (Some(status_code), Some(body)) => Ok(NextAction::ReturnSavedResponse(SavedResponse {
+            status_code: u16::try_from(status_code).context("Invalid saved status code.")?,
+            content_type: saved.response_content_type,
+            body,
+        })),
+        _ => Ok(NextAction::InProgress),
+    }
+}
+
+/// Saves the response to the request that claimed `key` at `claimed_at`,
+/// unless its claim was given up and taken by a retry.
+#[tracing::instrument(name = "Saving response for replay", skip(pool, response))]
+pub async fn save_response(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &IdempotencyKey,
+    claimed_at: DateTime<Utc>,
+    response: &SavedResponse,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE idempotency
+        SET response_status_code = $4, response_content_type = $5, response_body = $6
+        WHERE user_id = $1 AND idempotency_key = $2 AND created_at = $3
+        "#,
+        user_id,
+        key.as_ref(),
+        claimed_at,
+        response.status_code as i16,
+        response.content_type,
+        response.body
+    )
+    .execute(pool)
+    .await
+    .context("Failed to save the response.")?;
+
+    Ok(())
+}
+
+/// Frees `key` without saving a response, so a retry is handled afresh.
+#[tracing::instrument(name = "Releasing idempotency key", skip(pool))]
+pub async fn release_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &IdempotencyKey,
+    claimed_at: DateTime<Utc>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM idempotency
+        WHERE user_id = $1 AND idempotency_key = $2 AND created_at = $3
+        "#,
+        user_id,
+        key.as_ref(),
+        claimed_at
+    )
+    .execute(pool)
+    .await
+    .context("Failed to release the idempotency key.")?;
+
+    Ok(())
+}
+
+/// Deletes the saved responses that are no longer replayed. Returns how many
+/// were deleted.
+#[tracing::instrument(name = "Pruning idempotency keys", skip(pool))]
+pub async fn prune_idempotency_keys(pool: &PgPool) -> Result<u64, anyhow::Error> {
+    let result = sqlx::query!(
+        r#"DELETE FROM idempotency WHERE created_at < NOW() - make_interval(secs => $1)"#,
+        IDEMPOTENCY_TTL_SECS
+    )
+    .execute(pool)
+    .await
+    .context("Failed to prune idempotency keys.")?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,296 @@
+use crate::api::models::uploads::ImageVariant;
+use crate::domain::UploadType;
+use crate::storage::ObjectStorage;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader, Limits};
+use std::io::Cursor;
+
+/// Largest accepted width or height of an uploaded image.
+pub const MAX_DIMENSION: u32 = 8192;
+/// Longest edge of the sanitized image stored under the original key.
+pub const MAX_ORIGINAL_DIMENSION: u32 = 2048;
+const MAX_DECODE_ALLOC: u64 = 512 * 1024 * 1024;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ImageProcessingError {
+    #[error("File is not a supported image")]
+    UnsupportedFormat,
+    #[error("File content is {actual}, but it was declared as {declared}")]
+    ContentTypeMismatch { declared: String, actual: String },
+    #[error("Image is {width}x{height} pixels, the maximum is {MAX_DIMENSION}x{MAX_DIMENSION}")]
+    TooLarge { width: u32, height: u32 },
+    #[error("Image has no pixels")]
+    Empty,
+    #[error("Image could not be decoded: {0}")]
+    Decode(String),
+    #[error("Image could not be encoded: {0}")]
+    Encode(String),
+}
+
+#[derive(Debug)]
+pub struct ProcessedImage {
+    /// Re-encoded full image without any of the uploaded metadata.
+    pub original: Vec<u8>,
+    pub variants: Vec<(u32, Vec<u8>)>,
+}
+
+pub fn variant_key(key: &str, size: u32) -> String {
+    format!("{key}-{size}.webp")
+}
+
+/// Where the sanitized full image of a processed upload is kept. The upload
+/// itself stays as it was sent until it is claimed, so a claim that fails can
+/// be retried against it.
+pub fn sanitized_key(key: &str) -> String {
+    format!("{key}-original.webp")
+}
+
+/// The URL of the full image for an upload. Processed uploads, the ones with
+/// variants, are served from their sanitized copy.
+pub fn image_url(storage: &dyn ObjectStorage, key: &str, sizes: &[i32]) -> String {
+    if sizes.is_empty() {
+        storage.get_public_url(key)
+    } else {
+        storage.get_public_url(&sanitized_key(key))
+    }
+}
+
+pub fn variant_urls(storage: &dyn ObjectStorage, key: &str, sizes: &[i32]) -> Vec<ImageVariant> {
+    sizes
+        .iter()
+        .map(|size| ImageVariant {
+            size: *size,
+            url: storage.get_public_url(&variant_key(key, *size as u32)),
+        })
+        .collect()
+}
+
+/// Decodes an uploaded image, checks its magic bytes against the declared
+/// content type and its dimensions against [`MAX_DIMENSION`], and re-encodes
+/// it as WebP. Re-encoding drops EXIF, GPS and any other metadata; the EXIF
+/// orientation is applied to the pixels first. Animated GIFs keep only their
+/// first frame. Uploads that are not images are refused.
+pub fn process_image(
+    bytes: &[u8],
+    declared_content_type: &str,
+    upload_type: UploadType,
+) -> Result<ProcessedImage, ImageProcessingError> {
+    if matches!(upload_type, UploadType::PatternAudio) {
+        return Err(ImageProcessingError::UnsupportedFormat);
+    }
+    let format = image::guess_format(bytes).map_err(|_| ImageProcessingError::UnsupportedFormat)?;
+    let actual = format.to_mime_type();
+    if actual != declared_content_type {
+        return Err(ImageProcessingError::ContentTypeMismatch {
+            declared: declared_content_type.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(|e| decode_error(e, bytes))?;
+    let (width, height) = decoder.dimensions();
+    if width == 0 || height == 0 {
+        return Err(ImageProcessingError::Empty);
+    }
+    let orientation = decoder
+        .orientation()
+        .map_err(|e| ImageProcessingError::Decode(e.to_string()))?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| decode_error(e, bytes))?;
+    image.apply_orientation(orientation);
+
+    let original = if image.width().max(image.height()) > MAX_ORIGINAL_DIMENSION {
+        image.resize(
+            MAX_ORIGINAL_DIMENSION,
+            MAX_ORIGINAL_DIMENSION,
+            FilterType::CatmullRom,
+        )
+    } else {
+        image.clone()
+    };
+
+    let variants = upload_type
+        .variant_sizes()
+        .iter()
+        .map(|size| {
+            let variant = resize_variant(&image, upload_type, *size);
+            encode_webp(&variant).map(|bytes| (*size, bytes))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProcessedImage {
+        original: encode_webp(&original)?,
+        variants,
+    })
+}
+
+fn decode_error(e: image::ImageError, bytes: &[u8]) -> ImageProcessingError {
+    match e {
+        // The limits trip while the header is read, so read it again
+        // without them to report the dimensions.
+        image::ImageError::Limits(_) => {
+            let (width, height) = header_dimensions(bytes);
+            ImageProcessingError::TooLarge { width, height }
+        }
+        e => ImageProcessingError::Decode(e.to_string()),
+    }
+}
+
+fn header_dimensions(bytes: &[u8]) -> (u32, u32) {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .unwrap_or((0, 0))
+}
+
+/// Avatars and covers are cropped to a square, banners keep their aspect
+/// ratio. Images are never scaled up.
+fn resize_variant(image: &DynamicImage, upload_type: UploadType, size: u32) -> DynamicImage {
+    match upload_type {
+        UploadType::Avatar | UploadType::PatternCover => {
+            let edge = size.min(image.width()).min(image.height());
+            image.resize_to_fill(edge, edge, FilterType::CatmullRom)
+        }
+        UploadType::Banner => {
+            let width = size.min(image.width());
+            image.resize(width, u32::MAX, FilterType::CatmullRom)
+        }
+        UploadType::PatternAudio => unreachable!("process_image refuses audio uploads"),
+    }
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>, ImageProcessingError> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image.to_rgba8())
+        .write_with_encoder(WebPEncoder::new_lossless(&mut bytes))
+        .map_err(|e| ImageProcessingError::Encode(e.to_string()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::UploadType;
+    use crate::image_processing::{
+        process_image, sanitized_key, variant_key, ImageProcessingError,
+    };
+    use claims::{assert_err, assert_ok};
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn encoded(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    fn dimensions(bytes: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory_with_format(bytes, ImageFormat::WebP).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn avatars_get_square_webp_variants_without_upscaling() {
+        let bytes = encoded(400, 300, ImageFormat::Png);
+
+        let processed = assert_ok!(process_image(&bytes, "image/png", UploadType::Avatar));
+
+        let sizes: Vec<(u32, (u32, u32))> = processed
+            .variants
+            .iter()
+            .map(|(size, bytes)| (*size, dimensions(bytes)))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![(64, (64, 64)), (256, (256, 256)), (1024, (300, 300))]
+        );
+        assert_eq!(dimensions(&processed.original), (400, 300));
+    }
+
+    #[test]
+    fn banners_keep_their_aspect_ratio() {
+        let bytes = encoded(1600, 400, ImageFormat::Jpeg);
+
+        let processed = assert_ok!(process_image(&bytes, "image/jpeg", UploadType::Banner));
+
+        assert_eq!(dimensions(&processed.variants[0].1), (640, 160));
+        assert_eq!(dimensions(&processed.variants[2].1), (1600, 400));
+    }
+
+    #[test]
+    fn covers_are_cropped_to_a_square() {
+        let bytes = encoded(800, 600, ImageFormat::Png);
+
+        let processed = assert_ok!(process_image(&bytes, "image/png", UploadType::PatternCover));
+
+        assert_eq!(dimensions(&processed.variants[0].1), (256, 256));
+        assert_eq!(dimensions(&processed.variants[2].1), (600, 600));
+    }
+
+    #[test]
+    fn a_spoofed_content_type_is_rejected() {
+        let bytes = encoded(32, 32, ImageFormat::Gif);
+
+        let result = process_image(&bytes, "image/png", UploadType::Avatar);
+
+        assert_eq!(
+            assert_err!(result),
+            ImageProcessingError::ContentTypeMismatch {
+                declared: "image/png".to_string(),
+                actual: "image/gif".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn a_non_image_is_rejected() {
+        let result = process_image(b"MZ\x90\x00not an image", "image/png", UploadType::Avatar);
+
+        assert_eq!(assert_err!(result), ImageProcessingError::UnsupportedFormat);
+    }
+
+    #[test]
+    fn audio_uploads_are_never_processed_as_images() {
+        let bytes = encoded(32, 32, ImageFormat::Png);
+
+        let result = process_image(&bytes, "image/png", UploadType::PatternAudio);
+
+        assert_eq!(assert_err!(result), ImageProcessingError::UnsupportedFormat);
+    }
+
+    #[test]
+    fn oversized_images_are_rejected() {
+        let bytes = encoded(8193, 1, ImageFormat::Png);
+
+        let result = process_image(&bytes, "image/png", UploadType::Banner);
+
+        assert_eq!(
+            assert_err!(result),
+            ImageProcessingError::TooLarge {
+                width: 8193,
+                height: 1
+            }
+        );
+    }
+
+    #[test]
+    fn variant_keys_share_the_upload_prefix() {
+        assert_eq!(
+            variant_key("avatars/user/upload", 64),
+            "avatars/user/upload-64.webp"
+        );
+        assert_eq!(
+            sanitized_key("avatars/user/upload"),
+            "avatars/user/upload-original.webp"
+        );
+    }
+}
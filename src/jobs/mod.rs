@@ -0,0 +1,207 @@
+mod schedule;
+mod worker;
+
+pub use schedule::*;
+pub use worker::*;
+
+use crate::configuration::UploadSettings;
+use crate::idempotency::prune_idempotency_keys;
+use crate::metrics::begin_transaction;
+use crate::rate_limiting::prune_rate_limit_windows;
+use crate::storage::ObjectStorage;
+use crate::upload_sweeper::sweep_orphaned_uploads;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Work that is deferred to the job workers. The payload is stored as JSON,
+/// so renaming a variant or its fields orphans jobs already in the queue.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    /// Deletes a released upload with its image variants, unless it was
+    /// claimed again in the meantime.
+    DeleteUpload { key: String },
+    /// Deletes uploads nobody claimed within the orphan TTL.
+    SweepOrphanedUploads,
+    /// Deletes rate limit windows that ran out.
+    PruneRateLimits,
+    /// Deletes saved responses that are no longer replayed.
+    PruneIdempotencyKeys,
+}
+
+impl Job {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Job::DeleteUpload { .. } => "delete_upload",
+            Job::SweepOrphanedUploads => "sweep_orphaned_uploads",
+            Job::PruneRateLimits => "prune_rate_limits",
+            Job::PruneIdempotencyKeys => "prune_idempotency_keys",
+        }
+    }
+
+    /// Attempts before the job is dead-lettered.
+    pub fn max_attempts(&self) -> i32 {
+        match self {
+            Job::DeleteUpload { .. } => 10,
+            // The next scheduled sweep picks up whatever this one missed.
+            Job::SweepOrphanedUploads | Job::PruneRateLimits | Job::PruneIdempotencyKeys => 3,
+        }
+    }
+
+    pub async fn run(self, context: &JobContext) -> Result<(), anyhow::Error> {
+        match self {
+            Job::DeleteUpload { key } => {
+                delete_released_upload(&context.pool, context.storage.as_ref(), &key).await
+            }
+            Job::SweepOrphanedUploads => {
+                let swept = sweep_orphaned_uploads(
+                    &context.pool,
+                    context.storage.as_ref(),
+                    context.uploads.orphan_ttl(),
+                )
+                .await?;
+                if swept > 0 {
+                    tracing::info!(swept, "Swept orphaned uploads");
+                }
+                Ok(())
+            }
+            Job::PruneRateLimits => {
+                prune_rate_limit_windows(&context.pool).await?;
+                Ok(())
+            }
+            Job::PruneIdempotencyKeys => {
+                prune_idempotency_keys(&context.pool).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// What jobs need to run.
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    pub storage: Arc<dyn ObjectStorage>,
+    pub uploads: UploadSettings,
+}
+
+/// Queues `job` to run as soon as a worker is free. Nothing runs before the
+/// transaction commits, and nothing runs at all if it rolls back.
+pub async fn enqueue_job(
+    transaction: &mut Transaction<'_, Postgres>,
+    job: &Job,
+) -> Result<Uuid, anyhow::Error> {
+    schedule_job(transaction, job, Utc::now()).await
+}
+
+/// Queues `job` to run once `run_at` has passed.
+#[tracing::instrument(name = "Queueing job", skip(transaction, job), fields(kind = job.kind()))]
+pub async fn schedule_job(
+    transaction: &mut Transaction<'_, Postgres>,
+    job: &Job,
+    run_at: DateTime<Utc>,
+) -> Result<Uuid, anyhow::Error> {
+    let job_id = Uuid::new_v4();
+    let payload = serde_json::to_value(job).context("Failed to encode the job.")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO jobs (job_id, kind, payload, max_attempts, run_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        job_id,
+        job.kind(),
+        payload,
+        job.max_attempts(),
+        run_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to queue job.")?;
+
+    Ok(job_id)
+}
+
+/// The row is locked first, so a profile update cannot claim the upload
+/// while its objects are being deleted.
+#[tracing::instrument(name = "Deleting released upload", skip(pool, storage))]
+async fn delete_released_upload(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    key: &str,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to start a transaction to delete an upload.")?;
+
+    let released = sqlx::query_scalar!(
+        r#"SELECT key FROM uploads WHERE key = $1 AND claimed_at IS NULL FOR UPDATE"#,
+        key
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch released upload.")?;
+    if released.is_none() {
+        return Ok(());
+    }
+
+    storage
+        .delete_prefix(key)
+        .await
+        .context("Failed to delete released upload.")?;
+
+    sqlx::query!(r#"DELETE FROM uploads WHERE key = $1"#, key)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to forget released upload.")?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the deleted upload.")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jobs::Job;
+    use serde_json::json;
+
+    #[test]
+    fn jobs_are_stored_with_their_kind() {
+        let job = Job::DeleteUpload {
+            key: "avatars/a/b".to_string(),
+        };
+
+        let payload = serde_json::to_value(&job).unwrap();
+
+        assert_eq!(
+            payload,
+            json!({ "kind": "delete_upload", "key": "avatars/a/b" })
+        );
+        assert_eq!(payload["kind"], job.kind());
+        assert_eq!(serde_json::from_value::<Job>(payload).unwrap(), job);
+    }
+
+    #[test]
+    fn unit_jobs_round_trip() {
+        let payload = serde_json::to_value(Job::SweepOrphanedUploads).unwrap();
+
+        assert_eq!(payload, json!({ "kind": "sweep_orphaned_uploads" }));
+        assert_eq!(
+            serde_json::from_value::<Job>(payload).unwrap(),
+            Job::SweepOrphanedUploads
+        );
+    }
+
+    #[test]
+    fn unknown_kinds_are_rejected() {
+        let payload = json!({ "kind": "send_newsletter" });
+
+        assert!(serde_json::from_value::<Job>(payload).is_err());
+    }
+}
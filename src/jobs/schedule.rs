@@ -0,0 +1,139 @@
+use crate::configuration::Settings;
+use crate::jobs::{enqueue_job, Job};
+use crate::metrics::begin_transaction;
+use anyhow::Context;
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// A job that is enqueued again every `every`, on boundaries counted from
+/// the Unix epoch. An hourly schedule runs on the hour, whichever instance
+/// gets there first.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub name: &'static str,
+    pub every: Duration,
+    pub job: Job,
+}
+
+impl Schedule {
+    /// The first boundary strictly after `now`.
+    pub fn next_run_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let every = self.every.as_secs().max(1) as i64;
+        let next = (now.timestamp().div_euclid(every) + 1) * every;
+        Utc.timestamp_opt(next, 0).single().unwrap_or(now)
+    }
+}
+
+/// Recurring jobs, run by every worker process.
+pub fn schedules(configuration: &Settings) -> Vec<Schedule> {
+    vec![
+        Schedule {
+            name: "sweep_orphaned_uploads",
+            every: configuration.uploads.sweep_interval(),
+            job: Job::SweepOrphanedUploads,
+        },
+        Schedule {
+            name: "prune_rate_limits",
+            every: Duration::from_secs(60 * 60),
+            job: Job::PruneRateLimits,
+        },
+        Schedule {
+            name: "prune_idempotency_keys",
+            every: Duration::from_secs(60 * 60),
+            job: Job::PruneIdempotencyKeys,
+        },
+    ]
+}
+
+/// Enqueues every schedule that is due and moves it to its next run.
+/// Returns the number of jobs enqueued.
+#[tracing::instrument(name = "Enqueueing scheduled jobs", skip_all)]
+pub async fn enqueue_due_schedules(
+    pool: &PgPool,
+    schedules: &[Schedule],
+) -> Result<usize, anyhow::Error> {
+    let mut enqueued = 0;
+
+    for schedule in schedules {
+        let now = Utc::now();
+        let mut transaction = begin_transaction(pool)
+            .await
+            .context("Failed to start a transaction for a schedule.")?;
+
+        // A schedule seen for the first time is due right away.
+        sqlx::query!(
+            r#"
+            INSERT INTO job_schedules (name, next_run_at) VALUES ($1, $2)
+            ON CONFLICT (name) DO NOTHING
+            "#,
+            schedule.name,
+            now
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to register schedule.")?;
+
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE job_schedules SET next_run_at = $2
+            WHERE name = $1 AND next_run_at <= $3
+            "#,
+            schedule.name,
+            schedule.next_run_after(now),
+            now
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to advance schedule.")?
+        .rows_affected()
+            > 0;
+
+        if claimed {
+            enqueue_job(&mut transaction, &schedule.job).await?;
+            enqueued += 1;
+        }
+
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit a schedule.")?;
+    }
+
+    Ok(enqueued)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jobs::{Job, Schedule};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn hourly() -> Schedule {
+        Schedule {
+            name: "test",
+            every: Duration::from_secs(60 * 60),
+            job: Job::SweepOrphanedUploads,
+        }
+    }
+
+    #[test]
+    fn schedules_run_on_interval_boundaries() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 12, 34, 56).unwrap();
+
+        assert_eq!(
+            hourly().next_run_after(now),
+            Utc.with_ymd_and_hms(2026, 6, 1, 13, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn the_next_run_is_always_in_the_future() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 13, 0, 0).unwrap();
+
+        assert_eq!(
+            hourly().next_run_after(now),
+            Utc.with_ymd_and_hms(2026, 6, 1, 14, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::configuration::Settings;
+use crate::jobs::{enqueue_due_schedules, schedules, Job, JobContext};
+use crate::metrics::begin_transaction;
+use crate::startup::get_connection_pool;
+use crate::telemetry::spawn_with_tracing;
+use crate::utils::truncate_to_char_boundary;
+use anyhow::Context;
+use chrono::Utc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+use uuid::Uuid;
+
+const FIRST_RETRY_DELAY_SECS: u64 = 10;
+const MAX_RETRY_DELAY_SECS: u64 = 60 * 60;
+const MAX_ERROR_LENGTH: usize = 500;
+
+#[derive(Debug, PartialEq)]
+pub enum JobOutcome {
+    Succeeded,
+    Failed,
+    EmptyQueue,
+}
+
+/// Runs `concurrency` workers and the scheduler until `shutdown` is
+/// cancelled or one of them fails. Jobs running by then are finished first.
+pub async fn run_worker_until_stopped(
+    configuration: Settings,
+    shutdown: CancellationToken,
+) -> Result<(), anyhow::Error> {
+    let context = JobContext {
+        pool: get_connection_pool(&configuration.database),
+        storage: configuration.storage().await,
+        uploads: configuration.uploads.clone(),
+    };
+    let settings = configuration.jobs.clone();
+    let schedules = schedules(&configuration);
+    // Stops the other workers when one of them fails, without stopping
+    // whatever else `shutdown` is shared with.
+    let shutdown = shutdown.child_token();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for _ in 0..settings.concurrency.max(1) {
+        let context = context.clone();
+        let poll_interval = settings.poll_interval();
+        let shutdown = shutdown.clone();
+        tasks.spawn(async move {
+            let idle = || shutdown.run_until_cancelled(tokio::time::sleep(poll_interval));
+            while !shutdown.is_cancelled() {
+                match run_next_job(&context).await {
+                    Ok(JobOutcome::Succeeded | JobOutcome::Failed) => {}
+                    Ok(JobOutcome::EmptyQueue) => {
+                        idle().await;
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            error.cause_chain = ?e,
+                            error.message = %e,
+                            "Failed to run a job"
+                        );
+                        idle().await;
+                    }
+                }
+            }
+        });
+    }
+    tasks.spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            while !shutdown.is_cancelled() {
+                if let Err(e) = enqueue_due_schedules(&context.pool, &schedules).await {
+                    tracing::error!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to enqueue scheduled jobs"
+                    );
+                }
+                shutdown
+                    .run_until_cancelled(tokio::time::sleep(settings.poll_interval()))
+                    .await;
+            }
+        }
+    });
+
+    let mut outcome = Ok(());
+    while let Some(stopped) = tasks.join_next().await {
+        if let Err(e) = stopped {
+            shutdown.cancel();
+            outcome = Err(e).context("A job worker stopped.");
+        }
+    }
+    tracing::info!("Job workers stopped");
+    outcome
+}
+
+/// How long to wait before attempt `attempts + 1`, doubling from 10 seconds
+/// up to an hour.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    Duration::from_secs((FIRST_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Runs the job that has been due the longest, if any. The row stays locked
+/// while the job runs, so workers never run the same job at once, and a job
+/// whose worker dies is picked up again without losing an attempt. Jobs
+/// query through the pool, so a running job takes two connections.
+pub async fn run_next_job(context: &JobContext) -> Result<JobOutcome, anyhow::Error> {
+    let mut transaction = begin_transaction(&context.pool)
+        .await
+        .context("Failed to start a transaction for a job.")?;
+
+    let Some(row) = sqlx::query!(
+        r#"
+        SELECT job_id, kind, payload, attempts, max_attempts
+        FROM jobs
+        WHERE status = 'pending' AND run_at <= NOW()
+        ORDER BY run_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch the next job.")?
+    else {
+        return Ok(JobOutcome::EmptyQueue);
+    };
+
+    let attempts = row.attempts + 1;
+    let span = tracing::info_span!(
+        "Running job",
+        job_id = %row.job_id,
+        kind = %row.kind,
+        attempt = attempts
+    );
+    let result = run_job(context, row.job_id, row.payload)
+        .instrument(span.clone())
+        .await;
+
+    let outcome = match result {
+        Ok(()) => {
+            sqlx::query!(r#"DELETE FROM jobs WHERE job_id = $1"#, row.job_id)
+                .execute(&mut *transaction)
+                .await
+                .context("Failed to remove a finished job.")?;
+            JobOutcome::Succeeded
+        }
+        Err(e) => {
+            let dead = attempts >= row.max_attempts;
+            span.in_scope(|| {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    dead,
+                    "Job failed"
+                )
+            });
+            sqlx::query!(
+                r#"
+                UPDATE jobs SET
+                    attempts = $2,
+                    status = CASE WHEN $3 THEN 'dead' ELSE 'pending' END,
+                    run_at = CASE WHEN $3 THEN run_at ELSE $4 END,
+                    died_at = CASE WHEN $3 THEN NOW() END,
+                    last_error = $5
+                WHERE job_id = $1
+                "#,
+                row.job_id,
+                attempts,
+                dead,
+                Utc::now() + retry_delay(attempts),
+                truncate_to_char_boundary(format!("{e:#}"), MAX_ERROR_LENGTH)
+            )
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to record a failed job.")?;
+            JobOutcome::Failed
+        }
+    };
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a job run.")?;
+
+    Ok(outcome)
+}
+
+/// Runs the job on its own task, so a panicking job is recorded as a failed
+/// attempt instead of taking the worker down.
+async fn run_job(
+    context: &JobContext,
+    job_id: Uuid,
+    payload: serde_json::Value,
+) -> Result<(), anyhow::Error> {
+    let job: Job = serde_json::from_value(payload)
+        .with_context(|| format!("Failed to decode job {job_id}."))?;
+    let context = context.clone();
+
+    spawn_with_tracing(async move { job.run(&context).await })
+        .await
+        .context("The job panicked.")?
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jobs::retry_delay;
+    use std::time::Duration;
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(10));
+        assert_eq!(retry_delay(2), Duration::from_secs(20));
+        assert_eq!(retry_delay(5), Duration::from_secs(160));
+    }
+
+    #[test]
+    fn retry_delays_are_capped_at_an_hour() {
+        assert_eq!(retry_delay(10), Duration::from_secs(60 * 60));
+        assert_eq!(retry_delay(i32::MAX), Duration::from_secs(60 * 60));
+    }
+}
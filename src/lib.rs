@@ -1,11 +1,30 @@
 #![allow(clippy::toplevel_ref_arg)]
+pub mod admin;
 pub mod api;
 pub mod api_docs;
+pub mod audio_processing;
 pub mod authentication;
 pub mod configuration;
+pub mod cors;
 pub mod domain;
+pub mod feeds;
+pub mod fixtures;
+pub mod http_cache;
+pub mod idempotency;
+pub mod image_processing;
+pub mod jobs;
+pub mod live_sessions;
+pub mod metrics;
+pub mod pattern_cache;
+pub mod pattern_preview;
+pub mod problem;
+pub mod rate_limiting;
+pub mod repository;
 pub mod routes;
-pub mod s3_client;
 pub mod startup;
+pub mod storage;
 pub mod telemetry;
+pub mod upload_sweeper;
 pub mod utils;
+pub mod webhook_dispatcher;
+pub mod webhooks;
@@ -0,0 +1,231 @@
+use crate::api::models::live::{LiveKnob, LivePresence, LiveServerMessage};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const ROOM_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiveField {
+    Step { bar: i32, number: i32 },
+    Knob(LiveKnob),
+}
+
+struct Room {
+    sender: broadcast::Sender<String>,
+    users: HashMap<Uuid, LivePresence>,
+    field_versions: HashMap<LiveField, i64>,
+    known_version: i64,
+    reset_version: i64,
+}
+
+impl Room {
+    fn new(version: i64) -> Self {
+        let (sender, _) = broadcast::channel(ROOM_CAPACITY);
+        Self {
+            sender,
+            users: HashMap::new(),
+            field_versions: HashMap::new(),
+            known_version: version,
+            reset_version: version,
+        }
+    }
+
+    fn users(&self) -> Vec<LivePresence> {
+        let mut users: Vec<LivePresence> = self.users.values().cloned().collect();
+        users.sort_by(|a, b| {
+            a.username
+                .cmp(&b.username)
+                .then(a.connection_id.cmp(&b.connection_id))
+        });
+        users
+    }
+}
+
+/// In-process registry of live editing rooms, one per pattern with at least
+/// one connected client.
+///
+/// Edits are versioned per pattern. An edit conflicts when the field it
+/// targets changed after the client's `base_version`, or when the pattern was
+/// rewritten outside the live session (e.g. by a full `PUT`) since then, so
+/// concurrent edits to different steps or knobs merge cleanly.
+#[derive(Default)]
+pub struct LiveSessions {
+    rooms: Mutex<HashMap<Uuid, Room>>,
+}
+
+impl LiveSessions {
+    pub fn join(
+        &self,
+        pattern_id: Uuid,
+        presence: LivePresence,
+        version: i64,
+    ) -> (broadcast::Receiver<String>, Vec<LivePresence>) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms
+            .entry(pattern_id)
+            .or_insert_with(|| Room::new(version));
+        room.users.insert(presence.connection_id, presence);
+        (room.sender.subscribe(), room.users())
+    }
+
+    /// Removes the connection and returns the users still in the room, or
+    /// `None` once the room is empty and has been dropped.
+    pub fn leave(&self, pattern_id: Uuid, connection_id: Uuid) -> Option<Vec<LivePresence>> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.get_mut(&pattern_id)?;
+        room.users.remove(&connection_id);
+        if room.users.is_empty() {
+            rooms.remove(&pattern_id);
+            return None;
+        }
+        Some(room.users())
+    }
+
+    pub fn update_bar(
+        &self,
+        pattern_id: Uuid,
+        connection_id: Uuid,
+        bar: Option<i32>,
+    ) -> Vec<LivePresence> {
+        let mut rooms = self.rooms.lock().unwrap();
+        match rooms.get_mut(&pattern_id) {
+            Some(room) => {
+                if let Some(presence) = room.users.get_mut(&connection_id) {
+                    presence.bar = bar;
+                }
+                room.users()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn broadcast(&self, pattern_id: Uuid, message: &LiveServerMessage) {
+        let message = match serde_json::to_string(message) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::error!("Failed to serialize live message: {}", e);
+                return;
+            }
+        };
+        if let Some(room) = self.rooms.lock().unwrap().get(&pattern_id) {
+            // No receivers just means everyone left in the meantime.
+            let _ = room.sender.send(message);
+        }
+    }
+
+    /// Checks an edit against the room state. `stored_version` is the
+    /// pattern version read while holding the pattern row lock. Returns the
+    /// current version on conflict.
+    pub fn check_edit(
+        &self,
+        pattern_id: Uuid,
+        field: LiveField,
+        base_version: i64,
+        stored_version: i64,
+    ) -> Result<(), i64> {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(&pattern_id) else {
+            // Without a room there is no edit history to merge against.
+            return match base_version == stored_version {
+                true => Ok(()),
+                false => Err(stored_version),
+            };
+        };
+
+        if stored_version != room.known_version {
+            room.known_version = stored_version;
+            room.reset_version = stored_version;
+        }
+
+        let changed_at = room
+            .field_versions
+            .get(&field)
+            .copied()
+            .unwrap_or(0)
+            .max(room.reset_version);
+
+        if base_version < changed_at || base_version > stored_version {
+            return Err(stored_version);
+        }
+        Ok(())
+    }
+
+    pub fn record_edit(&self, pattern_id: Uuid, field: LiveField, version: i64) {
+        if let Some(room) = self.rooms.lock().unwrap().get_mut(&pattern_id) {
+            room.field_versions.insert(field, version);
+            room.known_version = version;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::live::{LiveKnob, LivePresence};
+    use crate::live_sessions::{LiveField, LiveSessions};
+    use claims::{assert_err, assert_none, assert_ok};
+    use uuid::Uuid;
+
+    fn presence() -> LivePresence {
+        LivePresence {
+            connection_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            username: "acid".to_string(),
+            bar: None,
+            can_edit: true,
+        }
+    }
+
+    #[test]
+    fn edits_to_different_fields_do_not_conflict() {
+        let sessions = LiveSessions::default();
+        let pattern_id = Uuid::new_v4();
+        sessions.join(pattern_id, presence(), 3);
+
+        let first = LiveField::Step { bar: 1, number: 1 };
+        assert_ok!(sessions.check_edit(pattern_id, first, 3, 3));
+        sessions.record_edit(pattern_id, first, 4);
+
+        let second = LiveField::Knob(LiveKnob::Decay);
+        assert_ok!(sessions.check_edit(pattern_id, second, 3, 4));
+    }
+
+    #[test]
+    fn a_stale_edit_to_the_same_field_conflicts() {
+        let sessions = LiveSessions::default();
+        let pattern_id = Uuid::new_v4();
+        sessions.join(pattern_id, presence(), 3);
+
+        let field = LiveField::Step { bar: 1, number: 1 };
+        sessions.record_edit(pattern_id, field, 4);
+
+        assert_err!(sessions.check_edit(pattern_id, field, 3, 4));
+        assert_ok!(sessions.check_edit(pattern_id, field, 4, 4));
+    }
+
+    #[test]
+    fn an_outside_write_makes_stale_edits_conflict() {
+        let sessions = LiveSessions::default();
+        let pattern_id = Uuid::new_v4();
+        sessions.join(pattern_id, presence(), 3);
+
+        let field = LiveField::Knob(LiveKnob::Resonance);
+        assert_err!(sessions.check_edit(pattern_id, field, 3, 4));
+        assert_ok!(sessions.check_edit(pattern_id, field, 4, 4));
+    }
+
+    #[test]
+    fn leaving_the_last_connection_drops_the_room() {
+        let sessions = LiveSessions::default();
+        let pattern_id = Uuid::new_v4();
+        let first = presence();
+        let second = presence();
+        sessions.join(pattern_id, first.clone(), 0);
+        sessions.join(pattern_id, second.clone(), 0);
+
+        let remaining = sessions.leave(pattern_id, first.connection_id).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_none!(sessions.leave(pattern_id, second.connection_id));
+    }
+}
@@ -1,17 +1,91 @@
 use acid::configuration::get_configuration;
+use acid::jobs::run_worker_until_stopped;
 use acid::startup::Application;
-use acid::telemetry::{get_subscriber, init_subscriber};
+use acid::telemetry::{get_subscriber, get_tracer, get_tracer_provider, init_subscriber};
+use acid::webhook_dispatcher::run_dispatcher_until_stopped;
 use dotenvy::dotenv;
+use std::collections::HashMap;
+use tokio::task::{Id, JoinError, JoinSet};
+use tokio_util::sync::CancellationToken;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
-    let subscriber = get_subscriber("api".into(), "info".into(), std::io::stdout);
+    let configuration = get_configuration()?;
+
+    let tracer_provider = get_tracer_provider("api", &configuration.tracing)?;
+    let tracer = get_tracer(tracer_provider.as_ref(), "api");
+    let subscriber = get_subscriber("api".into(), "info".into(), std::io::stdout, tracer);
     init_subscriber(subscriber);
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
+    let application = Application::build(configuration.clone()).await?;
+    let server = application.handle();
+    let shutdown = CancellationToken::new();
+    let mut tasks = JoinSet::new();
+    let mut task_names = HashMap::new();
+    let api = tasks.spawn(async move { Ok(application.run_until_stopped().await?) });
+    task_names.insert(api.id(), "API");
+    let dispatcher = tasks.spawn(run_dispatcher_until_stopped(
+        configuration.clone(),
+        shutdown.clone(),
+    ));
+    task_names.insert(dispatcher.id(), "Webhook dispatcher");
+    // Otherwise the `worker` binary runs the jobs.
+    if configuration.jobs.run_in_process {
+        let worker = tasks.spawn(run_worker_until_stopped(
+            configuration.clone(),
+            shutdown.clone(),
+        ));
+        task_names.insert(worker.id(), "Job worker");
+    }
+
+    // The API stops on SIGTERM once its requests in flight are done. Whatever
+    // stops first, the rest are asked to finish what they are working on.
+    if let Some(outcome) = tasks.join_next_with_id().await {
+        report_exit(&task_names, outcome);
+    }
+    shutdown.cancel();
+    let stopped = tokio::time::timeout(configuration.application.shutdown_timeout(), async {
+        server.stop(true).await;
+        while let Some(outcome) = tasks.join_next_with_id().await {
+            report_exit(&task_names, outcome);
+        }
+    })
+    .await;
+    if stopped.is_err() {
+        tracing::error!(tasks = tasks.len(), "Tasks did not stop in time");
+    }
 
-    let application = Application::build(configuration).await?;
-    application.run_until_stopped().await?;
+    if let Some(provider) = tracer_provider {
+        provider.shutdown()?;
+    }
 
     Ok(())
 }
+
+fn report_exit(
+    task_names: &HashMap<Id, &str>,
+    outcome: Result<(Id, Result<(), anyhow::Error>), JoinError>,
+) {
+    match outcome {
+        Ok((id, Ok(()))) => {
+            tracing::info!("{} has exited", task_names[&id])
+        }
+        Ok((id, Err(e))) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "{} failed",
+                task_names[&id]
+            )
+        }
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "{} task failed to complete",
+                task_names[&e.id()]
+            )
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::admin::{archive_stats, ArchiveStats};
+use crate::domain::UploadType;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::future::Future;
+use std::sync::{RwLock, Weak};
+use std::time::{Duration, Instant};
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_acquire_duration: Histogram,
+    db_query_duration: HistogramVec,
+    jwks_cache_lookups: IntCounterVec,
+    jwks_fetch_failures: IntCounter,
+    pattern_cache_lookups: IntCounterVec,
+    upload_presigns: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests handled"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time taken to handle HTTP requests",
+                ),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            db_pool_acquire_duration: Histogram::with_opts(HistogramOpts::new(
+                "db_pool_acquire_duration_seconds",
+                "Time requests and jobs waited for a pooled connection",
+            ))
+            .unwrap(),
+            db_query_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "db_query_duration_seconds",
+                    "Time taken by database operations",
+                ),
+                &["operation", "outcome"],
+            )
+            .unwrap(),
+            jwks_cache_lookups: IntCounterVec::new(
+                Opts::new(
+                    "jwks_cache_lookups_total",
+                    "Lookups of Cognito signing keys",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            jwks_fetch_failures: IntCounter::new(
+                "jwks_fetch_failures_total",
+                "Failed fetches of the Cognito signing keys",
+            )
+            .unwrap(),
+            pattern_cache_lookups: IntCounterVec::new(
+                Opts::new(
+                    "pattern_cache_lookups_total",
+                    "Lookups of public patterns in the in-process cache",
+                ),
+                &["result"],
+            )
+            .unwrap(),
+            upload_presigns: IntCounterVec::new(
+                Opts::new("upload_presigns_total", "Presigned upload URLs handed out"),
+                &["upload_type"],
+            )
+            .unwrap(),
+            registry: new_registry(),
+        };
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.db_pool_acquire_duration.clone()),
+            Box::new(metrics.db_query_duration.clone()),
+            Box::new(metrics.jwks_cache_lookups.clone()),
+            Box::new(metrics.jwks_fetch_failures.clone()),
+            Box::new(metrics.pattern_cache_lookups.clone()),
+            Box::new(metrics.upload_presigns.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("Metric names are unique");
+        }
+
+        metrics
+    }
+
+    pub fn record_jwks_lookup(&self, cache_hit: bool) {
+        let result = if cache_hit { "hit" } else { "miss" };
+        self.jwks_cache_lookups.with_label_values(&[result]).inc();
+    }
+
+    pub fn record_jwks_fetch_failure(&self) {
+        self.jwks_fetch_failures.inc();
+    }
+
+    pub fn record_pattern_cache_lookup(&self, cache_hit: bool) {
+        let result = if cache_hit { "hit" } else { "miss" };
+        self.pattern_cache_lookups
+            .with_label_values(&[result])
+            .inc();
+    }
+
+    pub fn record_presign(&self, upload_type: UploadType) {
+        self.upload_presigns
+            .with_label_values(&[upload_type.as_ref()])
+            .inc();
+    }
+
+    /// Renders every metric in the Prometheus text format along with the
+    /// state of the pool and the last archive count.
+    pub fn render(&self, pool: &PgPool, archive: &ArchiveGauges) -> String {
+        let mut families = self.registry.gather();
+        families.extend(sample(pool, archive).gather());
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&families, &mut buffer)
+            .expect("Metrics encode as text");
+        String::from_utf8(buffer).expect("Metrics are UTF-8")
+    }
+}
+
+/// The archive totals served on `/metrics`. Counting them takes a few
+/// `COUNT(*)` queries, so they are refreshed in the background by
+/// [`refresh_archive_gauges`] instead of on every scrape.
+#[derive(Default)]
+pub struct ArchiveGauges(RwLock<Option<ArchiveStats>>);
+
+/// Counts the archive every `interval`, until the API drops `gauges`.
+pub async fn refresh_archive_gauges(pool: PgPool, gauges: Weak<ArchiveGauges>, interval: Duration) {
+    loop {
+        let Some(gauges) = gauges.upgrade() else {
+            return;
+        };
+        match archive_stats(&pool).await {
+            Ok(stats) => *gauges.0.write().unwrap() = Some(stats),
+            Err(e) => tracing::warn!(error.cause_chain = ?e, "Failed to collect archive metrics"),
+        }
+        drop(gauges);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Reads the gauges that describe the current state of the pool and the
+/// archive. They go in a registry of their own, built for each scrape, so
+/// they always come from the pool being scraped.
+fn sample(pool: &PgPool, archive: &ArchiveGauges) -> Registry {
+    let registry = new_registry();
+
+    let connections = IntGaugeVec::new(
+        Opts::new("db_pool_connections", "Open database connections"),
+        &["state"],
+    )
+    .unwrap();
+    let idle = pool.num_idle() as i64;
+    connections.with_label_values(&["idle"]).set(idle);
+    connections
+        .with_label_values(&["active"])
+        .set(pool.size() as i64 - idle);
+    registry.register(Box::new(connections)).unwrap();
+
+    // Left out until the first count is in.
+    if let Some(stats) = archive.0.read().unwrap().as_ref() {
+        let gauges = [
+            ("users", "Registered users", stats.users),
+            ("patterns", "Stored patterns", stats.patterns),
+            ("public_patterns", "Public patterns", stats.public_patterns),
+            (
+                "unclaimed_uploads",
+                "Uploads not claimed yet",
+                stats.unclaimed_uploads,
+            ),
+            ("pending_jobs", "Jobs waiting to run", stats.pending_jobs),
+            (
+                "dead_jobs",
+                "Jobs that failed on every attempt",
+                stats.dead_jobs,
+            ),
+            (
+                "pending_webhook_deliveries",
+                "Webhook deliveries waiting to be sent",
+                stats.pending_webhook_deliveries,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let gauge = IntGauge::new(name, help).unwrap();
+            gauge.set(value);
+            registry.register(Box::new(gauge)).unwrap();
+        }
+    }
+
+    registry
+}
+
+fn new_registry() -> Registry {
+    Registry::new_custom(Some("acid".to_string()), None).expect("The metrics prefix is valid")
+}
+
+/// Records the time taken by the database `operation`, labelled with whether
+/// it succeeded.
+pub async fn time_query<T, E>(
+    operation: &'static str,
+    query: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = query.await;
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    METRICS
+        .db_query_duration
+        .with_label_values(&[operation, outcome])
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+/// Gets a pooled connection, recording how long it took to get one.
+pub async fn acquire_connection(pool: &PgPool) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+    let started = Instant::now();
+    let connection = pool.acquire().await;
+    METRICS
+        .db_pool_acquire_duration
+        .observe(started.elapsed().as_secs_f64());
+    connection
+}
+
+/// Starts a transaction, recording how long it took to get its connection.
+pub async fn begin_transaction(
+    pool: &PgPool,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let started = Instant::now();
+    let transaction = pool.begin().await;
+    // Beyond the wait for a connection, this only adds the BEGIN round trip.
+    METRICS
+        .db_pool_acquire_duration
+        .observe(started.elapsed().as_secs_f64());
+    transaction
+}
+
+/// Counts requests and their latency by route template rather than path, so
+/// IDs do not multiply the series.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let method = req.method().to_string();
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let result = next.call(req).await;
+
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    let labels = [method.as_str(), route.as_str(), status.as_str()];
+    METRICS.http_requests.with_label_values(&labels).inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+
+    result
+}
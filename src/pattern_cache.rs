@@ -0,0 +1,177 @@
+use crate::api::models::tb303::TB303Pattern;
+use crate::configuration::PatternCacheSettings;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+struct Entry {
+    stored: Instant,
+    pattern: TB303Pattern,
+}
+
+struct Entries {
+    patterns: LruCache<Uuid, Entry>,
+    /// Moves on every invalidation, so a read that started before a change
+    /// cannot put the old pattern back.
+    generation: u64,
+}
+
+/// Public patterns read recently, so hot ones are not read from the database
+/// on every request. Handlers that change or delete a pattern drop it once
+/// the change is committed. Callers check a cached pattern is still public
+/// and unchanged before serving it, which covers changes made by other
+/// instances or by `acidctl`; media attached elsewhere show when the entry
+/// expires.
+pub struct PatternCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+/// Taken before reading a pattern from the database and handed back with it.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheTicket(u64);
+
+impl PatternCache {
+    pub fn new(settings: &PatternCacheSettings) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                patterns: LruCache::new(
+                    NonZeroUsize::new(settings.capacity).unwrap_or(NonZeroUsize::MIN),
+                ),
+                generation: 0,
+            }),
+            capacity: settings.capacity,
+            ttl: settings.ttl(),
+        }
+    }
+
+    pub fn get(&self, pattern_id: Uuid) -> Option<TB303Pattern> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.patterns.get(&pattern_id)?;
+        if entry.stored.elapsed() < self.ttl {
+            return Some(entry.pattern.clone());
+        }
+        entries.patterns.pop(&pattern_id);
+        None
+    }
+
+    pub fn ticket(&self) -> CacheTicket {
+        CacheTicket(self.entries.lock().unwrap().generation)
+    }
+
+    /// Keeps `pattern` if it is public and nothing was invalidated since
+    /// `ticket` was taken. When full, the pattern read least recently makes
+    /// room.
+    pub fn insert(&self, ticket: CacheTicket, pattern: &TB303Pattern) {
+        let (Some(pattern_id), Some(true)) = (pattern.id, pattern.is_public) else {
+            return;
+        };
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.generation != ticket.0 {
+            return;
+        }
+
+        entries.patterns.put(
+            pattern_id,
+            Entry {
+                stored: Instant::now(),
+                pattern: pattern.clone(),
+            },
+        );
+    }
+
+    pub fn invalidate(&self, pattern_id: Uuid) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.generation += 1;
+        entries.patterns.pop(&pattern_id);
+    }
+
+    /// For changes to many patterns at once, such as deleting an account.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.generation += 1;
+        entries.patterns.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::tb303::TB303Pattern;
+    use crate::configuration::PatternCacheSettings;
+    use crate::pattern_cache::PatternCache;
+    use claims::{assert_none, assert_some};
+    use uuid::Uuid;
+
+    fn cache(capacity: usize) -> PatternCache {
+        PatternCache::new(&PatternCacheSettings {
+            capacity,
+            ttl_secs: 60,
+        })
+    }
+
+    fn pattern(is_public: bool) -> TB303Pattern {
+        serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "name": "Acid Tracks",
+            "is_public": is_public,
+            "bars": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_public_patterns_are_kept() {
+        let cache = cache(10);
+        let public = pattern(true);
+        let private = pattern(false);
+
+        cache.insert(cache.ticket(), &public);
+        cache.insert(cache.ticket(), &private);
+
+        assert_some!(cache.get(public.id.unwrap()));
+        assert_none!(cache.get(private.id.unwrap()));
+    }
+
+    #[test]
+    fn reads_started_before_an_invalidation_are_not_kept() {
+        let cache = cache(10);
+        let pattern = pattern(true);
+        let ticket = cache.ticket();
+
+        cache.invalidate(pattern.id.unwrap());
+        cache.insert(ticket, &pattern);
+
+        assert_none!(cache.get(pattern.id.unwrap()));
+    }
+
+    #[test]
+    fn the_pattern_read_least_recently_makes_room() {
+        let cache = cache(2);
+        let patterns = [pattern(true), pattern(true), pattern(true)];
+
+        cache.insert(cache.ticket(), &patterns[0]);
+        cache.insert(cache.ticket(), &patterns[1]);
+        cache.get(patterns[0].id.unwrap());
+        cache.insert(cache.ticket(), &patterns[2]);
+
+        assert_some!(cache.get(patterns[0].id.unwrap()));
+        assert_none!(cache.get(patterns[1].id.unwrap()));
+        assert_some!(cache.get(patterns[2].id.unwrap()));
+    }
+
+    #[test]
+    fn a_capacity_of_0_turns_the_cache_off() {
+        let cache = cache(0);
+        let pattern = pattern(true);
+
+        cache.insert(cache.ticket(), &pattern);
+
+        assert_none!(cache.get(pattern.id.unwrap()));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::api::models::tb303::TB303Bar;
+use std::fmt::Write;
+
+const STEPS_PER_BAR: usize = 16;
+const STEP_WIDTH: usize = 12;
+const ROW_HEIGHT: usize = 3;
+/// Three octaves: transposed down, untransposed and transposed up. `Chigh`
+/// of one octave is `C` of the next.
+const PITCHES: usize = 37;
+const GRID_HEIGHT: usize = PITCHES * ROW_HEIGHT;
+/// Accents and slides are marked in a lane below the notes.
+const LANE_HEIGHT: usize = 12;
+const HEIGHT: usize = GRID_HEIGHT + LANE_HEIGHT;
+
+const BACKGROUND: &str = "#111111";
+const BEAT_LINE: &str = "#1f1f1f";
+const BAR_LINE: &str = "#3a3a3a";
+const NOTE: &str = "#f0c419";
+const ACCENTED_NOTE: &str = "#ff5a1f";
+const SLIDE: &str = "#4fc3f7";
+
+const NOTES: [&str; 13] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B", "Chigh",
+];
+
+/// Row of the note counted from the bottom of the grid, `None` for steps
+/// without a note.
+fn pitch(note: Option<&str>, transpose: Option<&str>) -> Option<usize> {
+    let semitone = NOTES.iter().position(|n| Some(*n) == note)?;
+    let octave = match transpose {
+        Some("down") => 0,
+        Some("up") => 2,
+        _ => 1,
+    };
+    Some(octave * 12 + semitone)
+}
+
+fn row_top(pitch: usize) -> usize {
+    (PITCHES - 1 - pitch) * ROW_HEIGHT
+}
+
+/// A note as drawn: tied steps extend the note before them.
+struct DrawnNote {
+    start: usize,
+    steps: usize,
+    pitch: usize,
+    accent: bool,
+    slide: bool,
+}
+
+/// Lays the steps of all bars out on one timeline. Bars are expected in
+/// order; steps without a note or with an unknown one are rests.
+fn drawn_notes(bars: &[TB303Bar]) -> Vec<DrawnNote> {
+    let mut notes: Vec<DrawnNote> = Vec::new();
+    let mut sounding = false;
+
+    for (bar_index, bar) in bars.iter().enumerate() {
+        let mut steps: Vec<_> = bar.steps.iter().collect();
+        steps.sort_by_key(|step| step.number);
+        let mut expected = 1;
+        for step in steps {
+            // Missing steps are rests.
+            if step.number != expected {
+                sounding = false;
+            }
+            expected = step.number + 1;
+
+            let position = bar_index * STEPS_PER_BAR + (step.number as usize).saturating_sub(1);
+            match step.time.as_deref() {
+                Some("tied") if sounding => {
+                    let note = notes.last_mut().expect("A sounding note exists");
+                    note.steps = position + 1 - note.start;
+                    note.slide = step.slide.unwrap_or(false);
+                }
+                Some("note") => match pitch(step.note.as_deref(), step.transpose.as_deref()) {
+                    Some(pitch) => {
+                        notes.push(DrawnNote {
+                            start: position,
+                            steps: 1,
+                            pitch,
+                            accent: step.accent.unwrap_or(false),
+                            slide: step.slide.unwrap_or(false),
+                        });
+                        sounding = true;
+                    }
+                    None => sounding = false,
+                },
+                _ => sounding = false,
+            }
+        }
+        if expected <= STEPS_PER_BAR as i32 {
+            sounding = false;
+        }
+    }
+
+    notes
+}
+
+/// Width and height of the preview of a pattern with `bar_count` bars.
+pub fn preview_size(bar_count: usize) -> (usize, usize) {
+    (bar_count.max(1) * STEPS_PER_BAR * STEP_WIDTH, HEIGHT)
+}
+
+/// Renders a TB-303 pattern as an SVG step grid: time runs left to right,
+/// pitch bottom to top, accented notes are orange and slides are drawn as
+/// lines into the following note. The output only depends on the steps, so
+/// it contains nothing that users typed in.
+pub fn render_preview_svg(bars: &[TB303Bar]) -> String {
+    let steps = bars.len().max(1) * STEPS_PER_BAR;
+    let (width, _) = preview_size(bars.len());
+    let mut svg = String::new();
+
+    // Writing to a String cannot fail.
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {HEIGHT}" width="{width}" height="{HEIGHT}">"#
+    );
+    let _ = write!(
+        svg,
+        r#"<rect width="{width}" height="{HEIGHT}" fill="{BACKGROUND}"/>"#
+    );
+
+    for step in (4..steps).step_by(4) {
+        let x = step * STEP_WIDTH;
+        let colour = if step % STEPS_PER_BAR == 0 {
+            BAR_LINE
+        } else {
+            BEAT_LINE
+        };
+        let _ = write!(
+            svg,
+            r#"<line x1="{x}" y1="0" x2="{x}" y2="{HEIGHT}" stroke="{colour}"/>"#
+        );
+    }
+
+    let notes = drawn_notes(bars);
+    for (index, note) in notes.iter().enumerate() {
+        let x = note.start * STEP_WIDTH + 1;
+        let y = row_top(note.pitch);
+        let note_width = note.steps * STEP_WIDTH - 2;
+        let colour = if note.accent { ACCENTED_NOTE } else { NOTE };
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{note_width}" height="{ROW_HEIGHT}" fill="{colour}"/>"#
+        );
+
+        if note.accent {
+            let cx = x + (STEP_WIDTH - 2) / 2;
+            let cy = GRID_HEIGHT + LANE_HEIGHT / 2;
+            let _ = write!(
+                svg,
+                r#"<circle cx="{cx}" cy="{cy}" r="2" fill="{ACCENTED_NOTE}"/>"#
+            );
+        }
+
+        if note.slide {
+            let end = note.start + note.steps;
+            let lane_y = GRID_HEIGHT + LANE_HEIGHT / 2;
+            let x1 = end * STEP_WIDTH - 4;
+            let _ = write!(
+                svg,
+                r#"<line x1="{x1}" y1="{lane_y}" x2="{}" y2="{lane_y}" stroke="{SLIDE}" stroke-width="2"/>"#,
+                end * STEP_WIDTH + 4
+            );
+            if let Some(next) = notes.get(index + 1).filter(|next| next.start == end) {
+                let y1 = y + ROW_HEIGHT / 2;
+                let x2 = next.start * STEP_WIDTH + 1;
+                let y2 = row_top(next.pitch) + ROW_HEIGHT / 2;
+                let _ = write!(
+                    svg,
+                    r#"<line x1="{}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{SLIDE}"/>"#,
+                    x + note_width
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::tb303::{TB303Bar, TB303Step};
+    use crate::pattern_preview::{drawn_notes, render_preview_svg};
+    use uuid::Uuid;
+
+    fn step(number: i32, note: &str, time: &str) -> TB303Step {
+        TB303Step {
+            id: Uuid::new_v4(),
+            number,
+            note: Some(note.to_string()),
+            transpose: None,
+            time: Some(time.to_string()),
+            accent: Some(false),
+            slide: Some(false),
+        }
+    }
+
+    fn bar(number: i32, steps: Vec<TB303Step>) -> TB303Bar {
+        TB303Bar {
+            id: Uuid::new_v4(),
+            number,
+            steps,
+        }
+    }
+
+    #[test]
+    fn tied_steps_extend_the_previous_note() {
+        let bars = vec![bar(
+            1,
+            vec![
+                step(1, "C", "note"),
+                step(2, "C", "tied"),
+                step(3, "D", "tied"),
+            ],
+        )];
+
+        let notes = drawn_notes(&bars);
+
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].steps, 3);
+        assert_eq!(notes[0].pitch, 12);
+    }
+
+    #[test]
+    fn ties_after_a_rest_are_not_drawn() {
+        let bars = vec![bar(
+            1,
+            vec![
+                step(1, "C", "note"),
+                step(2, "C", "rest"),
+                step(3, "C", "tied"),
+            ],
+        )];
+
+        let notes = drawn_notes(&bars);
+
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].steps, 1);
+    }
+
+    #[test]
+    fn notes_tie_across_bars() {
+        let mut first: Vec<TB303Step> = (1..=15).map(|n| step(n, "C", "rest")).collect();
+        first.push(step(16, "C", "note"));
+        let bars = vec![bar(1, first), bar(2, vec![step(1, "C", "tied")])];
+
+        let notes = drawn_notes(&bars);
+
+        assert_eq!(notes.len(), 1);
+        assert_eq!((notes[0].start, notes[0].steps), (15, 2));
+    }
+
+    #[test]
+    fn transpose_moves_notes_an_octave() {
+        let mut up = step(1, "C", "note");
+        up.transpose = Some("up".to_string());
+        let mut down = step(2, "Chigh", "note");
+        down.transpose = Some("down".to_string());
+        let bars = vec![bar(1, vec![up, down])];
+
+        let pitches: Vec<usize> = drawn_notes(&bars).iter().map(|n| n.pitch).collect();
+
+        assert_eq!(pitches, vec![24, 12]);
+    }
+
+    #[test]
+    fn preview_is_as_wide_as_the_pattern() {
+        let bars = vec![bar(1, vec![]), bar(2, vec![])];
+
+        let svg = render_preview_svg(&bars);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains(r#"viewBox="0 0 384 123""#));
+    }
+
+    #[test]
+    fn accents_and_slides_are_drawn() {
+        let mut accented = step(1, "C", "note");
+        accented.accent = Some(true);
+        accented.slide = Some(true);
+        let bars = vec![bar(1, vec![accented, step(2, "G", "note")])];
+
+        let svg = render_preview_svg(&bars);
+
+        assert!(svg.contains("#ff5a1f"));
+        assert!(svg.contains("#4fc3f7"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+    }
+
+    #[test]
+    fn empty_patterns_render_one_empty_bar() {
+        let svg = render_preview_svg(&[]);
+
+        assert!(svg.contains(r#"width="192""#));
+        assert_eq!(svg.matches("<rect").count(), 1);
+    }
+}
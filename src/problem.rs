@@ -0,0 +1,258 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// What went wrong, for clients to match on. Codes are only ever added,
+/// never renamed.
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemCode {
+    BadRequest,
+    InvalidJson,
+    InvalidQuery,
+    InvalidPath,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PayloadTooLarge,
+    InvalidIdempotencyKey,
+    IdempotencyKeyReused,
+    IdempotencyKeyInUse,
+    RateLimited,
+    InternalError,
+    NotImplemented,
+}
+
+impl From<StatusCode> for ProblemCode {
+    fn from(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ProblemCode::Unauthorized,
+            StatusCode::FORBIDDEN => ProblemCode::Forbidden,
+            StatusCode::NOT_FOUND => ProblemCode::NotFound,
+            StatusCode::CONFLICT => ProblemCode::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => ProblemCode::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => ProblemCode::RateLimited,
+            StatusCode::NOT_IMPLEMENTED => ProblemCode::NotImplemented,
+            status if status.is_client_error() => ProblemCode::BadRequest,
+            _ => ProblemCode::InternalError,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldErrorCode {
+    Invalid,
+    OutOfRange,
+    Empty,
+    TooMany,
+    Duplicate,
+    NotSequential,
+    NotAllowed,
+}
+
+/// A problem with one value of the request body.
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// JSON pointer to the value, from the root of the body.
+    #[schema(example = "/bars/1/steps/4/number")]
+    pub pointer: String,
+    #[schema(example = "out_of_range")]
+    pub code: FieldErrorCode,
+    #[schema(example = "17 is not a valid step number value.")]
+    pub detail: String,
+}
+
+/// Every problem found in a request body, so clients can fix them all
+/// before trying again.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn add(
+        &mut self,
+        pointer: impl Into<String>,
+        code: FieldErrorCode,
+        detail: impl AsRef<str>,
+    ) {
+        self.0.push(FieldError {
+            pointer: pointer.into(),
+            code,
+            detail: detail.as_ref().trim_end().to_string(),
+        });
+    }
+
+    /// Keeps what `result` parsed to, or records why it failed under
+    /// `pointer`.
+    pub fn check<T>(
+        &mut self,
+        pointer: &str,
+        code: FieldErrorCode,
+        result: Result<T, String>,
+    ) -> Option<T> {
+        result
+            .map_err(|detail| self.add(pointer, code, detail))
+            .ok()
+    }
+
+    /// Adds problems found in the value at `pointer`, whose pointers start
+    /// from that value.
+    pub fn nest(&mut self, pointer: &str, errors: ValidationErrors) {
+        self.0.extend(errors.0.into_iter().map(|error| FieldError {
+            pointer: format!("{pointer}{}", error.pointer),
+            ..error
+        }));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let details: Vec<&str> = self.0.iter().map(|error| error.detail.as_str()).collect();
+        write!(f, "{}", details.join(" "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// An error response as described by RFC 7807, sent as
+/// `application/problem+json`.
+#[derive(Serialize, ToSchema, Debug)]
+pub struct Problem {
+    /// Always `about:blank`: `code` tells problems apart.
+    #[serde(rename = "type")]
+    #[schema(example = "about:blank")]
+    pub problem_type: String,
+    #[schema(example = "Bad Request")]
+    pub title: String,
+    #[schema(example = 400)]
+    pub status: u16,
+    #[schema(example = "validation_failed")]
+    pub code: ProblemCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "17 is not a valid step number value.")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, code: ProblemCode, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            code,
+            detail: Some(detail.into()),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Describes `error` by its status code and message.
+    pub fn from_error(error: &impl ResponseError) -> Self {
+        let status = error.status_code();
+        Self::new(status, status.into(), error.to_string())
+    }
+
+    pub fn validation(errors: &ValidationErrors) -> Self {
+        Self {
+            errors: errors.errors().to_vec(),
+            ..Self::new(
+                StatusCode::BAD_REQUEST,
+                ProblemCode::ValidationFailed,
+                errors.to_string(),
+            )
+        }
+    }
+
+    pub fn with_code(mut self, code: ProblemCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl From<Problem> for HttpResponse {
+    fn from(problem: Problem) -> Self {
+        HttpResponse::build(problem.status_code())
+            .content_type(PROBLEM_CONTENT_TYPE)
+            .json(problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem::{FieldErrorCode, Problem, ProblemCode, ValidationErrors};
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+
+    #[test]
+    fn nested_errors_point_from_the_root() {
+        let mut step = ValidationErrors::default();
+        step.add(
+            "/number",
+            FieldErrorCode::OutOfRange,
+            "17 is out of range. ",
+        );
+        let mut errors = ValidationErrors::default();
+
+        errors.nest("/bars/1/steps/4", step);
+
+        assert_eq!(errors.errors()[0].pointer, "/bars/1/steps/4/number");
+        assert_eq!(errors.errors()[0].detail, "17 is out of range.");
+    }
+
+    #[test]
+    fn validation_problems_list_every_error() {
+        let mut errors = ValidationErrors::default();
+        errors.add("/name", FieldErrorCode::Invalid, "Bad name.");
+        errors.add("/bars", FieldErrorCode::Empty, "No bars.");
+
+        let problem = serde_json::to_value(Problem::validation(&errors)).unwrap();
+
+        assert_eq!(
+            problem,
+            json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "code": "validation_failed",
+                "detail": "Bad name. No bars.",
+                "errors": [
+                    { "pointer": "/name", "code": "invalid", "detail": "Bad name." },
+                    { "pointer": "/bars", "code": "empty", "detail": "No bars." }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn codes_follow_the_status_by_default() {
+        assert_eq!(
+            ProblemCode::from(StatusCode::NOT_FOUND),
+            ProblemCode::NotFound
+        );
+        assert_eq!(
+            ProblemCode::from(StatusCode::IM_A_TEAPOT),
+            ProblemCode::BadRequest
+        );
+        assert_eq!(
+            ProblemCode::from(StatusCode::BAD_GATEWAY),
+            ProblemCode::InternalError
+        );
+    }
+}
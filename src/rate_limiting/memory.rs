@@ -0,0 +1,117 @@
+use crate::configuration::RateLimit;
+use crate::rate_limiting::{Budget, Decision, RateLimiter};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Past this many windows, expired ones are dropped before counting.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+struct Window {
+    started: Instant,
+    period: Duration,
+    count: u32,
+}
+
+impl Window {
+    fn is_over(&self, now: Instant) -> bool {
+        now.duration_since(self.started) >= self.period
+    }
+}
+
+/// Keeps the counts of this process only, so every instance of the API
+/// hands out the full budget.
+#[derive(Default)]
+pub struct InMemoryRateLimiter {
+    windows: Mutex<HashMap<(Budget, String), Window>>,
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn hit(
+        &self,
+        budget: Budget,
+        client: &str,
+        limit: RateLimit,
+    ) -> Result<Decision, anyhow::Error> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, window| !window.is_over(now));
+        }
+
+        let new_window = || Window {
+            started: now,
+            period: limit.period(),
+            count: 0,
+        };
+        let window = windows
+            .entry((budget, client.to_owned()))
+            .or_insert_with(new_window);
+        if window.is_over(now) {
+            *window = new_window();
+        }
+        window.count += 1;
+
+        let reset_after = window
+            .period
+            .saturating_sub(now.duration_since(window.started));
+        Ok(Decision::new(limit, window.count, reset_after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::RateLimit;
+    use crate::rate_limiting::{Budget, InMemoryRateLimiter, RateLimiter};
+
+    const LIMIT: RateLimit = RateLimit {
+        requests: 2,
+        period_secs: 60,
+    };
+
+    #[tokio::test]
+    async fn requests_over_the_limit_are_refused() {
+        let limiter = InMemoryRateLimiter::default();
+
+        let mut decisions = Vec::new();
+        for _ in 0..3 {
+            decisions.push(limiter.hit(Budget::Writes, "ip:1", LIMIT).await.unwrap());
+        }
+
+        let remaining: Vec<_> = decisions.iter().map(|d| d.remaining).collect();
+        let allowed: Vec<_> = decisions.iter().map(|d| d.allowed).collect();
+        assert_eq!(remaining, [1, 0, 0]);
+        assert_eq!(allowed, [true, true, false]);
+        assert!(decisions[2].reset_after.as_secs() <= 60);
+    }
+
+    #[tokio::test]
+    async fn clients_and_budgets_are_counted_apart() {
+        let limiter = InMemoryRateLimiter::default();
+        for _ in 0..2 {
+            limiter.hit(Budget::Writes, "ip:1", LIMIT).await.unwrap();
+        }
+
+        let other_client = limiter.hit(Budget::Writes, "ip:2", LIMIT).await.unwrap();
+        let other_budget = limiter.hit(Budget::Presigns, "ip:1", LIMIT).await.unwrap();
+
+        assert!(other_client.allowed);
+        assert!(other_budget.allowed);
+    }
+
+    #[tokio::test]
+    async fn windows_start_over_once_the_period_has_passed() {
+        let limiter = InMemoryRateLimiter::default();
+        let limit = RateLimit {
+            requests: 1,
+            period_secs: 0,
+        };
+
+        limiter.hit(Budget::Writes, "ip:1", limit).await.unwrap();
+        let decision = limiter.hit(Budget::Writes, "ip:1", limit).await.unwrap();
+
+        assert!(decision.allowed);
+    }
+}
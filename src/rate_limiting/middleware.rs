@@ -0,0 +1,184 @@
+use crate::authentication::{try_extract_user_id, UserId};
+use crate::configuration::{CognitoSettings, RateLimitSettings};
+use crate::problem::{Problem, ProblemCode};
+use crate::rate_limiting::{Budget, Decision, RateLimiter};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER, X_FORWARDED_FOR},
+    http::StatusCode,
+    middleware::Next,
+    web, HttpMessage, HttpResponse,
+};
+
+const RATELIMIT_LIMIT: &str = "ratelimit-limit";
+const RATELIMIT_REMAINING: &str = "ratelimit-remaining";
+const RATELIMIT_RESET: &str = "ratelimit-reset";
+
+/// Headers browsers need to be allowed to read.
+pub const RATE_LIMIT_HEADERS: [&str; 4] = [
+    RATELIMIT_LIMIT,
+    RATELIMIT_REMAINING,
+    RATELIMIT_RESET,
+    "retry-after",
+];
+
+pub async fn limit_writes(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    enforce(Budget::Writes, req, next).await
+}
+
+pub async fn limit_presigns(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    enforce(Budget::Presigns, req, next).await
+}
+
+pub async fn limit_expensive_reads(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    enforce(Budget::ExpensiveReads, req, next).await
+}
+
+/// Counts the request against `budget` and answers 429 once the client has
+/// used it up. Requests go through when the limiter itself fails, since
+/// refusing every request is worse than a burst getting past.
+async fn enforce<B: MessageBody + 'static>(
+    budget: Budget,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, actix_web::Error> {
+    let (Some(limiter), Some(settings)) = (
+        req.app_data::<web::Data<dyn RateLimiter>>().cloned(),
+        req.app_data::<web::Data<RateLimitSettings>>().cloned(),
+    ) else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+
+    let client = client_key(&req, &settings).await;
+    let decision = match limiter.hit(budget, &client, budget.limit(&settings)).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            tracing::error!(error.cause_chain = ?e, budget = budget.name(), "Failed to check the rate limit");
+            return next
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body);
+        }
+    };
+
+    if !decision.allowed {
+        tracing::info!(budget = budget.name(), %client, "Rate limit exceeded");
+        let mut response = HttpResponse::from(Problem::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            ProblemCode::RateLimited,
+            "Too many requests, try again later",
+        ));
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(reset_secs(&decision)));
+        insert_headers(response.headers_mut(), &decision);
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut response = next.call(req).await?;
+    insert_headers(response.headers_mut(), &decision);
+    Ok(response.map_into_left_body())
+}
+
+/// Who the request is counted against: the user when signed in, the client
+/// IP otherwise. A bearer token on an open route only counts when it is
+/// valid, so clients cannot dodge the IP budget by making tokens up.
+async fn client_key(req: &ServiceRequest, settings: &RateLimitSettings) -> String {
+    let user_id = req.extensions().get::<UserId>().copied();
+    let user_id = match (user_id, req.app_data::<web::Data<CognitoSettings>>()) {
+        (Some(user_id), _) => Some(user_id),
+        (None, Some(cognito)) => try_extract_user_id(req.headers(), cognito).await,
+        (None, None) => None,
+    };
+    if let Some(user_id) = user_id {
+        return format!("user:{user_id}");
+    }
+
+    let ip = forwarded_client_ip(req.headers(), settings.trusted_proxy_hops)
+        .or_else(|| req.peer_addr().map(|addr| addr.ip().to_string()));
+    format!("ip:{}", ip.as_deref().unwrap_or("unknown"))
+}
+
+/// The `X-Forwarded-For` entry added by the outermost of `hops` trusted
+/// proxies, i.e. the address that proxy saw the request come from. Entries
+/// further left are set by the client and not trusted.
+fn forwarded_client_ip(headers: &HeaderMap, hops: usize) -> Option<String> {
+    if hops == 0 {
+        return None;
+    }
+    let entries: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .map(str::trim)
+        .collect();
+    let index = entries.len().checked_sub(hops)?;
+    entries
+        .get(index)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+}
+
+/// Whole seconds until the window resets, rounded up so clients retrying
+/// after it are not refused again.
+fn reset_secs(decision: &Decision) -> u64 {
+    decision.reset_after.as_secs() + u64::from(decision.reset_after.subsec_nanos() > 0)
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &Decision) {
+    for (name, value) in [
+        (RATELIMIT_LIMIT, u64::from(decision.limit)),
+        (RATELIMIT_REMAINING, u64::from(decision.remaining)),
+        (RATELIMIT_RESET, reset_secs(decision)),
+    ] {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::forwarded_client_ip;
+    use actix_web::http::header::{HeaderMap, HeaderValue, X_FORWARDED_FOR};
+    use claims::{assert_none, assert_some_eq};
+
+    fn forwarded_for(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(X_FORWARDED_FOR, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn the_entry_added_by_the_outermost_trusted_proxy_is_used() {
+        let headers = forwarded_for(&["6.6.6.6, 203.0.113.7", "10.0.0.2"]);
+
+        assert_some_eq!(forwarded_client_ip(&headers, 1), "10.0.0.2");
+        assert_some_eq!(forwarded_client_ip(&headers, 2), "203.0.113.7");
+    }
+
+    #[test]
+    fn the_header_is_ignored_without_trusted_proxies() {
+        let headers = forwarded_for(&["203.0.113.7"]);
+
+        assert_none!(forwarded_client_ip(&headers, 0));
+    }
+
+    #[test]
+    fn requests_that_skipped_a_proxy_fall_back_to_the_peer() {
+        assert_none!(forwarded_client_ip(&forwarded_for(&["203.0.113.7"]), 2));
+        assert_none!(forwarded_client_ip(&HeaderMap::new(), 1));
+    }
+}
@@ -0,0 +1,69 @@
+mod memory;
+mod middleware;
+mod postgres;
+
+pub use memory::InMemoryRateLimiter;
+pub use middleware::{limit_expensive_reads, limit_presigns, limit_writes, RATE_LIMIT_HEADERS};
+pub use postgres::{prune_rate_limit_windows, PostgresRateLimiter};
+
+use crate::configuration::{RateLimit, RateLimitSettings};
+use std::time::Duration;
+
+/// Routes that draw from the same allowance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Writes,
+    Presigns,
+    ExpensiveReads,
+}
+
+impl Budget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Budget::Writes => "writes",
+            Budget::Presigns => "presigns",
+            Budget::ExpensiveReads => "expensive_reads",
+        }
+    }
+
+    pub fn limit(&self, settings: &RateLimitSettings) -> RateLimit {
+        match self {
+            Budget::Writes => settings.writes,
+            Budget::Presigns => settings.presigns,
+            Budget::ExpensiveReads => settings.expensive_reads,
+        }
+    }
+}
+
+/// Where a client stands after a request was counted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time left until the window starts over.
+    pub reset_after: Duration,
+}
+
+impl Decision {
+    /// `count` includes the request being decided on.
+    pub fn new(limit: RateLimit, count: u32, reset_after: Duration) -> Self {
+        Self {
+            allowed: count <= limit.requests,
+            limit: limit.requests,
+            remaining: limit.requests.saturating_sub(count),
+            reset_after,
+        }
+    }
+}
+
+/// Counts requests in fixed windows, per budget and client.
+#[async_trait::async_trait]
+pub trait RateLimiter: Send + Sync {
+    async fn hit(
+        &self,
+        budget: Budget,
+        client: &str,
+        limit: RateLimit,
+    ) -> Result<Decision, anyhow::Error>;
+}
@@ -0,0 +1,79 @@
+use crate::configuration::RateLimit;
+use crate::metrics::{acquire_connection, time_query};
+use crate::rate_limiting::{Budget, Decision, RateLimiter};
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::PgPool;
+
+/// Shares the counts between every instance using the same database.
+#[derive(Clone)]
+pub struct PostgresRateLimiter {
+    pool: PgPool,
+}
+
+impl PostgresRateLimiter {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for PostgresRateLimiter {
+    async fn hit(
+        &self,
+        budget: Budget,
+        client: &str,
+        limit: RateLimit,
+    ) -> Result<Decision, anyhow::Error> {
+        // The CASEs both see the row as it was, so a window that ran out is
+        // replaced in one statement.
+        let window = time_query("hit_rate_limit", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            sqlx::query!(
+                r#"
+                INSERT INTO rate_limits (budget, client, window_end, count)
+                VALUES ($1, $2, NOW() + make_interval(secs => $3), 1)
+                ON CONFLICT (budget, client) DO UPDATE SET
+                    count = CASE
+                        WHEN rate_limits.window_end <= NOW() THEN 1
+                        ELSE rate_limits.count + 1
+                    END,
+                    window_end = CASE
+                        WHEN rate_limits.window_end <= NOW() THEN EXCLUDED.window_end
+                        ELSE rate_limits.window_end
+                    END
+                RETURNING count, window_end
+                "#,
+                budget.name(),
+                client,
+                limit.period_secs as f64
+            )
+            .fetch_one(&mut *connection)
+            .await
+            .context("Failed to count the request against its rate limit.")
+        })
+        .await?;
+
+        let reset_after = (window.window_end - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        Ok(Decision::new(
+            limit,
+            u32::try_from(window.count).unwrap_or(u32::MAX),
+            reset_after,
+        ))
+    }
+}
+
+/// Deletes the windows that ran out. Returns how many were deleted.
+#[tracing::instrument(name = "Pruning rate limit windows", skip(pool))]
+pub async fn prune_rate_limit_windows(pool: &PgPool) -> Result<u64, anyhow::Error> {
+    let result = sqlx::query!("DELETE FROM rate_limits WHERE window_end <= NOW()")
+        .execute(pool)
+        .await
+        .context("Failed to prune rate limit windows.")?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,266 @@
+use crate::api::models::tb303::{TB303Bar, TB303Pattern, TB303Step};
+use crate::domain::{CollaboratorRole, Knob, NewTB303Pattern};
+use crate::repository::{
+    PatternRecord, PatternRepository, PublicPatternRecord, UserRecord, UserRepository,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Keeps everything in memory, so handlers can be exercised without
+/// Postgres. Uploads and webhook events are not modelled.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    users: HashMap<Uuid, StoredUser>,
+    patterns: HashMap<Uuid, StoredPattern>,
+    collaborators: HashMap<(Uuid, Uuid), CollaboratorRole>,
+    next_sequence: u64,
+}
+
+struct StoredUser {
+    username: String,
+    created_at: DateTime<Utc>,
+}
+
+struct StoredPattern {
+    user_id: Uuid,
+    /// Breaks ties between patterns created within the same instant.
+    sequence: u64,
+    pattern: TB303Pattern,
+}
+
+impl InMemoryRepository {
+    pub fn add_user(&self, user_id: Uuid, username: &str) {
+        self.state.lock().unwrap().users.insert(
+            user_id,
+            StoredUser {
+                username: username.to_string(),
+                created_at: Utc::now(),
+            },
+        );
+    }
+
+    pub fn add_collaborator(&self, pattern_id: Uuid, user_id: Uuid, role: CollaboratorRole) {
+        self.state
+            .lock()
+            .unwrap()
+            .collaborators
+            .insert((pattern_id, user_id), role);
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.state.lock().unwrap().patterns.len()
+    }
+}
+
+fn to_pattern(pattern_id: Uuid, new_pattern: &NewTB303Pattern) -> TB303Pattern {
+    let now = Utc::now();
+    let knob = |knob: &Option<Knob>| Some(knob.as_ref().map_or(0, |k| *k.as_ref()));
+    TB303Pattern {
+        id: Some(pattern_id),
+        name: new_pattern.name.as_ref().to_string(),
+        author: new_pattern.author.as_ref().map(|a| a.as_ref().to_string()),
+        title: new_pattern.title.as_ref().map(|t| t.as_ref().to_string()),
+        description: new_pattern
+            .description
+            .as_ref()
+            .map(|d| d.as_ref().to_string()),
+        tempo: new_pattern.tempo.as_ref().map(|t| *t.as_ref()),
+        waveform: new_pattern
+            .waveform
+            .as_ref()
+            .map(|w| w.as_ref().to_string()),
+        triplets: Some(new_pattern.triplets.unwrap_or(false)),
+        tuning: knob(&new_pattern.tuning),
+        cut_off_freq: knob(&new_pattern.cut_off_freq),
+        resonance: knob(&new_pattern.resonance),
+        env_mod: knob(&new_pattern.env_mod),
+        decay: knob(&new_pattern.decay),
+        accent: knob(&new_pattern.accent),
+        is_public: Some(new_pattern.is_public.unwrap_or(false)),
+        created_at: Some(now),
+        updated_at: Some(now),
+        audio_url: None,
+        cover_url: None,
+        bars: new_pattern
+            .bars
+            .iter()
+            .map(|bar| TB303Bar {
+                id: Uuid::new_v4(),
+                number: bar.number,
+                steps: bar
+                    .steps
+                    .iter()
+                    .map(|step| TB303Step {
+                        id: Uuid::new_v4(),
+                        number: *step.number.as_ref(),
+                        note: step.note.as_ref().map(|n| n.as_ref().to_string()),
+                        transpose: step.transpose.as_ref().map(|t| t.as_ref().to_string()),
+                        time: Some(step.time.as_ref().to_string()),
+                        accent: Some(step.accent.unwrap_or(false)),
+                        slide: Some(step.slide.unwrap_or(false)),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+#[async_trait::async_trait]
+impl PatternRepository for InMemoryRepository {
+    async fn fetch_pattern(
+        &self,
+        pattern_id: Uuid,
+    ) -> Result<Option<PatternRecord>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.patterns.get(&pattern_id).map(|stored| PatternRecord {
+            user_id: stored.user_id,
+            audio_key: None,
+            cover_key: None,
+            cover_variant_sizes: Vec::new(),
+            pattern: stored.pattern.clone(),
+        }))
+    }
+
+    async fn fetch_collaborator_role(
+        &self,
+        pattern_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<CollaboratorRole>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.collaborators.get(&(pattern_id, user_id)).copied())
+    }
+
+    async fn fetch_random_public_pattern_id(&self) -> Result<Option<Uuid>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        let public: Vec<Uuid> = state
+            .patterns
+            .iter()
+            .filter(|(_, stored)| stored.pattern.is_public == Some(true))
+            .map(|(id, _)| *id)
+            .collect();
+        if public.is_empty() {
+            return Ok(None);
+        }
+        let index = (Uuid::new_v4().as_u128() % public.len() as u128) as usize;
+        Ok(Some(public[index]))
+    }
+
+    async fn fetch_public_pattern_updated_at(
+        &self,
+        pattern_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .patterns
+            .get(&pattern_id)
+            .filter(|stored| stored.pattern.is_public == Some(true))
+            .and_then(|stored| stored.pattern.updated_at))
+    }
+
+    async fn list_public_patterns(
+        &self,
+        limit: i64,
+        offset: i64,
+        ascending: bool,
+    ) -> Result<(Vec<PublicPatternRecord>, i64), anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        let mut public: Vec<(Uuid, &StoredPattern)> = state
+            .patterns
+            .iter()
+            .filter(|(_, stored)| stored.pattern.is_public == Some(true))
+            .map(|(id, stored)| (*id, stored))
+            .collect();
+        public.sort_by_key(|(_, stored)| (stored.pattern.created_at, stored.sequence));
+        if !ascending {
+            public.reverse();
+        }
+        let total = public.len() as i64;
+
+        let records = public
+            .into_iter()
+            .filter_map(|(pattern_id, stored)| {
+                let user = state.users.get(&stored.user_id)?;
+                Some(PublicPatternRecord {
+                    pattern_id,
+                    name: stored.pattern.name.clone(),
+                    author: stored.pattern.author.clone(),
+                    title: stored.pattern.title.clone(),
+                    created_at: stored.pattern.created_at.unwrap_or_default(),
+                    updated_at: stored.pattern.updated_at.unwrap_or_default(),
+                    username: user.username.clone(),
+                    avatar_key: None,
+                    avatar_variant_sizes: Vec::new(),
+                    cover_key: None,
+                    cover_variant_sizes: Vec::new(),
+                })
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+
+        Ok((records, total))
+    }
+
+    async fn create_pattern(
+        &self,
+        user_id: Uuid,
+        new_pattern: &NewTB303Pattern,
+    ) -> Result<Uuid, anyhow::Error> {
+        let pattern_id = Uuid::new_v4();
+        let mut state = self.state.lock().unwrap();
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.patterns.insert(
+            pattern_id,
+            StoredPattern {
+                user_id,
+                sequence,
+                pattern: to_pattern(pattern_id, new_pattern),
+            },
+        );
+        Ok(pattern_id)
+    }
+
+    async fn fetch_pattern_owner(&self, pattern_id: Uuid) -> Result<Option<Uuid>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.patterns.get(&pattern_id).map(|stored| stored.user_id))
+    }
+
+    async fn delete_pattern(
+        &self,
+        pattern_id: Uuid,
+        owner_id: Uuid,
+    ) -> Result<bool, anyhow::Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.patterns.get(&pattern_id).map(|stored| stored.user_id) != Some(owner_id) {
+            return Ok(false);
+        }
+        state.patterns.remove(&pattern_id);
+        state.collaborators.retain(|(id, _), _| *id != pattern_id);
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn fetch_user(&self, user_id: Uuid) -> Result<Option<UserRecord>, anyhow::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.users.get(&user_id).map(|user| UserRecord {
+            user_id,
+            username: user.username.clone(),
+            avatar_key: None,
+            avatar_variant_sizes: Vec::new(),
+            banner_key: None,
+            banner_variant_sizes: Vec::new(),
+            created_at: user.created_at,
+            updated_at: user.created_at,
+        }))
+    }
+}
@@ -0,0 +1,103 @@
+mod memory;
+mod postgres;
+
+pub use memory::*;
+pub use postgres::*;
+
+use crate::api::models::tb303::TB303Pattern;
+use crate::domain::{CollaboratorRole, NewTB303Pattern};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A stored pattern together with what the API needs to decide who may see
+/// it. Media are returned as object keys; turning them into URLs is up to the
+/// caller.
+pub struct PatternRecord {
+    pub user_id: Uuid,
+    pub audio_key: Option<String>,
+    pub cover_key: Option<String>,
+    pub cover_variant_sizes: Vec<i32>,
+    pub pattern: TB303Pattern,
+}
+
+pub struct PublicPatternRecord {
+    pub pattern_id: Uuid,
+    pub name: String,
+    pub author: Option<String>,
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub username: String,
+    pub avatar_key: Option<String>,
+    pub avatar_variant_sizes: Vec<i32>,
+    pub cover_key: Option<String>,
+    pub cover_variant_sizes: Vec<i32>,
+}
+
+pub struct UserRecord {
+    pub user_id: Uuid,
+    pub username: String,
+    pub avatar_key: Option<String>,
+    pub avatar_variant_sizes: Vec<i32>,
+    pub banner_key: Option<String>,
+    pub banner_variant_sizes: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where patterns are kept. Implementations only store and retrieve; who may
+/// read or change a pattern is decided by the handlers.
+#[async_trait::async_trait]
+pub trait PatternRepository: Send + Sync {
+    /// Returns `None` when there is no pattern with `pattern_id`, whoever
+    /// owns it.
+    async fn fetch_pattern(&self, pattern_id: Uuid)
+        -> Result<Option<PatternRecord>, anyhow::Error>;
+
+    /// Returns the role `user_id` has been granted on `pattern_id`, if any.
+    /// The pattern owner has no collaborator role.
+    async fn fetch_collaborator_role(
+        &self,
+        pattern_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<CollaboratorRole>, anyhow::Error>;
+
+    async fn fetch_random_public_pattern_id(&self) -> Result<Option<Uuid>, anyhow::Error>;
+
+    /// Returns when the pattern was last changed, or `None` when it is gone
+    /// or private. Cheap enough to check cached copies against on every
+    /// read.
+    async fn fetch_public_pattern_updated_at(
+        &self,
+        pattern_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, anyhow::Error>;
+
+    /// Returns a page of public patterns ordered by creation time, and the
+    /// number of public patterns in total.
+    async fn list_public_patterns(
+        &self,
+        limit: i64,
+        offset: i64,
+        ascending: bool,
+    ) -> Result<(Vec<PublicPatternRecord>, i64), anyhow::Error>;
+
+    /// Saves a new pattern owned by `user_id` and returns its ID. Webhook
+    /// events for the new pattern are queued with it.
+    async fn create_pattern(
+        &self,
+        user_id: Uuid,
+        new_pattern: &NewTB303Pattern,
+    ) -> Result<Uuid, anyhow::Error>;
+
+    async fn fetch_pattern_owner(&self, pattern_id: Uuid) -> Result<Option<Uuid>, anyhow::Error>;
+
+    /// Deletes the pattern if it is still owned by `owner_id`. Returns false
+    /// if there was no such pattern.
+    async fn delete_pattern(&self, pattern_id: Uuid, owner_id: Uuid)
+        -> Result<bool, anyhow::Error>;
+}
+
+#[async_trait::async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn fetch_user(&self, user_id: Uuid) -> Result<Option<UserRecord>, anyhow::Error>;
+}
@@ -0,0 +1,389 @@
+use crate::api::models::tb303::{TB303Bar, TB303Pattern, TB303Step};
+use crate::domain::{CollaboratorRole, NewTB303Pattern};
+use crate::metrics::{acquire_connection, begin_transaction, time_query};
+use crate::repository::{
+    PatternRecord, PatternRepository, PublicPatternRecord, UserRecord, UserRepository,
+};
+use crate::routes::patterns::{
+    delete_pattern, enqueue_created_events, fetch_collaborator_role, insert_bars_tb303,
+    insert_pattern,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct PostgresRepository {
+    pool: PgPool,
+}
+
+impl PostgresRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PublicPatternRow {
+    pattern_id: Uuid,
+    name: String,
+    author: Option<String>,
+    title: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    username: String,
+    avatar_key: Option<String>,
+    avatar_variant_sizes: Option<Vec<i32>>,
+    cover_key: Option<String>,
+    cover_variant_sizes: Option<Vec<i32>>,
+}
+
+#[async_trait::async_trait]
+impl PatternRepository for PostgresRepository {
+    #[tracing::instrument(name = "Fetching pattern from the database", skip(self))]
+    async fn fetch_pattern(
+        &self,
+        pattern_id: Uuid,
+    ) -> Result<Option<PatternRecord>, anyhow::Error> {
+        time_query("fetch_pattern", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            let Some(pattern) = sqlx::query!(
+                r#"
+                SELECT
+                    p.pattern_id, p.user_id, p.name, p.author, p.title, p.description,
+                    p.waveform, p.triplets, p.tempo, p.tuning, p.cut_off_freq, p.resonance,
+                    p.env_mod, p.decay, p.accent, p.is_public, p.created_at, p.updated_at,
+                    a.key AS "audio_key?", c.key AS "cover_key?",
+                    cu.variant_sizes AS "cover_variant_sizes?"
+                FROM patterns_tb303 p
+                LEFT JOIN pattern_media a ON a.pattern_id = p.pattern_id AND a.kind = 'audio'
+                LEFT JOIN pattern_media c ON c.pattern_id = p.pattern_id AND c.kind = 'cover'
+                LEFT JOIN uploads cu ON cu.key = c.key
+                WHERE p.pattern_id = $1
+                "#,
+                pattern_id
+            )
+            .fetch_optional(&mut *connection)
+            .await
+            .context("Failed to fetch pattern details.")?
+            else {
+                return Ok(None);
+            };
+
+            let rows = sqlx::query!(
+                r#"
+                SELECT
+                    b.bar_id, b.number AS bar_number,
+                    s.step_id AS "step_id?", s.number AS "step_number?",
+                    s.note, s.transpose, s.time, s.accent, s.slide
+                FROM bars_tb303 b
+                LEFT JOIN steps_tb303 s ON s.bar_id = b.bar_id
+                WHERE b.pattern_id = $1
+                ORDER BY b.number, s.number
+                "#,
+                pattern_id
+            )
+            .fetch_all(&mut *connection)
+            .await
+            .context("Failed to fetch bars and steps for pattern.")?;
+
+            let mut bars: Vec<TB303Bar> = Vec::new();
+
+            for row in rows {
+                if bars.last().map(|b: &TB303Bar| b.id) != Some(row.bar_id) {
+                    bars.push(TB303Bar {
+                        id: row.bar_id,
+                        number: row.bar_number,
+                        steps: Vec::new(),
+                    });
+                }
+                if let Some(step_id) = row.step_id {
+                    bars.last_mut().unwrap().steps.push(TB303Step {
+                        id: step_id,
+                        number: row.step_number.unwrap(),
+                        note: row.note,
+                        transpose: row.transpose,
+                        time: row.time,
+                        accent: row.accent,
+                        slide: row.slide,
+                    });
+                }
+            }
+
+            Ok(Some(PatternRecord {
+                user_id: pattern.user_id,
+                audio_key: pattern.audio_key,
+                cover_key: pattern.cover_key,
+                cover_variant_sizes: pattern.cover_variant_sizes.unwrap_or_default(),
+                pattern: TB303Pattern {
+                    id: Some(pattern.pattern_id),
+                    name: pattern.name,
+                    author: pattern.author,
+                    title: pattern.title,
+                    description: pattern.description,
+                    tempo: pattern.tempo,
+                    tuning: pattern.tuning,
+                    waveform: pattern.waveform,
+                    triplets: pattern.triplets,
+                    cut_off_freq: pattern.cut_off_freq,
+                    resonance: pattern.resonance,
+                    env_mod: pattern.env_mod,
+                    decay: pattern.decay,
+                    accent: pattern.accent,
+                    created_at: Some(pattern.created_at),
+                    updated_at: Some(pattern.updated_at),
+                    is_public: pattern.is_public,
+                    audio_url: None,
+                    cover_url: None,
+                    bars,
+                },
+            }))
+        })
+        .await
+    }
+
+    async fn fetch_collaborator_role(
+        &self,
+        pattern_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<CollaboratorRole>, anyhow::Error> {
+        time_query("fetch_collaborator_role", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            fetch_collaborator_role(&mut *connection, pattern_id, user_id).await
+        })
+        .await
+    }
+
+    async fn fetch_random_public_pattern_id(&self) -> Result<Option<Uuid>, anyhow::Error> {
+        time_query("fetch_random_public_pattern_id", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            sqlx::query_scalar!(
+                r#"
+                SELECT pattern_id
+                FROM patterns_tb303
+                WHERE is_public = true
+                ORDER BY RANDOM()
+                LIMIT 1
+                "#
+            )
+            .fetch_optional(&mut *connection)
+            .await
+            .context("Failed to get a random pattern ID from database.")
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Checking a public pattern in the database", skip(self))]
+    async fn fetch_public_pattern_updated_at(
+        &self,
+        pattern_id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, anyhow::Error> {
+        time_query("fetch_public_pattern_updated_at", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            sqlx::query_scalar!(
+                r#"
+                SELECT updated_at FROM patterns_tb303
+                WHERE pattern_id = $1 AND is_public = true
+                "#,
+                pattern_id
+            )
+            .fetch_optional(&mut *connection)
+            .await
+            .context("Failed to check a public pattern.")
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Listing public patterns from the database", skip(self))]
+    async fn list_public_patterns(
+        &self,
+        limit: i64,
+        offset: i64,
+        ascending: bool,
+    ) -> Result<(Vec<PublicPatternRecord>, i64), anyhow::Error> {
+        time_query("list_public_patterns", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            let total: i64 =
+                sqlx::query_scalar!("SELECT COUNT(*) FROM patterns_tb303 WHERE is_public = true")
+                    .fetch_one(&mut *connection)
+                    .await
+                    .context("Failed to count public patterns.")?
+                    .unwrap_or(0);
+
+            let mut builder = sqlx::QueryBuilder::new(
+                r#"SELECT p.pattern_id, p.name, p.author, p.title, p.created_at, p.updated_at,
+                        u.username, u.avatar_key, a.variant_sizes AS avatar_variant_sizes,
+                        m.key AS cover_key, c.variant_sizes AS cover_variant_sizes
+                 FROM patterns_tb303 p
+                 JOIN users u ON u.user_id = p.user_id
+                 LEFT JOIN uploads a ON a.key = u.avatar_key
+                 LEFT JOIN pattern_media m ON m.pattern_id = p.pattern_id AND m.kind = 'cover'
+                 LEFT JOIN uploads c ON c.key = m.key
+                 WHERE p.is_public = true"#,
+            );
+
+            builder.push(" ORDER BY p.created_at ");
+            builder.push(if ascending { "ASC" } else { "DESC" });
+            builder.push(" LIMIT ").push_bind(limit);
+            builder.push(" OFFSET ").push_bind(offset);
+
+            let rows = builder
+                .build_query_as::<PublicPatternRow>()
+                .fetch_all(&mut *connection)
+                .await
+                .context("Failed to fetch public patterns.")?;
+
+            let records = rows
+                .into_iter()
+                .map(|r| PublicPatternRecord {
+                    pattern_id: r.pattern_id,
+                    name: r.name,
+                    author: r.author,
+                    title: r.title,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    username: r.username,
+                    avatar_key: r.avatar_key,
+                    avatar_variant_sizes: r.avatar_variant_sizes.unwrap_or_default(),
+                    cover_key: r.cover_key,
+                    cover_variant_sizes: r.cover_variant_sizes.unwrap_or_default(),
+                })
+                .collect();
+
+            Ok((records, total))
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Saving new pattern in the database", skip(self, new_pattern))]
+    async fn create_pattern(
+        &self,
+        user_id: Uuid,
+        new_pattern: &NewTB303Pattern,
+    ) -> Result<Uuid, anyhow::Error> {
+        time_query("create_pattern", async {
+            let mut transaction = begin_transaction(&self.pool)
+                .await
+                .context("Failed to start a new transaction.")?;
+
+            let pattern_id = insert_pattern(&mut transaction, new_pattern, &user_id.into())
+                .await
+                .context("Failed to insert new pattern in the database.")?;
+
+            insert_bars_tb303(&mut transaction, pattern_id, &new_pattern.bars)
+                .await
+                .context("Failed to insert new pattern bars and steps.")?;
+
+            enqueue_created_events(&mut transaction, pattern_id, new_pattern).await?;
+
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit the transaction to save tb303 pattern.")?;
+
+            Ok(pattern_id)
+        })
+        .await
+    }
+
+    async fn fetch_pattern_owner(&self, pattern_id: Uuid) -> Result<Option<Uuid>, anyhow::Error> {
+        time_query("fetch_pattern_owner", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            sqlx::query_scalar!(
+                r#"SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1"#,
+                pattern_id
+            )
+            .fetch_optional(&mut *connection)
+            .await
+            .context("Failed to fetch pattern owner.")
+        })
+        .await
+    }
+
+    #[tracing::instrument(name = "Deleting pattern from the database", skip(self))]
+    async fn delete_pattern(
+        &self,
+        pattern_id: Uuid,
+        owner_id: Uuid,
+    ) -> Result<bool, anyhow::Error> {
+        time_query("delete_pattern", async {
+            let mut transaction = begin_transaction(&self.pool)
+                .await
+                .context("Failed to begin a database transaction.")?;
+
+            let current_owner = sqlx::query_scalar!(
+                r#"SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE"#,
+                pattern_id
+            )
+            .fetch_optional(&mut *transaction)
+            .await
+            .context("Failed to fetch pattern owner.")?;
+
+            if current_owner != Some(owner_id) {
+                return Ok(false);
+            }
+
+            let deleted = delete_pattern(&mut transaction, pattern_id).await?;
+
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit the database transaction.")?;
+
+            Ok(deleted)
+        })
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl UserRepository for PostgresRepository {
+    #[tracing::instrument(name = "Fetching user from the database", skip(self))]
+    async fn fetch_user(&self, user_id: Uuid) -> Result<Option<UserRecord>, anyhow::Error> {
+        time_query("fetch_user", async {
+            let mut connection = acquire_connection(&self.pool)
+                .await
+                .context("Failed to get a database connection.")?;
+            let user = sqlx::query!(
+                r#"
+                SELECT u.user_id, u.username, u.avatar_key, u.banner_key, u.created_at, u.updated_at,
+                       avatar.variant_sizes AS "avatar_variant_sizes?",
+                       banner.variant_sizes AS "banner_variant_sizes?"
+                FROM users u
+                LEFT JOIN uploads avatar ON avatar.key = u.avatar_key
+                LEFT JOIN uploads banner ON banner.key = u.banner_key
+                WHERE u.user_id = $1
+                "#,
+                user_id
+            )
+            .fetch_optional(&mut *connection)
+            .await
+            .context("Failed to fetch user")?;
+
+            Ok(user.map(|user| UserRecord {
+                user_id: user.user_id,
+                username: user.username,
+                avatar_key: user.avatar_key,
+                avatar_variant_sizes: user.avatar_variant_sizes.unwrap_or_default(),
+                banner_key: user.banner_key,
+                banner_variant_sizes: user.banner_variant_sizes.unwrap_or_default(),
+                created_at: user.created_at,
+                updated_at: user.updated_at,
+            }))
+        })
+        .await
+    }
+}
@@ -0,0 +1,275 @@
+use crate::api::models::tb303::TB303Pattern;
+use crate::problem::Problem;
+use crate::routes::patterns::{fetch_pattern_by_id, GetPatternError};
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use anyhow::Context;
+use sqlx::PgPool;
+use url::Url;
+use uuid::Uuid;
+
+pub const PROVIDER_NAME: &str = "acidarchive";
+/// Pages only show public patterns, so shared caches may keep them briefly.
+const PAGE_MAX_AGE_SECS: u32 = 5 * 60;
+
+#[derive(thiserror::Error)]
+pub enum EmbedError {
+    #[error("Pattern not found")]
+    PatternNotFound,
+    #[error("URL does not point to a pattern")]
+    UnknownUrl,
+    #[error("Only the json format is supported")]
+    UnsupportedFormat,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl From<GetPatternError> for EmbedError {
+    fn from(e: GetPatternError) -> Self {
+        match e {
+            GetPatternError::UnexpectedError(e) => EmbedError::UnexpectedError(e),
+            _ => EmbedError::PatternNotFound,
+        }
+    }
+}
+
+impl std::fmt::Debug for EmbedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for EmbedError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            EmbedError::PatternNotFound | EmbedError::UnknownUrl => StatusCode::NOT_FOUND,
+            EmbedError::UnsupportedFormat => StatusCode::NOT_IMPLEMENTED,
+            EmbedError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+/// A public pattern with what embeds show about its owner.
+pub struct EmbedPattern {
+    pub pattern: TB303Pattern,
+    pub username: String,
+}
+
+impl EmbedPattern {
+    /// One line about the pattern, e.g. `Acid Trax by Phuture · shared by
+    /// username · 120 BPM · 2 bars`.
+    pub fn summary(&self) -> String {
+        let pattern = &self.pattern;
+        let song = song_credit(pattern.title.as_deref(), pattern.author.as_deref());
+        let bars = match pattern.bars.len() {
+            1 => "1 bar".to_string(),
+            n => format!("{n} bars"),
+        };
+
+        song.into_iter()
+            .chain(Some(format!("shared by {}", self.username)))
+            .chain(pattern.tempo.map(|tempo| format!("{tempo} BPM")))
+            .chain(Some(bars))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+/// Only public patterns can be embedded, whoever asks.
+#[tracing::instrument(name = "Fetching pattern for embedding", skip(pool, storage))]
+pub async fn fetch_embed_pattern(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+) -> Result<EmbedPattern, EmbedError> {
+    let pattern = fetch_pattern_by_id(pool, storage, pattern_id, None).await?;
+
+    let username = sqlx::query_scalar!(
+        r#"
+        SELECT u.username FROM patterns_tb303 p
+        JOIN users u ON u.user_id = p.user_id
+        WHERE p.pattern_id = $1
+        "#,
+        pattern_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to fetch pattern owner.")?
+    .ok_or(EmbedError::PatternNotFound)?;
+
+    Ok(EmbedPattern { pattern, username })
+}
+
+pub fn share_url(base_url: &str, pattern_id: Uuid) -> String {
+    format!(
+        "{}/patterns/tb303/{}",
+        base_url.trim_end_matches('/'),
+        pattern_id
+    )
+}
+
+pub fn player_url(base_url: &str, pattern_id: Uuid) -> String {
+    format!(
+        "{}/embed/patterns/tb303/{}",
+        base_url.trim_end_matches('/'),
+        pattern_id
+    )
+}
+
+/// Returns the pattern a share page or player URL of this API points to.
+pub fn pattern_id_from_url(base_url: &str, url: &str) -> Option<Uuid> {
+    let base = Url::parse(base_url).ok()?;
+    let url = Url::parse(url).ok()?;
+    if url.origin() != base.origin() {
+        return None;
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["patterns", "tb303", id] | ["embed", "patterns", "tb303", id] => id.parse().ok(),
+        _ => None,
+    }
+}
+
+/// An HTML page with a policy that only allows what the page itself uses.
+pub fn html_page_response(html: String, content_security_policy: &str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(PAGE_MAX_AGE_SECS),
+        ]))
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .insert_header(("Content-Security-Policy", content_security_policy))
+        .body(html)
+}
+
+/// The record a pattern was transcribed from, e.g. `Acid Trax by Phuture`,
+/// as far as it is known.
+pub fn song_credit(title: Option<&str>, author: Option<&str>) -> Option<String> {
+    match (title, author) {
+        (Some(title), Some(author)) => Some(format!("{title} by {author}")),
+        (Some(title), None) => Some(title.to_string()),
+        (None, Some(author)) => Some(format!("by {author}")),
+        (None, None) => None,
+    }
+}
+
+/// Escapes text for HTML element content and quoted attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::tb303::TB303Pattern;
+    use crate::routes::embeds::{escape_html, pattern_id_from_url, EmbedPattern};
+    use uuid::Uuid;
+
+    const BASE_URL: &str = "https://api.example.com";
+
+    fn embed_pattern(
+        title: Option<&str>,
+        author: Option<&str>,
+        tempo: Option<i32>,
+    ) -> EmbedPattern {
+        EmbedPattern {
+            pattern: TB303Pattern {
+                id: Some(Uuid::new_v4()),
+                name: "First pattern".to_string(),
+                author: author.map(str::to_string),
+                title: title.map(str::to_string),
+                description: None,
+                tempo,
+                waveform: None,
+                triplets: None,
+                tuning: None,
+                cut_off_freq: None,
+                resonance: None,
+                env_mod: None,
+                decay: None,
+                accent: None,
+                is_public: Some(true),
+                created_at: None,
+                updated_at: None,
+                audio_url: None,
+                cover_url: None,
+                bars: Vec::new(),
+            },
+            username: "username".to_string(),
+        }
+    }
+
+    #[test]
+    fn summary_lists_everything_known_about_the_pattern() {
+        let pattern = embed_pattern(Some("Acid Trax"), Some("Phuture"), Some(120));
+
+        assert_eq!(
+            pattern.summary(),
+            "Acid Trax by Phuture · shared by username · 120 BPM · 0 bars"
+        );
+    }
+
+    #[test]
+    fn summary_skips_what_is_missing() {
+        let pattern = embed_pattern(None, Some("Phuture"), None);
+
+        assert_eq!(
+            pattern.summary(),
+            "by Phuture · shared by username · 0 bars"
+        );
+    }
+
+    #[test]
+    fn share_page_and_player_urls_are_recognised() {
+        let id = Uuid::new_v4();
+
+        for url in [
+            format!("{BASE_URL}/patterns/tb303/{id}"),
+            format!("{BASE_URL}/patterns/tb303/{id}/?ref=forum"),
+            format!("{BASE_URL}/embed/patterns/tb303/{id}"),
+        ] {
+            assert_eq!(pattern_id_from_url(BASE_URL, &url), Some(id), "{url}");
+        }
+    }
+
+    #[test]
+    fn urls_of_other_sites_or_pages_are_not_recognised() {
+        let id = Uuid::new_v4();
+
+        for url in [
+            format!("https://evil.example.com/patterns/tb303/{id}"),
+            format!("http://api.example.com/patterns/tb303/{id}"),
+            format!("{BASE_URL}/v1/patterns/tb303/{id}"),
+            format!("{BASE_URL}/patterns/tb303/not-a-uuid"),
+            "not a url".to_string(),
+        ] {
+            assert_eq!(pattern_id_from_url(BASE_URL, &url), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn markup_is_escaped() {
+        assert_eq!(
+            escape_html(r#"<script>"a" & 'b'</script>"#),
+            "&lt;script&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/script&gt;"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+mod embed_pattern;
+mod oembed;
+mod player;
+mod share_page;
+
+pub use embed_pattern::*;
+pub use oembed::*;
+pub use player::*;
+pub use share_page::*;
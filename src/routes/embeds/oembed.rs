@@ -0,0 +1,78 @@
+use crate::api::models::embeds::{OEmbedParams, OEmbedResponse};
+use crate::pattern_preview::preview_size;
+use crate::problem::Problem;
+use crate::routes::embeds::{
+    escape_html, fetch_embed_pattern, pattern_id_from_url, player_url, EmbedError, PROVIDER_NAME,
+};
+use crate::routes::patterns::preview_url;
+use crate::startup::ApplicationBaseUrl;
+use crate::storage::ObjectStorage;
+use actix_web::web;
+use sqlx::PgPool;
+
+/// Size of the player iframe unless the consumer asks for a smaller one.
+pub const PLAYER_WIDTH: u32 = 640;
+pub const PLAYER_HEIGHT: u32 = 240;
+
+#[utoipa::path(
+    get,
+    path = "/oembed",
+    params(OEmbedParams),
+    responses(
+        (status = 200, description = "oEmbed data of the pattern", body = OEmbedResponse),
+        (status = 404, description = "The URL is not a public pattern", body = Problem, content_type = "application/problem+json"),
+        (status = 501, description = "The requested format is not supported", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+)]
+#[tracing::instrument(name = "Providing oEmbed data", skip(pool, storage, base_url))]
+pub async fn get_oembed(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    params: web::Query<OEmbedParams>,
+) -> Result<web::Json<OEmbedResponse>, EmbedError> {
+    let params = params.into_inner();
+    if params
+        .format
+        .as_deref()
+        .is_some_and(|format| format != "json")
+    {
+        return Err(EmbedError::UnsupportedFormat);
+    }
+
+    let pattern_id = pattern_id_from_url(&base_url.0, &params.url).ok_or(EmbedError::UnknownUrl)?;
+    let embed = fetch_embed_pattern(pool.as_ref(), storage.as_ref(), pattern_id).await?;
+
+    let width = params
+        .maxwidth
+        .map_or(PLAYER_WIDTH, |max| max.min(PLAYER_WIDTH));
+    let height = params
+        .maxheight
+        .map_or(PLAYER_HEIGHT, |max| max.min(PLAYER_HEIGHT));
+    let (thumbnail_width, thumbnail_height) = preview_size(embed.pattern.bars.len());
+    let html = format!(
+        r#"<iframe src="{}" width="{width}" height="{height}" title="{}" frameborder="0" loading="lazy"></iframe>"#,
+        escape_html(&player_url(&base_url.0, pattern_id)),
+        escape_html(&embed.pattern.name)
+    );
+
+    Ok(web::Json(OEmbedResponse {
+        kind: "rich".to_string(),
+        version: "1.0".to_string(),
+        title: embed.pattern.name.clone(),
+        author_name: embed.username.clone(),
+        provider_name: PROVIDER_NAME.to_string(),
+        provider_url: base_url.0.clone(),
+        html,
+        width,
+        height,
+        thumbnail_url: preview_url(
+            &base_url.0,
+            pattern_id,
+            embed.pattern.updated_at.unwrap_or_default(),
+        ),
+        thumbnail_width: thumbnail_width as u32,
+        thumbnail_height: thumbnail_height as u32,
+    }))
+}
@@ -0,0 +1,66 @@
+use crate::pattern_preview::render_preview_svg;
+use crate::routes::embeds::{
+    escape_html, fetch_embed_pattern, html_page_response, share_url, EmbedError,
+};
+use crate::startup::ApplicationBaseUrl;
+use crate::storage::ObjectStorage;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The page oEmbed and Twitter cards put in an iframe: the step grid, a link
+/// back to the share page and the reference clip, if the pattern has one.
+#[tracing::instrument(name = "Rendering pattern player", skip(pool, storage, base_url))]
+pub async fn get_player(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<HttpResponse, EmbedError> {
+    let pattern_id = pattern_id.into_inner();
+    let embed = fetch_embed_pattern(pool.as_ref(), storage.as_ref(), pattern_id).await?;
+
+    let title = escape_html(&embed.pattern.name);
+    let summary = escape_html(&embed.summary());
+    let page_url = escape_html(&share_url(&base_url.0, pattern_id));
+    let audio = embed
+        .pattern
+        .audio_url
+        .as_deref()
+        .map(|url| {
+            format!(
+                r#"<audio controls preload="none" src="{}"></audio>"#,
+                escape_html(url)
+            )
+        })
+        .unwrap_or_default();
+    let grid = render_preview_svg(&embed.pattern.bars);
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ margin: 0; padding: 8px; background: #0a0a0a; color: #eeeeee; font: 14px sans-serif; }}
+a {{ color: #f0c419; }}
+p {{ margin: 0 0 8px; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; }}
+svg {{ display: block; width: 100%; height: auto; max-height: 160px; }}
+audio {{ width: 100%; margin-top: 8px; }}
+</style>
+</head>
+<body>
+<p><a href="{page_url}" target="_blank" rel="noopener">{title}</a> · {summary}</p>
+{grid}
+{audio}
+</body>
+</html>
+"#
+    );
+
+    Ok(html_page_response(
+        html,
+        "default-src 'none'; style-src 'unsafe-inline'; media-src *; frame-ancestors *",
+    ))
+}
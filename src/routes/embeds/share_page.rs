@@ -0,0 +1,98 @@
+use crate::pattern_preview::{preview_size, render_preview_svg};
+use crate::routes::embeds::{
+    escape_html, fetch_embed_pattern, html_page_response, player_url, share_url, EmbedError,
+    PLAYER_HEIGHT, PLAYER_WIDTH, PROVIDER_NAME,
+};
+use crate::routes::patterns::preview_url;
+use crate::startup::ApplicationBaseUrl;
+use crate::storage::ObjectStorage;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Crawlers of chat apps and forums read the meta tags; people following the
+/// link see the step grid.
+#[tracing::instrument(name = "Rendering pattern share page", skip(pool, storage, base_url))]
+pub async fn get_share_page(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<HttpResponse, EmbedError> {
+    let pattern_id = pattern_id.into_inner();
+    let embed = fetch_embed_pattern(pool.as_ref(), storage.as_ref(), pattern_id).await?;
+    let base_url = &base_url.0;
+
+    let page_url = share_url(base_url, pattern_id);
+    let image_url = preview_url(
+        base_url,
+        pattern_id,
+        embed.pattern.updated_at.unwrap_or_default(),
+    );
+    let (image_width, image_height) = preview_size(embed.pattern.bars.len());
+    let oembed_url = format!(
+        "{}/oembed?url={}&format=json",
+        base_url.trim_end_matches('/'),
+        url::form_urlencoded::byte_serialize(page_url.as_bytes()).collect::<String>()
+    );
+
+    let title = escape_html(&embed.pattern.name);
+    let summary = escape_html(&embed.summary());
+    let page_url = escape_html(&page_url);
+    let image_url = escape_html(&image_url);
+    let player_url = escape_html(&player_url(base_url, pattern_id));
+    let oembed_url = escape_html(&oembed_url);
+    let description = embed
+        .pattern
+        .description
+        .as_deref()
+        .map(|description| format!("<p>{}</p>", escape_html(description)))
+        .unwrap_or_default();
+    let grid = render_preview_svg(&embed.pattern.bars);
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} · {PROVIDER_NAME}</title>
+<meta name="description" content="{summary}">
+<link rel="canonical" href="{page_url}">
+<link rel="alternate" type="application/json+oembed" href="{oembed_url}" title="{title}">
+<meta property="og:type" content="website">
+<meta property="og:site_name" content="{PROVIDER_NAME}">
+<meta property="og:title" content="{title}">
+<meta property="og:description" content="{summary}">
+<meta property="og:url" content="{page_url}">
+<meta property="og:image" content="{image_url}">
+<meta property="og:image:type" content="image/svg+xml">
+<meta property="og:image:width" content="{image_width}">
+<meta property="og:image:height" content="{image_height}">
+<meta name="twitter:card" content="player">
+<meta name="twitter:title" content="{title}">
+<meta name="twitter:description" content="{summary}">
+<meta name="twitter:image" content="{image_url}">
+<meta name="twitter:player" content="{player_url}">
+<meta name="twitter:player:width" content="{PLAYER_WIDTH}">
+<meta name="twitter:player:height" content="{PLAYER_HEIGHT}">
+<style>
+body {{ margin: 0 auto; max-width: 960px; padding: 24px; background: #0a0a0a; color: #eeeeee; font-family: sans-serif; }}
+svg {{ width: 100%; height: auto; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>{summary}</p>
+{grid}
+{description}
+</body>
+</html>
+"#
+    );
+
+    Ok(html_page_response(
+        html,
+        "default-src 'none'; style-src 'unsafe-inline'; frame-ancestors 'none'",
+    ))
+}
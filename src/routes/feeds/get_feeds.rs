@@ -0,0 +1,219 @@
+use crate::feeds::{Feed, FeedFormat, FeedItem};
+use crate::http_cache::conditional_response;
+use crate::problem::Problem;
+use crate::routes::embeds::{share_url, PROVIDER_NAME};
+use crate::startup::ApplicationBaseUrl;
+use crate::utils::error_chain_fmt;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Feed readers only care about what is new.
+const FEED_LENGTH: i64 = 50;
+const FEED_MAX_AGE_SECS: u32 = 5 * 60;
+
+#[derive(thiserror::Error)]
+pub enum FeedError {
+    #[error("Unknown feed format {0}, expected atom, rss or json")]
+    UnknownFormat(String),
+    #[error("User {0} not found")]
+    UserNotFound(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for FeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for FeedError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            FeedError::UnknownFormat(_) | FeedError::UserNotFound(_) => StatusCode::NOT_FOUND,
+            FeedError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+fn parse_format(extension: &str) -> Result<FeedFormat, FeedError> {
+    FeedFormat::parse(extension).ok_or_else(|| FeedError::UnknownFormat(extension.to_string()))
+}
+
+/// The newest public patterns, optionally only those of one user or of one
+/// `author` field value, compared case-insensitively.
+#[tracing::instrument(name = "Fetching feed items", skip(pool, base_url))]
+async fn fetch_feed_items(
+    pool: &PgPool,
+    base_url: &str,
+    user_id: Option<Uuid>,
+    author: Option<&str>,
+) -> Result<Vec<FeedItem>, anyhow::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.pattern_id, p.name, p.title, p.author, p.description,
+               p.created_at, p.updated_at, u.username
+        FROM patterns_tb303 p
+        JOIN users u ON u.user_id = p.user_id
+        WHERE p.is_public = true
+          AND ($1::uuid IS NULL OR p.user_id = $1)
+          AND ($2::text IS NULL OR LOWER(p.author) = LOWER($2))
+        ORDER BY p.created_at DESC
+        LIMIT $3
+        "#,
+        user_id,
+        author,
+        FEED_LENGTH
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to fetch feed items.")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FeedItem {
+            id: row.pattern_id,
+            url: share_url(base_url, row.pattern_id),
+            name: row.name,
+            title: row.title,
+            author: row.author,
+            username: row.username,
+            description: row.description,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+        .collect())
+}
+
+/// Renders the feed, or answers `304 Not Modified` when the reader's copy is
+/// current. Patterns that are deleted or made private change the `ETag`
+/// too, while `Last-Modified` only moves with updates.
+fn feed_response(req: &HttpRequest, feed: &Feed, format: FeedFormat) -> HttpResponse {
+    conditional_response(
+        req,
+        feed.render(format),
+        format.content_type(),
+        feed.updated_at(),
+        CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(FEED_MAX_AGE_SECS),
+        ]),
+    )
+}
+
+fn feed_url(base_url: &str, req: &HttpRequest) -> String {
+    format!("{}{}", base_url.trim_end_matches('/'), req.path())
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/feeds/patterns.{format}",
+    params(
+        ("format" = String, Path, description = "atom, rss or json")
+    ),
+    responses(
+        (status = 200, description = "The newest public patterns as an Atom, RSS or JSON Feed"),
+        (status = 304, description = "The feed has not changed"),
+        (status = 404, description = "Unknown feed format", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+)]
+#[tracing::instrument(name = "Getting public patterns feed", skip(req, pool, base_url))]
+pub async fn get_patterns_feed(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    format: web::Path<String>,
+) -> Result<HttpResponse, FeedError> {
+    let format = parse_format(&format)?;
+
+    let feed = Feed {
+        title: format!("{PROVIDER_NAME}: new patterns"),
+        home_page_url: base_url.0.clone(),
+        feed_url: feed_url(&base_url.0, &req),
+        items: fetch_feed_items(&pool, &base_url.0, None, None).await?,
+    };
+
+    Ok(feed_response(&req, &feed, format))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/feeds/users/{username}/patterns.{format}",
+    params(
+        ("username" = String, Path, description = "Who shared the patterns"),
+        ("format" = String, Path, description = "atom, rss or json")
+    ),
+    responses(
+        (status = 200, description = "The newest public patterns of the user as an Atom, RSS or JSON Feed"),
+        (status = 304, description = "The feed has not changed"),
+        (status = 404, description = "Unknown user or feed format", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+)]
+#[tracing::instrument(name = "Getting user patterns feed", skip(req, pool, base_url))]
+pub async fn get_user_patterns_feed(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, FeedError> {
+    let (username, format) = path.into_inner();
+    let format = parse_format(&format)?;
+
+    let user_id = sqlx::query_scalar!(r#"SELECT user_id FROM users WHERE username = $1"#, username)
+        .fetch_optional(pool.as_ref())
+        .await
+        .context("Failed to look up user by username.")?
+        .ok_or_else(|| FeedError::UserNotFound(username.clone()))?;
+
+    let feed = Feed {
+        title: format!("{PROVIDER_NAME}: patterns shared by {username}"),
+        home_page_url: base_url.0.clone(),
+        feed_url: feed_url(&base_url.0, &req),
+        items: fetch_feed_items(&pool, &base_url.0, Some(user_id), None).await?,
+    };
+
+    Ok(feed_response(&req, &feed, format))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/feeds/authors/{author}/patterns.{format}",
+    params(
+        ("author" = String, Path, description = "Artist of the transcribed records, matched case-insensitively"),
+        ("format" = String, Path, description = "atom, rss or json")
+    ),
+    responses(
+        (status = 200, description = "The newest public patterns of the author as an Atom, RSS or JSON Feed"),
+        (status = 304, description = "The feed has not changed"),
+        (status = 404, description = "Unknown feed format", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+)]
+#[tracing::instrument(name = "Getting author patterns feed", skip(req, pool, base_url))]
+pub async fn get_author_patterns_feed(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, FeedError> {
+    let (author, format) = path.into_inner();
+    let format = parse_format(&format)?;
+
+    let feed = Feed {
+        title: format!("{PROVIDER_NAME}: patterns by {author}"),
+        home_page_url: base_url.0.clone(),
+        feed_url: feed_url(&base_url.0, &req),
+        items: fetch_feed_items(&pool, &base_url.0, None, Some(&author)).await?,
+    };
+
+    Ok(feed_response(&req, &feed, format))
+}
@@ -0,0 +1,3 @@
+mod get_feeds;
+
+pub use get_feeds::*;
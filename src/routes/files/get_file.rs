@@ -0,0 +1,35 @@
+use crate::routes::files::FileError;
+use crate::storage::{LocalStorage, ObjectStorage, StorageError};
+use actix_web::{http::header, web, HttpResponse};
+use anyhow::Context;
+
+/// Serves files of the local storage backend. S3 serves them directly.
+#[tracing::instrument(name = "Serving local file", skip(storage))]
+pub async fn get_file(
+    storage: web::Data<LocalStorage>,
+    key: web::Path<String>,
+) -> Result<HttpResponse, FileError> {
+    let object = match storage.head_object(&key).await {
+        Ok(Some(object)) => object,
+        Ok(None) | Err(StorageError::InvalidKey(_)) => return Err(FileError::NotFound),
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context("Failed to look up file")
+                .into())
+        }
+    };
+    let body = storage
+        .get_object(&key)
+        .await
+        .context("Failed to read file")?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(
+            object
+                .content_type
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+        )
+        // Uploads are user content; never let browsers guess another type.
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(body))
+}
@@ -0,0 +1,42 @@
+mod get_file;
+mod put_file;
+
+pub use get_file::*;
+pub use put_file::*;
+
+use crate::problem::Problem;
+use crate::utils::error_chain_fmt;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+
+#[derive(thiserror::Error)]
+pub enum FileError {
+    #[error("File not found")]
+    NotFound,
+    #[error("Invalid or expired upload signature")]
+    InvalidSignature,
+    #[error("{0}")]
+    InvalidUpload(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for FileError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            FileError::NotFound => StatusCode::NOT_FOUND,
+            FileError::InvalidSignature => StatusCode::FORBIDDEN,
+            FileError::InvalidUpload(_) => StatusCode::BAD_REQUEST,
+            FileError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
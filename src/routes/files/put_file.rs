@@ -0,0 +1,56 @@
+use crate::routes::files::FileError;
+use crate::storage::{LocalStorage, ObjectStorage, UploadSignature};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use futures_util::StreamExt;
+
+/// Accepts uploads to URLs signed by the local storage backend, the way S3
+/// accepts presigned `PUT` requests.
+#[tracing::instrument(name = "Storing local file", skip(storage, req, payload))]
+pub async fn put_file(
+    req: HttpRequest,
+    storage: web::Data<LocalStorage>,
+    key: web::Path<String>,
+    upload: web::Query<UploadSignature>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, FileError> {
+    if !storage.verify_upload(&key, &upload) {
+        return Err(FileError::InvalidSignature);
+    }
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(upload.content_type.as_str()) {
+        return Err(FileError::InvalidSignature);
+    }
+
+    // The body is read up to the signed length, never further.
+    let expected = upload.content_length as usize;
+    let mut body = Vec::with_capacity(expected);
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| FileError::InvalidUpload(e.to_string()))?;
+        if body.len() + chunk.len() > expected {
+            return Err(FileError::InvalidUpload(format!(
+                "Body exceeds the signed length of {} bytes",
+                expected
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    if body.len() != expected {
+        return Err(FileError::InvalidUpload(format!(
+            "Body is {} bytes, expected {}",
+            body.len(),
+            expected
+        )));
+    }
+
+    storage
+        .put_object(&key, body, &upload.content_type)
+        .await
+        .context("Failed to store file")?;
+
+    Ok(HttpResponse::Ok().finish())
+}
@@ -1,5 +1,124 @@
-use actix_web::HttpResponse;
+use crate::api::models::health::{
+    DependencyHealth, HealthStatus, LivenessResponse, ReadinessChecks, ReadinessResponse,
+};
+use crate::authentication::fetch_jwks;
+use crate::configuration::{CognitoSettings, HealthSettings};
+use crate::storage::ObjectStorage;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::{Duration, Instant};
 
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    responses(
+        (status = 200, description = "The process is up", body = LivenessResponse)
+    ),
+)]
+pub async fn health_live() -> web::Json<LivenessResponse> {
+    web::Json(LivenessResponse {
+        status: HealthStatus::Ok,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "Every dependency is available", body = ReadinessResponse),
+        (status = 503, description = "At least one dependency is unavailable", body = ReadinessResponse)
+    ),
+)]
+#[tracing::instrument(name = "Checking readiness", skip_all)]
+pub async fn health_ready(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    cognito: web::Data<CognitoSettings>,
+    settings: web::Data<HealthSettings>,
+) -> HttpResponse {
+    let timeout = settings.timeout();
+    let (database, storage, jwks) = tokio::join!(
+        check("database", timeout, check_database(&pool)),
+        check("storage", timeout, async {
+            storage.check_reachable().await.map_err(anyhow::Error::from)
+        }),
+        check("jwks", timeout, async {
+            fetch_jwks(&cognito).await.map(|_| ())
+        }),
+    );
+
+    let checks = ReadinessChecks {
+        database,
+        storage,
+        jwks,
+    };
+    let is_ready = [&checks.database, &checks.storage, &checks.jwks]
+        .iter()
+        .all(|check| check.status == HealthStatus::Ok);
+
+    if is_ready {
+        HttpResponse::Ok().json(ReadinessResponse {
+            status: HealthStatus::Ok,
+            checks,
+        })
+    } else {
+        HttpResponse::ServiceUnavailable().json(ReadinessResponse {
+            status: HealthStatus::Unavailable,
+            checks,
+        })
+    }
+}
+
+async fn check(
+    name: &str,
+    timeout: Duration,
+    check: impl Future<Output = Result<(), anyhow::Error>>,
+) -> DependencyHealth {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let status = match result {
+        Ok(Ok(())) => HealthStatus::Ok,
+        Ok(Err(e)) => {
+            tracing::warn!(error.cause_chain = ?e, latency_ms, "Readiness check of {name} failed");
+            HealthStatus::Unavailable
+        }
+        Err(_) => {
+            tracing::warn!(latency_ms, "Readiness check of {name} timed out");
+            HealthStatus::Unavailable
+        }
+    };
+
+    DependencyHealth { status, latency_ms }
+}
+
+/// The schema may be ahead of this build while a newer version rolls out,
+/// but never behind it.
+async fn check_database(pool: &PgPool) -> Result<(), anyhow::Error> {
+    let applied: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+            .fetch_one(pool)
+            .await
+            .context("Failed to read the migration version")?;
+
+    let expected = sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .max();
+
+    match (applied, expected) {
+        (Some(applied), Some(expected)) if applied < expected => Err(anyhow::anyhow!(
+            "Migrations are pending: the schema is at {applied}, {expected} is needed"
+        )),
+        (None, Some(_)) => Err(anyhow::anyhow!("No migrations have been applied")),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,44 @@
+use crate::configuration::MetricsSettings;
+use crate::metrics::{ArchiveGauges, METRICS};
+use crate::problem::{Problem, ProblemCode};
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
+use actix_web::{http::header::ContentType, http::StatusCode, web, HttpRequest, HttpResponse};
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+#[tracing::instrument(name = "Rendering metrics", skip_all)]
+pub async fn get_metrics(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    settings: web::Data<MetricsSettings>,
+    archive: web::Data<ArchiveGauges>,
+) -> HttpResponse {
+    if !is_scraper(req.headers(), &settings) {
+        return Problem::new(
+            StatusCode::UNAUTHORIZED,
+            ProblemCode::Unauthorized,
+            "Unauthorized access",
+        )
+        .into();
+    }
+
+    HttpResponse::Ok()
+        .content_type(ContentType(
+            "text/plain; version=0.0.4; charset=utf-8".parse().unwrap(),
+        ))
+        .body(METRICS.render(&pool, &archive))
+}
+
+/// Compares digests rather than the tokens themselves, so the time taken
+/// does not tell how much of a guess was right.
+fn is_scraper(headers: &HeaderMap, settings: &MetricsSettings) -> bool {
+    let Some(token) = headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    Sha256::digest(token.as_bytes()) == Sha256::digest(settings.token.expose_secret().as_bytes())
+}
@@ -1,9 +1,18 @@
-mod health_check;
+pub mod embeds;
+pub mod feeds;
+pub mod files;
+pub mod health_check;
+pub mod metrics;
 pub mod patterns;
 pub mod uploads;
 pub mod users;
+pub mod webhooks;
 
+pub use embeds::*;
+pub use feeds::*;
+pub use files::*;
 pub use health_check::*;
 pub use patterns::*;
 pub use uploads::*;
 pub use users::*;
+pub use webhooks::*;
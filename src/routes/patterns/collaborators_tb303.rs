@@ -0,0 +1,266 @@
+use crate::api::models::tb303::{AddTB303Collaborator, TB303Collaborator};
+use crate::authentication::UserId;
+use crate::domain::CollaboratorRole;
+use crate::problem::Problem;
+use crate::utils::error_chain_fmt;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum CollaboratorError {
+    #[error("Pattern with ID {0} not found")]
+    PatternNotFound(Uuid),
+    #[error("User {0} not found")]
+    UserNotFound(String),
+    #[error("Collaborator {0} not found")]
+    CollaboratorNotFound(Uuid),
+    #[error("The pattern owner cannot be added as a collaborator")]
+    OwnerAsCollaborator,
+    #[error("Access denied: only the pattern owner can manage collaborators")]
+    AccessDenied,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CollaboratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for CollaboratorError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CollaboratorError::PatternNotFound(_)
+            | CollaboratorError::UserNotFound(_)
+            | CollaboratorError::CollaboratorNotFound(_) => StatusCode::NOT_FOUND,
+            CollaboratorError::OwnerAsCollaborator => StatusCode::BAD_REQUEST,
+            CollaboratorError::AccessDenied => StatusCode::FORBIDDEN,
+            CollaboratorError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+/// Returns the role `user_id` has been granted on `pattern_id`, if any.
+/// The pattern owner has no collaborator row and always gets `None`.
+#[tracing::instrument(name = "Fetching collaborator role", skip(executor))]
+pub async fn fetch_collaborator_role(
+    executor: impl PgExecutor<'_>,
+    pattern_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<CollaboratorRole>, anyhow::Error> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT role FROM pattern_collaborators_tb303
+        WHERE pattern_id = $1 AND user_id = $2
+        "#,
+        pattern_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .context("Failed to fetch collaborator role.")?;
+
+    role.map(|r| CollaboratorRole::parse(&r).map_err(anyhow::Error::msg))
+        .transpose()
+}
+
+async fn fetch_pattern_owner(pool: &PgPool, pattern_id: Uuid) -> Result<Uuid, CollaboratorError> {
+    sqlx::query!(
+        r#"SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1"#,
+        pattern_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to fetch pattern owner.")?
+    .map(|row| row.user_id)
+    .ok_or(CollaboratorError::PatternNotFound(pattern_id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/patterns/tb303/{pattern_id}/collaborators",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    responses(
+        (status = 200, description = "Collaborators retrieved successfully", body = Vec<TB303Collaborator>),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Listing TB303 pattern collaborators", skip(pool, user_id))]
+pub async fn list_tb303_collaborators(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<web::Json<Vec<TB303Collaborator>>, CollaboratorError> {
+    let pattern_id = pattern_id.into_inner();
+    let user_id = user_id.into_inner();
+
+    let owner_id = fetch_pattern_owner(pool.as_ref(), pattern_id).await?;
+    if owner_id != *user_id
+        && fetch_collaborator_role(pool.as_ref(), pattern_id, *user_id)
+            .await?
+            .is_none()
+    {
+        return Err(CollaboratorError::PatternNotFound(pattern_id));
+    }
+
+    let collaborators = sqlx::query!(
+        r#"
+        SELECT c.user_id, u.username, c.role, c.created_at
+        FROM pattern_collaborators_tb303 c
+        JOIN users u ON u.user_id = c.user_id
+        WHERE c.pattern_id = $1
+        ORDER BY c.created_at
+        "#,
+        pattern_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch collaborators.")?
+    .into_iter()
+    .map(|row| TB303Collaborator {
+        user_id: row.user_id,
+        username: row.username,
+        role: row.role,
+        created_at: row.created_at,
+    })
+    .collect();
+
+    Ok(web::Json(collaborators))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/patterns/tb303/{pattern_id}/collaborators",
+    request_body = AddTB303Collaborator,
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern to share")
+    ),
+    responses(
+        (status = 200, description = "Collaborator added successfully", body = TB303Collaborator),
+        (status = 400, description = "Invalid input", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern or user not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Adding TB303 pattern collaborator", skip(pool, user_id, body))]
+pub async fn add_tb303_collaborator(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    pattern_id: web::Path<Uuid>,
+    body: web::Json<AddTB303Collaborator>,
+) -> Result<web::Json<TB303Collaborator>, CollaboratorError> {
+    let pattern_id = pattern_id.into_inner();
+    let user_id = user_id.into_inner();
+    let body = body.into_inner();
+
+    let owner_id = fetch_pattern_owner(pool.as_ref(), pattern_id).await?;
+    if owner_id != *user_id {
+        return Err(CollaboratorError::AccessDenied);
+    }
+
+    let collaborator = sqlx::query!(
+        r#"SELECT user_id, username FROM users WHERE username = $1"#,
+        body.username
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .context("Failed to look up collaborator by username.")?
+    .ok_or_else(|| CollaboratorError::UserNotFound(body.username.clone()))?;
+
+    if collaborator.user_id == owner_id {
+        return Err(CollaboratorError::OwnerAsCollaborator);
+    }
+
+    let saved = sqlx::query!(
+        r#"
+        INSERT INTO pattern_collaborators_tb303 (pattern_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (pattern_id, user_id)
+        DO UPDATE SET role = EXCLUDED.role, updated_at = NOW()
+        RETURNING role, created_at
+        "#,
+        pattern_id,
+        collaborator.user_id,
+        body.role.as_ref()
+    )
+    .fetch_one(pool.as_ref())
+    .await
+    .context("Failed to save collaborator.")?;
+
+    Ok(web::Json(TB303Collaborator {
+        user_id: collaborator.user_id,
+        username: collaborator.username,
+        role: saved.role,
+        created_at: saved.created_at,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/patterns/tb303/{pattern_id}/collaborators/{user_id}",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern"),
+        ("user_id" = String, Path, description = "The ID of the collaborator to remove")
+    ),
+    responses(
+        (status = 204, description = "Collaborator removed successfully"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern or collaborator not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Removing TB303 pattern collaborator", skip(pool, user_id))]
+pub async fn remove_tb303_collaborator(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, CollaboratorError> {
+    let (pattern_id, collaborator_id) = path.into_inner();
+    let user_id = user_id.into_inner();
+
+    let owner_id = fetch_pattern_owner(pool.as_ref(), pattern_id).await?;
+    // Collaborators may remove themselves from a pattern shared with them.
+    if owner_id != *user_id && collaborator_id != *user_id {
+        return Err(CollaboratorError::AccessDenied);
+    }
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM pattern_collaborators_tb303 WHERE pattern_id = $1 AND user_id = $2
+        "#,
+        pattern_id,
+        collaborator_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .context("Failed to remove collaborator.")?;
+
+    if result.rows_affected() == 0 {
+        return Err(CollaboratorError::CollaboratorNotFound(collaborator_id));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,8 +1,14 @@
 use crate::authentication::UserId;
+use crate::domain::WebhookEvent;
+use crate::jobs::{enqueue_job, Job};
+use crate::pattern_cache::PatternCache;
+use crate::problem::Problem;
+use crate::repository::PatternRepository;
 use crate::utils::error_chain_fmt;
+use crate::webhooks::enqueue_pattern_event;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
 use anyhow::Context;
-use sqlx::PgPool;
+use sqlx::{Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(thiserror::Error)]
@@ -29,6 +35,10 @@ impl ResponseError for DeletePatternError {
             DeletePatternError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
 }
 
 #[utoipa::path(
@@ -39,79 +49,70 @@ impl ResponseError for DeletePatternError {
     ),
     responses(
         (status = 204, description = "Pattern deleted successfully"),
-        (status = 403, description = "Access denied"),
-        (status = 404, description = "Pattern not found"),
-        (status = 500, description = "Internal server error")
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Deleting TB303 pattern by ID", skip(pool, user_id))]
+#[tracing::instrument(name = "Deleting TB303 pattern by ID", skip(patterns, user_id, cache))]
 pub async fn delete_tb303_pattern(
-    pool: web::Data<PgPool>,
+    patterns: web::Data<dyn PatternRepository>,
     user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
     pattern_id: web::Path<Uuid>,
 ) -> Result<HttpResponse, DeletePatternError> {
     let pattern_id = pattern_id.into_inner();
     let user_id = user_id.into_inner();
 
-    delete_pattern_by_id(pool.as_ref(), pattern_id, *user_id).await?;
+    match patterns.fetch_pattern_owner(pattern_id).await? {
+        Some(owner_id) if owner_id != *user_id => return Err(DeletePatternError::AccessDenied),
+        Some(_) => {}
+        None => return Err(DeletePatternError::PatternNotFound(pattern_id)),
+    }
+
+    // The pattern may have been deleted or handed over since it was checked.
+    if !patterns.delete_pattern(pattern_id, *user_id).await? {
+        return Err(DeletePatternError::PatternNotFound(pattern_id));
+    }
+    cache.invalidate(pattern_id);
 
     Ok(HttpResponse::NoContent().finish())
 }
 
-async fn delete_pattern_by_id(
-    pool: &PgPool,
+/// Deletes the pattern, whoever owns it. Files attached to it are released
+/// and queued for deletion in the same transaction. Returns false if there
+/// was no such pattern.
+pub async fn delete_pattern(
+    transaction: &mut Transaction<'_, Postgres>,
     pattern_id: Uuid,
-    requesting_user_id: Uuid,
-) -> Result<(), DeletePatternError> {
-    let pattern_user_id = sqlx::query!(
+) -> Result<bool, anyhow::Error> {
+    let media_keys = sqlx::query_scalar!(
         r#"
-        SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1
+        UPDATE uploads SET claimed_at = NULL
+        WHERE key IN (SELECT key FROM pattern_media WHERE pattern_id = $1)
+        RETURNING key
         "#,
         pattern_id
     )
-    .fetch_optional(pool)
+    .fetch_all(&mut **transaction)
     .await
-    .context("Failed to fetch pattern owner.")?
-    .map(|row| row.user_id);
-
-    match pattern_user_id {
-        Some(owner_id) => {
-            if owner_id != requesting_user_id {
-                return Err(DeletePatternError::AccessDenied);
-            }
-        }
-        None => return Err(DeletePatternError::PatternNotFound(pattern_id)),
+    .context("Failed to release pattern media.")?;
+    for key in media_keys {
+        enqueue_job(transaction, &Job::DeleteUpload { key }).await?;
     }
 
-    let mut transaction = pool
-        .begin()
-        .await
-        .context("Failed to begin a database transaction.")?;
+    enqueue_pattern_event(transaction, WebhookEvent::PatternDeleted, pattern_id, false).await?;
 
     let result = sqlx::query!(
-        r#"
-        DELETE FROM patterns_tb303 WHERE pattern_id = $1 AND user_id = $2
-        "#,
-        pattern_id,
-        requesting_user_id
+        r#"DELETE FROM patterns_tb303 WHERE pattern_id = $1"#,
+        pattern_id
     )
-    .execute(&mut *transaction)
+    .execute(&mut **transaction)
     .await
     .context("Failed to delete pattern.")?;
 
-    if result.rows_affected() == 0 {
-        // This case should ideally not be reached if the initial check passes,
-        // but it's a safeguard.
-        return Err(DeletePatternError::PatternNotFound(pattern_id));
-    }
-
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit the database transaction.")?;
-
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
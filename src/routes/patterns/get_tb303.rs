@@ -1,8 +1,15 @@
-use crate::api::models::tb303::{TB303Bar, TB303Pattern, TB303Step};
+use crate::api::models::tb303::TB303Pattern;
 use crate::authentication::{try_extract_user_id, UserId};
 use crate::configuration::CognitoSettings;
-use crate::routes::patterns::PatternErrorResponse;
+use crate::http_cache::conditional_response;
+use crate::image_processing::image_url;
+use crate::metrics::METRICS;
+use crate::pattern_cache::PatternCache;
+use crate::problem::Problem;
+use crate::repository::{PatternRepository, PostgresRepository};
+use crate::storage::ObjectStorage;
 use crate::utils::error_chain_fmt;
+use actix_web::http::header::{CacheControl, CacheDirective, HeaderValue, VARY};
 use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
 use anyhow::Context;
 use sqlx::PgPool;
@@ -38,106 +45,90 @@ impl ResponseError for GetPatternError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(web::Json(PatternErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        }))
+        Problem::from_error(self).into()
     }
 }
-async fn fetch_pattern_by_id(
-    pool: &PgPool,
+
+/// Returns the pattern if `requesting_user_id` may see it. Private patterns
+/// are only visible to their owner and collaborators, and look missing to
+/// anyone else.
+pub async fn fetch_pattern(
+    patterns: &dyn PatternRepository,
+    storage: &dyn ObjectStorage,
     pattern_id: Uuid,
     requesting_user_id: Option<UserId>,
 ) -> Result<TB303Pattern, GetPatternError> {
-    let pattern = sqlx::query!(
-        r#"
-        SELECT
-            pattern_id, user_id, name, author, title, description,
-            waveform, triplets, tempo, tuning, cut_off_freq, resonance,
-            env_mod, decay, accent, is_public, created_at, updated_at
-        FROM patterns_tb303
-        WHERE pattern_id = $1
-        "#,
-        pattern_id
-    )
-    .fetch_optional(pool)
-    .await
-    .context("Failed to fetch pattern details.")?
-    .ok_or(GetPatternError::PatternNotFound(pattern_id))?;
+    let record = patterns
+        .fetch_pattern(pattern_id)
+        .await?
+        .ok_or(GetPatternError::PatternNotFound(pattern_id))?;
 
+    let is_public = record.pattern.is_public.unwrap_or(false);
     match requesting_user_id {
         Some(user_id) => {
-            if !pattern.is_public.unwrap_or(false) && pattern.user_id != *user_id {
+            if !is_public
+                && record.user_id != *user_id
+                && patterns
+                    .fetch_collaborator_role(pattern_id, *user_id)
+                    .await?
+                    .is_none()
+            {
                 return Err(GetPatternError::AccessDenied);
             }
         }
         None => {
-            if !pattern.is_public.unwrap_or(false) {
+            if !is_public {
                 return Err(GetPatternError::PatternNotFound(pattern_id));
             }
         }
     }
 
-    let rows = sqlx::query!(
-        r#"
-        SELECT
-            b.bar_id, b.number AS bar_number,
-            s.step_id AS "step_id?", s.number AS "step_number?",
-            s.note, s.transpose, s.time, s.accent, s.slide
-        FROM bars_tb303 b
-        LEFT JOIN steps_tb303 s ON s.bar_id = b.bar_id
-        WHERE b.pattern_id = $1
-        ORDER BY b.number, s.number
-        "#,
-        pattern_id
-    )
-    .fetch_all(pool)
-    .await
-    .context("Failed to fetch bars and steps for pattern.")?;
-
-    let mut bars: Vec<TB303Bar> = Vec::new();
-
-    for row in rows {
-        if bars.last().map(|b: &TB303Bar| b.id) != Some(row.bar_id) {
-            bars.push(TB303Bar {
-                id: row.bar_id,
-                number: row.bar_number,
-                steps: Vec::new(),
-            });
-        }
-        if let Some(step_id) = row.step_id {
-            bars.last_mut().unwrap().steps.push(TB303Step {
-                id: step_id,
-                number: row.step_number.unwrap(),
-                note: row.note,
-                transpose: row.transpose,
-                time: row.time,
-                accent: row.accent,
-                slide: row.slide,
-            });
+    Ok(TB303Pattern {
+        audio_url: record.audio_key.map(|key| storage.get_public_url(&key)),
+        cover_url: record
+            .cover_key
+            .map(|key| image_url(storage, &key, &record.cover_variant_sizes)),
+        ..record.pattern
+    })
+}
+
+/// How long clients and proxies may keep a public pattern without asking
+/// again.
+const PATTERN_MAX_AGE_SECS: u32 = 60;
+
+/// Like [`fetch_pattern`], but public patterns come from `cache` when they
+/// are in it, and are put in it otherwise. A cached pattern is only served
+/// while it is still public and unchanged, whoever changed it.
+pub async fn fetch_pattern_cached(
+    cache: &PatternCache,
+    patterns: &dyn PatternRepository,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+    requesting_user_id: Option<UserId>,
+) -> Result<TB303Pattern, GetPatternError> {
+    if let Some(pattern) = cache.get(pattern_id) {
+        let updated_at = patterns.fetch_public_pattern_updated_at(pattern_id).await?;
+        if updated_at.is_some() && updated_at == pattern.updated_at {
+            METRICS.record_pattern_cache_lookup(true);
+            return Ok(pattern);
         }
+        cache.invalidate(pattern_id);
     }
+    METRICS.record_pattern_cache_lookup(false);
+    let ticket = cache.ticket();
+    let pattern = fetch_pattern(patterns, storage, pattern_id, requesting_user_id).await?;
+    cache.insert(ticket, &pattern);
+    Ok(pattern)
+}
 
-    Ok(TB303Pattern {
-        id: Some(pattern.pattern_id),
-        name: pattern.name,
-        author: pattern.author,
-        title: pattern.title,
-        description: pattern.description,
-        tempo: pattern.tempo,
-        tuning: pattern.tuning,
-        waveform: pattern.waveform,
-        triplets: pattern.triplets,
-        cut_off_freq: pattern.cut_off_freq,
-        resonance: pattern.resonance,
-        env_mod: pattern.env_mod,
-        decay: pattern.decay,
-        accent: pattern.accent,
-        created_at: Some(pattern.created_at),
-        updated_at: Some(pattern.updated_at),
-        is_public: pattern.is_public,
-        bars,
-    })
+pub async fn fetch_pattern_by_id(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+    requesting_user_id: Option<UserId>,
+) -> Result<TB303Pattern, GetPatternError> {
+    let patterns = PostgresRepository::new(pool.clone());
+    fetch_pattern(&patterns, storage, pattern_id, requesting_user_id).await
 }
 
 #[utoipa::path(
@@ -145,33 +136,32 @@ async fn fetch_pattern_by_id(
     path = "/v1/patterns/tb303/random",
     responses(
         (status = 200, description = "Random pattern retrieved successfully", body = TB303Pattern),
-        (status = 404, description = "No patterns found"),
-        (status = 500, description = "Internal server error")
+        (status = 404, description = "No patterns found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Getting random TB303 pattern", skip(pool))]
+#[tracing::instrument(name = "Getting random TB303 pattern", skip(patterns, storage, cache))]
 pub async fn get_random_tb303_pattern(
-    pool: web::Data<PgPool>,
+    patterns: web::Data<dyn PatternRepository>,
+    storage: web::Data<dyn ObjectStorage>,
+    cache: web::Data<PatternCache>,
 ) -> Result<web::Json<TB303Pattern>, GetPatternError> {
-    let pattern_id = sqlx::query!(
-        r#"
-        SELECT pattern_id
-        FROM patterns_tb303
-        WHERE is_public = true
-        ORDER BY RANDOM()
-        LIMIT 1
-        "#
-    )
-    .fetch_optional(pool.as_ref())
-    .await
-    .context("Failed to get a random pattern ID from database.")?
-    .ok_or(GetPatternError::NoPatterns)?
-    .pattern_id;
+    let pattern_id = patterns
+        .fetch_random_public_pattern_id()
+        .await?
+        .ok_or(GetPatternError::NoPatterns)?;
 
-    let pattern = fetch_pattern_by_id(pool.as_ref(), pattern_id, None).await?;
+    let pattern = fetch_pattern_cached(
+        &cache,
+        patterns.as_ref(),
+        storage.as_ref(),
+        pattern_id,
+        None,
+    )
+    .await?;
 
     Ok(web::Json(pattern))
 }
@@ -184,23 +174,58 @@ pub async fn get_random_tb303_pattern(
     ),
     responses(
         (status = 200, description = "Pattern retrieved successfully", body = TB303Pattern),
-        (status = 404, description = "Pattern not found or access denied"),
-        (status = 500, description = "Internal server error")
+        (status = 304, description = "The pattern has not changed"),
+        (status = 404, description = "Pattern not found or access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Getting TB303 pattern by ID", skip(pool, cognito))]
+#[tracing::instrument(
+    name = "Getting TB303 pattern by ID",
+    skip(req, patterns, storage, cognito, cache)
+)]
 pub async fn get_tb303_pattern(
     req: HttpRequest,
-    pool: web::Data<PgPool>,
+    patterns: web::Data<dyn PatternRepository>,
+    storage: web::Data<dyn ObjectStorage>,
     cognito: web::Data<CognitoSettings>,
+    cache: web::Data<PatternCache>,
     pattern_id: web::Path<Uuid>,
-) -> Result<web::Json<TB303Pattern>, GetPatternError> {
+) -> Result<HttpResponse, GetPatternError> {
     let user_id = try_extract_user_id(req.headers(), &cognito).await;
     let pattern_id = pattern_id.into_inner();
 
-    let pattern = fetch_pattern_by_id(pool.as_ref(), pattern_id, user_id).await?;
-    Ok(web::Json(pattern))
+    let pattern = fetch_pattern_cached(
+        &cache,
+        patterns.as_ref(),
+        storage.as_ref(),
+        pattern_id,
+        user_id,
+    )
+    .await?;
+
+    // Private patterns are only for their owner and collaborators.
+    let cache_control = if pattern.is_public.unwrap_or(false) {
+        CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(PATTERN_MAX_AGE_SECS),
+        ])
+    } else {
+        CacheControl(vec![CacheDirective::Private, CacheDirective::NoCache])
+    };
+    let body = serde_json::to_vec(&pattern).context("Failed to serialize pattern")?;
+    let mut response = conditional_response(
+        &req,
+        body,
+        "application/json",
+        pattern.updated_at,
+        cache_control,
+    );
+    // Whether a pattern can be seen at all depends on who asks.
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("Authorization"));
+    Ok(response)
 }
@@ -0,0 +1,357 @@
+use crate::api::models::archive::{
+    ImportTB303Archive, ImportTB303Params, ImportTB303Pattern, ImportTB303Response,
+    ImportTB303Result, ImportTB303Status, ARCHIVE_VERSION,
+};
+use crate::api::models::tb303::CreateTB303Pattern;
+use crate::authentication::UserId;
+use crate::domain::NewTB303Pattern;
+use crate::metrics::begin_transaction;
+use crate::problem::{FieldErrorCode, Problem, ValidationErrors};
+use crate::routes::patterns::{
+    enqueue_created_events, fetch_pattern_by_id, insert_bars_tb303, insert_pattern_with_metadata,
+};
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum ImportPatternError {
+    #[error("Unsupported archive version {0}, expected {ARCHIVE_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ImportPatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ImportPatternError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ImportPatternError::UnsupportedVersion(_) => StatusCode::BAD_REQUEST,
+            ImportPatternError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+struct ImportMetadata {
+    id: Option<Uuid>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+struct PendingImport {
+    index: usize,
+    pattern_id: Uuid,
+    metadata: ImportMetadata,
+    new_pattern: NewTB303Pattern,
+}
+
+#[tracing::instrument(
+    name = "Fetching existing pattern fingerprints",
+    skip(pool, storage, names)
+)]
+async fn fetch_existing_fingerprints(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    user_id: UserId,
+    names: &[String],
+) -> Result<HashMap<String, Uuid>, anyhow::Error> {
+    let pattern_ids = sqlx::query_scalar!(
+        r#"
+        SELECT pattern_id FROM patterns_tb303
+        WHERE user_id = $1 AND name = ANY($2)
+        "#,
+        *user_id,
+        names
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to fetch existing patterns.")?;
+
+    let mut fingerprints = HashMap::new();
+    for pattern_id in pattern_ids {
+        // Stored patterns went through the same validation, so a pattern
+        // that no longer converts is simply not a duplicate candidate.
+        if let Some(new_pattern) = fetch_stored_pattern(pool, storage, pattern_id, user_id).await? {
+            fingerprints.insert(new_pattern.fingerprint(), pattern_id);
+        }
+    }
+
+    Ok(fingerprints)
+}
+
+/// Reads a stored pattern back in the shape it was created from, or `None`
+/// if it no longer passes validation.
+pub async fn fetch_stored_pattern(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+    owner_id: UserId,
+) -> Result<Option<NewTB303Pattern>, anyhow::Error> {
+    let pattern = fetch_pattern_by_id(pool, storage, pattern_id, Some(owner_id))
+        .await
+        .context("Failed to fetch existing pattern.")?;
+
+    Ok(serde_json::to_value(pattern)
+        .ok()
+        .and_then(|value| serde_json::from_value::<CreateTB303Pattern>(value).ok())
+        .and_then(|pattern| pattern.try_into().ok()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/patterns/tb303/import",
+    request_body = ImportTB303Archive,
+    params(ImportTB303Params),
+    responses(
+        (status = 200, description = "Archive imported or validated successfully", body = ImportTB303Response),
+        (status = 400, description = "Unsupported archive version or invalid patterns; nothing was imported", content(
+            (ImportTB303Response = "application/json"),
+            (Problem = "application/problem+json")
+        )),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Importing TB303 patterns",
+    skip(pool, storage, user_id, archive)
+)]
+pub async fn import_tb303_patterns(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    user_id: web::ReqData<UserId>,
+    params: web::Query<ImportTB303Params>,
+    archive: web::Json<ImportTB303Archive>,
+) -> Result<HttpResponse, ImportPatternError> {
+    let response = import_archive(
+        pool.as_ref(),
+        storage.as_ref(),
+        user_id.into_inner(),
+        archive.into_inner(),
+        params.dry_run.unwrap_or(false),
+    )
+    .await?;
+
+    if response.invalid > 0 {
+        return Ok(HttpResponse::BadRequest().json(response));
+    }
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Imports `archive` into the library of `user_id`. Nothing is written when
+/// any pattern is invalid; the response then has the status "fail" and says
+/// which patterns were rejected and why.
+pub async fn import_archive(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    user_id: UserId,
+    archive: ImportTB303Archive,
+    dry_run: bool,
+) -> Result<ImportTB303Response, ImportPatternError> {
+    if archive.version != ARCHIVE_VERSION {
+        return Err(ImportPatternError::UnsupportedVersion(archive.version));
+    }
+
+    let mut results = Vec::with_capacity(archive.patterns.len());
+    let mut parsed = Vec::new();
+    for (index, value) in archive.patterns.into_iter().enumerate() {
+        let name = value
+            .get("name")
+            .and_then(|name| name.as_str())
+            .map(str::to_string);
+        let outcome = serde_json::from_value::<ImportTB303Pattern>(value)
+            .map_err(|e| {
+                let mut errors = ValidationErrors::default();
+                errors.add("", FieldErrorCode::Invalid, e.to_string());
+                errors
+            })
+            .and_then(|import| {
+                let new_pattern: NewTB303Pattern = import.pattern.try_into()?;
+                Ok((
+                    ImportMetadata {
+                        id: import.id,
+                        created_at: import.created_at,
+                        updated_at: import.updated_at,
+                    },
+                    new_pattern,
+                ))
+            });
+        match outcome {
+            Ok((metadata, new_pattern)) => parsed.push((index, metadata, new_pattern)),
+            Err(pattern_errors) => {
+                let mut errors = ValidationErrors::default();
+                errors.nest(&format!("/patterns/{index}"), pattern_errors);
+                results.push(ImportTB303Result {
+                    index,
+                    name,
+                    status: ImportTB303Status::Invalid,
+                    pattern_id: None,
+                    error: Some(errors.to_string()),
+                    errors: errors.errors().to_vec(),
+                })
+            }
+        }
+    }
+
+    let names: Vec<String> = parsed
+        .iter()
+        .map(|(_, _, new_pattern)| new_pattern.name.as_ref().to_string())
+        .collect();
+    let mut fingerprints = fetch_existing_fingerprints(pool, storage, user_id, &names).await?;
+
+    let requested_ids: Vec<Uuid> = parsed
+        .iter()
+        .filter_map(|(_, metadata, _)| metadata.id)
+        .collect();
+    let taken_ids: HashMap<Uuid, Uuid> = sqlx::query!(
+        r#"SELECT pattern_id, user_id FROM patterns_tb303 WHERE pattern_id = ANY($1)"#,
+        &requested_ids
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to check archived pattern IDs.")?
+    .into_iter()
+    .map(|row| (row.pattern_id, row.user_id))
+    .collect();
+
+    let mut claimed_ids = HashSet::new();
+    let mut pending = Vec::new();
+    for (index, metadata, new_pattern) in parsed {
+        let name = Some(new_pattern.name.as_ref().to_string());
+        let fingerprint = new_pattern.fingerprint();
+
+        let existing = match metadata
+            .id
+            .and_then(|id| taken_ids.get(&id).map(|o| (id, o)))
+        {
+            Some((id, owner)) if *owner == *user_id => Some(id),
+            _ => fingerprints.get(&fingerprint).copied(),
+        };
+        if let Some(existing) = existing {
+            results.push(ImportTB303Result {
+                index,
+                name,
+                status: ImportTB303Status::Duplicate,
+                pattern_id: Some(existing),
+                error: None,
+                errors: Vec::new(),
+            });
+            continue;
+        }
+
+        // IDs that belong to another account, or that appear twice in the
+        // archive, are replaced so patterns can move between accounts.
+        let pattern_id = match metadata.id {
+            Some(id) if !taken_ids.contains_key(&id) && claimed_ids.insert(id) => id,
+            _ => Uuid::new_v4(),
+        };
+        claimed_ids.insert(pattern_id);
+        fingerprints.insert(fingerprint, pattern_id);
+
+        results.push(ImportTB303Result {
+            index,
+            name,
+            status: ImportTB303Status::Created,
+            pattern_id: Some(pattern_id),
+            error: None,
+            errors: Vec::new(),
+        });
+        pending.push(PendingImport {
+            index,
+            pattern_id,
+            metadata,
+            new_pattern,
+        });
+    }
+    results.sort_by_key(|result| result.index);
+
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let invalid = count(ImportTB303Status::Invalid);
+    let mut response = ImportTB303Response {
+        status: "success".to_string(),
+        dry_run,
+        created: count(ImportTB303Status::Created),
+        duplicates: count(ImportTB303Status::Duplicate),
+        invalid,
+        results: Vec::new(),
+    };
+
+    if invalid > 0 {
+        // The archive is imported as a whole or not at all.
+        response.status = "fail".to_string();
+        response.created = 0;
+        for result in results.iter_mut() {
+            if result.status == ImportTB303Status::Created {
+                result.pattern_id = None;
+            }
+        }
+        response.results = results;
+        return Ok(response);
+    }
+    response.results = results;
+
+    if dry_run {
+        return Ok(response);
+    }
+
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to start a transaction for import.")?;
+
+    for pending in &pending {
+        let now = Utc::now();
+        let created_at = pending.metadata.created_at.unwrap_or(now);
+        let updated_at = pending.metadata.updated_at.unwrap_or(created_at);
+
+        insert_pattern_with_metadata(
+            &mut transaction,
+            pending.pattern_id,
+            &pending.new_pattern,
+            &user_id,
+            created_at,
+            updated_at,
+        )
+        .await
+        .with_context(|| format!("Failed to insert archived pattern {}.", pending.index))?;
+
+        insert_bars_tb303(
+            &mut transaction,
+            pending.pattern_id,
+            &pending.new_pattern.bars,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to insert bars and steps of archived pattern {}.",
+                pending.index
+            )
+        })?;
+
+        enqueue_created_events(&mut transaction, pending.pattern_id, &pending.new_pattern).await?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the pattern import.")?;
+
+    Ok(response)
+}
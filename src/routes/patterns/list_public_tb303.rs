@@ -1,14 +1,20 @@
 use crate::api::models::pagination::PaginationParams;
 use crate::api::models::sort::SortParams;
 use crate::api::models::tb303::{PaginatedPublicTB303PatternSummary, PublicTB303PatternSummary};
-use crate::routes::patterns::PatternErrorResponse;
-use crate::s3_client::S3Client;
+use crate::http_cache::conditional_response;
+use crate::image_processing::{image_url, variant_urls};
+use crate::problem::{Problem, ProblemCode};
+use crate::repository::PatternRepository;
+use crate::routes::patterns::preview_url;
+use crate::startup::ApplicationBaseUrl;
+use crate::storage::ObjectStorage;
 use crate::utils::error_chain_fmt;
-use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
 use anyhow::Context;
-use chrono::{DateTime, Utc};
-use sqlx::PgPool;
-use uuid::Uuid;
+
+/// The front page lists these on every load, and a minute old is new enough.
+const LISTING_MAX_AGE_SECS: u32 = 60;
 
 #[derive(thiserror::Error)]
 pub enum ListPublicPatternsError {
@@ -24,17 +30,23 @@ pub enum ListPublicPatternsError {
     params(PaginationParams),
     responses(
         (status = 200, description = "Public patterns retrieved successfully.", body = PaginatedPublicTB303PatternSummary),
-        (status = 400, description = "Invalid pagination parameters"),
-        (status = 500, description = "Internal server error.")
+        (status = 304, description = "The page has not changed"),
+        (status = 400, description = "Invalid pagination parameters", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error.", body = Problem, content_type = "application/problem+json")
     ),
 )]
-#[tracing::instrument(name = "Listing public TB303 patterns", skip(pool, s3_client))]
+#[tracing::instrument(
+    name = "Listing public TB303 patterns",
+    skip(req, patterns, storage, base_url)
+)]
 pub async fn list_public_tb303_patterns(
-    pool: web::Data<PgPool>,
-    s3_client: web::Data<S3Client>,
+    req: HttpRequest,
+    patterns: web::Data<dyn PatternRepository>,
+    storage: web::Data<dyn ObjectStorage>,
+    base_url: web::Data<ApplicationBaseUrl>,
     pagination: web::Query<PaginationParams>,
     sort: web::Query<SortParams>,
-) -> Result<web::Json<PaginatedPublicTB303PatternSummary>, ListPublicPatternsError> {
+) -> Result<HttpResponse, ListPublicPatternsError> {
     let limit = pagination.limit.unwrap_or(20);
     let offset = pagination.offset.unwrap_or(0);
     let order = sort.order.as_deref().unwrap_or("desc").to_lowercase();
@@ -55,74 +67,78 @@ pub async fn list_public_tb303_patterns(
         ));
     }
 
-    let response = fetch_public_pattern_list(&pool, &s3_client, limit, offset, &order)
-        .await
-        .context("Failed to fetch public patterns")?;
-
-    Ok(web::Json(response))
-}
+    let response = fetch_public_pattern_list(
+        patterns.as_ref(),
+        storage.as_ref(),
+        &base_url.0,
+        limit,
+        offset,
+        &order,
+    )
+    .await
+    .context("Failed to fetch public patterns")?;
 
-#[derive(sqlx::FromRow)]
-struct PublicPatternRow {
-    pattern_id: Uuid,
-    name: String,
-    author: Option<String>,
-    title: Option<String>,
-    is_public: Option<bool>,
-    created_at: DateTime<Utc>,
-    updated_at: DateTime<Utc>,
-    username: String,
-    avatar_key: Option<String>,
+    // No Last-Modified: patterns leaving the page would not move it, so a
+    // stale page could be confirmed with If-Modified-Since. The ETag covers
+    // them.
+    let body = serde_json::to_vec(&response).context("Failed to serialize public patterns")?;
+    Ok(conditional_response(
+        &req,
+        body,
+        "application/json",
+        None,
+        CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(LISTING_MAX_AGE_SECS),
+        ]),
+    ))
 }
 
 async fn fetch_public_pattern_list(
-    pool: &PgPool,
-    s3_client: &S3Client,
+    patterns: &dyn PatternRepository,
+    storage: &dyn ObjectStorage,
+    base_url: &str,
     limit: i64,
     offset: i64,
     order: &str,
-) -> Result<PaginatedPublicTB303PatternSummary, sqlx::Error> {
-    let total: i64 =
-        sqlx::query_scalar!("SELECT COUNT(*) FROM patterns_tb303 WHERE is_public = true")
-            .fetch_one(pool)
-            .await?
-            .unwrap_or(0);
-
-    let mut builder = sqlx::QueryBuilder::new(
-        r#"SELECT p.pattern_id, p.name, p.author, p.title, p.is_public, p.created_at, p.updated_at,
-                        u.username, u.avatar_key
-                 FROM patterns_tb303 p
-                 JOIN users u ON u.user_id = p.user_id
-                 WHERE p.is_public = true"#,
-    );
-
-    builder.push(" ORDER BY p.created_at ");
-    builder.push(if order == "asc" { "ASC" } else { "DESC" });
-    builder.push(" LIMIT ").push_bind(limit);
-    builder.push(" OFFSET ").push_bind(offset);
-
-    let rows = builder
-        .build_query_as::<PublicPatternRow>()
-        .fetch_all(pool)
+) -> Result<PaginatedPublicTB303PatternSummary, anyhow::Error> {
+    let (records, total) = patterns
+        .list_public_patterns(limit, offset, order == "asc")
         .await?;
 
-    let data = rows
+    let data = records
         .into_iter()
         .map(|r| {
             let avatar_url = r
                 .avatar_key
                 .as_ref()
-                .map(|key| s3_client.get_public_url(key));
+                .map(|key| image_url(storage, key, &r.avatar_variant_sizes));
+            let avatar_variants = match r.avatar_key {
+                Some(ref key) => variant_urls(storage, key, &r.avatar_variant_sizes),
+                None => Vec::new(),
+            };
+            let cover_url = r
+                .cover_key
+                .as_ref()
+                .map(|key| image_url(storage, key, &r.cover_variant_sizes));
+            let cover_variants = match r.cover_key {
+                Some(ref key) => variant_urls(storage, key, &r.cover_variant_sizes),
+                None => Vec::new(),
+            };
             PublicTB303PatternSummary {
                 pattern_id: r.pattern_id,
                 name: r.name,
                 author: r.author,
                 title: r.title,
-                is_public: r.is_public.unwrap(),
+                is_public: true,
+                preview_url: preview_url(base_url, r.pattern_id, r.updated_at),
                 created_at: r.created_at,
                 updated_at: r.updated_at,
                 username: r.username,
                 avatar_url,
+                avatar_variants,
+                cover_url,
+                cover_variants,
             }
         })
         .collect();
@@ -150,10 +166,13 @@ impl ResponseError for ListPublicPatternsError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        let error = PatternErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        };
-        HttpResponse::build(self.status_code()).json(web::Json(error))
+        let problem = Problem::from_error(self);
+        match self {
+            ListPublicPatternsError::ValidationError(_) => {
+                problem.with_code(ProblemCode::ValidationFailed)
+            }
+            _ => problem,
+        }
+        .into()
     }
 }
@@ -1,6 +1,6 @@
-use crate::api::models::tb303::TB303PatternSummary;
+use crate::api::models::tb303::{SharedTB303PatternSummary, TB303PatternSummary};
 use crate::authentication::UserId;
-use crate::routes::patterns::PatternErrorResponse;
+use crate::problem::Problem;
 use crate::utils::error_chain_fmt;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
 use anyhow::Context;
@@ -18,7 +18,7 @@ pub enum ListPatternsError {
     path = "/v1/patterns/tb303",
     responses(
         (status = 200, description = "Pattern list retrieved successfully.", body = Vec<TB303PatternSummary>),
-        (status = 500, description = "Internal server error.")
+        (status = 500, description = "Internal server error.", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
@@ -71,6 +71,69 @@ async fn fetch_pattern_list(
     Ok(patterns_response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/patterns/tb303/shared",
+    responses(
+        (status = 200, description = "Patterns shared with the user retrieved successfully.", body = Vec<SharedTB303PatternSummary>),
+        (status = 401, description = "Unauthorized.", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error.", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Listing TB303 patterns shared with user", skip(pool, user_id))]
+pub async fn list_shared_tb303_patterns(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+) -> Result<web::Json<Vec<SharedTB303PatternSummary>>, ListPatternsError> {
+    let user_id = user_id.into_inner();
+
+    let patterns = fetch_shared_pattern_list(&pool, &user_id)
+        .await
+        .context("Failed to fetch shared patterns")?;
+
+    Ok(web::Json(patterns))
+}
+
+async fn fetch_shared_pattern_list(
+    pool: &PgPool,
+    user_id: &UserId,
+) -> Result<Vec<SharedTB303PatternSummary>, sqlx::Error> {
+    let patterns = sqlx::query!(
+        r#"
+        SELECT p.pattern_id, p.name, p.author, p.title, p.is_public, p.created_at, p.updated_at,
+               u.username, c.role
+        FROM pattern_collaborators_tb303 c
+        JOIN patterns_tb303 p ON p.pattern_id = c.pattern_id
+        JOIN users u ON u.user_id = p.user_id
+        WHERE c.user_id = $1
+        ORDER BY p.updated_at DESC
+        "#,
+        user_id.deref()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let patterns_response = patterns
+        .into_iter()
+        .map(|pattern| SharedTB303PatternSummary {
+            pattern_id: pattern.pattern_id,
+            name: pattern.name,
+            author: pattern.author,
+            title: pattern.title,
+            is_public: pattern.is_public.unwrap_or(false),
+            username: pattern.username,
+            role: pattern.role,
+            created_at: pattern.created_at,
+            updated_at: pattern.updated_at,
+        })
+        .collect();
+
+    Ok(patterns_response)
+}
+
 impl std::fmt::Debug for ListPatternsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         error_chain_fmt(self, f)
@@ -85,10 +148,6 @@ impl ResponseError for ListPatternsError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        let error = PatternErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        };
-        HttpResponse::build(self.status_code()).json(web::Json(error))
+        Problem::from_error(self).into()
     }
 }
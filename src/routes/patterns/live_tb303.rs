@@ -0,0 +1,569 @@
+use crate::api::models::live::{LiveClientMessage, LiveKnob, LivePresence, LiveServerMessage};
+use crate::api::models::tb303::{CreateTB303Step, TB303Step};
+use crate::authentication::{try_extract_user_id, validate_token, QueryToken, UserId};
+use crate::configuration::CognitoSettings;
+use crate::domain::{Knob, NewTB303Step, WebhookEvent};
+use crate::live_sessions::{LiveField, LiveSessions};
+use crate::metrics::begin_transaction;
+use crate::pattern_cache::PatternCache;
+use crate::problem::{Problem, ValidationErrors};
+use crate::routes::patterns::fetch_collaborator_role;
+use crate::utils::error_chain_fmt;
+use crate::webhooks::enqueue_pattern_event;
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use actix_ws::{Message, MessageStream, Session};
+use anyhow::Context;
+use secrecy::ExposeSecret;
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
+use std::convert::TryInto;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::Instrument;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum LivePatternError {
+    #[error("Unauthorized access")]
+    Unauthorized,
+    #[error("Pattern with ID {0} not found")]
+    PatternNotFound(Uuid),
+    #[error("{0}")]
+    HandshakeError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for LivePatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for LivePatternError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            LivePatternError::Unauthorized => StatusCode::UNAUTHORIZED,
+            LivePatternError::PatternNotFound(_) => StatusCode::NOT_FOUND,
+            LivePatternError::HandshakeError(_) => StatusCode::BAD_REQUEST,
+            LivePatternError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+enum LiveEditError {
+    ReadOnly,
+    ValidationError(String),
+    Conflict { base_version: i64, version: i64 },
+    UnexpectedError(anyhow::Error),
+}
+
+impl From<anyhow::Error> for LiveEditError {
+    fn from(e: anyhow::Error) -> Self {
+        LiveEditError::UnexpectedError(e)
+    }
+}
+
+impl From<LiveEditError> for LiveServerMessage {
+    fn from(e: LiveEditError) -> Self {
+        match e {
+            LiveEditError::ReadOnly => LiveServerMessage::Error {
+                message: "You don't have permission to edit this pattern".to_string(),
+            },
+            LiveEditError::ValidationError(message) => LiveServerMessage::Error { message },
+            LiveEditError::Conflict {
+                base_version,
+                version,
+            } => LiveServerMessage::Conflict {
+                base_version,
+                version,
+                message: "The field was changed by someone else, reload and retry".to_string(),
+            },
+            LiveEditError::UnexpectedError(e) => {
+                tracing::error!("Failed to apply live edit: {:?}", e);
+                LiveServerMessage::Error {
+                    message: "Failed to apply edit".to_string(),
+                }
+            }
+        }
+    }
+}
+
+struct LiveConnection {
+    pattern_id: Uuid,
+    connection_id: Uuid,
+    user_id: UserId,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/patterns/tb303/{pattern_id}/live",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern to edit"),
+        ("token" = Option<String>, Query, description = "ID token, for clients that cannot set the Authorization header on WebSocket requests")
+    ),
+    responses(
+        (status = 101, description = "Switched to a WebSocket carrying LiveClientMessage and LiveServerMessage frames"),
+        (status = 400, description = "Not a WebSocket handshake", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found or access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Opening live TB303 pattern session",
+    skip(req, body, pool, cognito, live_sessions, cache, query_token)
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn live_tb303_pattern(
+    req: HttpRequest,
+    body: web::Payload,
+    pool: web::Data<PgPool>,
+    cognito: web::Data<CognitoSettings>,
+    live_sessions: web::Data<LiveSessions>,
+    cache: web::Data<PatternCache>,
+    pattern_id: web::Path<Uuid>,
+    query_token: Option<web::ReqData<QueryToken>>,
+) -> Result<HttpResponse, LivePatternError> {
+    let pattern_id = pattern_id.into_inner();
+
+    // Browsers cannot set headers on WebSocket requests, so the token may
+    // also come in the query string.
+    let user_id = match try_extract_user_id(req.headers(), &cognito).await {
+        Some(user_id) => Some(user_id),
+        None => match query_token {
+            Some(token) => validate_token(token.0.expose_secret(), &cognito).await,
+            None => None,
+        },
+    }
+    .ok_or(LivePatternError::Unauthorized)?;
+
+    let pattern = sqlx::query!(
+        r#"SELECT user_id, is_public, version FROM patterns_tb303 WHERE pattern_id = $1"#,
+        pattern_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .context("Failed to fetch pattern.")?
+    .ok_or(LivePatternError::PatternNotFound(pattern_id))?;
+
+    let is_owner = pattern.user_id == *user_id;
+    let role = if is_owner {
+        None
+    } else {
+        fetch_collaborator_role(pool.as_ref(), pattern_id, *user_id).await?
+    };
+    let can_edit = is_owner || role.is_some_and(|r| r.can_edit());
+    if !is_owner && role.is_none() && !pattern.is_public.unwrap_or(false) {
+        return Err(LivePatternError::PatternNotFound(pattern_id));
+    }
+
+    let username =
+        sqlx::query_scalar!(r#"SELECT username FROM users WHERE user_id = $1"#, *user_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .context("Failed to fetch username.")?
+            .unwrap_or_else(|| user_id.to_string());
+
+    let (response, session, stream) = actix_ws::handle(&req, body)
+        .map_err(|e| LivePatternError::HandshakeError(e.to_string()))?;
+
+    let connection = LiveConnection {
+        pattern_id,
+        connection_id: Uuid::new_v4(),
+        user_id,
+    };
+    let (receiver, users) = live_sessions.join(
+        pattern_id,
+        LivePresence {
+            connection_id: connection.connection_id,
+            user_id: *user_id,
+            username,
+            bar: None,
+            can_edit,
+        },
+        pattern.version,
+    );
+    let welcome = LiveServerMessage::Welcome {
+        connection_id: connection.connection_id,
+        version: pattern.version,
+        can_edit,
+        users: users.clone(),
+    };
+    live_sessions.broadcast(pattern_id, &LiveServerMessage::Presence { users });
+
+    actix_web::rt::spawn(
+        run_live_session(
+            connection,
+            session,
+            stream,
+            receiver,
+            pool,
+            live_sessions,
+            cache,
+            welcome,
+        )
+        .instrument(tracing::Span::current()),
+    );
+
+    Ok(response)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_live_session(
+    connection: LiveConnection,
+    mut session: Session,
+    mut stream: MessageStream,
+    mut receiver: broadcast::Receiver<String>,
+    pool: web::Data<PgPool>,
+    live_sessions: web::Data<LiveSessions>,
+    cache: web::Data<PatternCache>,
+    welcome: LiveServerMessage,
+) {
+    let mut open = send_message(&mut session, &welcome).await;
+
+    while open {
+        tokio::select! {
+            message = stream.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let reply =
+                        handle_client_message(&connection, &text, &pool, &live_sessions, &cache)
+                            .await;
+                    if let Some(reply) = reply {
+                        open = send_message(&mut session, &reply).await;
+                    }
+                }
+                Some(Ok(Message::Ping(bytes))) => open = session.pong(&bytes).await.is_ok(),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => open = false,
+                Some(Ok(_)) => {}
+            },
+            update = receiver.recv() => match update {
+                Ok(text) => open = session.text(text).await.is_ok(),
+                Err(RecvError::Lagged(missed)) => {
+                    let message = LiveServerMessage::Error {
+                        message: format!("Missed {missed} updates, reload the pattern"),
+                    };
+                    open = send_message(&mut session, &message).await;
+                }
+                Err(RecvError::Closed) => open = false,
+            },
+        }
+    }
+
+    if let Some(users) = live_sessions.leave(connection.pattern_id, connection.connection_id) {
+        live_sessions.broadcast(
+            connection.pattern_id,
+            &LiveServerMessage::Presence { users },
+        );
+    }
+    let _ = session.close(None).await;
+}
+
+async fn send_message(session: &mut Session, message: &LiveServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => session.text(text).await.is_ok(),
+        Err(e) => {
+            tracing::error!("Failed to serialize live message: {}", e);
+            true
+        }
+    }
+}
+
+async fn handle_client_message(
+    connection: &LiveConnection,
+    text: &str,
+    pool: &PgPool,
+    live_sessions: &LiveSessions,
+    cache: &PatternCache,
+) -> Option<LiveServerMessage> {
+    let message = match serde_json::from_str::<LiveClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return Some(LiveServerMessage::Error {
+                message: e.to_string(),
+            })
+        }
+    };
+
+    let result = match message {
+        LiveClientMessage::Presence { bar } => {
+            let users =
+                live_sessions.update_bar(connection.pattern_id, connection.connection_id, bar);
+            Ok(LiveServerMessage::Presence { users })
+        }
+        LiveClientMessage::StepEdit {
+            base_version,
+            bar,
+            step,
+        } => apply_step_edit(pool, live_sessions, connection, base_version, bar, step)
+            .await
+            .inspect(|_| cache.invalidate(connection.pattern_id)),
+        LiveClientMessage::KnobEdit {
+            base_version,
+            knob,
+            value,
+        } => apply_knob_edit(pool, live_sessions, connection, base_version, knob, value)
+            .await
+            .inspect(|_| cache.invalidate(connection.pattern_id)),
+    };
+
+    match result {
+        Ok(update) => {
+            live_sessions.broadcast(connection.pattern_id, &update);
+            None
+        }
+        Err(e) => Some(e.into()),
+    }
+}
+
+/// Locks the pattern and returns its version, if the user may still edit
+/// it. Roles are checked on every edit, since they can be changed or revoked
+/// while the session is open.
+async fn lock_pattern_version(
+    transaction: &mut Transaction<'_, Postgres>,
+    connection: &LiveConnection,
+) -> Result<i64, LiveEditError> {
+    let pattern = sqlx::query!(
+        r#"SELECT user_id, version FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE"#,
+        connection.pattern_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await
+    .context("Failed to lock pattern.")?
+    .ok_or_else(|| LiveEditError::ValidationError("Pattern no longer exists".to_string()))?;
+
+    if pattern.user_id != *connection.user_id {
+        let role = fetch_collaborator_role(
+            &mut **transaction,
+            connection.pattern_id,
+            *connection.user_id,
+        )
+        .await?;
+        if !role.is_some_and(|r| r.can_edit()) {
+            return Err(LiveEditError::ReadOnly);
+        }
+    }
+    Ok(pattern.version)
+}
+
+#[tracing::instrument(
+    name = "Applying live step edit",
+    skip(pool, live_sessions, connection, step),
+    fields(pattern_id = %connection.pattern_id)
+)]
+async fn apply_step_edit(
+    pool: &PgPool,
+    live_sessions: &LiveSessions,
+    connection: &LiveConnection,
+    base_version: i64,
+    bar: i32,
+    step: CreateTB303Step,
+) -> Result<LiveServerMessage, LiveEditError> {
+    let step: NewTB303Step = step
+        .try_into()
+        .map_err(|e: ValidationErrors| LiveEditError::ValidationError(e.to_string()))?;
+    let number = *step.number.as_ref();
+    let field = LiveField::Step { bar, number };
+    let pattern_id = connection.pattern_id;
+
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to start a transaction for live edit.")?;
+
+    let stored_version = lock_pattern_version(&mut transaction, connection).await?;
+    live_sessions
+        .check_edit(pattern_id, field, base_version, stored_version)
+        .map_err(|version| LiveEditError::Conflict {
+            base_version,
+            version,
+        })?;
+
+    let bar_id = sqlx::query_scalar!(
+        r#"SELECT bar_id FROM bars_tb303 WHERE pattern_id = $1 AND number = $2"#,
+        pattern_id,
+        bar
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch bar.")?
+    .ok_or_else(|| LiveEditError::ValidationError(format!("Bar {bar} does not exist")))?;
+
+    let note = step.note.as_ref().map(|n| n.as_ref());
+    let transpose = step.transpose.as_ref().map(|t| t.as_ref());
+    let accent = step.accent.unwrap_or(false);
+    let slide = step.slide.unwrap_or(false);
+
+    let updated_step_id = sqlx::query_scalar!(
+        r#"
+        UPDATE steps_tb303
+        SET note = $1, transpose = $2, time = $3, accent = $4, slide = $5, updated_at = NOW()
+        WHERE bar_id = $6 AND number = $7
+        RETURNING step_id
+        "#,
+        note,
+        transpose,
+        step.time.as_ref(),
+        accent,
+        slide,
+        bar_id,
+        number
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to update step.")?;
+
+    let step_id = match updated_step_id {
+        Some(step_id) => step_id,
+        None => {
+            let count = sqlx::query_scalar!(
+                r#"SELECT COUNT(*) AS "count!" FROM steps_tb303 WHERE bar_id = $1"#,
+                bar_id
+            )
+            .fetch_one(&mut *transaction)
+            .await
+            .context("Failed to count steps.")?;
+
+            if i64::from(number) != count + 1 {
+                return Err(LiveEditError::ValidationError(format!(
+                    "Bar {}: missing step in sequence: expected {}, found {}",
+                    bar,
+                    count + 1,
+                    number
+                )));
+            }
+
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO steps_tb303 (step_id, bar_id, number, note, transpose, time, accent, slide)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING step_id
+                "#,
+                Uuid::new_v4(),
+                bar_id,
+                number,
+                note,
+                transpose,
+                step.time.as_ref(),
+                accent,
+                slide
+            )
+            .fetch_one(&mut *transaction)
+            .await
+            .context("Failed to insert step.")?
+        }
+    };
+
+    let version = sqlx::query_scalar!(
+        r#"
+        UPDATE patterns_tb303 SET version = version + 1, updated_at = NOW()
+        WHERE pattern_id = $1
+        RETURNING version
+        "#,
+        pattern_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to bump pattern version.")?;
+
+    // Live edits never change visibility.
+    enqueue_pattern_event(
+        &mut transaction,
+        WebhookEvent::PatternUpdated,
+        pattern_id,
+        false,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit live step edit.")?;
+    live_sessions.record_edit(pattern_id, field, version);
+
+    Ok(LiveServerMessage::StepEdited {
+        version,
+        user_id: *connection.user_id,
+        bar,
+        step: TB303Step {
+            id: step_id,
+            number,
+            note: note.map(str::to_string),
+            transpose: transpose.map(str::to_string),
+            time: Some(step.time.as_ref().to_string()),
+            accent: Some(accent),
+            slide: Some(slide),
+        },
+    })
+}
+
+#[tracing::instrument(
+    name = "Applying live knob edit",
+    skip(pool, live_sessions, connection),
+    fields(pattern_id = %connection.pattern_id)
+)]
+async fn apply_knob_edit(
+    pool: &PgPool,
+    live_sessions: &LiveSessions,
+    connection: &LiveConnection,
+    base_version: i64,
+    knob: LiveKnob,
+    value: i32,
+) -> Result<LiveServerMessage, LiveEditError> {
+    let value = Knob::parse(value).map_err(LiveEditError::ValidationError)?;
+    let field = LiveField::Knob(knob);
+    let pattern_id = connection.pattern_id;
+
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to start a transaction for live edit.")?;
+
+    let stored_version = lock_pattern_version(&mut transaction, connection).await?;
+    live_sessions
+        .check_edit(pattern_id, field, base_version, stored_version)
+        .map_err(|version| LiveEditError::Conflict {
+            base_version,
+            version,
+        })?;
+
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE patterns_tb303 SET ");
+    query_builder
+        .push(knob.column())
+        .push(" = ")
+        .push_bind(*value.as_ref())
+        .push(", version = version + 1, updated_at = NOW() WHERE pattern_id = ")
+        .push_bind(pattern_id)
+        .push(" RETURNING version");
+
+    let version: i64 = query_builder
+        .build_query_scalar()
+        .fetch_one(&mut *transaction)
+        .await
+        .context("Failed to update knob.")?;
+
+    // Live edits never change visibility.
+    enqueue_pattern_event(
+        &mut transaction,
+        WebhookEvent::PatternUpdated,
+        pattern_id,
+        false,
+    )
+    .await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit live knob edit.")?;
+    live_sessions.record_edit(pattern_id, field, version);
+
+    Ok(LiveServerMessage::KnobEdited {
+        version,
+        user_id: *connection.user_id,
+        knob,
+        value: *value.as_ref(),
+    })
+}
@@ -0,0 +1,591 @@
+use crate::api::models::tb303::{
+    AttachTB303Audio, AttachTB303Cover, TB303PatternAudio, TB303PatternCover,
+};
+use crate::audio_processing::probe_audio;
+use crate::authentication::UserId;
+use crate::domain::UploadType;
+use crate::image_processing::{image_url, variant_urls};
+use crate::jobs::{enqueue_job, Job};
+use crate::metrics::begin_transaction;
+use crate::pattern_cache::PatternCache;
+use crate::problem::Problem;
+use crate::routes::uploads::{
+    discard_processed_upload, process_image_upload, verify_upload, ProcessUploadError,
+    VerifyUploadError,
+};
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum PatternMediaError {
+    #[error("Pattern with ID {0} not found")]
+    PatternNotFound(Uuid),
+    #[error("Access denied: only the pattern owner can manage its media")]
+    AccessDenied,
+    #[error("Invalid key")]
+    InvalidKey,
+    #[error("No file has been uploaded for this key")]
+    UploadNotFound,
+    #[error("{0}")]
+    UploadMismatch(String),
+    #[error("{0}")]
+    InvalidAudio(String),
+    #[error("{0}")]
+    InvalidImage(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl From<VerifyUploadError> for PatternMediaError {
+    fn from(e: VerifyUploadError) -> Self {
+        match e {
+            VerifyUploadError::InvalidKey => PatternMediaError::InvalidKey,
+            VerifyUploadError::UploadNotFound => PatternMediaError::UploadNotFound,
+            VerifyUploadError::UploadMismatch(message) => {
+                PatternMediaError::UploadMismatch(message)
+            }
+            VerifyUploadError::UnexpectedError(e) => PatternMediaError::UnexpectedError(e),
+        }
+    }
+}
+
+impl From<ProcessUploadError> for PatternMediaError {
+    fn from(e: ProcessUploadError) -> Self {
+        match e {
+            ProcessUploadError::InvalidImage(message) => PatternMediaError::InvalidImage(message),
+            ProcessUploadError::UnexpectedError(e) => PatternMediaError::UnexpectedError(e),
+        }
+    }
+}
+
+impl std::fmt::Debug for PatternMediaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for PatternMediaError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PatternMediaError::PatternNotFound(_) => StatusCode::NOT_FOUND,
+            PatternMediaError::AccessDenied => StatusCode::FORBIDDEN,
+            PatternMediaError::InvalidKey
+            | PatternMediaError::UploadNotFound
+            | PatternMediaError::UploadMismatch(_)
+            | PatternMediaError::InvalidAudio(_)
+            | PatternMediaError::InvalidImage(_) => StatusCode::BAD_REQUEST,
+            PatternMediaError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+/// The `kind` of a `pattern_media` row. A pattern has at most one of each.
+#[derive(Debug, Clone, Copy)]
+enum MediaKind {
+    Audio,
+    Cover,
+}
+
+impl AsRef<str> for MediaKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            MediaKind::Audio => "audio",
+            MediaKind::Cover => "cover",
+        }
+    }
+}
+
+/// A verified and processed upload, ready to be attached.
+struct NewMedia<'a> {
+    key: &'a str,
+    content_type: &'a str,
+    duration_ms: Option<i64>,
+    variant_sizes: Vec<i32>,
+}
+
+/// Locks the pattern until the end of the surrounding transaction, if any,
+/// and checks that `user_id` owns it.
+async fn lock_owned_pattern(
+    executor: impl PgExecutor<'_>,
+    pattern_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), PatternMediaError> {
+    let owner_id = sqlx::query_scalar!(
+        r#"SELECT user_id FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE"#,
+        pattern_id
+    )
+    .fetch_optional(executor)
+    .await
+    .context("Failed to fetch pattern owner.")?
+    .ok_or(PatternMediaError::PatternNotFound(pattern_id))?;
+
+    if owner_id != user_id {
+        return Err(PatternMediaError::AccessDenied);
+    }
+    Ok(())
+}
+
+/// Claims the upload and attaches it to the pattern in place of the previous
+/// media of the same kind, whose upload is released and queued for deletion.
+/// `transaction` must hold the locks taken by `lock_owned_pattern` and
+/// `verify_upload`.
+#[tracing::instrument(name = "Attaching media to pattern", skip(transaction, media))]
+async fn attach_media(
+    mut transaction: Transaction<'_, Postgres>,
+    pattern_id: Uuid,
+    kind: MediaKind,
+    media: NewMedia<'_>,
+) -> Result<(), PatternMediaError> {
+    let previous = sqlx::query_scalar!(
+        r#"
+        SELECT key FROM pattern_media
+        WHERE pattern_id = $1 AND kind = $2
+        FOR UPDATE
+        "#,
+        pattern_id,
+        kind.as_ref()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch previous pattern media.")?;
+
+    // Verification locked the upload, so it is still there unclaimed.
+    sqlx::query!(
+        r#"
+        UPDATE uploads SET claimed_at = NOW(), variant_sizes = $2
+        WHERE key = $1 AND claimed_at IS NULL
+        "#,
+        media.key,
+        &media.variant_sizes
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to claim upload.")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO pattern_media (pattern_id, kind, key, content_type, duration_ms)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (pattern_id, kind) DO UPDATE SET
+            key = EXCLUDED.key,
+            content_type = EXCLUDED.content_type,
+            duration_ms = EXCLUDED.duration_ms,
+            created_at = NOW()
+        "#,
+        pattern_id,
+        kind.as_ref(),
+        media.key,
+        media.content_type,
+        media.duration_ms
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to attach media to pattern.")?;
+
+    if let Some(ref previous) = previous {
+        sqlx::query!(
+            r#"UPDATE uploads SET claimed_at = NULL WHERE key = $1"#,
+            previous
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to release previous pattern media.")?;
+        enqueue_job(
+            &mut transaction,
+            &Job::DeleteUpload {
+                key: previous.clone(),
+            },
+        )
+        .await?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the database transaction.")?;
+
+    Ok(())
+}
+
+/// Removes the pattern's media of `kind`, if any, and queues its upload for
+/// deletion.
+#[tracing::instrument(name = "Detaching media from pattern", skip(pool))]
+async fn detach_media(
+    pool: &PgPool,
+    user_id: Uuid,
+    pattern_id: Uuid,
+    kind: MediaKind,
+) -> Result<(), PatternMediaError> {
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to begin a database transaction.")?;
+
+    lock_owned_pattern(&mut *transaction, pattern_id, user_id).await?;
+
+    let key = sqlx::query_scalar!(
+        r#"
+        DELETE FROM pattern_media WHERE pattern_id = $1 AND kind = $2
+        RETURNING key
+        "#,
+        pattern_id,
+        kind.as_ref()
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to remove pattern media.")?;
+
+    if let Some(ref key) = key {
+        sqlx::query!(
+            r#"UPDATE uploads SET claimed_at = NULL WHERE key = $1"#,
+            key
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to release pattern media.")?;
+        enqueue_job(&mut transaction, &Job::DeleteUpload { key: key.clone() }).await?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the database transaction.")?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Fetching pattern audio", skip(pool, storage))]
+async fn fetch_pattern_audio(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+    key: &str,
+) -> Result<Option<TB303PatternAudio>, anyhow::Error> {
+    let audio = sqlx::query!(
+        r#"
+        SELECT key, content_type, duration_ms FROM pattern_media
+        WHERE pattern_id = $1 AND kind = 'audio' AND key = $2
+        "#,
+        pattern_id,
+        key
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to fetch pattern audio.")?;
+
+    Ok(audio.map(|audio| TB303PatternAudio {
+        url: storage.get_public_url(&audio.key),
+        content_type: audio.content_type,
+        duration_ms: audio.duration_ms.unwrap_or(0),
+    }))
+}
+
+#[tracing::instrument(name = "Fetching pattern cover", skip(pool, storage))]
+async fn fetch_pattern_cover(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    pattern_id: Uuid,
+    key: &str,
+) -> Result<Option<TB303PatternCover>, anyhow::Error> {
+    let cover = sqlx::query!(
+        r#"
+        SELECT m.key, u.variant_sizes FROM pattern_media m
+        JOIN uploads u ON u.key = m.key
+        WHERE m.pattern_id = $1 AND m.kind = 'cover' AND m.key = $2
+        "#,
+        pattern_id,
+        key
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to fetch pattern cover.")?;
+
+    Ok(cover.map(|cover| TB303PatternCover {
+        url: image_url(storage, &cover.key, &cover.variant_sizes),
+        variants: variant_urls(storage, &cover.key, &cover.variant_sizes),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/patterns/tb303/{pattern_id}/audio",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    request_body = AttachTB303Audio,
+    responses(
+        (status = 200, description = "Audio attached to the pattern, replacing any previous clip", body = TB303PatternAudio),
+        (status = 400, description = "The upload is missing, does not match or is not a supported audio clip", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Attaching audio to TB303 pattern",
+    skip(pool, storage, user_id, body, cache)
+)]
+pub async fn put_tb303_audio(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
+    pattern_id: web::Path<Uuid>,
+    body: web::Json<AttachTB303Audio>,
+) -> Result<web::Json<TB303PatternAudio>, PatternMediaError> {
+    let user_id = user_id.into_inner();
+    let pattern_id = pattern_id.into_inner();
+    let key = body.into_inner().audio_key;
+
+    lock_owned_pattern(pool.as_ref(), pattern_id, *user_id).await?;
+
+    let Some(content_type) = verify_upload(
+        pool.as_ref(),
+        storage.as_ref(),
+        *user_id,
+        &key,
+        UploadType::PatternAudio,
+    )
+    .await?
+    else {
+        // Attaching the current clip again changes nothing.
+        return fetch_pattern_audio(pool.as_ref(), storage.as_ref(), pattern_id, &key)
+            .await?
+            .map(web::Json)
+            .ok_or(PatternMediaError::InvalidKey);
+    };
+
+    // Downloading and probing can take a while, so it happens before the
+    // pattern and the upload are locked.
+    let bytes = storage
+        .get_object(&key)
+        .await
+        .context("Failed to fetch audio for validation")?;
+    let declared = content_type.clone();
+    let info = web::block(move || probe_audio(bytes, &declared))
+        .await
+        .context("Audio validation was cancelled")?
+        .map_err(|e| PatternMediaError::InvalidAudio(e.to_string()))?;
+
+    let mut transaction = begin_transaction(&pool)
+        .await
+        .context("Failed to begin a database transaction.")?;
+    lock_owned_pattern(&mut *transaction, pattern_id, *user_id).await?;
+    // Checked again under the lock, in case the upload was claimed or swept
+    // in the meantime.
+    verify_upload(
+        &mut *transaction,
+        storage.as_ref(),
+        *user_id,
+        &key,
+        UploadType::PatternAudio,
+    )
+    .await?
+    .ok_or(PatternMediaError::InvalidKey)?;
+
+    attach_media(
+        transaction,
+        pattern_id,
+        MediaKind::Audio,
+        NewMedia {
+            key: &key,
+            content_type: &content_type,
+            duration_ms: Some(info.duration_ms),
+            variant_sizes: Vec::new(),
+        },
+    )
+    .await?;
+    cache.invalidate(pattern_id);
+
+    Ok(web::Json(TB303PatternAudio {
+        url: storage.get_public_url(&key),
+        content_type,
+        duration_ms: info.duration_ms,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/patterns/tb303/{pattern_id}/audio",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    responses(
+        (status = 204, description = "Audio removed from the pattern, or it had none"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Removing audio from TB303 pattern", skip(pool, user_id, cache))]
+pub async fn delete_tb303_audio(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<HttpResponse, PatternMediaError> {
+    let pattern_id = pattern_id.into_inner();
+    detach_media(
+        pool.as_ref(),
+        *user_id.into_inner(),
+        pattern_id,
+        MediaKind::Audio,
+    )
+    .await?;
+    cache.invalidate(pattern_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/patterns/tb303/{pattern_id}/cover",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    request_body = AttachTB303Cover,
+    responses(
+        (status = 200, description = "Cover attached to the pattern, replacing any previous one", body = TB303PatternCover),
+        (status = 400, description = "The upload is missing, does not match or is not a valid image", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Attaching cover to TB303 pattern",
+    skip(pool, storage, user_id, body, cache)
+)]
+pub async fn put_tb303_cover(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
+    pattern_id: web::Path<Uuid>,
+    body: web::Json<AttachTB303Cover>,
+) -> Result<web::Json<TB303PatternCover>, PatternMediaError> {
+    let user_id = user_id.into_inner();
+    let pattern_id = pattern_id.into_inner();
+    let key = body.into_inner().cover_key;
+
+    lock_owned_pattern(pool.as_ref(), pattern_id, *user_id).await?;
+
+    let Some(content_type) = verify_upload(
+        pool.as_ref(),
+        storage.as_ref(),
+        *user_id,
+        &key,
+        UploadType::PatternCover,
+    )
+    .await?
+    else {
+        // Attaching the current cover again changes nothing.
+        return fetch_pattern_cover(pool.as_ref(), storage.as_ref(), pattern_id, &key)
+            .await?
+            .map(web::Json)
+            .ok_or(PatternMediaError::InvalidKey);
+    };
+
+    // Processing can take a while, so it happens before the pattern and the
+    // upload are locked.
+    let sizes = process_image_upload(
+        storage.as_ref(),
+        &key,
+        UploadType::PatternCover,
+        content_type,
+    )
+    .await?;
+
+    let mut transaction = begin_transaction(&pool)
+        .await
+        .context("Failed to begin a database transaction.")?;
+    lock_owned_pattern(&mut *transaction, pattern_id, *user_id).await?;
+    // Checked again under the lock, in case the upload was claimed or swept
+    // in the meantime.
+    verify_upload(
+        &mut *transaction,
+        storage.as_ref(),
+        *user_id,
+        &key,
+        UploadType::PatternCover,
+    )
+    .await?
+    .ok_or(PatternMediaError::InvalidKey)?;
+
+    attach_media(
+        transaction,
+        pattern_id,
+        MediaKind::Cover,
+        NewMedia {
+            key: &key,
+            // Processing re-encodes the upload.
+            content_type: "image/webp",
+            duration_ms: None,
+            variant_sizes: sizes.clone(),
+        },
+    )
+    .await?;
+    cache.invalidate(pattern_id);
+    discard_processed_upload(storage.as_ref(), &key).await;
+
+    Ok(web::Json(TB303PatternCover {
+        url: image_url(storage.as_ref(), &key, &sizes),
+        variants: variant_urls(storage.as_ref(), &key, &sizes),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/patterns/tb303/{pattern_id}/cover",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    responses(
+        (status = 204, description = "Cover removed from the pattern, or it had none"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Removing cover from TB303 pattern", skip(pool, user_id, cache))]
+pub async fn delete_tb303_cover(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<HttpResponse, PatternMediaError> {
+    let pattern_id = pattern_id.into_inner();
+    detach_media(
+        pool.as_ref(),
+        *user_id.into_inner(),
+        pattern_id,
+        MediaKind::Cover,
+    )
+    .await?;
+    cache.invalidate(pattern_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,13 +1,21 @@
+mod collaborators_tb303;
 mod delete_tb303;
 mod get_tb303;
+mod import_tb303;
 mod list_public_tb303;
 mod list_tb303;
+mod live_tb303;
+mod media_tb303;
 pub mod post_tb303;
-mod response;
+mod preview_tb303;
 
+pub use collaborators_tb303::*;
 pub use delete_tb303::*;
 pub use get_tb303::*;
+pub use import_tb303::*;
 pub use list_public_tb303::*;
 pub use list_tb303::*;
+pub use live_tb303::*;
+pub use media_tb303::*;
 pub use post_tb303::*;
-pub use response::*;
+pub use preview_tb303::*;
@@ -1,14 +1,19 @@
-use crate::api::models::tb303::CreateTB303Pattern;
+use crate::api::models::tb303::{CreateTB303Bar, CreateTB303Pattern, CreateTB303Step};
 use crate::authentication::UserId;
 use crate::domain::{
     Author, Description, Knob, Name, NewTB303Bar, NewTB303Pattern, NewTB303Step, StepNumber, Tempo,
-    Title,
+    Title, WebhookEvent,
 };
-use crate::routes::patterns::PatternErrorResponse;
+use crate::metrics::begin_transaction;
+use crate::pattern_cache::PatternCache;
+use crate::problem::{FieldErrorCode, Problem, ValidationErrors};
+use crate::repository::PatternRepository;
+use crate::routes::patterns::fetch_collaborator_role;
 use crate::utils::error_chain_fmt;
+use crate::webhooks::enqueue_pattern_event;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
 use anyhow::Context;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::{Executor, PgPool, Postgres, QueryBuilder, Transaction};
 use std::collections::HashSet;
 use std::convert::TryInto;
@@ -32,7 +37,7 @@ pub struct PatternTB303ResponseData {
 #[derive(thiserror::Error)]
 pub enum CreatePatternError {
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -41,169 +46,232 @@ pub enum CreatePatternError {
 pub enum UpdatePatternError {
     #[error("Pattern with ID {0} not found")]
     PatternNotFound(Uuid),
+    #[error("Access denied: you don't have permission to edit this pattern")]
+    AccessDenied,
+    #[error("Only the pattern owner can change its visibility")]
+    VisibilityChangeDenied,
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
 impl TryInto<NewTB303Pattern> for CreateTB303Pattern {
-    type Error = String;
+    type Error = ValidationErrors;
 
     fn try_into(self) -> Result<NewTB303Pattern, Self::Error> {
-        fn parse_optional<T, U, F>(opt: Option<U>, parse_fn: F) -> Result<Option<T>, String>
-        where
-            F: FnOnce(U) -> Result<T, String>,
-        {
-            opt.map(parse_fn).transpose().map_err(|e| e.to_string())
-        }
-
-        let name = Name::parse(self.name).map_err(|e| e.to_string())?;
-        let author = parse_optional(self.author, Author::parse)?;
-        let title = parse_optional(self.title, Title::parse)?;
-        let description = parse_optional(self.description, Description::parse)?;
-        let waveform = self.waveform;
-        let triplets = self.triplets;
-        let tempo = parse_optional(self.tempo, Tempo::parse)?;
-        let tuning = parse_optional(self.tuning, Knob::parse)?;
-        let cut_off_freq = parse_optional(self.cut_off_freq, Knob::parse)?;
-        let resonance = parse_optional(self.resonance, Knob::parse)?;
-        let env_mod = parse_optional(self.env_mod, Knob::parse)?;
-        let decay = parse_optional(self.decay, Knob::parse)?;
-        let accent = parse_optional(self.accent, Knob::parse)?;
-        let is_public = self.is_public;
+        use FieldErrorCode::{Duplicate, Empty, Invalid, NotSequential, OutOfRange, TooMany};
+
+        let mut errors = ValidationErrors::default();
+        let name = errors.check("/name", Invalid, Name::parse(self.name));
+        let author = self
+            .author
+            .and_then(|a| errors.check("/author", Invalid, Author::parse(a)));
+        let title = self
+            .title
+            .and_then(|t| errors.check("/title", Invalid, Title::parse(t)));
+        let description = self
+            .description
+            .and_then(|d| errors.check("/description", Invalid, Description::parse(d)));
+        let tempo = self
+            .tempo
+            .and_then(|t| errors.check("/tempo", OutOfRange, Tempo::parse(t)));
+        let mut knob = |field: &str, value: Option<i32>| {
+            value.and_then(|v| errors.check(&format!("/{field}"), OutOfRange, Knob::parse(v)))
+        };
+        let tuning = knob("tuning", self.tuning);
+        let cut_off_freq = knob("cut_off_freq", self.cut_off_freq);
+        let resonance = knob("resonance", self.resonance);
+        let env_mod = knob("env_mod", self.env_mod);
+        let decay = knob("decay", self.decay);
+        let accent = knob("accent", self.accent);
 
         if self.bars.is_empty() {
-            return Err("Pattern must contain at least one step.".to_string());
+            errors.add("/bars", Empty, "Pattern must contain at least one step.");
         }
         if self.bars.len() > 16 {
-            return Err("Pattern can only have up to 16 bars".to_string());
+            errors.add("/bars", TooMany, "Pattern can only have up to 16 bars");
         }
 
         let mut seen_bar_numbers = HashSet::new();
-        for bar in &self.bars {
+        for (index, bar) in self.bars.iter().enumerate() {
             if !seen_bar_numbers.insert(bar.number) {
-                return Err(format!("Duplicate bar number: {}", bar.number));
+                errors.add(
+                    format!("/bars/{index}/number"),
+                    Duplicate,
+                    format!("Duplicate bar number: {}", bar.number),
+                );
             }
         }
-
-        let mut bar_numbers: Vec<i32> = self.bars.iter().map(|b| b.number).collect();
-        bar_numbers.sort();
-
-        if bar_numbers[0] != 1 {
-            return Err("Bar sequence must start with 1".to_string());
+        match find_gap(self.bars.iter().map(|b| b.number)) {
+            Some((1, _)) => errors.add("/bars", NotSequential, "Bar sequence must start with 1"),
+            Some((expected, found)) => errors.add(
+                "/bars",
+                NotSequential,
+                format!("Missing bar in sequence: expected {expected}, found {found}"),
+            ),
+            None => {}
         }
 
-        for i in 1..bar_numbers.len() {
-            if bar_numbers[i] != bar_numbers[i - 1] + 1 {
-                return Err(format!(
-                    "Missing bar in sequence: expected {}, found {}",
-                    bar_numbers[i - 1] + 1,
-                    bar_numbers[i]
-                ));
+        let mut bars = Vec::with_capacity(self.bars.len());
+        for (index, bar) in self.bars.into_iter().enumerate() {
+            let pointer = format!("/bars/{index}");
+            match bar.try_into() {
+                Ok(bar) => bars.push(bar),
+                Err(bar_errors) => errors.nest(&pointer, bar_errors),
             }
         }
 
-        let bars: Result<Vec<NewTB303Bar>, String> = self
-            .bars
-            .into_iter()
-            .map(|bar| {
-                let bar_number = bar.number;
-
-                if bar.steps.is_empty() {
-                    return Err(format!(
-                        "Bar {} must contain at least one step.",
-                        bar_number
-                    ));
-                }
-                if bar.steps.len() > 16 {
-                    return Err(format!("Bar {} can only have up to 16 steps.", bar_number));
-                }
+        match name {
+            Some(name) if errors.is_empty() => Ok(NewTB303Pattern {
+                name,
+                author,
+                title,
+                description,
+                waveform: self.waveform,
+                triplets: self.triplets,
+                tempo,
+                tuning,
+                cut_off_freq,
+                resonance,
+                env_mod,
+                decay,
+                accent,
+                is_public: self.is_public,
+                bars,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
 
-                let steps: Result<Vec<NewTB303Step>, String> = bar
-                    .steps
-                    .into_iter()
-                    .map(|step| {
-                        if step.time.as_ref() == "rest"
-                            && (step.note.is_some() || step.transpose.is_some())
-                        {
-                            return Err(format!(
-                                "Step {} is marked as 'rest' but contains a note or octave.",
-                                step.number
-                            ));
-                        }
-                        Ok(NewTB303Step {
-                            number: StepNumber::parse(step.number).map_err(|e| e.to_string())?,
-                            note: step.note,
-                            transpose: step.transpose,
-                            time: step.time,
-                            accent: step.accent,
-                            slide: step.slide,
-                        })
-                    })
-                    .collect();
-
-                let steps = steps?;
-
-                let mut seen_step_numbers = HashSet::new();
-                for step in &steps {
-                    if !seen_step_numbers.insert(step.number.as_ref()) {
-                        return Err(format!(
-                            "Duplicate step number in bar {}: {}",
-                            bar_number,
-                            step.number.as_ref()
-                        ));
-                    }
-                }
+impl TryInto<NewTB303Bar> for CreateTB303Bar {
+    type Error = ValidationErrors;
 
-                let mut step_numbers: Vec<i32> = steps.iter().map(|s| *s.number.as_ref()).collect();
-                step_numbers.sort();
+    fn try_into(self) -> Result<NewTB303Bar, Self::Error> {
+        use FieldErrorCode::{Duplicate, Empty, NotSequential, TooMany};
 
-                if step_numbers[0] != 1 {
-                    return Err(format!(
-                        "Bar {}: step sequence must start with 1",
-                        bar_number
-                    ));
-                }
-                for i in 1..step_numbers.len() {
-                    if step_numbers[i] != step_numbers[i - 1] + 1 {
-                        return Err(format!(
-                            "Bar {}: missing step in sequence: expected {}, found {}",
-                            bar_number,
-                            step_numbers[i - 1] + 1,
-                            step_numbers[i]
-                        ));
-                    }
-                }
-                Ok(NewTB303Bar {
-                    number: bar_number,
-                    steps,
-                })
-            })
-            .collect();
+        let bar_number = self.number;
+        let mut errors = ValidationErrors::default();
 
-        let bars = bars?;
+        if self.steps.is_empty() {
+            errors.add(
+                "/steps",
+                Empty,
+                format!("Bar {} must contain at least one step.", bar_number),
+            );
+        }
+        if self.steps.len() > 16 {
+            errors.add(
+                "/steps",
+                TooMany,
+                format!("Bar {} can only have up to 16 steps.", bar_number),
+            );
+        }
 
-        Ok(NewTB303Pattern {
-            name,
-            author,
-            title,
-            description,
-            waveform,
-            triplets,
-            tempo,
-            tuning,
-            cut_off_freq,
-            resonance,
-            env_mod,
-            decay,
-            accent,
-            is_public,
-            bars,
+        let mut seen_step_numbers = HashSet::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            if !seen_step_numbers.insert(step.number) {
+                errors.add(
+                    format!("/steps/{index}/number"),
+                    Duplicate,
+                    format!(
+                        "Duplicate step number in bar {}: {}",
+                        bar_number, step.number
+                    ),
+                );
+            }
+        }
+        // Numbers out of range are reported on the step instead.
+        let valid_numbers = self
+            .steps
+            .iter()
+            .map(|s| s.number)
+            .filter(|&n| StepNumber::parse(n).is_ok());
+        match find_gap(valid_numbers) {
+            Some((1, _)) => errors.add(
+                "/steps",
+                NotSequential,
+                format!("Bar {}: step sequence must start with 1", bar_number),
+            ),
+            Some((expected, found)) => errors.add(
+                "/steps",
+                NotSequential,
+                format!(
+                    "Bar {}: missing step in sequence: expected {}, found {}",
+                    bar_number, expected, found
+                ),
+            ),
+            None => {}
+        }
+
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for (index, step) in self.steps.into_iter().enumerate() {
+            match step.try_into() {
+                Ok(step) => steps.push(step),
+                Err(step_errors) => errors.nest(&format!("/steps/{index}"), step_errors),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(NewTB303Bar {
+            number: bar_number,
+            steps,
         })
     }
 }
 
+impl TryInto<NewTB303Step> for CreateTB303Step {
+    type Error = ValidationErrors;
+
+    fn try_into(self) -> Result<NewTB303Step, Self::Error> {
+        let mut errors = ValidationErrors::default();
+        let number = errors.check(
+            "/number",
+            FieldErrorCode::OutOfRange,
+            StepNumber::parse(self.number),
+        );
+        if self.time.as_ref() == "rest" {
+            let message = format!(
+                "Step {} is marked as 'rest' but contains a note or octave.",
+                self.number
+            );
+            if self.note.is_some() {
+                errors.add("/note", FieldErrorCode::NotAllowed, &message);
+            }
+            if self.transpose.is_some() {
+                errors.add("/transpose", FieldErrorCode::NotAllowed, &message);
+            }
+        }
+
+        match number {
+            Some(number) if errors.is_empty() => Ok(NewTB303Step {
+                number,
+                note: self.note,
+                transpose: self.transpose,
+                time: self.time,
+                accent: self.accent,
+                slide: self.slide,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// The first number missing from `numbers` counting up from 1, and the
+/// number found in its place. Duplicates are reported separately.
+fn find_gap(numbers: impl Iterator<Item = i32>) -> Option<(i32, i32)> {
+    let mut numbers: Vec<i32> = numbers.collect();
+    numbers.sort();
+    numbers.dedup();
+
+    (1..)
+        .zip(numbers)
+        .find(|(expected, number)| expected != number)
+}
+
 #[tracing::instrument(
     name = "Saving tb303 pattern steps in the database",
     skip(transaction, steps)
@@ -271,8 +339,8 @@ pub async fn insert_bars_tb303(
     path = "/v1/patterns/tb303",
     responses(
         (status = 200, description = "Pattern created successfully", body = PatternTB303Response),
-        (status = 400, description = "Invalid input"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Invalid input", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
@@ -280,11 +348,11 @@ pub async fn insert_bars_tb303(
 )]
 #[tracing::instrument(
     name = "Adding new pattern"
-    skip(pattern, pool, user_id)
+    skip(pattern, patterns, user_id)
 )]
 pub async fn create_tb303_pattern(
     pattern: web::Json<CreateTB303Pattern>,
-    pool: web::Data<PgPool>,
+    patterns: web::Data<dyn PatternRepository>,
     user_id: web::ReqData<UserId>,
 ) -> Result<web::Json<PatternTB303Response>, CreatePatternError> {
     let user_id = user_id.into_inner();
@@ -294,23 +362,10 @@ pub async fn create_tb303_pattern(
         .try_into()
         .map_err(CreatePatternError::ValidationError)?;
 
-    let mut transaction = pool
-        .begin()
-        .await
-        .context("Failed to start a new transaction.")?;
-
-    let pattern_id = insert_pattern(&mut transaction, &new_pattern, &user_id)
+    let pattern_id = patterns
+        .create_pattern(*user_id, &new_pattern)
         .await
-        .context("Failed to insert new pattern in the database.")?;
-
-    insert_bars_tb303(&mut transaction, pattern_id, &new_pattern.bars)
-        .await
-        .context("Failed to insert new pattern bars and steps.")?;
-
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit the transaction to save tb303 pattern.")?;
+        .context("Failed to save new pattern.")?;
 
     Ok(web::Json(PatternTB303Response {
         status: "success".to_string(),
@@ -326,9 +381,10 @@ pub async fn create_tb303_pattern(
     path = "/v1/patterns/tb303/{pattern_id}",
     responses(
         (status = 200, description = "Pattern updated successfully", body = PatternTB303Response),
-        (status = 400, description = "Invalid input"),
-        (status = 404, description = "Pattern not found"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Invalid input", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Access denied or visibility change by a non-owner", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Pattern not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     params(
         ("pattern_id" = String, Path, description = "ID of the pattern to update")
@@ -337,12 +393,13 @@ pub async fn create_tb303_pattern(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Updating tb303 pattern", skip(pattern, pool, user_id))]
+#[tracing::instrument(name = "Updating tb303 pattern", skip(pattern, pool, user_id, cache))]
 pub async fn update_tb303_pattern(
     pattern_id: web::Path<Uuid>,
     pattern: web::Json<CreateTB303Pattern>,
     pool: web::Data<PgPool>,
     user_id: web::ReqData<UserId>,
+    cache: web::Data<PatternCache>,
 ) -> Result<web::Json<PatternTB303Response>, UpdatePatternError> {
     let pattern_id = pattern_id.into_inner();
 
@@ -353,28 +410,78 @@ pub async fn update_tb303_pattern(
         .try_into()
         .map_err(UpdatePatternError::ValidationError)?;
 
-    let mut transaction = pool
-        .begin()
+    let mut transaction = begin_transaction(&pool)
         .await
         .context("Failed to start a transaction for update")?;
 
-    let exists = sqlx::query_scalar!(
+    let existing = sqlx::query!(
         r#"
-        SELECT EXISTS (
-            SELECT 1 FROM patterns_tb303 WHERE pattern_id = $1 AND user_id = $2
-        ) AS "exists!"
+        SELECT user_id, is_public FROM patterns_tb303 WHERE pattern_id = $1 FOR UPDATE
         "#,
-        pattern_id,
-        *user_id
+        pattern_id
     )
-    .fetch_one(&mut *transaction)
+    .fetch_optional(&mut *transaction)
     .await
-    .context("Failed to check if pattern exists")?;
+    .context("Failed to check if pattern exists")?
+    .ok_or(UpdatePatternError::PatternNotFound(pattern_id))?;
+
+    let is_public = if existing.user_id == *user_id {
+        new_pattern.is_public.unwrap_or(false)
+    } else {
+        match fetch_collaborator_role(&mut *transaction, pattern_id, *user_id).await? {
+            Some(role) if role.can_edit() => {
+                let current = existing.is_public.unwrap_or(false);
+                if new_pattern.is_public.is_some_and(|p| p != current) {
+                    return Err(UpdatePatternError::VisibilityChangeDenied);
+                }
+                current
+            }
+            Some(_) => return Err(UpdatePatternError::AccessDenied),
+            None => return Err(UpdatePatternError::PatternNotFound(pattern_id)),
+        }
+    };
 
-    if !exists {
-        return Err(UpdatePatternError::PatternNotFound(pattern_id));
+    replace_pattern_content(&mut transaction, pattern_id, &new_pattern, is_public).await?;
+
+    let was_public = existing.is_public.unwrap_or(false);
+    enqueue_pattern_event(
+        &mut transaction,
+        WebhookEvent::PatternUpdated,
+        pattern_id,
+        was_public,
+    )
+    .await?;
+    if is_public != was_public {
+        let event = if is_public {
+            WebhookEvent::PatternPublished
+        } else {
+            WebhookEvent::PatternUnpublished
+        };
+        enqueue_pattern_event(&mut transaction, event, pattern_id, was_public).await?;
     }
 
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    cache.invalidate(pattern_id);
+
+    Ok(web::Json(PatternTB303Response {
+        status: "success".to_string(),
+        data: PatternTB303ResponseData {
+            id: pattern_id.to_string(),
+        },
+    }))
+}
+
+/// Overwrites the content of an existing pattern, bars included. The owner
+/// and creation time are kept.
+pub async fn replace_pattern_content(
+    transaction: &mut Transaction<'_, Postgres>,
+    pattern_id: Uuid,
+    new_pattern: &NewTB303Pattern,
+    is_public: bool,
+) -> Result<(), anyhow::Error> {
     sqlx::query!(
         r#"
         UPDATE patterns_tb303
@@ -392,7 +499,8 @@ pub async fn update_tb303_pattern(
             decay = $12,
             accent = $13,
             is_public = $14,
-            updated_at = $15
+            updated_at = $15,
+            version = version + 1
         WHERE pattern_id = $16
         "#,
         new_pattern.name.as_ref(),
@@ -428,11 +536,11 @@ pub async fn update_tb303_pattern(
             .as_ref()
             .map(|v| v.as_ref())
             .unwrap_or(&0),
-        new_pattern.is_public.unwrap_or(false),
+        is_public,
         Utc::now(),
         pattern_id,
     )
-    .execute(&mut *transaction)
+    .execute(&mut **transaction)
     .await
     .context("Failed to update the pattern")?;
 
@@ -440,25 +548,34 @@ pub async fn update_tb303_pattern(
         r#"DELETE FROM bars_tb303 WHERE pattern_id = $1"#,
         pattern_id
     )
-    .execute(&mut *transaction)
+    .execute(&mut **transaction)
     .await
     .context("Failed to delete old bars")?;
 
-    insert_bars_tb303(&mut transaction, pattern_id, &new_pattern.bars)
+    insert_bars_tb303(transaction, pattern_id, &new_pattern.bars)
         .await
         .context("Failed to insert updated bars")?;
 
-    transaction
-        .commit()
-        .await
-        .context("Failed to commit transaction")?;
+    Ok(())
+}
 
-    Ok(web::Json(PatternTB303Response {
-        status: "success".to_string(),
-        data: PatternTB303ResponseData {
-            id: pattern_id.to_string(),
-        },
-    }))
+/// A pattern that is public from the start is also published.
+pub async fn enqueue_created_events(
+    transaction: &mut Transaction<'_, Postgres>,
+    pattern_id: Uuid,
+    new_pattern: &NewTB303Pattern,
+) -> Result<(), anyhow::Error> {
+    enqueue_pattern_event(transaction, WebhookEvent::PatternCreated, pattern_id, false).await?;
+    if new_pattern.is_public.unwrap_or(false) {
+        enqueue_pattern_event(
+            transaction,
+            WebhookEvent::PatternPublished,
+            pattern_id,
+            false,
+        )
+        .await?;
+    }
+    Ok(())
 }
 
 #[tracing::instrument(
@@ -470,8 +587,22 @@ pub async fn insert_pattern(
     new_pattern: &NewTB303Pattern,
     user_id: &UserId,
 ) -> Result<Uuid, sqlx::Error> {
-    let pattern_id = Uuid::new_v4();
+    let now = Utc::now();
+    insert_pattern_with_metadata(transaction, Uuid::new_v4(), new_pattern, user_id, now, now).await
+}
 
+#[tracing::instrument(
+    name = "Saving tb303 pattern with metadata in the database",
+    skip(new_pattern, transaction, user_id)
+)]
+pub async fn insert_pattern_with_metadata(
+    transaction: &mut Transaction<'_, Postgres>,
+    pattern_id: Uuid,
+    new_pattern: &NewTB303Pattern,
+    user_id: &UserId,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<Uuid, sqlx::Error> {
     let query = sqlx::query!(
         r#"
         INSERT INTO patterns_tb303 (
@@ -531,8 +662,8 @@ pub async fn insert_pattern(
             .map(|a| a.as_ref())
             .unwrap_or(&0),
         new_pattern.is_public.unwrap_or(false),
-        Utc::now(),
-        Utc::now()
+        updated_at,
+        created_at
     );
 
     transaction.execute(query).await?;
@@ -555,12 +686,11 @@ impl ResponseError for CreatePatternError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        let error = PatternErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        };
-
-        HttpResponse::build(self.status_code()).json(web::Json(error))
+        match self {
+            CreatePatternError::ValidationError(errors) => Problem::validation(errors),
+            _ => Problem::from_error(self),
+        }
+        .into()
     }
 }
 
@@ -570,15 +700,94 @@ impl ResponseError for UpdatePatternError {
             UpdatePatternError::ValidationError(_) => StatusCode::BAD_REQUEST,
             UpdatePatternError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             UpdatePatternError::PatternNotFound(_) => StatusCode::NOT_FOUND,
+            UpdatePatternError::AccessDenied | UpdatePatternError::VisibilityChangeDenied => {
+                StatusCode::FORBIDDEN
+            }
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        let error = PatternErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
+        match self {
+            UpdatePatternError::ValidationError(errors) => Problem::validation(errors),
+            _ => Problem::from_error(self),
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::models::tb303::{CreateTB303Bar, CreateTB303Step};
+    use crate::domain::{NewTB303Bar, NewTB303Step, Note, Time};
+    use crate::problem::{FieldErrorCode, ValidationErrors};
+    use crate::routes::patterns::post_tb303::find_gap;
+    use claims::{assert_none, assert_ok};
+
+    fn step(number: i32, time: Time) -> CreateTB303Step {
+        CreateTB303Step {
+            number,
+            note: None,
+            transpose: None,
+            time,
+            accent: None,
+            slide: None,
+        }
+    }
+
+    #[test]
+    fn gaps_are_found_in_any_order() {
+        assert_none!(find_gap([2, 1, 3].into_iter()));
+        assert_eq!(find_gap([2, 3].into_iter()), Some((1, 2)));
+        assert_eq!(find_gap([1, 1, 4, 2].into_iter()), Some((3, 4)));
+    }
+
+    #[test]
+    fn a_valid_bar_is_converted() {
+        let bar = CreateTB303Bar {
+            number: 1,
+            steps: vec![step(2, Time::Note), step(1, Time::Rest)],
         };
 
-        HttpResponse::build(self.status_code()).json(web::Json(error))
+        let bar: Result<NewTB303Bar, ValidationErrors> = bar.try_into();
+
+        assert_ok!(bar);
+    }
+
+    #[test]
+    fn step_errors_point_into_the_bar() {
+        let bar = CreateTB303Bar {
+            number: 3,
+            steps: vec![
+                step(1, Time::Note),
+                step(1, Time::Note),
+                step(0, Time::Note),
+            ],
+        };
+
+        let errors: ValidationErrors = TryInto::<NewTB303Bar>::try_into(bar).err().unwrap();
+
+        let errors: Vec<_> = errors
+            .errors()
+            .iter()
+            .map(|e| (e.pointer.as_str(), e.code))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("/steps/1/number", FieldErrorCode::Duplicate),
+                ("/steps/2/number", FieldErrorCode::OutOfRange),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_rest_with_a_note_is_rejected() {
+        let mut rest = step(1, Time::Rest);
+        rest.note = Some(Note::C);
+
+        let errors = TryInto::<NewTB303Step>::try_into(rest).err().unwrap();
+
+        assert_eq!(errors.errors()[0].pointer, "/note");
+        assert_eq!(errors.errors()[0].code, FieldErrorCode::NotAllowed);
     }
 }
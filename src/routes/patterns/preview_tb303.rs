@@ -0,0 +1,77 @@
+use crate::authentication::try_extract_user_id;
+use crate::configuration::CognitoSettings;
+use crate::pattern_preview::render_preview_svg;
+use crate::problem::Problem;
+use crate::routes::patterns::{fetch_pattern_by_id, GetPatternError};
+use crate::storage::ObjectStorage;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How long shared caches may keep the preview of a public pattern. Links
+/// carry the pattern's last update, so edits show up right away.
+const PREVIEW_MAX_AGE_SECS: u32 = 24 * 60 * 60;
+
+pub fn preview_url(base_url: &str, pattern_id: Uuid, updated_at: DateTime<Utc>) -> String {
+    format!(
+        "{}/v1/patterns/tb303/{}/preview.svg?v={}",
+        base_url.trim_end_matches('/'),
+        pattern_id,
+        updated_at.timestamp()
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/patterns/tb303/{pattern_id}/preview.svg",
+    params(
+        ("pattern_id" = String, Path, description = "The ID of the TB303 pattern")
+    ),
+    responses(
+        (status = 200, description = "SVG step grid of the pattern", content_type = "image/svg+xml", body = String),
+        (status = 404, description = "Pattern not found or access denied", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Rendering TB303 pattern preview",
+    skip(req, pool, storage, cognito)
+)]
+pub async fn get_tb303_preview(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    cognito: web::Data<CognitoSettings>,
+    pattern_id: web::Path<Uuid>,
+) -> Result<HttpResponse, GetPatternError> {
+    let user_id = try_extract_user_id(req.headers(), &cognito).await;
+
+    let pattern = fetch_pattern_by_id(
+        pool.as_ref(),
+        storage.as_ref(),
+        pattern_id.into_inner(),
+        user_id,
+    )
+    .await?;
+
+    let cache_control = if pattern.is_public.unwrap_or(false) {
+        CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(PREVIEW_MAX_AGE_SECS),
+        ])
+    } else {
+        CacheControl(vec![CacheDirective::Private, CacheDirective::NoStore])
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header(cache_control)
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .insert_header(("Content-Security-Policy", "default-src 'none'"))
+        .body(render_preview_svg(&pattern.bars)))
+}
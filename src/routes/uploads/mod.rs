@@ -1,3 +1,7 @@
 mod presign;
+mod process;
+mod verify;
 
 pub use presign::*;
+pub use process::*;
+pub use verify::*;
@@ -1,8 +1,10 @@
 use crate::api::models::uploads::{PresignRequest, PresignResponse};
 use crate::authentication::UserId;
-use crate::s3_client::S3Client;
-use crate::utils::{get_error_response, get_fail_response};
-use actix_web::{web, HttpResponse};
+use crate::metrics::METRICS;
+use crate::problem::{FieldErrorCode, Problem, ProblemCode, ValidationErrors};
+use crate::storage::ObjectStorage;
+use actix_web::{http::StatusCode, web, HttpResponse};
+use sqlx::PgPool;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -14,8 +16,8 @@ const PRESIGN_EXPIRY_SECS: u64 = 300; // 5 minutes
     request_body = PresignRequest,
     responses(
         (status = 200, description = "Presigned URL generated successfully", body = PresignResponse),
-        (status = 400, description = "Invalid upload_type or content_type"),
-        (status = 401, description = "Unauthorized"),
+        (status = 400, description = "Invalid upload_type or content_type, or a content_type the upload_type does not accept", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
     ),
     security(
         ("token" = [])
@@ -23,34 +25,77 @@ const PRESIGN_EXPIRY_SECS: u64 = 300; // 5 minutes
 )]
 #[tracing::instrument(
     name = "Generating presigned upload URL",
-    skip(s3_client, user_id, body)
+    skip(pool, storage, user_id, body)
 )]
 pub async fn presign_upload(
-    s3_client: web::Data<S3Client>,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
     user_id: web::ReqData<UserId>,
     body: web::Json<PresignRequest>,
 ) -> HttpResponse {
+    let mut errors = ValidationErrors::default();
+    if !body.upload_type.accepts(body.content_type) {
+        errors.add(
+            "/content_type",
+            FieldErrorCode::NotAllowed,
+            format!(
+                "Content type {} is not allowed for {} uploads",
+                body.content_type.as_ref(),
+                body.upload_type.as_ref()
+            ),
+        );
+    }
+
     let max_size = body.upload_type.max_size_bytes();
     if body.content_length > max_size {
-        return HttpResponse::BadRequest().json(get_fail_response(format!(
-            "File too large. Maximum size: {} bytes",
-            max_size
-        )));
+        errors.add(
+            "/content_length",
+            FieldErrorCode::OutOfRange,
+            format!("File too large. Maximum size: {} bytes", max_size),
+        );
     }
 
     if body.content_length == 0 {
-        return HttpResponse::BadRequest()
-            .json(get_fail_response("File size must be greater than 0"));
+        errors.add(
+            "/content_length",
+            FieldErrorCode::OutOfRange,
+            "File size must be greater than 0",
+        );
+    }
+
+    if !errors.is_empty() {
+        return Problem::validation(&errors).into();
     }
 
+    let user_id = user_id.into_inner();
     let key = format!(
         "{}/{}/{}",
         body.upload_type.s3_prefix(),
-        *user_id.into_inner(),
+        *user_id,
         Uuid::new_v4()
     );
 
-    match s3_client
+    // Recorded so the upload can be verified when it is claimed, and swept
+    // if it never is.
+    if let Err(e) = sqlx::query!(
+        r#"
+        INSERT INTO uploads (key, user_id, upload_type, content_type, content_length)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        key,
+        *user_id,
+        body.upload_type.as_ref(),
+        body.content_type.as_ref(),
+        body.content_length as i64
+    )
+    .execute(pool.as_ref())
+    .await
+    {
+        tracing::error!("Failed to record upload: {}", e);
+        return presign_failed();
+    }
+
+    match storage
         .presign_put(
             &key,
             body.content_type.as_ref(),
@@ -59,11 +104,22 @@ pub async fn presign_upload(
         )
         .await
     {
-        Ok(upload_url) => HttpResponse::Ok().json(PresignResponse { upload_url, key }),
+        Ok(upload_url) => {
+            METRICS.record_presign(body.upload_type);
+            HttpResponse::Ok().json(PresignResponse { upload_url, key })
+        }
         Err(e) => {
             tracing::error!("Failed to generate presigned URL: {}", e);
-            HttpResponse::InternalServerError()
-                .json(get_error_response("Failed to generate upload URL"))
+            presign_failed()
         }
     }
 }
+
+fn presign_failed() -> HttpResponse {
+    Problem::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ProblemCode::InternalError,
+        "Failed to generate upload URL",
+    )
+    .into()
+}
@@ -0,0 +1,66 @@
+use crate::domain::UploadType;
+use crate::image_processing::{process_image, sanitized_key, variant_key};
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use actix_web::web;
+use anyhow::Context;
+
+#[derive(thiserror::Error)]
+pub enum ProcessUploadError {
+    #[error("{0}")]
+    InvalidImage(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ProcessUploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Stores a sanitized WebP of the uploaded image and its resized variants
+/// next to it, leaving the upload untouched so that a claim that fails can be
+/// retried. Returns the variant sizes.
+#[tracing::instrument(name = "Processing uploaded image", skip(storage))]
+pub async fn process_image_upload(
+    storage: &dyn ObjectStorage,
+    key: &str,
+    upload_type: UploadType,
+    content_type: String,
+) -> Result<Vec<i32>, ProcessUploadError> {
+    let bytes = storage
+        .get_object(key)
+        .await
+        .context("Failed to fetch upload for processing")?;
+
+    let processed = web::block(move || process_image(&bytes, &content_type, upload_type))
+        .await
+        .context("Image processing was cancelled")?
+        .map_err(|e| ProcessUploadError::InvalidImage(e.to_string()))?;
+
+    let mut sizes = Vec::with_capacity(processed.variants.len());
+    for (size, bytes) in processed.variants {
+        storage
+            .put_object(&variant_key(key, size), bytes, "image/webp")
+            .await
+            .context("Failed to store image variant")?;
+        sizes.push(size as i32);
+    }
+    storage
+        .put_object(&sanitized_key(key), processed.original, "image/webp")
+        .await
+        .context("Failed to store sanitized image")?;
+
+    Ok(sizes)
+}
+
+/// Deletes an upload that was processed and claimed, since only its
+/// sanitized copy is served. Failing to is not worth failing the request
+/// for: the object goes with its variants once the upload is released.
+#[tracing::instrument(name = "Discarding processed upload", skip(storage))]
+pub async fn discard_processed_upload(storage: &dyn ObjectStorage, key: &str) {
+    if let Err(e) = storage.delete_object(key).await {
+        tracing::warn!(error.cause_chain = ?e, "Failed to delete processed upload");
+    }
+}
@@ -0,0 +1,89 @@
+use crate::domain::UploadType;
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use anyhow::Context;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum VerifyUploadError {
+    #[error("Invalid key")]
+    InvalidKey,
+    #[error("No file has been uploaded for this key")]
+    UploadNotFound,
+    #[error("{0}")]
+    UploadMismatch(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for VerifyUploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Checks that `key` was handed out to the user for `upload_type` and that
+/// the stored object matches what was presigned. Returns the declared
+/// content type of uploads that still need to be processed, or `None` when
+/// the key was claimed before.
+///
+/// The upload stays locked until the end of the surrounding transaction, if
+/// any, so the sweeper cannot delete it before the caller claims it there.
+#[tracing::instrument(name = "Verifying upload", skip(executor, storage))]
+pub async fn verify_upload(
+    executor: impl PgExecutor<'_>,
+    storage: &dyn ObjectStorage,
+    user_id: Uuid,
+    key: &str,
+    upload_type: UploadType,
+) -> Result<Option<String>, VerifyUploadError> {
+    let expected = format!("{}/{}/", upload_type.s3_prefix(), user_id);
+    if !key.starts_with(&expected) {
+        return Err(VerifyUploadError::InvalidKey);
+    }
+
+    let upload = sqlx::query!(
+        r#"
+        SELECT content_type, content_length, claimed_at
+        FROM uploads
+        WHERE key = $1 AND user_id = $2 AND upload_type = $3
+        FOR UPDATE
+        "#,
+        key,
+        user_id,
+        upload_type.as_ref()
+    )
+    .fetch_optional(executor)
+    .await
+    .context("Failed to fetch upload")?
+    .ok_or(VerifyUploadError::InvalidKey)?;
+
+    // The key was claimed, and verified, before.
+    if upload.claimed_at.is_some() {
+        return Ok(None);
+    }
+
+    let object = storage
+        .head_object(key)
+        .await
+        .context("Failed to verify upload")?
+        .ok_or(VerifyUploadError::UploadNotFound)?;
+
+    let size = object.size.unwrap_or(0);
+    if size != upload.content_length || size as u64 > upload_type.max_size_bytes() {
+        return Err(VerifyUploadError::UploadMismatch(format!(
+            "Uploaded file is {} bytes, expected {} bytes",
+            size, upload.content_length
+        )));
+    }
+    if object.content_type.as_deref() != Some(upload.content_type.as_str()) {
+        return Err(VerifyUploadError::UploadMismatch(format!(
+            "Uploaded file has content type {}, expected {}",
+            object.content_type.as_deref().unwrap_or("unknown"),
+            upload.content_type
+        )));
+    }
+
+    Ok(Some(upload.content_type))
+}
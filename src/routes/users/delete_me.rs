@@ -0,0 +1,216 @@
+use crate::api::models::users::{DeleteUserParams, PatternDisposition};
+use crate::authentication::UserId;
+use crate::domain::{UploadType, WebhookEvent};
+use crate::metrics::begin_transaction;
+use crate::pattern_cache::PatternCache;
+use crate::problem::Problem;
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use crate::webhooks::enqueue_pattern_event;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Owner of patterns handed over by deleted accounts.
+pub const ANONYMOUS_USER_ID: Uuid = Uuid::nil();
+
+#[derive(thiserror::Error)]
+pub enum DeleteUserError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for DeleteUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for DeleteUserError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteUserError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+/// Deletes every avatar and banner object uploaded by the user, including
+/// ones that were replaced and are no longer referenced by the profile.
+#[tracing::instrument(name = "Deleting user files", skip(storage))]
+async fn delete_user_files(
+    storage: &dyn ObjectStorage,
+    user_id: Uuid,
+) -> Result<usize, anyhow::Error> {
+    let mut deleted = 0;
+    for upload_type in UploadType::ALL {
+        let prefix = format!("{}/{}/", upload_type.s3_prefix(), user_id);
+        let objects = storage
+            .list_objects(&prefix)
+            .await
+            .context("Failed to list user files.")?;
+        for object in objects {
+            storage
+                .delete_object(&object.key)
+                .await
+                .context("Failed to delete user file.")?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me",
+    params(DeleteUserParams),
+    responses(
+        (status = 204, description = "Account deleted, or already deleted"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Deleting current user", skip(pool, storage, user_id, cache))]
+pub async fn delete_me(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    storage: web::Data<dyn ObjectStorage>,
+    cache: web::Data<PatternCache>,
+    params: web::Query<DeleteUserParams>,
+) -> Result<HttpResponse, DeleteUserError> {
+    let user_id = user_id.into_inner();
+    let disposition = params.patterns.unwrap_or_default();
+
+    // Files go first: if this fails the account is still intact and the
+    // request can simply be retried.
+    let deleted_files = delete_user_files(storage.as_ref(), *user_id).await?;
+
+    let mut transaction = begin_transaction(&pool)
+        .await
+        .context("Failed to start a transaction for account deletion.")?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT user_id FROM users WHERE user_id = $1 FOR UPDATE"#,
+        *user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch user for deletion.")?
+    .is_some();
+
+    if !exists {
+        tracing::info!(%deleted_files, "Account was already deleted");
+        return Ok(HttpResponse::NoContent().finish());
+    }
+
+    let reassigned_patterns = match disposition {
+        PatternDisposition::Reassign => sqlx::query!(
+            r#"
+            UPDATE patterns_tb303 SET user_id = $2, updated_at = NOW()
+            WHERE user_id = $1
+            "#,
+            *user_id,
+            ANONYMOUS_USER_ID
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to reassign patterns.")?
+        .rows_affected(),
+        PatternDisposition::Delete => 0,
+    };
+
+    // Only global webhooks hear about it, the account's own go with it.
+    let public_pattern_ids = sqlx::query_scalar!(
+        r#"SELECT pattern_id FROM patterns_tb303 WHERE user_id = $1 AND is_public = TRUE"#,
+        *user_id
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .context("Failed to fetch public patterns.")?;
+    for pattern_id in public_pattern_ids {
+        enqueue_pattern_event(
+            &mut transaction,
+            WebhookEvent::PatternDeleted,
+            pattern_id,
+            true,
+        )
+        .await?;
+    }
+
+    let deleted_patterns =
+        sqlx::query!(r#"DELETE FROM patterns_tb303 WHERE user_id = $1"#, *user_id)
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to delete patterns.")?
+            .rows_affected();
+
+    // The user's files are gone, including audio of reassigned patterns.
+    sqlx::query!(
+        r#"
+        DELETE FROM pattern_media
+        WHERE key IN (SELECT key FROM uploads WHERE user_id = $1)
+        "#,
+        *user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to detach deleted files from patterns.")?;
+
+    sqlx::query!(r#"DELETE FROM uploads WHERE user_id = $1"#, *user_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete upload records.")?;
+
+    sqlx::query!(r#"DELETE FROM idempotency WHERE user_id = $1"#, *user_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete saved responses.")?;
+
+    sqlx::query!(r#"DELETE FROM users WHERE user_id = $1"#, *user_id)
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to delete user.")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO account_deletions (
+            deletion_id, user_id, pattern_disposition,
+            deleted_patterns, reassigned_patterns, deleted_files
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        *user_id,
+        disposition.as_ref(),
+        deleted_patterns as i32,
+        reassigned_patterns as i32,
+        deleted_files as i32
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to log account deletion.")?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit account deletion.")?;
+    // Reassigned patterns lose their audio and cover.
+    cache.clear();
+
+    tracing::info!(
+        disposition = disposition.as_ref(),
+        %deleted_patterns,
+        %reassigned_patterns,
+        %deleted_files,
+        "Account deleted"
+    );
+
+    Ok(HttpResponse::NoContent().finish())
+}
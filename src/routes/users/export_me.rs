@@ -0,0 +1,160 @@
+use crate::api::models::archive::{TB303PatternArchive, ARCHIVE_FORMAT, ARCHIVE_VERSION};
+use crate::authentication::UserId;
+use crate::problem::Problem;
+use crate::routes::patterns::{fetch_pattern_by_id, GetPatternError};
+use crate::storage::ObjectStorage;
+use crate::utils::{e500, error_chain_fmt};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{http::StatusCode, web, web::Bytes, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::Utc;
+use futures_util::stream;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum ExportUserError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ExportUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ExportUserError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ExportUserError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+struct ExportState {
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    user_id: UserId,
+    pattern_ids: std::vec::IntoIter<Uuid>,
+    header: Option<String>,
+    first: bool,
+    finished: bool,
+}
+
+/// Produces the next chunk of the archive: the header, one pattern per
+/// chunk, then the closing brackets. Patterns are loaded one at a time so
+/// large libraries are never held in memory as a whole. Patterns deleted
+/// since the export started are left out.
+async fn next_chunk(
+    mut state: ExportState,
+) -> Option<(Result<Bytes, actix_web::Error>, ExportState)> {
+    if let Some(header) = state.header.take() {
+        return Some((Ok(Bytes::from(header)), state));
+    }
+    if state.finished {
+        return None;
+    }
+
+    let pattern = loop {
+        let Some(pattern_id) = state.pattern_ids.next() else {
+            state.finished = true;
+            return Some((Ok(Bytes::from_static(b"]}")), state));
+        };
+        match fetch_pattern_by_id(
+            state.pool.as_ref(),
+            state.storage.as_ref(),
+            pattern_id,
+            Some(state.user_id),
+        )
+        .await
+        {
+            Err(GetPatternError::PatternNotFound(_)) => continue,
+            result => break result,
+        }
+    };
+
+    let chunk = pattern
+        .map_err(e500)
+        .and_then(|pattern| serde_json::to_string(&pattern).map_err(e500))
+        .map(|pattern| match std::mem::replace(&mut state.first, false) {
+            true => Bytes::from(pattern),
+            false => Bytes::from(format!(",{}", pattern)),
+        });
+    if chunk.is_err() {
+        // The status line is already sent, so cut the archive short rather
+        // than emit a document that looks complete.
+        state.finished = true;
+        state.pattern_ids = Vec::new().into_iter();
+    }
+    Some((chunk, state))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/export",
+    responses(
+        (status = 200, description = "Archive of all of the user's patterns", body = TB303PatternArchive),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Exporting current user's patterns",
+    skip(pool, storage, user_id)
+)]
+pub async fn export_me(
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn ObjectStorage>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, ExportUserError> {
+    let user_id = user_id.into_inner();
+
+    let pattern_ids = sqlx::query_scalar!(
+        r#"
+        SELECT pattern_id FROM patterns_tb303
+        WHERE user_id = $1
+        ORDER BY created_at, pattern_id
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch patterns to export.")?;
+
+    let exported_at = Utc::now();
+    let header = format!(
+        r#"{{"format":{},"version":{},"exported_at":{},"patterns":["#,
+        serde_json::to_string(ARCHIVE_FORMAT).context("Failed to encode archive header.")?,
+        ARCHIVE_VERSION,
+        serde_json::to_string(&exported_at).context("Failed to encode archive header.")?,
+    );
+
+    let state = ExportState {
+        pool,
+        storage,
+        user_id,
+        pattern_ids: pattern_ids.into_iter(),
+        header: Some(header),
+        first: true,
+        finished: false,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "acidarchive-export-{}.json",
+                exported_at.format("%Y%m%d%H%M%S")
+            ))],
+        })
+        .streaming(stream::unfold(state, next_chunk)))
+}
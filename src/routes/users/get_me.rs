@@ -1,11 +1,12 @@
 use crate::api::models::users::UserResponse;
 use crate::authentication::UserId;
-use crate::routes::users::UserErrorResponse;
-use crate::s3_client::S3Client;
+use crate::image_processing::{image_url, variant_urls};
+use crate::problem::Problem;
+use crate::repository::UserRepository;
+use crate::storage::ObjectStorage;
 use crate::utils::error_chain_fmt;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
-use anyhow::Context;
-use sqlx::PgPool;
+use uuid::Uuid;
 
 #[derive(thiserror::Error)]
 pub enum GetUserError {
@@ -27,10 +28,7 @@ impl ResponseError for GetUserError {
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(UserErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        })
+        Problem::from_error(self).into()
     }
 }
 
@@ -39,49 +37,63 @@ impl ResponseError for GetUserError {
     path = "/v1/users/me",
     responses(
         (status = 200, description = "User profile retrieved successfully", body = UserResponse),
-        (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal server error")
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Getting current user", skip(pool, s3_client))]
+#[tracing::instrument(name = "Getting current user", skip(users, storage))]
 pub async fn get_me(
-    pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
     user_id: web::ReqData<UserId>,
-    s3_client: web::Data<S3Client>,
+    storage: web::Data<dyn ObjectStorage>,
 ) -> Result<web::Json<UserResponse>, GetUserError> {
     let user_id = user_id.into_inner();
 
-    let user = sqlx::query!(
-        r#"
-        SELECT user_id, username, avatar_key, banner_key, created_at, updated_at
-        FROM users
-        WHERE user_id = $1
-        "#,
-        *user_id
-    )
-    .fetch_one(pool.as_ref())
-    .await
-    .context("Failed to fetch user")?;
+    let user = fetch_user_response(users.as_ref(), storage.as_ref(), *user_id).await?;
+
+    Ok(web::Json(user))
+}
+
+#[tracing::instrument(name = "Fetching user profile", skip(users, storage))]
+pub async fn fetch_user_response(
+    users: &dyn UserRepository,
+    storage: &dyn ObjectStorage,
+    user_id: Uuid,
+) -> Result<UserResponse, anyhow::Error> {
+    let user = users
+        .fetch_user(user_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("User {user_id} not found"))?;
 
     let avatar_url = user
         .avatar_key
         .as_ref()
-        .map(|key| s3_client.get_public_url(key));
+        .map(|key| image_url(storage, key, &user.avatar_variant_sizes));
+    let avatar_variants = match user.avatar_key {
+        Some(ref key) => variant_urls(storage, key, &user.avatar_variant_sizes),
+        None => Vec::new(),
+    };
 
     let banner_url = user
         .banner_key
         .as_ref()
-        .map(|key| s3_client.get_public_url(key));
+        .map(|key| image_url(storage, key, &user.banner_variant_sizes));
+    let banner_variants = match user.banner_key {
+        Some(ref key) => variant_urls(storage, key, &user.banner_variant_sizes),
+        None => Vec::new(),
+    };
 
-    Ok(web::Json(UserResponse {
+    Ok(UserResponse {
         user_id: user.user_id,
         username: user.username,
         avatar_url,
+        avatar_variants,
         banner_url,
+        banner_variants,
         created_at: user.created_at,
         updated_at: user.updated_at,
-    }))
+    })
 }
@@ -0,0 +1,177 @@
+use crate::api::models::users::{
+    UserDataCollaboration, UserDataExport, UserDataFile, UserDataProfile, UserDataWebhook,
+    UserDataWebhookDelivery,
+};
+use crate::authentication::UserId;
+use crate::domain::UploadType;
+use crate::problem::Problem;
+use crate::routes::patterns::fetch_pattern_by_id;
+use crate::storage::ObjectStorage;
+use crate::utils::error_chain_fmt;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::PgPool;
+
+#[derive(thiserror::Error)]
+pub enum GetUserDataError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for GetUserDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for GetUserDataError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetUserDataError::UserNotFound => StatusCode::NOT_FOUND,
+            GetUserDataError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        Problem::from_error(self).into()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/data",
+    responses(
+        (status = 200, description = "Everything stored about the user", body = UserDataExport),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Exporting current user's data", skip(pool, storage, user_id))]
+pub async fn get_me_data(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    storage: web::Data<dyn ObjectStorage>,
+) -> Result<HttpResponse, GetUserDataError> {
+    let user_id = user_id.into_inner();
+    let exported_at = Utc::now();
+
+    let profile = sqlx::query_as!(
+        UserDataProfile,
+        r#"
+        SELECT user_id, username, avatar_key, banner_key, created_at, updated_at
+        FROM users
+        WHERE user_id = $1
+        "#,
+        *user_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .context("Failed to fetch user")?
+    .ok_or(GetUserDataError::UserNotFound)?;
+
+    let mut files = Vec::new();
+    for upload_type in UploadType::ALL {
+        let prefix = format!("{}/{}/", upload_type.s3_prefix(), *user_id);
+        let objects = storage
+            .list_objects(&prefix)
+            .await
+            .context("Failed to list user files.")?;
+        files.extend(objects.into_iter().map(|object| UserDataFile {
+            url: storage.get_public_url(&object.key),
+            key: object.key,
+            size: object.size,
+            last_modified: object.last_modified,
+        }));
+    }
+
+    let pattern_ids = sqlx::query_scalar!(
+        r#"
+        SELECT pattern_id FROM patterns_tb303
+        WHERE user_id = $1
+        ORDER BY created_at, pattern_id
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch user patterns.")?;
+
+    let mut patterns = Vec::with_capacity(pattern_ids.len());
+    for pattern_id in pattern_ids {
+        let pattern =
+            fetch_pattern_by_id(pool.as_ref(), storage.as_ref(), pattern_id, Some(user_id))
+                .await
+                .context("Failed to fetch user pattern.")?;
+        patterns.push(pattern);
+    }
+
+    let collaborations = sqlx::query_as!(
+        UserDataCollaboration,
+        r#"
+        SELECT pattern_id, role, created_at
+        FROM pattern_collaborators_tb303
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch user collaborations.")?;
+
+    let webhooks = sqlx::query_as!(
+        UserDataWebhook,
+        r#"
+        SELECT webhook_id AS id, url, events, is_global, created_at
+        FROM webhooks
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch user webhooks.")?;
+
+    let webhook_deliveries = sqlx::query_as!(
+        UserDataWebhookDelivery,
+        r#"
+        SELECT d.delivery_id AS id, d.webhook_id, d.event_id, d.event, d.status, d.attempts,
+               d.last_attempt_at, d.last_status_code, d.last_error, d.created_at, d.delivered_at
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.webhook_id = d.webhook_id
+        WHERE w.user_id = $1
+        ORDER BY d.created_at DESC
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch user webhook deliveries.")?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "acidarchive-data-{}.json",
+                exported_at.format("%Y%m%d%H%M%S")
+            ))],
+        })
+        .json(UserDataExport {
+            exported_at,
+            profile,
+            files,
+            patterns,
+            collaborations,
+            webhooks,
+            webhook_deliveries,
+        }))
+}
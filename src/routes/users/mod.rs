@@ -1,7 +1,11 @@
+mod delete_me;
+mod export_me;
 mod get_me;
+mod get_me_data;
 mod patch_me;
-mod response;
 
+pub use delete_me::*;
+pub use export_me::*;
 pub use get_me::*;
+pub use get_me_data::*;
 pub use patch_me::*;
-pub use response::*;
@@ -1,9 +1,18 @@
 use crate::api::models::users::{UpdateUserRequest, UserResponse};
 use crate::authentication::UserId;
 use crate::domain::UploadType;
-use crate::routes::users::UserErrorResponse;
-use crate::s3_client::S3Client;
+use crate::jobs::{enqueue_job, Job};
+use crate::metrics::begin_transaction;
+use crate::problem::Problem;
+use crate::repository::UserRepository;
+use crate::routes::uploads::{
+    discard_processed_upload, process_image_upload, verify_upload, ProcessUploadError,
+    VerifyUploadError,
+};
+use crate::routes::users::fetch_user_response;
+use crate::storage::ObjectStorage;
 use crate::utils::error_chain_fmt;
+use crate::webhooks::enqueue_profile_event;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
 use anyhow::Context;
 use sqlx::PgPool;
@@ -14,10 +23,36 @@ pub enum PatchUserError {
     NoFieldsToUpdate,
     #[error("Invalid key")]
     InvalidKey,
+    #[error("No file has been uploaded for this key")]
+    UploadNotFound,
+    #[error("{0}")]
+    UploadMismatch(String),
+    #[error("{0}")]
+    InvalidImage(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
+impl From<VerifyUploadError> for PatchUserError {
+    fn from(e: VerifyUploadError) -> Self {
+        match e {
+            VerifyUploadError::InvalidKey => PatchUserError::InvalidKey,
+            VerifyUploadError::UploadNotFound => PatchUserError::UploadNotFound,
+            VerifyUploadError::UploadMismatch(message) => PatchUserError::UploadMismatch(message),
+            VerifyUploadError::UnexpectedError(e) => PatchUserError::UnexpectedError(e),
+        }
+    }
+}
+
+impl From<ProcessUploadError> for PatchUserError {
+    fn from(e: ProcessUploadError) -> Self {
+        match e {
+            ProcessUploadError::InvalidImage(message) => PatchUserError::InvalidImage(message),
+            ProcessUploadError::UnexpectedError(e) => PatchUserError::UnexpectedError(e),
+        }
+    }
+}
+
 impl std::fmt::Debug for PatchUserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         error_chain_fmt(self, f)
@@ -29,15 +64,15 @@ impl ResponseError for PatchUserError {
         match self {
             PatchUserError::NoFieldsToUpdate => StatusCode::BAD_REQUEST,
             PatchUserError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            PatchUserError::InvalidKey => StatusCode::BAD_REQUEST,
+            PatchUserError::InvalidKey
+            | PatchUserError::UploadNotFound
+            | PatchUserError::UploadMismatch(_)
+            | PatchUserError::InvalidImage(_) => StatusCode::BAD_REQUEST,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(UserErrorResponse {
-            status: "error".to_string(),
-            message: self.to_string(),
-        })
+        Problem::from_error(self).into()
     }
 }
 
@@ -47,19 +82,20 @@ impl ResponseError for PatchUserError {
     request_body = UpdateUserRequest,
     responses(
         (status = 200, description = "User profile updated successfully", body = UserResponse),
-        (status = 400, description = "Bad request - no fields to update"),
-        (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal server error")
+        (status = 400, description = "Bad request - no fields to update, or the upload is missing, does not match or is not a valid image", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
     ),
     security(
         ("token" = [])
     ),
 )]
-#[tracing::instrument(name = "Updating current user", skip(pool, s3_client))]
+#[tracing::instrument(name = "Updating current user", skip(pool, users, storage))]
 pub async fn patch_me(
     pool: web::Data<PgPool>,
+    users: web::Data<dyn UserRepository>,
     user_id: web::ReqData<UserId>,
-    s3_client: web::Data<S3Client>,
+    storage: web::Data<dyn ObjectStorage>,
     body: web::Json<UpdateUserRequest>,
 ) -> Result<web::Json<UserResponse>, PatchUserError> {
     let user_id = user_id.into_inner();
@@ -68,52 +104,119 @@ pub async fn patch_me(
         return Err(PatchUserError::NoFieldsToUpdate);
     }
 
-    if let Some(ref key) = body.avatar_key {
-        let expected = format!("{}/{}/", UploadType::Avatar.s3_prefix(), *user_id);
-        if !key.starts_with(&expected) {
-            return Err(PatchUserError::InvalidKey);
+    // Processing can take a while, so it happens before the uploads are
+    // locked.
+    let mut claimed = Vec::new();
+    for (key, upload_type) in [
+        (&body.avatar_key, UploadType::Avatar),
+        (&body.banner_key, UploadType::Banner),
+    ] {
+        let Some(key) = key else { continue };
+        if let Some(content_type) =
+            verify_upload(pool.as_ref(), storage.as_ref(), *user_id, key, upload_type).await?
+        {
+            let sizes =
+                process_image_upload(storage.as_ref(), key, upload_type, content_type).await?;
+            claimed.push((key.clone(), upload_type, sizes));
         }
     }
-    if let Some(ref key) = body.banner_key {
-        let expected = format!("{}/{}/", UploadType::Banner.s3_prefix(), *user_id);
-        if !key.starts_with(&expected) {
-            return Err(PatchUserError::InvalidKey);
-        }
+
+    let mut transaction = begin_transaction(&pool)
+        .await
+        .context("Failed to start a transaction for user update")?;
+    for (key, upload_type, _) in &claimed {
+        // Checked again under the lock, in case the upload was claimed or
+        // swept in the meantime.
+        verify_upload(
+            &mut *transaction,
+            storage.as_ref(),
+            *user_id,
+            key,
+            *upload_type,
+        )
+        .await?
+        .ok_or(PatchUserError::InvalidKey)?;
     }
 
-    let user = sqlx::query!(
+    let previous = sqlx::query!(
+        r#"SELECT avatar_key, banner_key FROM users WHERE user_id = $1 FOR UPDATE"#,
+        *user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to fetch user")?;
+
+    sqlx::query!(
         r#"
         UPDATE users SET
             avatar_key = COALESCE($2, avatar_key),
             banner_key = COALESCE($3, banner_key),
             updated_at = NOW()
         WHERE user_id = $1
-        RETURNING user_id, username, avatar_key, banner_key, created_at, updated_at
         "#,
         *user_id,
         body.avatar_key,
         body.banner_key
     )
-    .fetch_one(pool.as_ref())
+    .execute(&mut *transaction)
     .await
     .context("Failed to update user")?;
 
+    for (key, _, sizes) in &claimed {
+        // Verification locked the upload, so it is still there unclaimed.
+        sqlx::query!(
+            r#"
+            UPDATE uploads SET claimed_at = NOW(), variant_sizes = $2
+            WHERE key = $1 AND claimed_at IS NULL
+            "#,
+            key,
+            sizes
+        )
+        .execute(&mut *transaction)
+        .await
+        .context("Failed to claim upload")?;
+    }
+
+    let replaced: Vec<String> = [
+        (previous.avatar_key, &body.avatar_key),
+        (previous.banner_key, &body.banner_key),
+    ]
+    .into_iter()
+    .filter_map(|(old, new)| match (old, new) {
+        (Some(old), Some(new)) if old != *new => Some(old),
+        _ => None,
+    })
+    .collect();
+    sqlx::query!(
+        r#"UPDATE uploads SET claimed_at = NULL WHERE key = ANY($1)"#,
+        &replaced
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to release replaced uploads")?;
+    for key in replaced {
+        enqueue_job(&mut transaction, &Job::DeleteUpload { key }).await?;
+    }
+
+    enqueue_profile_event(&mut transaction, *user_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit user update")?;
+    for (key, _, _) in &claimed {
+        discard_processed_upload(storage.as_ref(), key).await;
+    }
+
+    let mut user = fetch_user_response(users.as_ref(), storage.as_ref(), *user_id).await?;
+
     let version = user.updated_at.timestamp();
-    let avatar_url = user
-        .avatar_key
-        .as_ref()
-        .map(|key| format!("{}?v={}", s3_client.get_public_url(key), version));
-    let banner_url = user
-        .banner_key
-        .as_ref()
-        .map(|key| format!("{}?v={}", s3_client.get_public_url(key), version));
-
-    Ok(web::Json(UserResponse {
-        user_id: user.user_id,
-        username: user.username,
-        avatar_url,
-        banner_url,
-        created_at: user.created_at,
-        updated_at: user.updated_at,
-    }))
+    for url in [&mut user.avatar_url, &mut user.banner_url]
+        .into_iter()
+        .flatten()
+    {
+        url.push_str(&format!("?v={}", version));
+    }
+
+    Ok(web::Json(user))
 }
@@ -0,0 +1,130 @@
+use crate::api::models::webhooks::WebhookDelivery;
+use crate::authentication::UserId;
+use crate::problem::Problem;
+use crate::routes::webhooks::{ensure_webhook_owner, WebhookError};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The log keeps every delivery, but only the newest are listed.
+const DELIVERY_LOG_LENGTH: i64 = 100;
+
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks/{webhook_id}/deliveries",
+    params(
+        ("webhook_id" = Uuid, Path, description = "The ID of the webhook")
+    ),
+    responses(
+        (status = 200, description = "The newest deliveries, newest first", body = Vec<WebhookDelivery>),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Listing webhook deliveries", skip(pool, user_id))]
+pub async fn list_webhook_deliveries(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    webhook_id: web::Path<Uuid>,
+) -> Result<web::Json<Vec<WebhookDelivery>>, WebhookError> {
+    let webhook_id = webhook_id.into_inner();
+    ensure_webhook_owner(&pool, webhook_id, *user_id.into_inner()).await?;
+
+    let deliveries = sqlx::query!(
+        r#"
+        SELECT delivery_id, event_id, event, status, attempts, next_attempt_at,
+               last_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE webhook_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        webhook_id,
+        DELIVERY_LOG_LENGTH
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch webhook deliveries.")?
+    .into_iter()
+    .map(|row| WebhookDelivery {
+        id: row.delivery_id,
+        event_id: row.event_id,
+        event: row.event,
+        // Only pending deliveries will be attempted again.
+        next_attempt_at: (row.status == "pending").then_some(row.next_attempt_at),
+        status: row.status,
+        attempts: row.attempts,
+        last_attempt_at: row.last_attempt_at,
+        last_status_code: row.last_status_code,
+        last_error: row.last_error,
+        created_at: row.created_at,
+        delivered_at: row.delivered_at,
+    })
+    .collect();
+
+    Ok(web::Json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks/{webhook_id}/deliveries/{delivery_id}/redeliver",
+    params(
+        ("webhook_id" = Uuid, Path, description = "The ID of the webhook"),
+        ("delivery_id" = Uuid, Path, description = "The delivery to send again")
+    ),
+    responses(
+        (status = 202, description = "A new delivery of the same event was queued", body = WebhookDelivery),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook or delivery not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Redelivering webhook delivery", skip(pool, user_id))]
+pub async fn redeliver_webhook_delivery(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, WebhookError> {
+    let (webhook_id, delivery_id) = path.into_inner();
+    ensure_webhook_owner(&pool, webhook_id, *user_id.into_inner()).await?;
+
+    // The original stays in the log; the copy keeps its event id so
+    // receivers can tell it is the same event.
+    let delivery = sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (delivery_id, webhook_id, event_id, event, payload)
+        SELECT gen_random_uuid(), webhook_id, event_id, event, payload
+        FROM webhook_deliveries
+        WHERE delivery_id = $1 AND webhook_id = $2
+        RETURNING delivery_id, event_id, event, status, attempts, next_attempt_at, created_at
+        "#,
+        delivery_id,
+        webhook_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .context("Failed to queue a redelivery.")?
+    .ok_or(WebhookError::DeliveryNotFound(delivery_id))?;
+
+    Ok(HttpResponse::Accepted().json(WebhookDelivery {
+        id: delivery.delivery_id,
+        event_id: delivery.event_id,
+        event: delivery.event,
+        status: delivery.status,
+        attempts: delivery.attempts,
+        next_attempt_at: Some(delivery.next_attempt_at),
+        last_attempt_at: None,
+        last_status_code: None,
+        last_error: None,
+        created_at: delivery.created_at,
+        delivered_at: None,
+    }))
+}
@@ -0,0 +1,281 @@
+use crate::api::models::webhooks::{CreateWebhook, Webhook};
+use crate::authentication::UserId;
+use crate::configuration::WebhookSettings;
+use crate::domain::{WebhookEvent, WebhookUrl};
+use crate::metrics::begin_transaction;
+use crate::problem::{Problem, ProblemCode};
+use crate::startup::HmacSecret;
+use crate::utils::error_chain_fmt;
+use crate::webhooks::webhook_secret;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MAX_WEBHOOKS_PER_USER: i64 = 10;
+
+#[derive(thiserror::Error)]
+pub enum WebhookError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Only admins can register global webhooks")]
+    AdminOnly,
+    #[error("Webhook with ID {0} not found")]
+    WebhookNotFound(Uuid),
+    #[error("Delivery with ID {0} not found")]
+    DeliveryNotFound(Uuid),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for WebhookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WebhookError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            WebhookError::AdminOnly => StatusCode::FORBIDDEN,
+            WebhookError::WebhookNotFound(_) | WebhookError::DeliveryNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            WebhookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let problem = Problem::from_error(self);
+        match self {
+            WebhookError::ValidationError(_) => problem.with_code(ProblemCode::ValidationFailed),
+            _ => problem,
+        }
+        .into()
+    }
+}
+
+struct WebhookRow {
+    webhook_id: Uuid,
+    url: String,
+    events: Vec<String>,
+    is_global: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl WebhookRow {
+    fn into_webhook(self, hmac_secret: &HmacSecret) -> Result<Webhook, anyhow::Error> {
+        let events = self
+            .events
+            .iter()
+            .map(|event| WebhookEvent::parse(event))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(anyhow::Error::msg)?;
+
+        Ok(Webhook {
+            id: self.webhook_id,
+            secret: webhook_secret(hmac_secret, self.webhook_id),
+            url: self.url,
+            events,
+            is_global: self.is_global,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// Webhooks can only be seen and managed by whoever registered them.
+#[tracing::instrument(name = "Checking webhook owner", skip(pool))]
+pub async fn ensure_webhook_owner(
+    pool: &PgPool,
+    webhook_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), WebhookError> {
+    sqlx::query_scalar!(
+        r#"SELECT webhook_id FROM webhooks WHERE webhook_id = $1 AND user_id = $2"#,
+        webhook_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to fetch webhook.")?
+    .map(|_| ())
+    .ok_or(WebhookError::WebhookNotFound(webhook_id))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks",
+    request_body = CreateWebhook,
+    responses(
+        (status = 201, description = "Webhook registered", body = Webhook),
+        (status = 400, description = "Invalid URL or events, or too many webhooks", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Only admins can register global webhooks", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(
+    name = "Registering webhook",
+    skip(pool, settings, hmac_secret, user_id, body)
+)]
+pub async fn create_webhook(
+    pool: web::Data<PgPool>,
+    settings: web::Data<WebhookSettings>,
+    hmac_secret: web::Data<HmacSecret>,
+    user_id: web::ReqData<UserId>,
+    body: web::Json<CreateWebhook>,
+) -> Result<HttpResponse, WebhookError> {
+    let user_id = user_id.into_inner();
+    let body = body.into_inner();
+
+    let url = WebhookUrl::parse(body.url, settings.allow_private_networks)
+        .map_err(WebhookError::ValidationError)?;
+    let events = body.events.unwrap_or_else(|| WebhookEvent::ALL.to_vec());
+    if events.is_empty() {
+        return Err(WebhookError::ValidationError(
+            "At least one event is required".to_string(),
+        ));
+    }
+    // Stored once each, in a stable order.
+    let events: Vec<String> = WebhookEvent::ALL
+        .iter()
+        .filter(|event| events.contains(event))
+        .map(|event| event.as_ref().to_string())
+        .collect();
+    let is_global = body.is_global.unwrap_or(false);
+
+    let mut transaction = begin_transaction(&pool)
+        .await
+        .context("Failed to start a transaction for a new webhook.")?;
+
+    let user = sqlx::query!(
+        r#"
+        SELECT is_admin, (SELECT COUNT(*) FROM webhooks WHERE user_id = $1) AS "webhooks!"
+        FROM users WHERE user_id = $1
+        FOR UPDATE
+        "#,
+        *user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to fetch user.")?;
+
+    if is_global && !user.is_admin {
+        return Err(WebhookError::AdminOnly);
+    }
+    if user.webhooks >= MAX_WEBHOOKS_PER_USER {
+        return Err(WebhookError::ValidationError(format!(
+            "At most {MAX_WEBHOOKS_PER_USER} webhooks can be registered"
+        )));
+    }
+
+    let webhook = sqlx::query_as!(
+        WebhookRow,
+        r#"
+        INSERT INTO webhooks (webhook_id, user_id, url, events, is_global)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING webhook_id, url, events, is_global, created_at
+        "#,
+        Uuid::new_v4(),
+        *user_id,
+        url.as_ref(),
+        &events,
+        is_global
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .context("Failed to insert webhook.")?;
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the new webhook.")?;
+
+    Ok(HttpResponse::Created().json(webhook.into_webhook(&hmac_secret)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks",
+    responses(
+        (status = 200, description = "Your webhooks", body = Vec<Webhook>),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Listing webhooks", skip(pool, hmac_secret, user_id))]
+pub async fn list_webhooks(
+    pool: web::Data<PgPool>,
+    hmac_secret: web::Data<HmacSecret>,
+    user_id: web::ReqData<UserId>,
+) -> Result<web::Json<Vec<Webhook>>, WebhookError> {
+    let user_id = user_id.into_inner();
+
+    let webhooks = sqlx::query_as!(
+        WebhookRow,
+        r#"
+        SELECT webhook_id, url, events, is_global, created_at
+        FROM webhooks
+        WHERE user_id = $1
+        ORDER BY created_at
+        "#,
+        *user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .context("Failed to fetch webhooks.")?
+    .into_iter()
+    .map(|row| row.into_webhook(&hmac_secret))
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(web::Json(webhooks))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/webhooks/{webhook_id}",
+    params(
+        ("webhook_id" = Uuid, Path, description = "The ID of the webhook")
+    ),
+    responses(
+        (status = 204, description = "Webhook and its delivery log deleted"),
+        (status = 401, description = "Unauthorized", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = Problem, content_type = "application/problem+json")
+    ),
+    security(
+        ("token" = [])
+    ),
+)]
+#[tracing::instrument(name = "Deleting webhook", skip(pool, user_id))]
+pub async fn delete_webhook(
+    pool: web::Data<PgPool>,
+    user_id: web::ReqData<UserId>,
+    webhook_id: web::Path<Uuid>,
+) -> Result<HttpResponse, WebhookError> {
+    let webhook_id = webhook_id.into_inner();
+    let user_id = user_id.into_inner();
+
+    let result = sqlx::query!(
+        r#"DELETE FROM webhooks WHERE webhook_id = $1 AND user_id = $2"#,
+        webhook_id,
+        *user_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .context("Failed to delete webhook.")?;
+
+    if result.rows_affected() == 0 {
+        return Err(WebhookError::WebhookNotFound(webhook_id));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
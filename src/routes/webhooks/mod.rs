@@ -0,0 +1,5 @@
+mod deliveries;
+mod manage;
+
+pub use deliveries::*;
+pub use manage::*;
@@ -1,30 +1,60 @@
 use crate::api_docs::ApiDoc;
-use crate::authentication::reject_unauthorized_users;
-use crate::configuration::{DatabaseSettings, Settings};
-use crate::routes::{health_check, patterns, uploads, users};
-use crate::s3_client::S3Client;
-use crate::utils::get_error_response;
-use actix_cors::Cors;
+use crate::authentication::{redact_query_token, reject_unauthorized_users};
+use crate::configuration::{
+    ApplicationSettings, CorsSettings, DatabaseSettings, HealthSettings, MetricsSettings,
+    PatternCacheSettings, RateLimitBackend, RateLimitSettings, Settings, StorageBackend,
+    WebhookSettings,
+};
+use crate::cors::cors;
+use crate::fixtures::load_fixture_file;
+use crate::idempotency::{idempotent, IdempotentBodyLimit};
+use crate::live_sessions::LiveSessions;
+use crate::metrics::{refresh_archive_gauges, track_requests, ArchiveGauges};
+use crate::pattern_cache::PatternCache;
+use crate::problem::{Problem, ProblemCode};
+use crate::rate_limiting::{
+    limit_expensive_reads, limit_presigns, limit_writes, InMemoryRateLimiter, PostgresRateLimiter,
+    RateLimiter,
+};
+use crate::repository::{PatternRepository, PostgresRepository, UserRepository};
+use crate::routes::{
+    embeds, feeds, files, health_check, health_live, health_ready, metrics, patterns, uploads,
+    users, webhooks,
+};
+use crate::storage::{LocalStorage, ObjectStorage};
 use actix_web::{
-    dev::Server, error, error::JsonPayloadError, middleware::from_fn, web, web::Data,
-    web::JsonConfig, App, HttpResponse, HttpServer,
+    dev::{Server, ServerHandle},
+    error,
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    guard,
+    http::StatusCode,
+    middleware::{from_fn, Compress},
+    web,
+    web::{Data, JsonConfig, PathConfig, QueryConfig},
+    App, HttpRequest, HttpResponse, HttpServer,
 };
+use secrecy::Secret;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::net::TcpListener;
+use std::sync::Arc;
 use tracing_actix_web::TracingLogger;
 use utoipa::OpenApi;
 use utoipa_rapidoc::RapiDoc;
 use utoipa_swagger_ui::SwaggerUi;
 
+/// Archives carry whole libraries, so imports get a larger body limit than
+/// the default JSON config.
+const IMPORT_PAYLOAD_LIMIT: usize = 32 * 1024 * 1024;
+
 pub struct Application {
     port: u16,
     server: Server,
+    connection_pool: PgPool,
 }
 
 impl Application {
-    pub async fn build(configuration: Settings) -> Result<Self, anyhow::Error> {
+    pub async fn build(mut configuration: Settings) -> Result<Self, anyhow::Error> {
         let connection_pool = get_connection_pool(&configuration.database);
-        let s3_client = configuration.s3.client().await;
 
         let address = format!(
             "{}:{}",
@@ -32,85 +62,284 @@ impl Application {
         );
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr()?.port();
-        let server = run(listener, connection_pool, configuration.cognito, s3_client).await?;
+        // The default base URL has to point at the port actually bound.
+        configuration.application.port = port;
+
+        let local_storage = match configuration.storage.backend {
+            StorageBackend::Local => Some(Arc::new(configuration.local_storage())),
+            StorageBackend::S3 => None,
+        };
+        let storage: Arc<dyn ObjectStorage> = match local_storage {
+            Some(ref local_storage) => local_storage.clone(),
+            None => configuration.storage().await,
+        };
 
-        Ok(Self { port, server })
+        if configuration.fixtures.load_on_startup {
+            let report = load_fixture_file(
+                &connection_pool,
+                storage.as_ref(),
+                &configuration.fixtures.path,
+            )
+            .await?;
+            tracing::info!(?report, "Loaded fixtures");
+        }
+
+        let server = run(
+            listener,
+            connection_pool.clone(),
+            &configuration.application,
+            HmacSecret(configuration.application.hmac_secret.clone()),
+            configuration.cognito,
+            configuration.webhooks,
+            configuration.health,
+            configuration.rate_limits,
+            configuration.cors,
+            configuration.pattern_cache,
+            configuration.metrics,
+            storage,
+            local_storage,
+        )
+        .await?;
+
+        Ok(Self {
+            port,
+            server,
+            connection_pool,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    /// Stops the server the way signals do.
+    pub fn handle(&self) -> ServerHandle {
+        self.server.handle()
+    }
+
+    /// Serves until a signal arrives. SIGTERM stops accepting connections
+    /// and gives requests in flight `shutdown_timeout_secs` to finish, SIGINT
+    /// drops them. The connection pool is closed last.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let outcome = self.server.await;
+        self.connection_pool.close().await;
+        tracing::info!("API stopped");
+        outcome
     }
 }
 
 pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
-    PgPoolOptions::new().connect_lazy_with(configuration.connect_options())
+    PgPoolOptions::new()
+        .max_connections(configuration.max_connections)
+        .acquire_timeout(configuration.acquire_timeout())
+        .connect_lazy_with(configuration.connect_options())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run(
     listener: TcpListener,
     db_pool: PgPool,
+    application_settings: &ApplicationSettings,
+    hmac_secret: HmacSecret,
     cognito_settings: crate::configuration::CognitoSettings,
-    s3_client: S3Client,
+    webhook_settings: WebhookSettings,
+    health_settings: HealthSettings,
+    rate_limit_settings: RateLimitSettings,
+    cors_settings: CorsSettings,
+    pattern_cache_settings: PatternCacheSettings,
+    metrics_settings: MetricsSettings,
+    storage: Arc<dyn ObjectStorage>,
+    local_storage: Option<Arc<LocalStorage>>,
 ) -> Result<Server, anyhow::Error> {
+    let repository = Arc::new(PostgresRepository::new(db_pool.clone()));
+    let pattern_repository: Data<dyn PatternRepository> = Data::from(repository.clone() as Arc<_>);
+    let user_repository: Data<dyn UserRepository> = Data::from(repository as Arc<_>);
+    let rate_limiter: Data<dyn RateLimiter> = match rate_limit_settings.backend {
+        RateLimitBackend::Memory => Data::from(Arc::new(InMemoryRateLimiter::default()) as Arc<_>),
+        RateLimitBackend::Postgres => {
+            Data::from(Arc::new(PostgresRateLimiter::new(db_pool.clone())) as Arc<_>)
+        }
+    };
+    let rate_limit_settings = Data::new(rate_limit_settings);
     let db_pool = Data::new(db_pool);
+    let base_url = Data::new(ApplicationBaseUrl(application_settings.base_url()));
+    let json_limit = application_settings.json_limit_bytes;
+    let hmac_secret = Data::new(hmac_secret);
     let cognito_settings = Data::new(cognito_settings);
-    let s3_client = Data::new(s3_client);
+    let webhook_settings = Data::new(webhook_settings);
+    let health_settings = Data::new(health_settings);
+    let storage = Data::from(storage);
+    let local_storage = local_storage.map(Data::from);
+    let live_sessions = Data::new(LiveSessions::default());
+    let pattern_cache = Data::new(PatternCache::new(&pattern_cache_settings));
+    let archive_gauges = Arc::new(ArchiveGauges::default());
+    tokio::spawn(refresh_archive_gauges(
+        db_pool.get_ref().clone(),
+        Arc::downgrade(&archive_gauges),
+        metrics_settings.refresh_interval(),
+    ));
+    let archive_gauges = Data::from(archive_gauges);
+    let metrics_settings = Data::new(metrics_settings);
 
     let server = HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .max_age(3600);
-
         App::new()
-            .wrap(cors)
+            .wrap(Compress::default())
+            .wrap(cors(&cors_settings))
             .wrap(TracingLogger::default())
+            .wrap(from_fn(redact_query_token))
+            .wrap(from_fn(track_requests))
             .service(
                 web::scope("/v1")
+                    .configure(|cfg| {
+                        // The local backend stands in for S3, so the API
+                        // serves its files and signed uploads.
+                        if let Some(ref local_storage) = local_storage {
+                            cfg.service(
+                                web::resource("/files/{key:.*}")
+                                    .app_data(local_storage.clone())
+                                    .route(web::get().to(files::get_file))
+                                    .route(web::put().to(files::put_file)),
+                            );
+                        }
+                    })
                     .service(
                         web::scope("/patterns")
-                            .route(
-                                "/tb303/random",
-                                web::get().to(patterns::get_random_tb303_pattern),
+                            .service(
+                                web::resource("/tb303/random")
+                                    .wrap(from_fn(limit_expensive_reads))
+                                    .route(web::get().to(patterns::get_random_tb303_pattern)),
                             )
-                            .route(
-                                "/tb303/public",
-                                web::get().to(patterns::list_public_tb303_patterns),
+                            .service(
+                                web::resource("/tb303/public")
+                                    .wrap(from_fn(limit_expensive_reads))
+                                    .route(web::get().to(patterns::list_public_tb303_patterns)),
+                            )
+                            .service(
+                                web::resource("/tb303/shared")
+                                    .wrap(from_fn(reject_unauthorized_users))
+                                    .route(web::get().to(patterns::list_shared_tb303_patterns)),
                             )
                             .route(
                                 "/tb303/{pattern_id}",
                                 web::get().to(patterns::get_tb303_pattern),
                             )
+                            .route(
+                                "/tb303/{pattern_id}/preview.svg",
+                                web::get().to(patterns::get_tb303_preview),
+                            )
+                            .route(
+                                "/tb303/{pattern_id}/live",
+                                web::get().to(patterns::live_tb303_pattern),
+                            )
                             .service(
                                 web::scope("")
                                     .wrap(from_fn(reject_unauthorized_users))
-                                    .route("/tb303", web::post().to(patterns::create_tb303_pattern))
+                                    .service(
+                                        web::resource("/tb303")
+                                            .guard(guard::Post())
+                                            .wrap(from_fn(idempotent))
+                                            .wrap(from_fn(limit_writes))
+                                            .route(web::post().to(patterns::create_tb303_pattern)),
+                                    )
                                     .route("/tb303", web::get().to(patterns::list_tb303_patterns))
+                                    .service(
+                                        web::resource("/tb303/import")
+                                            .app_data(ApiError::json_error(
+                                                JsonConfig::default().limit(IMPORT_PAYLOAD_LIMIT),
+                                            ))
+                                            .app_data(IdempotentBodyLimit(IMPORT_PAYLOAD_LIMIT))
+                                            .wrap(from_fn(idempotent))
+                                            .route(web::post().to(patterns::import_tb303_patterns)),
+                                    )
                                     .route(
                                         "/tb303/{pattern_id}",
                                         web::delete().to(patterns::delete_tb303_pattern),
                                     )
+                                    .service(
+                                        web::resource("/tb303/{pattern_id}")
+                                            .guard(guard::Put())
+                                            .wrap(from_fn(idempotent))
+                                            .wrap(from_fn(limit_writes))
+                                            .route(web::put().to(patterns::update_tb303_pattern)),
+                                    )
                                     .route(
-                                        "/tb303/{pattern_id}",
-                                        web::put().to(patterns::update_tb303_pattern),
+                                        "/tb303/{pattern_id}/audio",
+                                        web::put().to(patterns::put_tb303_audio),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/audio",
+                                        web::delete().to(patterns::delete_tb303_audio),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/cover",
+                                        web::put().to(patterns::put_tb303_cover),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/cover",
+                                        web::delete().to(patterns::delete_tb303_cover),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/collaborators",
+                                        web::get().to(patterns::list_tb303_collaborators),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/collaborators",
+                                        web::post().to(patterns::add_tb303_collaborator),
+                                    )
+                                    .route(
+                                        "/tb303/{pattern_id}/collaborators/{user_id}",
+                                        web::delete().to(patterns::remove_tb303_collaborator),
                                     ),
                             ),
                     )
+                    .service(
+                        web::scope("/feeds")
+                            .route(
+                                "/patterns.{format}",
+                                web::get().to(feeds::get_patterns_feed),
+                            )
+                            .route(
+                                "/users/{username}/patterns.{format}",
+                                web::get().to(feeds::get_user_patterns_feed),
+                            )
+                            .route(
+                                "/authors/{author}/patterns.{format}",
+                                web::get().to(feeds::get_author_patterns_feed),
+                            ),
+                    )
                     .service(
                         web::scope("/uploads")
                             .wrap(from_fn(reject_unauthorized_users))
-                            .route("/presign", web::post().to(uploads::presign_upload)),
+                            .service(
+                                web::resource("/presign")
+                                    .wrap(from_fn(idempotent))
+                                    .wrap(from_fn(limit_presigns))
+                                    .route(web::post().to(uploads::presign_upload)),
+                            ),
+                    )
+                    .service(
+                        web::scope("/webhooks")
+                            .wrap(from_fn(reject_unauthorized_users))
+                            .route("", web::post().to(webhooks::create_webhook))
+                            .route("", web::get().to(webhooks::list_webhooks))
+                            .route("/{webhook_id}", web::delete().to(webhooks::delete_webhook))
+                            .route(
+                                "/{webhook_id}/deliveries",
+                                web::get().to(webhooks::list_webhook_deliveries),
+                            )
+                            .route(
+                                "/{webhook_id}/deliveries/{delivery_id}/redeliver",
+                                web::post().to(webhooks::redeliver_webhook_delivery),
+                            ),
                     )
                     .service(
                         web::scope("/users")
                             .wrap(from_fn(reject_unauthorized_users))
                             .route("/me", web::get().to(users::get_me))
-                            .route("/me", web::patch().to(users::patch_me)),
+                            .route("/me", web::patch().to(users::patch_me))
+                            .route("/me", web::delete().to(users::delete_me))
+                            .route("/me/export", web::get().to(users::export_me))
+                            .route("/me/data", web::get().to(users::get_me_data)),
                     ),
             )
             .service(RapiDoc::new("/api-docs/openapi.json").path("/docs"))
@@ -119,35 +348,106 @@ async fn run(
                     .url("/api-docs/openapi.json", ApiDoc::openapi()),
             )
             .route("/health_check", web::get().to(health_check))
+            .route("/health/live", web::get().to(health_live))
+            .route("/health/ready", web::get().to(health_ready))
+            .service(
+                web::resource("/metrics")
+                    .app_data(metrics_settings.clone())
+                    .app_data(archive_gauges.clone())
+                    .route(web::get().to(metrics::get_metrics)),
+            )
+            .route("/oembed", web::get().to(embeds::get_oembed))
+            .route(
+                "/patterns/tb303/{pattern_id}",
+                web::get().to(embeds::get_share_page),
+            )
+            .route(
+                "/embed/patterns/tb303/{pattern_id}",
+                web::get().to(embeds::get_player),
+            )
             .app_data(db_pool.clone())
+            .app_data(base_url.clone())
+            .app_data(hmac_secret.clone())
             .app_data(cognito_settings.clone())
-            .app_data(s3_client.clone())
-            .app_data(ApiError::json_error(JsonConfig::default()))
+            .app_data(webhook_settings.clone())
+            .app_data(health_settings.clone())
+            .app_data(rate_limit_settings.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(storage.clone())
+            .app_data(pattern_repository.clone())
+            .app_data(user_repository.clone())
+            .app_data(live_sessions.clone())
+            .app_data(pattern_cache.clone())
+            .app_data(ApiError::json_error(
+                JsonConfig::default().limit(json_limit),
+            ))
+            .app_data(IdempotentBodyLimit(json_limit))
+            .app_data(ApiError::query_error(QueryConfig::default()))
+            .app_data(ApiError::path_error(PathConfig::default()))
+            .default_service(web::to(ApiError::not_found))
     })
-    .listen(listener)?
-    .run();
+    .keep_alive(application_settings.keep_alive())
+    .client_request_timeout(application_settings.request_timeout())
+    .shutdown_timeout(application_settings.shutdown_timeout_secs);
+    let server = match application_settings.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    let server = server.listen(listener)?.run();
 
     Ok(server)
 }
 
+/// Where clients reach the API, for links the API hands out to itself.
+pub struct ApplicationBaseUrl(pub String);
+
 #[derive(Clone)]
-pub struct HmacSecret(pub String);
+pub struct HmacSecret(pub Secret<String>);
 
 pub struct ApiError;
 
 impl ApiError {
     pub fn json_error(cfg: JsonConfig) -> JsonConfig {
-        cfg.limit(1024 * 1024)
-            .error_handler(|err: JsonPayloadError, _req| {
-                let error = err.to_string();
-                let slice = &error[..error.find(" at").unwrap_or(error.len())];
-
-                // create custom error response
-                error::InternalError::from_response(
-                    err,
-                    HttpResponse::BadRequest().json(get_error_response(slice.to_string())),
-                )
-                .into()
-            })
+        cfg.error_handler(|err: JsonPayloadError, _req| {
+            let error = err.to_string();
+            let slice = &error[..error.find(" at").unwrap_or(error.len())];
+
+            let problem = Problem::new(StatusCode::BAD_REQUEST, ProblemCode::InvalidJson, slice);
+            error::InternalError::from_response(err, problem.into()).into()
+        })
+    }
+
+    pub fn query_error(cfg: QueryConfig) -> QueryConfig {
+        cfg.error_handler(|err: QueryPayloadError, _req| {
+            let problem = Problem::new(
+                StatusCode::BAD_REQUEST,
+                ProblemCode::InvalidQuery,
+                err.to_string(),
+            );
+            error::InternalError::from_response(err, problem.into()).into()
+        })
+    }
+
+    /// Path segments that do not parse, such as a pattern ID that is not a
+    /// UUID.
+    pub fn path_error(cfg: PathConfig) -> PathConfig {
+        cfg.error_handler(|err: PathError, _req| {
+            let problem = Problem::new(
+                StatusCode::BAD_REQUEST,
+                ProblemCode::InvalidPath,
+                err.to_string(),
+            );
+            error::InternalError::from_response(err, problem.into()).into()
+        })
+    }
+
+    /// Answers requests no route matches.
+    pub async fn not_found(req: HttpRequest) -> HttpResponse {
+        Problem::new(
+            StatusCode::NOT_FOUND,
+            ProblemCode::NotFound,
+            format!("Nothing is served at {}.", req.path()),
+        )
+        .into()
     }
 }
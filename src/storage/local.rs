@@ -0,0 +1,466 @@
+use crate::storage::{ObjectStorage, StorageError, StoredObject};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Route the API serves local files and signed uploads from.
+pub const LOCAL_FILES_PATH: &str = "/v1/files";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Query string of a signed upload URL. The signature covers the key and
+/// every other field, so none of them can be changed by the uploader.
+#[derive(serde::Deserialize, Debug)]
+pub struct UploadSignature {
+    pub content_type: String,
+    pub content_length: u64,
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Keeps objects on the local filesystem and lets the API stand in for S3:
+/// uploads go to signed URLs under [`LOCAL_FILES_PATH`] and files are served
+/// from there too. Objects live under `{root}/objects` and their content type
+/// under `{root}/meta`.
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+    public_base_url: String,
+    signing_key: Secret<String>,
+}
+
+impl LocalStorage {
+    /// `base_url` is where clients reach the API. `public_base_url`
+    /// overrides the URL files are served from, e.g. a CDN in front of it.
+    pub fn new(
+        root: PathBuf,
+        base_url: String,
+        public_base_url: Option<String>,
+        signing_key: Secret<String>,
+    ) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let public_base_url = match public_base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("{}{}", base_url, LOCAL_FILES_PATH),
+        };
+
+        Self {
+            root,
+            base_url,
+            public_base_url,
+            signing_key,
+        }
+    }
+
+    /// Checks the signature of an upload URL and that it has not expired.
+    pub fn verify_upload(&self, key: &str, upload: &UploadSignature) -> bool {
+        if upload.expires < Utc::now().timestamp() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(&upload.signature) else {
+            return false;
+        };
+        self.mac(
+            key,
+            &upload.content_type,
+            upload.content_length,
+            upload.expires,
+        )
+        .verify_slice(&signature)
+        .is_ok()
+    }
+
+    fn mac(&self, key: &str, content_type: &str, content_length: u64, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.signing_key.expose_secret().as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(format!("{key}\n{content_type}\n{content_length}\n{expires}").as_bytes());
+        mac
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn object_path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.objects_dir().join(validate_key(key)?))
+    }
+
+    fn meta_path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.root.join("meta").join(validate_key(key)?))
+    }
+
+    async fn read_content_type(&self, key: &str) -> Result<Option<String>, StorageError> {
+        match tokio::fs::read_to_string(self.meta_path(key)?).await {
+            Ok(content_type) => Ok(Some(content_type)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StorageError::HeadError(format!("{key}: {e}"))),
+        }
+    }
+}
+
+/// Keys become paths, so anything that could leave the storage root is
+/// rejected.
+fn validate_key(key: &str) -> Result<&str, StorageError> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && !segment.contains(['\\', '\0'])
+        });
+
+    if valid {
+        Ok(key)
+    } else {
+        Err(StorageError::InvalidKey(key.to_string()))
+    }
+}
+
+/// Writes through a temporary file so readers never see partial objects.
+async fn write_file(root: &Path, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp_dir = root.join("tmp");
+    tokio::fs::create_dir_all(&tmp_dir).await?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let tmp_path = tmp_dir.join(Uuid::new_v4().to_string());
+    tokio::fs::write(&tmp_path, contents).await?;
+    if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+async fn remove_file(path: &Path) -> std::io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStorage for LocalStorage {
+    async fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_length: u64,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        validate_key(key)?;
+        let expires_in = chrono::Duration::from_std(expires_in)
+            .map_err(|e| StorageError::PresignError(e.to_string()))?;
+        let expires = (Utc::now() + expires_in).timestamp();
+        let signature = hex::encode(
+            self.mac(key, content_type, content_length, expires)
+                .finalize()
+                .into_bytes(),
+        );
+
+        let mut url = url::Url::parse(&format!("{}{}/{}", self.base_url, LOCAL_FILES_PATH, key))
+            .map_err(|e| StorageError::PresignError(format!("{key}: {e}")))?;
+        url.query_pairs_mut()
+            .append_pair("content_type", content_type)
+            .append_pair("content_length", &content_length.to_string())
+            .append_pair("expires", &expires.to_string())
+            .append_pair("signature", &signature);
+
+        Ok(url.to_string())
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+        let objects_dir = self.objects_dir();
+        // Only the directory the prefix points into needs to be walked.
+        let start = match prefix.rsplit_once('/') {
+            Some((dir, _)) => objects_dir.join(validate_key(dir)?),
+            None => objects_dir.clone(),
+        };
+
+        let mut objects = Vec::new();
+        let mut dirs = vec![start];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(StorageError::ListError(format!("{prefix}: {e}"))),
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| StorageError::ListError(format!("{prefix}: {e}")))?
+            {
+                let path = entry.path();
+                let metadata = entry
+                    .metadata()
+                    .await
+                    .map_err(|e| StorageError::ListError(format!("{prefix}: {e}")))?;
+                if metadata.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                let Some(key) = path
+                    .strip_prefix(&objects_dir)
+                    .ok()
+                    .and_then(|relative| relative.to_str())
+                    .map(|relative| relative.replace(std::path::MAIN_SEPARATOR, "/"))
+                else {
+                    continue;
+                };
+                if key.starts_with(prefix) {
+                    objects.push(StoredObject {
+                        key,
+                        size: Some(metadata.len() as i64),
+                        content_type: None,
+                        last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+                    });
+                }
+            }
+        }
+
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, StorageError> {
+        let metadata = match tokio::fs::metadata(self.object_path(key)?).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StorageError::HeadError(format!("{key}: {e}"))),
+        };
+
+        Ok(Some(StoredObject {
+            key: key.to_string(),
+            size: Some(metadata.len() as i64),
+            content_type: self.read_content_type(key).await?,
+            last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        }))
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        tokio::fs::read(self.object_path(key)?)
+            .await
+            .map_err(|e| StorageError::GetError(format!("{key}: {e}")))
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        let object_path = self.object_path(key)?;
+        let meta_path = self.meta_path(key)?;
+
+        write_file(&self.root, &meta_path, content_type.as_bytes())
+            .await
+            .map_err(|e| StorageError::PutError(format!("{key}: {e}")))?;
+        write_file(&self.root, &object_path, &body)
+            .await
+            .map_err(|e| StorageError::PutError(format!("{key}: {e}")))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        remove_file(&self.object_path(key)?)
+            .await
+            .map_err(|e| StorageError::DeleteError(format!("{key}: {e}")))?;
+        remove_file(&self.meta_path(key)?)
+            .await
+            .map_err(|e| StorageError::DeleteError(format!("{key}: {e}")))
+    }
+
+    fn get_public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_base_url, key)
+    }
+
+    async fn check_reachable(&self) -> Result<(), StorageError> {
+        let objects_dir = self.objects_dir();
+        tokio::fs::create_dir_all(&objects_dir)
+            .await
+            .map_err(|e| StorageError::Unreachable(format!("{}: {e}", objects_dir.display())))?;
+        let metadata = tokio::fs::metadata(&objects_dir)
+            .await
+            .map_err(|e| StorageError::Unreachable(format!("{}: {e}", objects_dir.display())))?;
+        if metadata.permissions().readonly() {
+            return Err(StorageError::Unreachable(format!(
+                "{} is read-only",
+                objects_dir.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{LocalStorage, ObjectStorage, StorageError, UploadSignature};
+    use chrono::Utc;
+    use claims::{assert_matches, assert_none, assert_ok, assert_some};
+    use hmac::Mac;
+    use secrecy::Secret;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn storage() -> LocalStorage {
+        LocalStorage::new(
+            std::env::temp_dir().join(format!("acid-storage-{}", Uuid::new_v4())),
+            "http://localhost:8000/".to_string(),
+            None,
+            Secret::new("signing-key".to_string()),
+        )
+    }
+
+    fn signature_of(url: &str) -> UploadSignature {
+        let url = url::Url::parse(url).unwrap();
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        UploadSignature {
+            content_type: query["content_type"].clone(),
+            content_length: query["content_length"].parse().unwrap(),
+            expires: query["expires"].parse().unwrap(),
+            signature: query["signature"].clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn objects_round_trip() {
+        let storage = storage();
+
+        assert_ok!(
+            storage
+                .put_object("avatars/user/a", vec![1, 2, 3], "image/webp")
+                .await
+        );
+        assert_ok!(
+            storage
+                .put_object("avatars/user/a-64.webp", vec![4], "image/webp")
+                .await
+        );
+
+        let object = assert_some!(assert_ok!(storage.head_object("avatars/user/a").await));
+        assert_eq!(object.size, Some(3));
+        assert_eq!(object.content_type.as_deref(), Some("image/webp"));
+        assert_eq!(
+            assert_ok!(storage.get_object("avatars/user/a").await),
+            vec![1, 2, 3]
+        );
+
+        let keys: Vec<String> = assert_ok!(storage.list_objects("avatars/user/a").await)
+            .into_iter()
+            .map(|object| object.key)
+            .collect();
+        assert_eq!(keys, vec!["avatars/user/a", "avatars/user/a-64.webp"]);
+
+        assert_eq!(assert_ok!(storage.delete_prefix("avatars/user/").await), 2);
+        assert_none!(assert_ok!(storage.head_object("avatars/user/a").await));
+        assert_ok!(storage.delete_object("avatars/user/a").await);
+    }
+
+    #[tokio::test]
+    async fn keys_that_leave_the_root_are_rejected() {
+        let storage = storage();
+
+        for key in [
+            "",
+            "/etc/passwd",
+            "avatars/../../secret",
+            "avatars//a",
+            "a\\b",
+        ] {
+            assert_matches!(
+                storage.put_object(key, vec![], "image/png").await,
+                Err(StorageError::InvalidKey(_))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn storage_is_unreachable_when_the_root_is_not_a_directory() {
+        let root = std::env::temp_dir().join(format!("acid-storage-{}", Uuid::new_v4()));
+        assert_ok!(tokio::fs::write(&root, b"not a directory").await);
+        let broken = LocalStorage::new(
+            root,
+            "http://localhost:8000/".to_string(),
+            None,
+            Secret::new("signing-key".to_string()),
+        );
+
+        assert_ok!(storage().check_reachable().await);
+        assert_matches!(
+            broken.check_reachable().await,
+            Err(StorageError::Unreachable(_))
+        );
+    }
+
+    #[tokio::test]
+    async fn presigned_urls_point_at_the_api() {
+        let storage = storage();
+
+        let url = assert_ok!(
+            storage
+                .presign_put("avatars/user/a", "image/png", 3, Duration::from_secs(60))
+                .await
+        );
+
+        assert!(url.starts_with("http://localhost:8000/v1/files/avatars/user/a?"));
+        assert_eq!(
+            storage.get_public_url("avatars/user/a"),
+            "http://localhost:8000/v1/files/avatars/user/a"
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_signatures_cover_every_field() {
+        let storage = storage();
+        let url = assert_ok!(
+            storage
+                .presign_put("avatars/user/a", "image/png", 3, Duration::from_secs(60))
+                .await
+        );
+        let signature = signature_of(&url);
+
+        assert!(storage.verify_upload("avatars/user/a", &signature));
+        assert!(!storage.verify_upload("avatars/user/b", &signature));
+        assert!(!storage.verify_upload(
+            "avatars/user/a",
+            &UploadSignature {
+                content_length: 4,
+                ..signature_of(&url)
+            }
+        ));
+        assert!(!storage.verify_upload(
+            "avatars/user/a",
+            &UploadSignature {
+                content_type: "image/gif".to_string(),
+                ..signature_of(&url)
+            }
+        ));
+    }
+
+    #[test]
+    fn expired_upload_signatures_are_rejected() {
+        let storage = storage();
+        let expires = Utc::now().timestamp() - 1;
+        let signature = storage
+            .mac("avatars/user/a", "image/png", 3, expires)
+            .finalize()
+            .into_bytes();
+
+        assert!(!storage.verify_upload(
+            "avatars/user/a",
+            &UploadSignature {
+                content_type: "image/png".to_string(),
+                content_length: 3,
+                expires,
+                signature: hex::encode(signature),
+            }
+        ));
+    }
+}
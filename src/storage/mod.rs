@@ -0,0 +1,86 @@
+mod local;
+mod s3;
+
+pub use local::*;
+pub use s3::*;
+
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Failed to generate presigned URL: {0}")]
+    PresignError(String),
+    #[error("Failed to list objects: {0}")]
+    ListError(String),
+    #[error("Failed to delete object: {0}")]
+    DeleteError(String),
+    #[error("Failed to fetch object metadata: {0}")]
+    HeadError(String),
+    #[error("Failed to fetch object: {0}")]
+    GetError(String),
+    #[error("Failed to store object: {0}")]
+    PutError(String),
+    #[error("Invalid object key: {0}")]
+    InvalidKey(String),
+    #[error("Storage is unreachable: {0}")]
+    Unreachable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: Option<i64>,
+    pub content_type: Option<String>,
+    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Where uploaded files live. Browsers upload straight to the URL returned by
+/// [`ObjectStorage::presign_put`] and read files from
+/// [`ObjectStorage::get_public_url`]; the API only touches objects to verify,
+/// process and delete them.
+#[async_trait::async_trait]
+pub trait ObjectStorage: Send + Sync {
+    /// Returns a URL that accepts a single `PUT` of exactly `content_length`
+    /// bytes of `content_type` until it expires.
+    async fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_length: u64,
+        expires_in: Duration,
+    ) -> Result<String, StorageError>;
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError>;
+
+    /// Returns `None` when there is no object stored under `key`.
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, StorageError>;
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+
+    async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError>;
+
+    /// Deleting a key that does not exist succeeds, so this is safe to retry.
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError>;
+
+    /// Deletes every object whose key starts with `prefix` and returns how
+    /// many were deleted.
+    async fn delete_prefix(&self, prefix: &str) -> Result<usize, StorageError> {
+        let objects = self.list_objects(prefix).await?;
+        for object in &objects {
+            self.delete_object(&object.key).await?;
+        }
+        Ok(objects.len())
+    }
+
+    fn get_public_url(&self, key: &str) -> String;
+
+    /// Succeeds when objects can be stored, e.g. the bucket exists and the
+    /// credentials are accepted.
+    async fn check_reachable(&self) -> Result<(), StorageError>;
+}
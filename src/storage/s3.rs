@@ -0,0 +1,244 @@
+use crate::storage::{ObjectStorage, StorageError, StoredObject};
+use std::time::Duration;
+
+pub struct S3Storage {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    public_base_url: String,
+}
+
+impl S3Storage {
+    /// Path-style addressing (`{endpoint}/{bucket}/{key}`) is needed by most
+    /// S3-compatible servers such as MinIO and LocalStack, which are usually
+    /// not reachable through bucket subdomains. `public_base_url` overrides
+    /// the URL files are served from, e.g. a CDN in front of the bucket.
+    pub async fn new(
+        region: String,
+        bucket: String,
+        endpoint_url: Option<String>,
+        force_path_style: bool,
+        public_base_url: Option<String>,
+    ) -> Self {
+        let public_base_url = match public_base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => bucket_url(&region, &bucket, endpoint_url.as_deref(), force_path_style),
+        };
+
+        let mut config_loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_config::Region::new(region));
+        if let Some(endpoint) = endpoint_url {
+            config_loader = config_loader.endpoint_url(endpoint);
+        }
+
+        let sdk_config = config_loader.load().await;
+        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(force_path_style)
+            .build();
+        let client = aws_sdk_s3::Client::from_conf(s3_config);
+
+        Self {
+            client,
+            bucket,
+            public_base_url,
+        }
+    }
+}
+
+/// The URL objects of `bucket` are reachable at without a CDN in front.
+fn bucket_url(region: &str, bucket: &str, endpoint_url: Option<&str>, path_style: bool) -> String {
+    let endpoint = endpoint_url
+        .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+
+    if path_style {
+        return format!("{}/{}", endpoint, bucket);
+    }
+    match endpoint.split_once("://") {
+        Some((scheme, host)) => format!("{}://{}.{}", scheme, bucket, host),
+        None => format!("{}.{}", bucket, endpoint),
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStorage for S3Storage {
+    async fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        content_length: u64,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        let presigning_config = aws_sdk_s3::presigning::PresigningConfig::expires_in(expires_in)
+            .map_err(|e| {
+                StorageError::PresignError(format!("Failed to create presigning config: {e}"))
+            })?;
+
+        let presigned_request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .content_length(content_length as i64)
+            .presigned(presigning_config)
+            .await
+            .map_err(|e| StorageError::PresignError(format!("Failed to presign request: {e}")))?;
+
+        Ok(presigned_request.uri().to_string())
+    }
+
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|e| StorageError::ListError(format!("{prefix}: {e}")))?;
+
+            objects.extend(output.contents().iter().filter_map(|object| {
+                Some(StoredObject {
+                    key: object.key()?.to_string(),
+                    size: object.size(),
+                    content_type: None,
+                    last_modified: object
+                        .last_modified()
+                        .and_then(|t| chrono::DateTime::from_timestamp(t.secs(), t.subsec_nanos())),
+                })
+            }));
+
+            match output.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn head_object(&self, key: &str) -> Result<Option<StoredObject>, StorageError> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(StoredObject {
+                key: key.to_string(),
+                size: output.content_length(),
+                content_type: output.content_type().map(str::to_string),
+                last_modified: output
+                    .last_modified()
+                    .and_then(|t| chrono::DateTime::from_timestamp(t.secs(), t.subsec_nanos())),
+            })),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(StorageError::HeadError(format!("{key}: {e}"))),
+        }
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::GetError(format!("{key}: {e}")))?;
+
+        let body = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| StorageError::GetError(format!("{key}: {e}")))?;
+
+        Ok(body.into_bytes().to_vec())
+    }
+
+    async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StorageError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(aws_sdk_s3::primitives::ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| StorageError::PutError(format!("{key}: {e}")))?;
+
+        Ok(())
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::DeleteError(format!("{key}: {e}")))?;
+
+        Ok(())
+    }
+
+    fn get_public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_base_url, key)
+    }
+
+    async fn check_reachable(&self) -> Result<(), StorageError> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map_err(|e| StorageError::Unreachable(format!("{}: {e}", self.bucket)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::s3::bucket_url;
+
+    #[test]
+    fn aws_buckets_use_virtual_hosted_urls() {
+        assert_eq!(
+            bucket_url("eu-west-1", "acid", None, false),
+            "https://acid.s3.eu-west-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn path_style_puts_the_bucket_in_the_path() {
+        assert_eq!(
+            bucket_url("eu-west-1", "acid", Some("http://localhost:9000/"), true),
+            "http://localhost:9000/acid"
+        );
+        assert_eq!(
+            bucket_url("eu-west-1", "acid", None, true),
+            "https://s3.eu-west-1.amazonaws.com/acid"
+        );
+    }
+
+    #[test]
+    fn custom_endpoints_without_path_style_use_bucket_subdomains() {
+        assert_eq!(
+            bucket_url("auto", "acid", Some("https://storage.example.com"), false),
+            "https://acid.storage.example.com"
+        );
+    }
+}
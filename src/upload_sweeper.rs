@@ -0,0 +1,67 @@
+use crate::metrics::begin_transaction;
+use crate::storage::ObjectStorage;
+use anyhow::Context;
+use chrono::Utc;
+use sqlx::PgPool;
+use std::time::Duration;
+
+const SWEEP_BATCH_SIZE: i64 = 100;
+
+/// Deletes uploads nobody claimed within `ttl`, including released uploads
+/// whose delete jobs did not get to them.
+/// Returns the number of uploads removed.
+#[tracing::instrument(name = "Sweeping orphaned uploads", skip(pool, storage))]
+pub async fn sweep_orphaned_uploads(
+    pool: &PgPool,
+    storage: &dyn ObjectStorage,
+    ttl: Duration,
+) -> Result<usize, anyhow::Error> {
+    let cutoff = Utc::now() - chrono::Duration::from_std(ttl).context("Invalid orphan TTL.")?;
+    let mut swept = 0;
+
+    loop {
+        let mut transaction = begin_transaction(pool)
+            .await
+            .context("Failed to start a transaction for the sweep.")?;
+
+        // Locked rows cannot be claimed until the object is gone and the
+        // row deleted, and rows a claim has locked are left alone.
+        let keys = sqlx::query_scalar!(
+            r#"
+            SELECT key FROM uploads
+            WHERE claimed_at IS NULL AND created_at < $1
+            ORDER BY created_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+            cutoff,
+            SWEEP_BATCH_SIZE
+        )
+        .fetch_all(&mut *transaction)
+        .await
+        .context("Failed to fetch orphaned uploads.")?;
+
+        if keys.is_empty() {
+            return Ok(swept);
+        }
+
+        // A failed claim can leave image variants next to the upload.
+        for key in &keys {
+            storage
+                .delete_prefix(key)
+                .await
+                .context("Failed to delete orphaned upload.")?;
+        }
+
+        sqlx::query!(r#"DELETE FROM uploads WHERE key = ANY($1)"#, &keys)
+            .execute(&mut *transaction)
+            .await
+            .context("Failed to forget orphaned uploads.")?;
+
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit the sweep.")?;
+        swept += keys.len();
+    }
+}
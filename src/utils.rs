@@ -18,22 +18,42 @@ pub fn error_chain_fmt(
     Ok(())
 }
 
-#[derive(serde::Serialize)]
-pub struct ErrorResponse {
-    status: String,
-    message: String,
+/// Cuts `s` down to at most `max_len` bytes without splitting a character.
+pub fn truncate_to_char_boundary(mut s: String, max_len: usize) -> String {
+    if s.len() > max_len {
+        let mut end = max_len;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+    s
 }
 
-pub fn get_error_response(message: impl Into<String>) -> ErrorResponse {
-    ErrorResponse {
-        status: "error".to_string(),
-        message: message.into(),
+/// Resolves on SIGTERM or SIGINT, the signals the API server stops on.
+pub async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM.");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
     }
 }
 
-pub fn get_fail_response(message: impl Into<String>) -> ErrorResponse {
-    ErrorResponse {
-        status: "fail".to_string(),
-        message: message.into(),
+#[cfg(test)]
+mod tests {
+    use crate::utils::truncate_to_char_boundary;
+
+    #[test]
+    fn long_strings_are_truncated_on_a_char_boundary() {
+        let truncated = truncate_to_char_boundary("ä".repeat(10), 5);
+
+        assert_eq!(truncated, "ää");
+    }
+
+    #[test]
+    fn short_strings_are_kept() {
+        assert_eq!(truncate_to_char_boundary("short".to_string(), 5), "short");
     }
 }
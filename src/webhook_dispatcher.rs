@@ -0,0 +1,269 @@
+use crate::configuration::{Settings, WebhookSettings};
+use crate::domain::is_public_address;
+use crate::metrics::begin_transaction;
+use crate::startup::{get_connection_pool, HmacSecret};
+use crate::utils::truncate_to_char_boundary;
+use crate::webhooks::{
+    signature_header, webhook_secret, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use url::{Host, Url};
+
+/// A delivery is given up after this many failed attempts, a little over two
+/// days after the event with the retry delays below.
+pub const MAX_ATTEMPTS: i32 = 15;
+const FIRST_RETRY_DELAY_SECS: u64 = 30;
+const MAX_RETRY_DELAY_SECS: u64 = 12 * 60 * 60;
+const MAX_ERROR_LENGTH: usize = 500;
+/// How long a claimed delivery waits beyond the send timeout before another
+/// dispatcher may take it over, e.g. because the first one died mid-send.
+const CLAIM_MARGIN_SECS: u64 = 60;
+
+#[derive(Debug, PartialEq)]
+pub enum DispatchOutcome {
+    Attempted,
+    EmptyQueue,
+}
+
+/// Sends deliveries until `shutdown` is cancelled. A delivery being sent by
+/// then is finished and its outcome recorded first.
+pub async fn run_dispatcher_until_stopped(
+    configuration: Settings,
+    shutdown: CancellationToken,
+) -> Result<(), anyhow::Error> {
+    let pool = get_connection_pool(&configuration.database);
+    let hmac_secret = HmacSecret(configuration.application.hmac_secret);
+    let settings = configuration.webhooks;
+    let idle = || shutdown.run_until_cancelled(tokio::time::sleep(settings.poll_interval()));
+
+    while !shutdown.is_cancelled() {
+        match dispatch_next_delivery(&pool, &settings, &hmac_secret).await {
+            Ok(DispatchOutcome::Attempted) => {}
+            Ok(DispatchOutcome::EmptyQueue) => {
+                idle().await;
+            }
+            Err(e) => {
+                tracing::error!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to dispatch a webhook delivery"
+                );
+                idle().await;
+            }
+        }
+    }
+
+    tracing::info!("Webhook dispatcher stopped");
+    Ok(())
+}
+
+/// How long to wait before attempt `attempts + 1`, doubling from 30 seconds
+/// up to 12 hours.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    Duration::from_secs((FIRST_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Sends the delivery that has been due the longest, if any. The delivery is
+/// claimed first, by counting the attempt and pushing it back past the send
+/// timeout, so dispatchers on several instances never send it at once and no
+/// transaction is held open while the receiver answers. A dispatcher that
+/// dies mid-send leaves it to be retried once the claim runs out.
+#[tracing::instrument(name = "Dispatching webhook delivery", skip_all)]
+pub async fn dispatch_next_delivery(
+    pool: &PgPool,
+    settings: &WebhookSettings,
+    hmac_secret: &HmacSecret,
+) -> Result<DispatchOutcome, anyhow::Error> {
+    let mut transaction = begin_transaction(pool)
+        .await
+        .context("Failed to start a transaction for a webhook delivery.")?;
+
+    let Some(delivery) = sqlx::query!(
+        r#"
+        SELECT d.delivery_id, d.webhook_id, d.event, d.payload, d.attempts, w.url
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.webhook_id = d.webhook_id
+        WHERE d.status = 'pending' AND d.next_attempt_at <= NOW()
+        ORDER BY d.next_attempt_at
+        LIMIT 1
+        FOR UPDATE OF d SKIP LOCKED
+        "#
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .context("Failed to fetch the next webhook delivery.")?
+    else {
+        return Ok(DispatchOutcome::EmptyQueue);
+    };
+
+    let attempts = delivery.attempts + 1;
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries SET
+            attempts = $2,
+            next_attempt_at = $3,
+            last_attempt_at = NOW()
+        WHERE delivery_id = $1
+        "#,
+        delivery.delivery_id,
+        attempts,
+        Utc::now() + settings.timeout() + Duration::from_secs(CLAIM_MARGIN_SECS)
+    )
+    .execute(&mut *transaction)
+    .await
+    .context("Failed to claim a webhook delivery.")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a webhook delivery claim.")?;
+
+    let body = serde_json::to_vec(&delivery.payload).context("Failed to encode the payload.")?;
+    let secret = webhook_secret(hmac_secret, delivery.webhook_id);
+    let signature = signature_header(&secret, Utc::now().timestamp(), &body);
+
+    let result = send(
+        settings,
+        &delivery.url,
+        &[
+            (SIGNATURE_HEADER, signature),
+            (EVENT_HEADER, delivery.event.clone()),
+            (DELIVERY_HEADER, delivery.delivery_id.to_string()),
+        ],
+        body,
+    )
+    .await;
+
+    let (status_code, error) = match result {
+        Ok(status) if status.is_success() => (Some(status.as_u16()), None),
+        Ok(status) => (
+            Some(status.as_u16()),
+            Some(format!("Receiver returned {status}")),
+        ),
+        Err(e) => (
+            None,
+            Some(truncate_to_char_boundary(
+                format!("{e:#}"),
+                MAX_ERROR_LENGTH,
+            )),
+        ),
+    };
+    let (status, next_attempt_at): (&str, Option<DateTime<Utc>>) = match error {
+        None => ("succeeded", None),
+        Some(_) if attempts >= MAX_ATTEMPTS => ("failed", None),
+        Some(_) => ("pending", Some(Utc::now() + retry_delay(attempts))),
+    };
+    tracing::info!(
+        delivery_id = %delivery.delivery_id,
+        attempts,
+        status,
+        status_code,
+        error,
+        "Attempted webhook delivery"
+    );
+
+    // Skipped when another dispatcher took the delivery over meanwhile.
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries SET
+            status = $3,
+            next_attempt_at = COALESCE($4, next_attempt_at),
+            last_status_code = $5,
+            last_error = $6,
+            delivered_at = CASE WHEN $3 = 'succeeded' THEN NOW() END
+        WHERE delivery_id = $1 AND attempts = $2
+        "#,
+        delivery.delivery_id,
+        attempts,
+        status,
+        next_attempt_at,
+        status_code.map(i32::from),
+        error
+    )
+    .execute(pool)
+    .await
+    .context("Failed to record a webhook delivery attempt.")?;
+
+    Ok(DispatchOutcome::Attempted)
+}
+
+/// POSTs the payload without following redirects. Names are resolved here
+/// and the connection is pinned to the checked addresses, so a webhook
+/// cannot be pointed into the private network after it was registered.
+async fn send(
+    settings: &WebhookSettings,
+    url: &str,
+    headers: &[(&str, String)],
+    body: Vec<u8>,
+) -> Result<reqwest::StatusCode, anyhow::Error> {
+    let url = Url::parse(url).context("Invalid webhook URL.")?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| anyhow!("Webhook URL has no port."))?;
+
+    let mut client = reqwest::Client::builder()
+        .timeout(settings.timeout())
+        .redirect(reqwest::redirect::Policy::none());
+    let addresses: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Domain(domain)) => {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+                .await
+                .with_context(|| format!("Failed to resolve {domain}."))?
+                .collect();
+            client = client.resolve_to_addrs(domain, &addresses);
+            addresses
+        }
+        None => return Err(anyhow!("Webhook URL has no host.")),
+    };
+    if !settings.allow_private_networks
+        && addresses
+            .iter()
+            .any(|address| !is_public_address(address.ip()))
+    {
+        return Err(anyhow!("Webhook URL resolves to a private address."));
+    }
+
+    let mut request = client
+        .build()
+        .context("Failed to build the webhook client.")?
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "acidarchive-webhooks");
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .context("Failed to send the webhook.")?;
+    Ok(response.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::webhook_dispatcher::{retry_delay, MAX_ATTEMPTS};
+    use std::time::Duration;
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(8), Duration::from_secs(30 * 128));
+    }
+
+    #[test]
+    fn retry_delays_are_capped() {
+        assert_eq!(retry_delay(MAX_ATTEMPTS), Duration::from_secs(12 * 60 * 60));
+        assert_eq!(retry_delay(i32::MAX), Duration::from_secs(12 * 60 * 60));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::api::models::webhooks::{WebhookEventData, WebhookPattern, WebhookPayload, WebhookUser};
+use crate::domain::WebhookEvent;
+use crate::startup::HmacSecret;
+use anyhow::Context;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "X-Acid-Signature";
+pub const EVENT_HEADER: &str = "X-Acid-Event";
+pub const DELIVERY_HEADER: &str = "X-Acid-Delivery";
+
+/// The key a webhook's payloads are signed with. It is derived from the
+/// application secret rather than stored, so recreating a webhook is how its
+/// secret is rotated.
+pub fn webhook_secret(hmac_secret: &HmacSecret, webhook_id: Uuid) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_secret.0.expose_secret().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(b"webhook:");
+    mac.update(webhook_id.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// The `X-Acid-Signature` header: `t={timestamp},v1={signature}`, where the
+/// signature is the hex HMAC-SHA256 of `{timestamp}.{body}`. Signing the
+/// timestamp lets receivers reject replayed deliveries.
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={timestamp},v1={}",
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Queues `event` for the pattern owner's webhooks and, while the pattern is
+/// or was public, for global webhooks. Call it in the transaction that makes
+/// the change, and before a deletion.
+#[tracing::instrument(name = "Queueing pattern webhook event", skip(transaction))]
+pub async fn enqueue_pattern_event(
+    transaction: &mut Transaction<'_, Postgres>,
+    event: WebhookEvent,
+    pattern_id: Uuid,
+    was_public: bool,
+) -> Result<(), anyhow::Error> {
+    let pattern = sqlx::query_as!(
+        WebhookPattern,
+        r#"
+        SELECT p.pattern_id AS id, p.name, p.author, p.title,
+               COALESCE(p.is_public, false) AS "is_public!",
+               p.user_id, u.username, p.created_at, p.updated_at
+        FROM patterns_tb303 p
+        JOIN users u ON u.user_id = p.user_id
+        WHERE p.pattern_id = $1
+        "#,
+        pattern_id
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to fetch the pattern of a webhook event.")?;
+
+    let user_id = pattern.user_id;
+    let is_visible = was_public || pattern.is_public;
+    enqueue_event(
+        transaction,
+        event,
+        user_id,
+        is_visible,
+        WebhookEventData::Pattern { pattern },
+    )
+    .await
+}
+
+/// Queues `profile.updated` for the user's webhooks and global webhooks.
+#[tracing::instrument(name = "Queueing profile webhook event", skip(transaction))]
+pub async fn enqueue_profile_event(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let user = sqlx::query_as!(
+        WebhookUser,
+        r#"SELECT user_id, username, updated_at FROM users WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .context("Failed to fetch the user of a webhook event.")?;
+
+    enqueue_event(
+        transaction,
+        WebhookEvent::ProfileUpdated,
+        user_id,
+        true,
+        WebhookEventData::Profile { user },
+    )
+    .await
+}
+
+async fn enqueue_event(
+    transaction: &mut Transaction<'_, Postgres>,
+    event: WebhookEvent,
+    user_id: Uuid,
+    include_global: bool,
+    data: WebhookEventData,
+) -> Result<(), anyhow::Error> {
+    let event_id = Uuid::new_v4();
+    let payload = WebhookPayload {
+        id: event_id,
+        event,
+        created_at: Utc::now(),
+        data,
+    };
+    let payload =
+        serde_json::to_value(&payload).context("Failed to serialize a webhook payload.")?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (delivery_id, webhook_id, event_id, event, payload)
+        SELECT gen_random_uuid(), webhook_id, $1, $2, $3
+        FROM webhooks
+        WHERE $2 = ANY(events)
+          AND (user_id = $4 OR (is_global AND $5))
+        "#,
+        event_id,
+        event.as_ref(),
+        payload,
+        user_id,
+        include_global
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to queue webhook deliveries.")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::startup::HmacSecret;
+    use crate::webhooks::{signature_header, webhook_secret};
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    #[test]
+    fn webhook_secrets_differ_per_webhook_and_application() {
+        let secret = HmacSecret(Secret::new("application".to_string()));
+        let other = HmacSecret(Secret::new("other".to_string()));
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert_eq!(webhook_secret(&secret, a), webhook_secret(&secret, a));
+        assert_ne!(webhook_secret(&secret, a), webhook_secret(&secret, b));
+        assert_ne!(webhook_secret(&secret, a), webhook_secret(&other, a));
+        assert_eq!(webhook_secret(&secret, a).len(), 64);
+    }
+
+    #[test]
+    fn signatures_cover_the_timestamp_and_body() {
+        let header = signature_header("secret", 1700000000, b"{}");
+
+        assert!(header.starts_with("t=1700000000,v1="));
+        assert_ne!(header, signature_header("secret", 1700000001, b"{}"));
+        assert_ne!(header, signature_header("secret", 1700000000, b"[]"));
+        assert_ne!(header, signature_header("other", 1700000000, b"{}"));
+    }
+
+    #[test]
+    fn signatures_match_a_known_vector() {
+        // printf '1700000000.{}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            signature_header("secret", 1700000000, b"{}"),
+            "t=1700000000,v1=b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+        );
+    }
+}
@@ -0,0 +1,3 @@
+mod patterns;
+mod stats;
+mod users;
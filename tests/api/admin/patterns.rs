@@ -0,0 +1,238 @@
+use crate::helpers::{spawn_app, TestApp};
+use crate::test_data::get_valid_tb303_pattern_data;
+use acid::admin::{
+    delete_pattern, export_patterns, import_patterns, reassign_pattern, set_pattern_visibility,
+    AdminError,
+};
+use claims::assert_ok;
+use serde_json::json;
+use uuid::Uuid;
+
+fn archive(names: &[&str]) -> String {
+    let patterns: Vec<serde_json::Value> = names
+        .iter()
+        .map(|name| {
+            let mut pattern: serde_json::Value =
+                serde_json::from_str(&get_valid_tb303_pattern_data(None)).unwrap();
+            pattern["name"] = json!(name);
+            pattern
+        })
+        .collect();
+    json!({ "version": 1, "patterns": patterns }).to_string()
+}
+
+async fn fetch_pattern(app: &TestApp, pattern_id: Uuid) -> (Uuid, Option<bool>, i64) {
+    let row = sqlx::query!(
+        "SELECT user_id, is_public, version FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch pattern.");
+    (row.user_id, row.is_public, row.version)
+}
+
+async fn pending_events(app: &TestApp) -> Vec<String> {
+    sqlx::query_scalar!("SELECT event FROM webhook_deliveries ORDER BY created_at")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn imported_patterns_can_be_exported_again() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+    let user = user_id.to_string();
+
+    // Act
+    let response = import_patterns(
+        &app.db_pool,
+        app.storage.as_ref(),
+        &user,
+        &archive(&["First", "Second"]),
+        false,
+    )
+    .await;
+    let exported = export_patterns(&app.db_pool, app.storage.as_ref(), Some(&user)).await;
+
+    // Assert
+    assert_eq!(assert_ok!(response).created, 2);
+    let exported = assert_ok!(exported);
+    let names: Vec<&str> = exported.patterns.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["First", "Second"]);
+}
+
+#[tokio::test]
+async fn import_patterns_dry_run_saves_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let response = import_patterns(
+        &app.db_pool,
+        app.storage.as_ref(),
+        &user_id.to_string(),
+        &archive(&["First"]),
+        true,
+    )
+    .await;
+
+    // Assert
+    assert!(assert_ok!(response).dry_run);
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM patterns_tb303 WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn import_patterns_rejects_malformed_archives() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+    let user = user_id.to_string();
+
+    for archive in ["not json", r#"{ "version": 99, "patterns": [] }"#] {
+        // Act
+        let result =
+            import_patterns(&app.db_pool, app.storage.as_ref(), &user, archive, false).await;
+
+        // Assert
+        assert!(
+            matches!(result, Err(AdminError::ValidationError(_))),
+            "{archive} was accepted"
+        );
+    }
+}
+
+#[tokio::test]
+async fn export_patterns_only_includes_the_given_user() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let other_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 2, Some(false)).await;
+    app.create_test_patterns(&other_id, 1, Some(true)).await;
+
+    // Act
+    let exported = export_patterns(
+        &app.db_pool,
+        app.storage.as_ref(),
+        Some(&user_id.to_string()),
+    )
+    .await;
+
+    // Assert
+    let exported = assert_ok!(exported);
+    let ids: Vec<Uuid> = exported.patterns.iter().filter_map(|p| p.id).collect();
+    assert_eq!(ids, pattern_ids);
+}
+
+#[tokio::test]
+async fn reassign_pattern_moves_it_to_the_new_owner() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner_id = Uuid::new_v4();
+    let new_owner_id = Uuid::new_v4();
+    let pattern_id = app.create_test_patterns(&owner_id, 1, Some(false)).await[0];
+    app.create_test_collaborator(&pattern_id, &new_owner_id, "editor")
+        .await;
+    let (_, _, version) = fetch_pattern(&app, pattern_id).await;
+
+    // Act
+    let result = reassign_pattern(&app.db_pool, pattern_id, &new_owner_id.to_string()).await;
+
+    // Assert
+    assert_ok!(result);
+    let (user_id, _, new_version) = fetch_pattern(&app, pattern_id).await;
+    assert_eq!(user_id, new_owner_id);
+    assert_eq!(new_version, version + 1);
+    let collaborators = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM pattern_collaborators_tb303 WHERE pattern_id = $1"#,
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(collaborators, 0);
+}
+
+#[tokio::test]
+async fn set_pattern_visibility_publishes_once_and_notifies_webhooks() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner_id = Uuid::new_v4();
+    let pattern_id = app.create_test_patterns(&owner_id, 1, Some(false)).await[0];
+    app.create_test_webhook(
+        &owner_id,
+        "https://example.com/hook",
+        &["pattern.published"],
+        false,
+    )
+    .await;
+
+    // Act
+    let first = set_pattern_visibility(&app.db_pool, pattern_id, true).await;
+    let second = set_pattern_visibility(&app.db_pool, pattern_id, true).await;
+
+    // Assert
+    assert!(assert_ok!(first));
+    assert!(!assert_ok!(second));
+    assert_eq!(fetch_pattern(&app, pattern_id).await.1, Some(true));
+    assert_eq!(pending_events(&app).await, ["pattern.published"]);
+}
+
+#[tokio::test]
+async fn delete_pattern_removes_the_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner_id = Uuid::new_v4();
+    let pattern_id = app.create_test_patterns(&owner_id, 1, Some(true)).await[0];
+
+    // Act
+    let result = delete_pattern(&app.db_pool, pattern_id).await;
+
+    // Assert
+    assert_ok!(result);
+    let remaining = sqlx::query_scalar!(
+        "SELECT pattern_id FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .unwrap();
+    assert!(remaining.is_none());
+}
+
+#[tokio::test]
+async fn unknown_patterns_are_reported() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let results = [
+        reassign_pattern(&app.db_pool, pattern_id, &user_id.to_string()).await,
+        set_pattern_visibility(&app.db_pool, pattern_id, true)
+            .await
+            .map(|_| ()),
+        delete_pattern(&app.db_pool, pattern_id).await,
+    ];
+
+    // Assert
+    for result in results {
+        assert!(matches!(result, Err(AdminError::PatternNotFound(id)) if id == pattern_id));
+    }
+}
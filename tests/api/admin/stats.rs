@@ -0,0 +1,27 @@
+use crate::helpers::spawn_app;
+use acid::admin::archive_stats;
+use claims::assert_ok;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn archive_stats_counts_users_and_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let before = assert_ok!(archive_stats(&app.db_pool).await);
+    let user_id = Uuid::new_v4();
+    app.create_test_patterns(&user_id, 2, Some(true)).await;
+    app.create_test_patterns(&user_id, 1, Some(false)).await;
+
+    // Act
+    let after = assert_ok!(archive_stats(&app.db_pool).await);
+
+    // Assert
+    assert_eq!(after.users, before.users + 1);
+    assert_eq!(after.patterns, before.patterns + 3);
+    assert_eq!(after.public_patterns, before.public_patterns + 2);
+    assert_eq!(after.private_patterns, before.private_patterns + 1);
+    assert_eq!(
+        after.patterns_last_30_days,
+        before.patterns_last_30_days + 3
+    );
+}
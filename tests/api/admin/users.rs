@@ -0,0 +1,135 @@
+use crate::helpers::spawn_app;
+use acid::admin::{create_user, resolve_user, set_admin, set_username, AdminError};
+use claims::{assert_err, assert_ok};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn create_user_persists_the_user_with_the_given_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+
+    // Act
+    let created = create_user(&app.db_pool, Some(user_id), "acid_fan".to_string(), true).await;
+
+    // Assert
+    assert_eq!(assert_ok!(created), user_id);
+    let saved = sqlx::query!(
+        "SELECT username, is_admin FROM users WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch created user.");
+    assert_eq!(saved.username, "acid_fan");
+    assert!(saved.is_admin);
+}
+
+#[tokio::test]
+async fn create_user_rejects_invalid_usernames() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let result = create_user(&app.db_pool, None, "acid fan".to_string(), false).await;
+
+    // Assert
+    assert!(matches!(result, Err(AdminError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn create_user_rejects_existing_ids() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let result = create_user(&app.db_pool, Some(user_id), "acid_fan".to_string(), false).await;
+
+    // Assert
+    assert!(matches!(result, Err(AdminError::UserExists(id)) if id == user_id));
+}
+
+#[tokio::test]
+async fn usernames_are_unique_regardless_of_case() {
+    // Arrange
+    let app = spawn_app().await;
+    assert_ok!(create_user(&app.db_pool, None, "AcidFan".to_string(), false).await);
+
+    // Act
+    let result = create_user(&app.db_pool, None, "acidfan".to_string(), false).await;
+
+    // Assert
+    assert!(matches!(result, Err(AdminError::UsernameTaken(_))));
+}
+
+#[tokio::test]
+async fn set_username_renames_a_user_found_by_id_or_username() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+
+    // Act
+    assert_ok!(set_username(&app.db_pool, &user_id.to_string(), "first".to_string()).await);
+    assert_ok!(set_username(&app.db_pool, "first", "second".to_string()).await);
+
+    // Assert
+    assert_eq!(
+        assert_ok!(resolve_user(&app.db_pool, "second").await),
+        user_id
+    );
+    assert_err!(resolve_user(&app.db_pool, "first").await);
+}
+
+#[tokio::test]
+async fn set_username_keeps_the_current_name_when_it_only_changes_case() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = assert_ok!(create_user(&app.db_pool, None, "acidfan".to_string(), false).await);
+
+    // Act
+    let result = set_username(&app.db_pool, "acidfan", "AcidFan".to_string()).await;
+
+    // Assert
+    assert_ok!(result);
+    assert_eq!(
+        assert_ok!(resolve_user(&app.db_pool, "AcidFan").await),
+        user_id
+    );
+}
+
+#[tokio::test]
+async fn set_admin_grants_and_revokes_admin_rights() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+    let is_admin = || async {
+        sqlx::query_scalar!("SELECT is_admin FROM users WHERE user_id = $1", user_id)
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap()
+    };
+
+    // Act & Assert
+    assert_ok!(set_admin(&app.db_pool, &user_id.to_string(), true).await);
+    assert!(is_admin().await);
+    assert_ok!(set_admin(&app.db_pool, &user_id.to_string(), false).await);
+    assert!(!is_admin().await);
+}
+
+#[tokio::test]
+async fn unknown_users_are_reported() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let by_name = set_admin(&app.db_pool, "nobody", true).await;
+    let by_id = set_admin(&app.db_pool, &Uuid::new_v4().to_string(), true).await;
+
+    // Assert
+    assert!(matches!(by_name, Err(AdminError::UserNotFound(_))));
+    assert!(matches!(by_id, Err(AdminError::UserNotFound(_))));
+}
@@ -0,0 +1,135 @@
+use crate::helpers::{spawn_app, spawn_app_with, TestApp};
+use reqwest::{Method, Response};
+
+async fn spawn_app_for_our_origins() -> TestApp {
+    spawn_app_with(|c| {
+        c.cors.allowed_origins = vec![
+            "https://acidarchive.com".into(),
+            "https://*.acidarchive.com".into(),
+        ];
+        c.cors.allowed_methods = vec!["GET".into(), "POST".into()];
+        c.cors.allowed_headers = vec!["Authorization".into(), "Content-Type".into()];
+    })
+    .await
+}
+
+async fn preflight(app: &TestApp, origin: &str) -> Response {
+    app.api_client
+        .request(
+            Method::OPTIONS,
+            format!("{}/v1/patterns/tb303", &app.address),
+        )
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", "POST")
+        .header(
+            "Access-Control-Request-Headers",
+            "authorization,content-type",
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn allowed_origin(response: &Response) -> Option<&str> {
+    response
+        .headers()
+        .get("Access-Control-Allow-Origin")
+        .map(|value| value.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn any_origin_is_allowed_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = preflight(&app, "http://localhost:3000").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(allowed_origin(&response), Some("http://localhost:3000"));
+    assert_eq!(
+        response.headers()["Access-Control-Max-Age"]
+            .to_str()
+            .unwrap(),
+        "3600"
+    );
+}
+
+#[tokio::test]
+async fn configured_origins_and_their_subdomains_are_allowed() {
+    // Arrange
+    let app = spawn_app_for_our_origins().await;
+
+    for origin in ["https://acidarchive.com", "https://embed.acidarchive.com"] {
+        // Act
+        let response = preflight(&app, origin).await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16(), "{origin}");
+        assert_eq!(allowed_origin(&response), Some(origin));
+    }
+}
+
+#[tokio::test]
+async fn other_origins_are_refused() {
+    // Arrange
+    let app = spawn_app_for_our_origins().await;
+
+    for origin in ["https://evil.com", "https://acidarchive.com.evil.com"] {
+        // Act
+        let response = preflight(&app, origin).await;
+
+        // Assert
+        assert_eq!(400, response.status().as_u16(), "{origin}");
+        assert_eq!(allowed_origin(&response), None);
+    }
+}
+
+#[tokio::test]
+async fn unlisted_methods_are_refused() {
+    // Arrange
+    let app = spawn_app_for_our_origins().await;
+
+    // Act
+    let response = app
+        .api_client
+        .request(
+            Method::OPTIONS,
+            format!("{}/v1/patterns/tb303", &app.address),
+        )
+        .header("Origin", "https://acidarchive.com")
+        .header("Access-Control-Request-Method", "DELETE")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unauthorized_responses_follow_the_policy() {
+    // Arrange
+    let app = spawn_app_for_our_origins().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/v1/users/me", &app.address))
+        .header("Origin", "https://embed.acidarchive.com")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        allowed_origin(&response),
+        Some("https://embed.acidarchive.com")
+    );
+    assert!(response
+        .headers()
+        .get("Access-Control-Allow-Methods")
+        .is_none());
+}
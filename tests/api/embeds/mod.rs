@@ -0,0 +1,3 @@
+mod oembed;
+mod player;
+mod share_page;
@@ -0,0 +1,132 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn oembed_returns_rich_embed_for_public_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let owner_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&owner_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("{}/patterns/tb303/{}", app.base_url, pattern_id);
+
+    // Act
+    let response = app.get_oembed(&[("url", &url)]).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "rich");
+    assert_eq!(body["version"], "1.0");
+    assert_eq!(body["title"], "Pattern 1");
+    assert_eq!(body["author_name"], owner_id.to_string());
+    assert!(body["html"].as_str().unwrap().contains(&format!(
+        "src=\"{}/embed/patterns/tb303/{}\"",
+        app.base_url, pattern_id
+    )));
+    assert!(body["thumbnail_url"]
+        .as_str()
+        .unwrap()
+        .contains("/preview.svg"));
+}
+
+#[tokio::test]
+async fn oembed_accepts_player_urls() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("{}/embed/patterns/tb303/{}", app.base_url, pattern_id);
+
+    // Act
+    let response = app.get_oembed(&[("url", &url), ("format", "json")]).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oembed_respects_maxwidth_and_maxheight() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("{}/patterns/tb303/{}", app.base_url, pattern_id);
+
+    // Act
+    let response = app
+        .get_oembed(&[("url", &url), ("maxwidth", "320"), ("maxheight", "1000")])
+        .await;
+
+    // Assert
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["width"], 320);
+    assert_eq!(body["height"], 240);
+}
+
+#[tokio::test]
+async fn oembed_returns_404_for_private_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("{}/patterns/tb303/{}", app.base_url, pattern_id);
+
+    // Act
+    let response = app.get_oembed(&[("url", &url)]).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oembed_returns_404_for_urls_of_other_sites() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("https://example.com/patterns/tb303/{}", pattern_id);
+
+    // Act
+    let response = app.get_oembed(&[("url", &url)]).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oembed_returns_501_for_xml_format() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let url = format!("{}/patterns/tb303/{}", app.base_url, pattern_id);
+
+    // Act
+    let response = app.get_oembed(&[("url", &url), ("format", "xml")]).await;
+
+    // Assert
+    assert_eq!(501, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn oembed_returns_400_without_url() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_oembed(&[]).await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,46 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn player_shows_step_grid_and_can_be_framed() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_player(pattern_id).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let policy = response
+        .headers()
+        .get("Content-Security-Policy")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(policy.contains("frame-ancestors *"));
+    let html = response.text().await.unwrap();
+    assert!(html.contains("<svg"));
+    assert!(html.contains(&format!("{}/patterns/tb303/{}", app.base_url, pattern_id)));
+    assert!(!html.contains("<audio"));
+}
+
+#[tokio::test]
+async fn player_returns_404_for_private_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_player(pattern_id).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
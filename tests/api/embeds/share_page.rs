@@ -0,0 +1,77 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn share_page_carries_open_graph_and_twitter_tags() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_share_page(pattern_id).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    let html = response.text().await.unwrap();
+    assert!(html.contains(r#"<meta property="og:title" content="Pattern 1">"#));
+    assert!(html.contains(r#"<meta property="og:description" content="Pattern 1 by Author 1"#));
+    assert!(html.contains(&format!(
+        r#"<meta property="og:image" content="{}/v1/patterns/tb303/{}/preview.svg?v="#,
+        app.base_url, pattern_id
+    )));
+    assert!(html.contains(&format!(
+        r#"<meta name="twitter:player" content="{}/embed/patterns/tb303/{}">"#,
+        app.base_url, pattern_id
+    )));
+    assert!(html.contains(r#"type="application/json+oembed""#));
+    assert!(html.contains("<svg"));
+}
+
+#[tokio::test]
+async fn share_page_escapes_pattern_text() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    sqlx::query!(
+        r#"UPDATE patterns_tb303 SET name = $2 WHERE pattern_id = $1"#,
+        pattern_id,
+        r#""><script>alert(1)</script>"#
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_share_page(pattern_id).await;
+
+    // Assert
+    let html = response.text().await.unwrap();
+    assert!(!html.contains("<script>"));
+    assert!(html.contains("&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;"));
+}
+
+#[tokio::test]
+async fn share_page_returns_404_for_private_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_share_page(pattern_id).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
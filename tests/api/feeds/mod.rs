@@ -0,0 +1 @@
+mod patterns_feed;
@@ -0,0 +1,231 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn feeds_are_served_in_every_format() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+
+    for (extension, content_type, marker) in [
+        ("atom", "application/atom+xml; charset=utf-8", "<feed"),
+        ("rss", "application/rss+xml; charset=utf-8", "<rss"),
+        (
+            "json",
+            "application/feed+json; charset=utf-8",
+            "jsonfeed.org",
+        ),
+    ] {
+        // Act
+        let response = app.get_feed(&format!("patterns.{extension}"), &[]).await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16(), "{extension}");
+        assert_eq!(
+            response.headers().get("Content-Type").unwrap(),
+            content_type
+        );
+        assert!(response.headers().get("ETag").is_some());
+        assert!(response.headers().get("Last-Modified").is_some());
+        let body = response.text().await.unwrap();
+        assert!(body.contains(marker), "{extension}");
+        assert!(body.contains("Pattern 1"), "{extension}");
+    }
+}
+
+#[tokio::test]
+async fn feeds_only_list_public_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let public_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 2, Some(true))
+        .await;
+    let private_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+
+    // Act
+    let response = app.get_feed("patterns.json", &[]).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let feed: serde_json::Value = response.json().await.unwrap();
+    let ids: Vec<&str> = feed["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    for id in public_ids {
+        assert!(ids.contains(&id.to_string().as_str()));
+    }
+    assert!(!ids.contains(&private_ids[0].to_string().as_str()));
+}
+
+#[tokio::test]
+async fn feed_items_link_to_share_pages() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+
+    // Act
+    let response = app.get_feed("patterns.json", &[]).await;
+
+    // Assert
+    let feed: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        feed["feed_url"],
+        format!("{}/v1/feeds/patterns.json", app.base_url)
+    );
+    assert_eq!(
+        feed["items"][0]["url"],
+        format!("{}/patterns/tb303/{}", app.base_url, pattern_ids[0])
+    );
+    assert_eq!(feed["items"][0]["_acidarchive"]["author"], "Author 1");
+}
+
+#[tokio::test]
+async fn user_feeds_only_list_patterns_of_the_user() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    app.create_test_patterns(&Uuid::new_v4(), 2, Some(true))
+        .await;
+
+    // Act
+    let response = app
+        .get_feed(&format!("users/{user_id}/patterns.json"), &[])
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let feed: serde_json::Value = response.json().await.unwrap();
+    let items = feed["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], pattern_ids[0].to_string());
+    assert_eq!(items[0]["authors"][0]["name"], user_id.to_string());
+}
+
+#[tokio::test]
+async fn user_feeds_return_404_for_unknown_users() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_feed("users/nobody/patterns.atom", &[]).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn author_feeds_match_the_author_case_insensitively() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_test_patterns(&Uuid::new_v4(), 3, Some(true))
+        .await;
+
+    // Act
+    let response = app.get_feed("authors/author%202/patterns.json", &[]).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let feed: serde_json::Value = response.json().await.unwrap();
+    let items = feed["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["_acidarchive"]["author"], "Author 2");
+}
+
+#[tokio::test]
+async fn feeds_return_404_for_unknown_formats() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_feed("patterns.xml", &[]).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn feeds_return_304_for_a_matching_etag() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let response = app.get_feed("patterns.atom", &[]).await;
+    let etag = response
+        .headers()
+        .get("ETag")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Act
+    let response = app
+        .get_feed("patterns.atom", &[("If-None-Match", &etag)])
+        .await;
+
+    // Assert
+    assert_eq!(304, response.status().as_u16());
+    assert_eq!(response.headers().get("ETag").unwrap(), etag.as_str());
+    assert!(response.text().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn feeds_return_304_when_not_modified_since() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let response = app.get_feed("patterns.rss", &[]).await;
+    let last_modified = response
+        .headers()
+        .get("Last-Modified")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Act
+    let response = app
+        .get_feed("patterns.rss", &[("If-Modified-Since", &last_modified)])
+        .await;
+
+    // Assert
+    assert_eq!(304, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn feeds_change_their_etag_when_a_pattern_is_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let response = app.get_feed("patterns.atom", &[]).await;
+    let etag = response
+        .headers()
+        .get("ETag")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    app.create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+
+    // Act
+    let response = app
+        .get_feed("patterns.atom", &[("If-None-Match", &etag)])
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_ne!(response.headers().get("ETag").unwrap(), etag.as_str());
+}
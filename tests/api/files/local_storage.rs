@@ -0,0 +1,149 @@
+use crate::helpers::{spawn_app, spawn_app_with_local_storage, TestApp};
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn presign(app: &TestApp, key: &str, content_type: &str, content_length: u64) -> String {
+    app.storage
+        .presign_put(key, content_type, content_length, Duration::from_secs(60))
+        .await
+        .expect("Failed to presign upload.")
+}
+
+fn test_key() -> String {
+    format!("avatars/{}/{}", Uuid::new_v4(), Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn signed_upload_is_stored_and_served() {
+    // Arrange
+    let app = spawn_app_with_local_storage().await;
+    let key = test_key();
+    let upload_url = presign(&app, &key, "image/png", 3).await;
+
+    // Act
+    let upload = app
+        .api_client
+        .put(&upload_url)
+        .header("Content-Type", "image/png")
+        .body(vec![1u8, 2, 3])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = app
+        .api_client
+        .get(app.storage.get_public_url(&key))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(upload.status().as_u16(), 200);
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(response.bytes().await.unwrap().as_ref(), &[1u8, 2, 3]);
+}
+
+#[tokio::test]
+async fn upload_with_tampered_signature_is_rejected() {
+    // Arrange
+    let app = spawn_app_with_local_storage().await;
+    let key = test_key();
+    let upload_url = presign(&app, &key, "image/png", 3).await;
+    let tampered = upload_url.replace("content_length=3", "content_length=4");
+
+    // Act
+    let response = app
+        .api_client
+        .put(&tampered)
+        .header("Content-Type", "image/png")
+        .body(vec![1u8, 2, 3, 4])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(app.storage.head_object(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn upload_with_another_content_type_is_rejected() {
+    // Arrange
+    let app = spawn_app_with_local_storage().await;
+    let key = test_key();
+    let upload_url = presign(&app, &key, "image/png", 3).await;
+
+    // Act
+    let response = app
+        .api_client
+        .put(&upload_url)
+        .header("Content-Type", "text/html")
+        .body(vec![1u8, 2, 3])
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn upload_of_another_size_is_rejected() {
+    // Arrange
+    let app = spawn_app_with_local_storage().await;
+    let key = test_key();
+    let upload_url = presign(&app, &key, "image/png", 3).await;
+
+    for body in [vec![1u8, 2], vec![1u8, 2, 3, 4]] {
+        // Act
+        let response = app
+            .api_client
+            .put(&upload_url)
+            .header("Content-Type", "image/png")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400);
+    }
+    assert!(app.storage.head_object(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn missing_files_return_404() {
+    // Arrange
+    let app = spawn_app_with_local_storage().await;
+
+    for path in ["avatars/missing", "avatars/..%2F..%2Fetc%2Fpasswd"] {
+        // Act
+        let response = app
+            .api_client
+            .get(format!("{}/v1/files/{}", app.address, path))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 404);
+    }
+}
+
+#[tokio::test]
+async fn files_are_not_served_with_the_s3_backend() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/v1/files/avatars/some-file", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -0,0 +1,178 @@
+use crate::helpers::{spawn_app, TestApp};
+use acid::fixtures::{load_fixture_file, load_fixtures, FixtureError, FixtureReport, Fixtures};
+use claims::assert_ok;
+use uuid::Uuid;
+
+const ARCHIVE: &str = "fixtures/archive.yaml";
+const USER_ID: &str = "6f0ad2a6-0b8e-4c1e-a1e4-8b1b7ad3a303";
+const PATTERN_ID: &str = "0d6d5d51-6a2e-4c5e-9f43-36a1c3a3b303";
+
+fn fixtures(name: &str, note: &str) -> Fixtures {
+    fixtures_with_step(name, &format!(r#"number: 1, note: "{note}", time: note"#))
+}
+
+fn fixtures_with_step(name: &str, step: &str) -> Fixtures {
+    let yaml = format!(
+        r#"
+users:
+  - id: {USER_ID}
+    username: fixture_user
+patterns:
+  - id: {PATTERN_ID}
+    user_id: {USER_ID}
+    name: "{name}"
+    is_public: true
+    bars:
+      - number: 1
+        steps:
+          - {{ {step} }}
+"#
+    );
+    Fixtures::parse(&yaml).expect("Failed to parse fixtures")
+}
+
+async fn load(app: &TestApp, fixtures: Fixtures) -> Result<FixtureReport, FixtureError> {
+    load_fixtures(&app.db_pool, app.storage.as_ref(), fixtures).await
+}
+
+async fn stored_notes(app: &TestApp, pattern_id: Uuid) -> Vec<Option<String>> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT s.note FROM steps_tb303 s
+        JOIN bars_tb303 b ON b.bar_id = s.bar_id
+        WHERE b.pattern_id = $1
+        ORDER BY b.number, s.number
+        "#,
+        pattern_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn the_archive_fixtures_load_once() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let first = load_fixture_file(&app.db_pool, app.storage.as_ref(), ARCHIVE).await;
+    let second = load_fixture_file(&app.db_pool, app.storage.as_ref(), ARCHIVE).await;
+
+    // Assert
+    let first = assert_ok!(first);
+    assert!(first.patterns_created > 0);
+    assert_eq!(
+        assert_ok!(second),
+        FixtureReport {
+            users_created: 0,
+            patterns_created: 0,
+            patterns_updated: 0,
+            patterns_unchanged: first.patterns_created,
+        }
+    );
+    let public =
+        sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM patterns_tb303 WHERE is_public"#)
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(public as usize, first.patterns_created);
+}
+
+#[tokio::test]
+async fn changed_fixtures_update_the_pattern_in_place() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_id = Uuid::parse_str(PATTERN_ID).unwrap();
+    assert_ok!(load(&app, fixtures("Original", "C")).await);
+
+    // Act
+    let report = load(&app, fixtures("Corrected", "D")).await;
+
+    // Assert
+    assert_eq!(assert_ok!(report).patterns_updated, 1);
+    let name = sqlx::query_scalar!(
+        "SELECT name FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(name, "Corrected");
+    assert_eq!(
+        stored_notes(&app, pattern_id).await,
+        [Some("D".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn patterns_edited_in_the_database_are_restored() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_id = Uuid::parse_str(PATTERN_ID).unwrap();
+    assert_ok!(load(&app, fixtures("Original", "C")).await);
+    sqlx::query!(
+        "UPDATE patterns_tb303 SET is_public = false WHERE pattern_id = $1",
+        pattern_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let report = load(&app, fixtures("Original", "C")).await;
+
+    // Assert
+    assert_eq!(assert_ok!(report).patterns_updated, 1);
+    let is_public = sqlx::query_scalar!(
+        "SELECT is_public FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(is_public, Some(true));
+}
+
+#[tokio::test]
+async fn invalid_fixtures_write_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let result = load(
+        &app,
+        fixtures_with_step("Bad step", "number: 17, time: rest"),
+    )
+    .await;
+
+    // Assert
+    assert!(matches!(result, Err(FixtureError::ValidationError(_))));
+    let users = sqlx::query_scalar!(
+        "SELECT user_id FROM users WHERE user_id = $1",
+        Uuid::parse_str(USER_ID).unwrap()
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .unwrap();
+    assert!(users.is_none());
+}
+
+#[tokio::test]
+async fn existing_users_keep_their_username() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::parse_str(USER_ID).unwrap();
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let report = load(&app, fixtures("Original", "C")).await;
+
+    // Assert
+    assert_eq!(assert_ok!(report).users_created, 0);
+    let username = sqlx::query_scalar!("SELECT username FROM users WHERE user_id = $1", user_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(username, user_id.to_string());
+}
@@ -1,4 +1,6 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, spawn_app_with, TestApp};
+use crate::jwks_mock::MockJwks;
+use serde_json::json;
 
 #[tokio::test]
 async fn health_check_works() {
@@ -14,3 +16,109 @@ async fn health_check_works() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+async fn get_ready(app: &TestApp) -> (u16, serde_json::Value) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/ready", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("Invalid JSON"))
+}
+
+#[tokio::test]
+async fn health_live_returns_ok() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body, json!({ "status": "ok" }));
+}
+
+#[tokio::test]
+async fn health_ready_returns_200_when_every_dependency_is_available() {
+    // Arrange
+    let jwks_url = MockJwks::start();
+    let app = spawn_app_with(|c| c.cognito.jwks_url = Some(jwks_url)).await;
+
+    // Act
+    let (status, body) = get_ready(&app).await;
+
+    // Assert
+    assert_eq!(200, status, "{body}");
+    assert_eq!(body["status"], "ok");
+    for dependency in ["database", "storage", "jwks"] {
+        assert_eq!(body["checks"][dependency]["status"], "ok");
+        assert!(body["checks"][dependency]["latency_ms"].is_u64());
+        assert!(body["checks"][dependency].get("error").is_none());
+    }
+}
+
+#[tokio::test]
+async fn health_ready_returns_503_when_the_jwks_has_no_keys() {
+    // Arrange
+    let jwks_url = MockJwks::start_with(json!({ "keys": [] }));
+    let app = spawn_app_with(|c| c.cognito.jwks_url = Some(jwks_url)).await;
+
+    // Act
+    let (status, body) = get_ready(&app).await;
+
+    // Assert
+    assert_eq!(503, status);
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["checks"]["jwks"]["status"], "unavailable");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+}
+
+#[tokio::test]
+async fn health_ready_returns_503_when_migrations_are_pending() {
+    // Arrange
+    let jwks_url = MockJwks::start();
+    let app = spawn_app_with(|c| c.cognito.jwks_url = Some(jwks_url)).await;
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let (status, body) = get_ready(&app).await;
+
+    // Assert
+    assert_eq!(503, status);
+    assert_eq!(body["checks"]["database"]["status"], "unavailable");
+    // The reason is logged, not sent.
+    assert!(body["checks"]["database"].get("error").is_none());
+}
+
+#[tokio::test]
+async fn health_ready_times_out_unreachable_storage() {
+    // Arrange
+    let jwks_url = MockJwks::start();
+    let app = spawn_app_with(|c| {
+        c.cognito.jwks_url = Some(jwks_url);
+        c.s3.endpoint_url = Some("http://10.255.255.1:9000".to_string());
+        c.health.timeout_secs = 1;
+    })
+    .await;
+
+    // Act
+    let (status, body) = get_ready(&app).await;
+
+    // Assert
+    assert_eq!(503, status);
+    assert_eq!(body["checks"]["storage"]["status"], "unavailable");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    assert!(body["checks"]["storage"]["latency_ms"].as_u64().unwrap() < 2000);
+}
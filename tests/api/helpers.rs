@@ -1,12 +1,22 @@
-use acid::configuration::{get_configuration, CognitoSettings, DatabaseSettings, S3Settings};
-use acid::startup::{get_connection_pool, Application};
+use crate::s3_mock::MockS3;
+use acid::configuration::{
+    get_configuration, CognitoSettings, DatabaseSettings, MetricsSettings, S3Settings, Settings,
+    StorageBackend, UploadSettings, WebhookSettings,
+};
+use acid::jobs::{run_next_job, JobContext, JobOutcome};
+use acid::startup::{get_connection_pool, Application, HmacSecret};
+use acid::storage::ObjectStorage;
 use acid::telemetry::{get_subscriber, init_subscriber};
+use acid::webhook_dispatcher::{dispatch_next_delivery, DispatchOutcome};
+use actix_web::dev::ServerHandle;
 use dotenvy::dotenv;
+use futures_util::{SinkExt, StreamExt};
 use once_cell::sync::Lazy;
 use reqwest::Client;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::sync::Arc;
 use uuid::Uuid;
 
 // Ensure that the `tracing` stack is only initialised once using `once_cell`
@@ -15,10 +25,11 @@ static TRACING: Lazy<()> = Lazy::new(|| {
     let subscriber_name = "test".to_string();
 
     if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        let subscriber =
+            get_subscriber(subscriber_name, default_filter_level, std::io::stdout, None);
         init_subscriber(subscriber);
     } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink, None);
         init_subscriber(subscriber);
     };
 });
@@ -89,10 +100,22 @@ pub async fn get_user_token(
 
 pub struct TestApp {
     pub address: String,
+    /// Where the app says it is reached, for links it hands out.
+    pub base_url: String,
     pub db_pool: PgPool,
     pub api_client: Client,
     pub cognito: CognitoSettings,
     pub s3: S3Settings,
+    pub s3_mock: MockS3,
+    pub storage: Arc<dyn ObjectStorage>,
+    pub hmac_secret: HmacSecret,
+    pub webhooks: WebhookSettings,
+    pub uploads: UploadSettings,
+    pub metrics: MetricsSettings,
+    /// Stops the app like SIGTERM does.
+    pub server: ServerHandle,
+    /// What the app was started with, for running its background tasks.
+    pub configuration: Settings,
 }
 
 impl TestApp {
@@ -234,6 +257,387 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn put_pattern_tb303_audio(
+        &self,
+        pattern_id: &Uuid,
+        body: String,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!("{}/v1/patterns/tb303/{}/audio", &self.address, pattern_id);
+
+        let request = self
+            .api_client
+            .put(&url)
+            .header("Content-Type", "application/json");
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_pattern_tb303_audio(
+        &self,
+        pattern_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!("{}/v1/patterns/tb303/{}/audio", &self.address, pattern_id);
+
+        let request = self.api_client.delete(&url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn put_pattern_tb303_cover(
+        &self,
+        pattern_id: &Uuid,
+        body: String,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!("{}/v1/patterns/tb303/{}/cover", &self.address, pattern_id);
+
+        let request = self
+            .api_client
+            .put(&url)
+            .header("Content-Type", "application/json");
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_pattern_tb303_cover(
+        &self,
+        pattern_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!("{}/v1/patterns/tb303/{}/cover", &self.address, pattern_id);
+
+        let request = self.api_client.delete(&url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_pattern_tb303_preview(
+        &self,
+        pattern_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!(
+            "{}/v1/patterns/tb303/{}/preview.svg",
+            &self.address, pattern_id
+        );
+
+        let request = self.api_client.get(&url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_oembed(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/oembed", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_share_page(&self, pattern_id: &Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/patterns/tb303/{}", &self.address, pattern_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_player(&self, pattern_id: &Uuid) -> reqwest::Response {
+        self.api_client
+            .get(format!(
+                "{}/embed/patterns/tb303/{}",
+                &self.address, pattern_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Fetches a feed by its path below `/v1/feeds`, e.g. `patterns.atom`.
+    pub async fn get_feed(&self, path: &str, headers: &[(&str, &str)]) -> reqwest::Response {
+        let mut request = self
+            .api_client
+            .get(format!("{}/v1/feeds/{}", &self.address, path));
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_webhook(&self, body: String, token: Option<String>) -> reqwest::Response {
+        let request = self
+            .api_client
+            .post(format!("{}/v1/webhooks", &self.address))
+            .header("Content-Type", "application/json");
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_webhooks(&self, token: Option<String>) -> reqwest::Response {
+        let request = self
+            .api_client
+            .get(format!("{}/v1/webhooks", &self.address));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn delete_webhook(
+        &self,
+        webhook_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let request = self
+            .api_client
+            .delete(format!("{}/v1/webhooks/{}", &self.address, webhook_id));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        webhook_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let request = self.api_client.get(format!(
+            "{}/v1/webhooks/{}/deliveries",
+            &self.address, webhook_id
+        ));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn redeliver_webhook_delivery(
+        &self,
+        webhook_id: &Uuid,
+        delivery_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let request = self.api_client.post(format!(
+            "{}/v1/webhooks/{}/deliveries/{}/redeliver",
+            &self.address, webhook_id, delivery_id
+        ));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn list_shared_patterns_tb303(&self, token: Option<String>) -> reqwest::Response {
+        let request = self
+            .api_client
+            .get(format!("{}/v1/patterns/tb303/shared", &self.address));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn list_collaborators_tb303(
+        &self,
+        pattern_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!(
+            "{}/v1/patterns/tb303/{}/collaborators",
+            &self.address, pattern_id
+        );
+
+        let request = self.api_client.get(&url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_collaborator_tb303(
+        &self,
+        pattern_id: &Uuid,
+        body: String,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!(
+            "{}/v1/patterns/tb303/{}/collaborators",
+            &self.address, pattern_id
+        );
+
+        let request = self
+            .api_client
+            .post(&url)
+            .header("Content-Type", "application/json");
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_collaborator_tb303(
+        &self,
+        pattern_id: &Uuid,
+        user_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!(
+            "{}/v1/patterns/tb303/{}/collaborators/{}",
+            &self.address, pattern_id, user_id
+        );
+
+        let request = self.api_client.delete(&url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_pattern_tb303_live(
+        &self,
+        pattern_id: &Uuid,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let url = format!("{}/v1/patterns/tb303/{}/live", &self.address, pattern_id);
+
+        let request = self.api_client.get(&url).query(&[("token", token)]);
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    /// Opens a live session on the pattern, authenticated with `token` in the
+    /// query string as browsers do.
+    pub async fn connect_pattern_tb303_live(&self, pattern_id: &Uuid, token: &str) -> LiveClient {
+        let url = format!(
+            "{}/v1/patterns/tb303/{}/live?token={}",
+            self.address.replacen("http", "ws", 1),
+            pattern_id,
+            token
+        );
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .expect("Failed to open live session.");
+        LiveClient { socket }
+    }
+
+    pub async fn post_import_tb303(
+        &self,
+        body: String,
+        dry_run: Option<bool>,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let mut url = format!("{}/v1/patterns/tb303/import", &self.address);
+        if let Some(dry_run) = dry_run {
+            url.push_str(&format!("?dry_run={dry_run}"));
+        }
+
+        let request = self
+            .api_client
+            .post(url)
+            .header("Content-Type", "application/json");
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_presign(&self, body: String, token: Option<String>) -> reqwest::Response {
         let request = self
             .api_client
@@ -267,6 +671,55 @@ impl TestApp {
         request.send().await.expect("Failed to execute request.")
     }
 
+    pub async fn get_user_me_export(&self, token: Option<String>) -> reqwest::Response {
+        let request = self
+            .api_client
+            .get(format!("{}/v1/users/me/export", &self.address));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn delete_user_me(
+        &self,
+        patterns: Option<&str>,
+        token: Option<String>,
+    ) -> reqwest::Response {
+        let mut url = format!("{}/v1/users/me", &self.address);
+        if let Some(patterns) = patterns {
+            url.push_str(&format!("?patterns={patterns}"));
+        }
+
+        let request = self.api_client.delete(url);
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_user_me_data(&self, token: Option<String>) -> reqwest::Response {
+        let request = self
+            .api_client
+            .get(format!("{}/v1/users/me/data", &self.address));
+
+        let request = if let Some(token) = token {
+            request.header("Authorization", format!("Bearer {token}"))
+        } else {
+            request
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
     pub async fn patch_user_me(&self, body: String, token: Option<String>) -> reqwest::Response {
         let request = self
             .api_client
@@ -286,11 +739,19 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
-    pub async fn get_presign_key(&self, token: &str, upload_type: &str) -> String {
+    /// Presigns an upload and PUTs a file of `content_length` bytes to the
+    /// returned URL, like a browser would. Returns the object key.
+    pub async fn upload_test_file(
+        &self,
+        token: &str,
+        upload_type: &str,
+        content_type: &str,
+        file: Vec<u8>,
+    ) -> String {
         let body = serde_json::json!({
             "upload_type": upload_type,
-            "content_type": "image/png",
-            "content_length": 1024
+            "content_type": content_type,
+            "content_length": file.len()
         });
 
         let response = self
@@ -298,9 +759,27 @@ impl TestApp {
             .await;
         let body: serde_json::Value = response.json().await.unwrap();
 
+        self.api_client
+            .put(body["upload_url"].as_str().unwrap())
+            .header("Content-Type", content_type)
+            .body(file)
+            .send()
+            .await
+            .expect("Failed to upload test file.");
+
         body["key"].as_str().unwrap().to_string()
     }
 
+    pub async fn upload_test_image(&self, token: &str, upload_type: &str) -> String {
+        self.upload_test_file(token, upload_type, "image/png", test_png(300, 200))
+            .await
+    }
+
+    pub async fn upload_test_audio(&self, token: &str) -> String {
+        self.upload_test_file(token, "pattern_audio", "audio/wav", test_wav(8000, 8000))
+            .await
+    }
+
     pub async fn get_test_user_token(&self) -> String {
         get_user_token(
             dotenvy::var("TEST_USER_USERNAME").unwrap().as_str(),
@@ -331,6 +810,73 @@ impl TestApp {
             .expect("Failed to create test user");
     }
 
+    pub async fn create_test_webhook(
+        &self,
+        user_id: &Uuid,
+        url: &str,
+        events: &[&str],
+        is_global: bool,
+    ) -> Uuid {
+        self.create_test_user(user_id).await;
+
+        let webhook_id = Uuid::new_v4();
+        let events: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+        sqlx::query(
+            "INSERT INTO webhooks (webhook_id, user_id, url, events, is_global) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(webhook_id)
+        .bind(user_id)
+        .bind(url)
+        .bind(&events)
+        .bind(is_global)
+        .execute(&self.db_pool)
+        .await
+        .expect("Failed to create test webhook");
+
+        webhook_id
+    }
+
+    /// Sends every webhook delivery that is due, like the dispatcher would.
+    pub async fn dispatch_pending_webhooks(&self) {
+        loop {
+            if let DispatchOutcome::EmptyQueue =
+                dispatch_next_delivery(&self.db_pool, &self.webhooks, &self.hmac_secret)
+                    .await
+                    .expect("Failed to dispatch webhook delivery")
+            {
+                break;
+            }
+        }
+    }
+
+    pub fn job_context(&self) -> JobContext {
+        JobContext {
+            pool: self.db_pool.clone(),
+            storage: self.storage.clone(),
+            uploads: self.uploads.clone(),
+        }
+    }
+
+    /// Runs every job that is due, like the workers would.
+    pub async fn run_pending_jobs(&self) {
+        let context = self.job_context();
+        while run_next_job(&context).await.expect("Failed to run job") != JobOutcome::EmptyQueue {}
+    }
+
+    pub async fn create_test_collaborator(&self, pattern_id: &Uuid, user_id: &Uuid, role: &str) {
+        self.create_test_user(user_id).await;
+
+        sqlx::query(
+            "INSERT INTO pattern_collaborators_tb303 (pattern_id, user_id, role) VALUES ($1, $2, $3)",
+        )
+        .bind(pattern_id)
+        .bind(user_id)
+        .bind(role)
+        .execute(&self.db_pool)
+        .await
+        .expect("Failed to create test collaborator");
+    }
+
     pub async fn create_test_patterns(
         &self,
         user_id: &Uuid,
@@ -369,18 +915,80 @@ impl TestApp {
     }
 }
 
+pub struct LiveClient {
+    socket: tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+}
+
+impl LiveClient {
+    pub async fn send(&mut self, message: serde_json::Value) {
+        self.socket
+            .send(tokio_tungstenite::tungstenite::Message::text(
+                message.to_string(),
+            ))
+            .await
+            .expect("Failed to send live message.");
+    }
+
+    /// Skips other messages, such as presence updates, until one of `kind`
+    /// arrives.
+    pub async fn receive(&mut self, kind: &str) -> serde_json::Value {
+        loop {
+            let message =
+                tokio::time::timeout(std::time::Duration::from_secs(5), self.socket.next())
+                    .await
+                    .expect("Timed out waiting for a live message.")
+                    .expect("The live session was closed.")
+                    .expect("Failed to read live message.");
+            let Ok(text) = message.to_text() else {
+                continue;
+            };
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(text) else {
+                continue;
+            };
+            if message["type"] == kind {
+                return message;
+            }
+        }
+    }
+}
+
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Keeps uploads in a temporary directory served by the app instead of S3.
+pub async fn spawn_app_with_local_storage() -> TestApp {
+    spawn_app_with(|c| {
+        c.storage.backend = StorageBackend::Local;
+        c.storage.local.root = std::env::temp_dir()
+            .join(format!("acid-test-{}", Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+    })
+    .await
+}
+
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
     Lazy::force(&TRACING);
 
     dotenv().ok();
 
+    let (s3_mock, s3_address) = MockS3::start();
+
     // randomize configuration to ensure test isolation
-    let configuration = {
+    let mut configuration = {
         let mut c = get_configuration().expect("Failed to read configuration.");
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
-        // Use a fake endpoint for tests - presigned URLs will point here
-        c.s3.endpoint_url = Some("http://localhost:4566".to_string());
+        // Presigned URLs and S3 calls point at the in-process mock
+        c.s3.endpoint_url = Some(s3_address);
+        // Webhook receivers run on loopback
+        c.webhooks.allow_private_networks = true;
+        // Every test starts from an empty archive
+        c.fixtures.load_on_startup = false;
+        configure(&mut c);
         c
     };
 
@@ -390,7 +998,9 @@ pub async fn spawn_app() -> TestApp {
         .await
         .expect("Failed to build application.");
     let application_port = application.port();
-    let _ = tokio::spawn(application.run_until_stopped());
+    let server = application.handle();
+    tokio::spawn(application.run_until_stopped());
+    configuration.application.port = application_port;
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -399,11 +1009,20 @@ pub async fn spawn_app() -> TestApp {
         .unwrap();
 
     let test_app = TestApp {
+        configuration: configuration.clone(),
         address: format!("http://localhost:{application_port}"),
+        base_url: configuration.application.base_url(),
         db_pool: get_connection_pool(&configuration.database),
         api_client: client,
+        storage: configuration.storage().await,
+        hmac_secret: HmacSecret(configuration.application.hmac_secret),
+        webhooks: configuration.webhooks,
+        uploads: configuration.uploads,
+        metrics: configuration.metrics,
         cognito: configuration.cognito,
         s3: configuration.s3,
+        s3_mock,
+        server,
     };
 
     test_app
@@ -454,3 +1073,34 @@ async fn configure_database(config: &DatabaseSettings) -> PgPool {
 
     connection_pool
 }
+
+pub fn test_png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgb8(image::RgbImage::new(width, height))
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .expect("Failed to encode test image.");
+    bytes
+}
+
+/// A mono 16-bit PCM WAV file of silence.
+pub fn test_wav(sample_rate: u32, samples: u32) -> Vec<u8> {
+    let data_len = samples * 2;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    bytes.resize(bytes.len() + data_len as usize, 0);
+    bytes
+}
@@ -0,0 +1,386 @@
+use crate::helpers::{spawn_app, TestApp};
+use crate::test_data::get_valid_tb303_pattern_data;
+use acid::authentication::UserId;
+use acid::idempotency::{idempotent, prune_idempotency_keys, IdempotentBodyLimit};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::middleware::from_fn;
+use actix_web::web::{self, Data};
+use actix_web::{test, App, HttpMessage, HttpResponse};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Mounts the middleware in front of handlers that count their calls, with
+/// requests made as `user_id` as if they had been authenticated.
+struct IdempotentService {
+    app: TestApp,
+    calls: Data<AtomicUsize>,
+    gate: Data<Gate>,
+}
+
+/// Holds `/wait` requests until the test lets them go.
+#[derive(Default)]
+struct Gate {
+    entered: Notify,
+    release: Notify,
+}
+
+async fn count(calls: Data<AtomicUsize>, body: web::Bytes) -> HttpResponse {
+    let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+    HttpResponse::Created().json(json!({ "call": call, "body": body.len() }))
+}
+
+async fn fail_first(calls: Data<AtomicUsize>) -> HttpResponse {
+    match calls.fetch_add(1, Ordering::SeqCst) {
+        0 => HttpResponse::InternalServerError().finish(),
+        _ => HttpResponse::Created().finish(),
+    }
+}
+
+async fn wait(calls: Data<AtomicUsize>, gate: Data<Gate>) -> HttpResponse {
+    calls.fetch_add(1, Ordering::SeqCst);
+    gate.entered.notify_one();
+    gate.release.notified().await;
+    HttpResponse::Created().finish()
+}
+
+impl IdempotentService {
+    async fn new() -> Self {
+        Self {
+            app: spawn_app().await,
+            calls: Data::new(AtomicUsize::new(0)),
+            gate: Data::new(Gate::default()),
+        }
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    async fn call(&self, user_id: Uuid, request: test::TestRequest) -> ServiceResponse {
+        let service = test::init_service(
+            App::new()
+                .wrap(from_fn(idempotent))
+                .wrap_fn(move |req, srv| {
+                    req.extensions_mut().insert(UserId::from(user_id));
+                    srv.call(req)
+                })
+                .app_data(Data::new(self.app.db_pool.clone()))
+                .app_data(IdempotentBodyLimit(BODY_LIMIT))
+                .app_data(self.calls.clone())
+                .app_data(self.gate.clone())
+                .route("/count", web::post().to(count))
+                .route("/other", web::post().to(count))
+                .route("/fail_first", web::post().to(fail_first))
+                .route("/wait", web::post().to(wait)),
+        )
+        .await;
+        test::call_service(&service, request.to_request()).await
+    }
+}
+
+const BODY_LIMIT: usize = 1024;
+
+fn post(path: &str, key: Option<&str>, body: &str) -> test::TestRequest {
+    let request = test::TestRequest::post()
+        .uri(path)
+        .set_payload(body.to_string());
+    match key {
+        Some(key) => request.insert_header(("Idempotency-Key", key)),
+        None => request,
+    }
+}
+
+#[tokio::test]
+async fn retries_get_the_first_response_again() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+
+    // Act
+    let first = service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+    let retry = service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert_eq!(201, first.status().as_u16());
+    assert_eq!(201, retry.status().as_u16());
+    assert!(first.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(retry.headers().get("Idempotent-Replayed").unwrap(), "true");
+    assert_eq!(
+        retry.headers().get("Content-Type").unwrap(),
+        "application/json"
+    );
+    assert_eq!(test::read_body(first).await, test::read_body(retry).await);
+    assert_eq!(service.calls(), 1);
+}
+
+#[tokio::test]
+async fn requests_without_a_key_are_always_handled() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+
+    // Act
+    service.call(user_id, post("/count", None, "{}")).await;
+    service.call(user_id, post("/count", None, "{}")).await;
+
+    // Assert
+    assert_eq!(service.calls(), 2);
+}
+
+#[tokio::test]
+async fn reusing_a_key_for_a_different_request_returns_422() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+    service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Act
+    let other_body = service
+        .call(user_id, post("/count", Some("k1"), r#"{"name":"x"}"#))
+        .await;
+    let other_route = service
+        .call(user_id, post("/other", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert_eq!(422, other_body.status().as_u16());
+    assert_eq!(422, other_route.status().as_u16());
+    let body: serde_json::Value = test::read_body_json(other_body).await;
+    assert_eq!(body["code"], "idempotency_key_reused");
+    assert_eq!(service.calls(), 1);
+}
+
+#[tokio::test]
+async fn a_concurrent_duplicate_returns_409() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+
+    // Act
+    let (first, duplicate) = tokio::join!(
+        service.call(user_id, post("/wait", Some("k1"), "{}")),
+        async {
+            service.gate.entered.notified().await;
+            let duplicate = service.call(user_id, post("/wait", Some("k1"), "{}")).await;
+            service.gate.release.notify_one();
+            duplicate
+        }
+    );
+
+    // Assert
+    assert_eq!(201, first.status().as_u16());
+    assert_eq!(409, duplicate.status().as_u16());
+    assert_eq!(service.calls(), 1);
+}
+
+#[tokio::test]
+async fn keys_abandoned_mid_request_can_be_claimed_again() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+    // The client goes away while the handler runs, so the request is dropped
+    // with the key claimed and no response saved.
+    tokio::select! {
+        _ = service.call(user_id, post("/wait", Some("k1"), "{}")) => unreachable!(),
+        _ = service.gate.entered.notified() => {}
+    }
+    let retry = service.call(user_id, post("/wait", Some("k1"), "{}")).await;
+    assert_eq!(409, retry.status().as_u16());
+    sqlx::query("UPDATE idempotency SET created_at = NOW() - INTERVAL '2 minutes'")
+        .execute(&service.app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let retry = service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert_eq!(201, retry.status().as_u16());
+    assert!(retry.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(service.calls(), 2);
+}
+
+#[tokio::test]
+async fn server_errors_are_not_replayed() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+
+    // Act
+    let first = service
+        .call(user_id, post("/fail_first", Some("k1"), ""))
+        .await;
+    let retry = service
+        .call(user_id, post("/fail_first", Some("k1"), ""))
+        .await;
+
+    // Assert
+    assert_eq!(500, first.status().as_u16());
+    assert_eq!(201, retry.status().as_u16());
+    assert_eq!(service.calls(), 2);
+}
+
+#[tokio::test]
+async fn keys_belong_to_one_user() {
+    // Arrange
+    let service = IdempotentService::new().await;
+
+    // Act
+    service
+        .call(Uuid::new_v4(), post("/count", Some("k1"), "{}"))
+        .await;
+    let response = service
+        .call(Uuid::new_v4(), post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert!(response.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(service.calls(), 2);
+}
+
+#[tokio::test]
+async fn invalid_keys_are_rejected() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let too_long = "k".repeat(256);
+
+    // Act
+    let response = service
+        .call(Uuid::new_v4(), post("/count", Some(&too_long), "{}"))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(service.calls(), 0);
+}
+
+#[tokio::test]
+async fn keys_can_be_used_again_once_a_day_has_passed() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+    service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+    sqlx::query("UPDATE idempotency SET created_at = NOW() - INTERVAL '25 hours'")
+        .execute(&service.app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert!(response.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(service.calls(), 2);
+}
+
+#[tokio::test]
+async fn pruning_deletes_only_expired_keys() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+    service
+        .call(user_id, post("/count", Some("old"), "{}"))
+        .await;
+    service
+        .call(user_id, post("/count", Some("new"), "{}"))
+        .await;
+    sqlx::query(
+        "UPDATE idempotency SET created_at = NOW() - INTERVAL '25 hours' WHERE idempotency_key = 'old'",
+    )
+    .execute(&service.app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let pruned = prune_idempotency_keys(&service.app.db_pool).await.unwrap();
+
+    // Assert
+    assert_eq!(pruned, 1);
+    let keys: Vec<String> = sqlx::query_scalar("SELECT idempotency_key FROM idempotency")
+        .fetch_all(&service.app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(keys, ["new"]);
+}
+
+#[tokio::test]
+async fn retried_pattern_creation_creates_one_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let body = get_valid_tb303_pattern_data(None);
+    let create = || {
+        app.api_client
+            .post(format!("{}/v1/patterns/tb303", &app.address))
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Idempotency-Key", "create-1")
+            .body(body.clone())
+            .send()
+    };
+
+    // Act
+    let first = create().await.expect("Failed to execute request.");
+    let retry = create().await.expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+    let first: serde_json::Value = first.json().await.unwrap();
+    let retry: serde_json::Value = retry.json().await.unwrap();
+    assert_eq!(first["data"]["id"], retry["data"]["id"]);
+    let patterns: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM patterns_tb303")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(patterns, 1);
+}
+
+#[tokio::test]
+async fn the_query_string_is_part_of_the_request() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let user_id = Uuid::new_v4();
+    service
+        .call(user_id, post("/count?dry_run=true", Some("k1"), "{}"))
+        .await;
+
+    // Act
+    let response = service
+        .call(user_id, post("/count", Some("k1"), "{}"))
+        .await;
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+    assert_eq!(service.calls(), 1);
+}
+
+#[tokio::test]
+async fn bodies_over_the_limit_are_refused_unread() {
+    // Arrange
+    let service = IdempotentService::new().await;
+    let body = "x".repeat(BODY_LIMIT + 1);
+
+    // Act
+    let response = service
+        .call(Uuid::new_v4(), post("/count", Some("k1"), &body))
+        .await;
+
+    // Assert
+    assert_eq!(413, response.status().as_u16());
+    assert_eq!(service.calls(), 0);
+}
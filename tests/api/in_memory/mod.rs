@@ -0,0 +1,116 @@
+mod patterns;
+mod users;
+
+use acid::authentication::UserId;
+use acid::configuration::{CognitoSettings, PatternCacheSettings};
+use acid::pattern_cache::PatternCache;
+use acid::repository::{InMemoryRepository, PatternRepository, UserRepository};
+use acid::routes::get_me;
+use acid::routes::patterns::{
+    create_tb303_pattern, delete_tb303_pattern, get_random_tb303_pattern, get_tb303_pattern,
+    list_public_tb303_patterns,
+};
+use acid::startup::ApplicationBaseUrl;
+use acid::storage::{LocalStorage, ObjectStorage};
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::web::{self, Data};
+use actix_web::{test, App, HttpMessage};
+use secrecy::Secret;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Runs the real handlers against [`InMemoryRepository`], so no database or
+/// Cognito is needed. Requests are made as `user_id` when one is given, as if
+/// the authentication middleware had let them through.
+pub struct InMemoryApp {
+    pub repository: Arc<InMemoryRepository>,
+    pub storage: Arc<dyn ObjectStorage>,
+    pub cache: Data<PatternCache>,
+}
+
+impl InMemoryApp {
+    pub fn new() -> Self {
+        let storage = LocalStorage::new(
+            std::env::temp_dir().join("acid-in-memory"),
+            "http://localhost".to_string(),
+            None,
+            Secret::new("in-memory-signing-key".to_string()),
+        );
+        Self {
+            repository: Arc::new(InMemoryRepository::default()),
+            storage: Arc::new(storage),
+            cache: Data::new(PatternCache::new(&PatternCacheSettings {
+                capacity: 100,
+                ttl_secs: 60,
+            })),
+        }
+    }
+
+    pub async fn call(&self, user_id: Option<Uuid>, request: test::TestRequest) -> ServiceResponse {
+        let patterns: Data<dyn PatternRepository> =
+            Data::from(self.repository.clone() as Arc<dyn PatternRepository>);
+        let users: Data<dyn UserRepository> =
+            Data::from(self.repository.clone() as Arc<dyn UserRepository>);
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(move |req, srv| {
+                    if let Some(user_id) = user_id {
+                        req.extensions_mut().insert(UserId::from(user_id));
+                    }
+                    srv.call(req)
+                })
+                .app_data(patterns)
+                .app_data(users)
+                .app_data(Data::from(self.storage.clone()))
+                .app_data(self.cache.clone())
+                .app_data(Data::new(ApplicationBaseUrl(
+                    "http://localhost".to_string(),
+                )))
+                .app_data(Data::new(CognitoSettings {
+                    region: "us-east-1".to_string(),
+                    user_pool_id: "in-memory".to_string(),
+                    user_pool_client_id: "in-memory".to_string(),
+                    jwks_url: None,
+                }))
+                .route(
+                    "/v1/patterns/tb303/random",
+                    web::get().to(get_random_tb303_pattern),
+                )
+                .route(
+                    "/v1/patterns/tb303/public",
+                    web::get().to(list_public_tb303_patterns),
+                )
+                .route(
+                    "/v1/patterns/tb303/{pattern_id}",
+                    web::get().to(get_tb303_pattern),
+                )
+                .route(
+                    "/v1/patterns/tb303/{pattern_id}",
+                    web::delete().to(delete_tb303_pattern),
+                )
+                .route("/v1/patterns/tb303", web::post().to(create_tb303_pattern))
+                .route("/v1/users/me", web::get().to(get_me)),
+        )
+        .await;
+        test::call_service(&app, request.to_request()).await
+    }
+
+    /// Creates a pattern through the API as `user_id` and returns its ID.
+    pub async fn create_pattern(&self, user_id: Uuid, is_public: bool) -> Uuid {
+        let body: serde_json::Value = serde_json::from_str(
+            &crate::test_data::get_valid_tb303_pattern_data(Some(is_public)),
+        )
+        .unwrap();
+        let response = self
+            .call(
+                Some(user_id),
+                test::TestRequest::post()
+                    .uri("/v1/patterns/tb303")
+                    .set_json(body),
+            )
+            .await;
+        assert!(response.status().is_success());
+        let body: serde_json::Value = test::read_body_json(response).await;
+        body["data"]["id"].as_str().unwrap().parse().unwrap()
+    }
+}
@@ -0,0 +1,376 @@
+use crate::in_memory::InMemoryApp;
+use acid::domain::CollaboratorRole;
+use acid::routes::patterns::{fetch_pattern, fetch_pattern_cached, GetPatternError};
+use actix_web::test::{read_body_json, TestRequest};
+use claims::assert_ok;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn created_patterns_can_be_fetched_by_anyone_when_public() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let pattern_id = app.create_pattern(Uuid::new_v4(), true).await;
+
+    // Act
+    let response = app
+        .call(
+            None,
+            TestRequest::get().uri(&format!("/v1/patterns/tb303/{pattern_id}")),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["id"], pattern_id.to_string());
+    assert_eq!(body["name"], "Pattern 1");
+    assert_eq!(body["bars"][0]["steps"][0]["note"], "D");
+}
+
+#[tokio::test]
+async fn invalid_patterns_are_rejected_before_anything_is_saved() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let body = serde_json::json!({ "name": "No bars", "bars": [] });
+
+    // Act
+    let response = app
+        .call(
+            Some(Uuid::new_v4()),
+            TestRequest::post().uri("/v1/patterns/tb303").set_json(body),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(app.repository.pattern_count(), 0);
+}
+
+#[tokio::test]
+async fn invalid_patterns_are_described_field_by_field() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let body = serde_json::json!({
+        "name": " ",
+        "tempo": 1000,
+        "bars": [
+            { "number": 1, "steps": [{ "number": 1, "time": "note", "note": "C" }] },
+            {
+                "number": 2,
+                "steps": [
+                    { "number": 1, "time": "rest", "note": "C" },
+                    { "number": 17, "time": "note" },
+                    { "number": 4, "time": "note" }
+                ]
+            }
+        ]
+    });
+
+    // Act
+    let response = app
+        .call(
+            Some(Uuid::new_v4()),
+            TestRequest::post().uri("/v1/patterns/tb303").set_json(body),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "application/problem+json"
+    );
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["code"], "validation_failed");
+    let errors: Vec<(&str, &str)> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["pointer"].as_str().unwrap(), e["code"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            ("/name", "invalid"),
+            ("/tempo", "out_of_range"),
+            ("/bars/1/steps", "not_sequential"),
+            ("/bars/1/steps/0/note", "not_allowed"),
+            ("/bars/1/steps/1/number", "out_of_range"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn missing_patterns_are_reported_as_problems() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let pattern_id = Uuid::new_v4();
+
+    // Act
+    let response = app
+        .call(
+            None,
+            TestRequest::get().uri(&format!("/v1/patterns/tb303/{pattern_id}")),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "code": "not_found",
+            "detail": format!("Pattern with ID {pattern_id} not found")
+        })
+    );
+}
+
+#[tokio::test]
+async fn private_patterns_are_only_visible_to_the_owner_and_collaborators() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let owner_id = Uuid::new_v4();
+    let viewer_id = Uuid::new_v4();
+    let pattern_id = app.create_pattern(owner_id, false).await;
+    app.repository
+        .add_collaborator(pattern_id, viewer_id, CollaboratorRole::Viewer);
+    let fetch = |user_id: Option<Uuid>| {
+        fetch_pattern(
+            app.repository.as_ref(),
+            app.storage.as_ref(),
+            pattern_id,
+            user_id.map(Into::into),
+        )
+    };
+
+    // Act & Assert
+    assert_ok!(fetch(Some(owner_id)).await);
+    assert_ok!(fetch(Some(viewer_id)).await);
+    assert!(matches!(
+        fetch(Some(Uuid::new_v4())).await,
+        Err(GetPatternError::AccessDenied)
+    ));
+    assert!(matches!(
+        fetch(None).await,
+        Err(GetPatternError::PatternNotFound(id)) if id == pattern_id
+    ));
+}
+
+#[tokio::test]
+async fn the_random_pattern_is_never_private() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let user_id = Uuid::new_v4();
+    let public_id = app.create_pattern(user_id, true).await;
+    app.create_pattern(user_id, false).await;
+
+    for _ in 0..10 {
+        // Act
+        let response = app
+            .call(None, TestRequest::get().uri("/v1/patterns/tb303/random"))
+            .await;
+
+        // Assert
+        assert_eq!(200, response.status().as_u16());
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["id"], public_id.to_string());
+    }
+}
+
+#[tokio::test]
+async fn the_random_pattern_returns_404_without_public_patterns() {
+    // Arrange
+    let app = InMemoryApp::new();
+    app.create_pattern(Uuid::new_v4(), false).await;
+
+    // Act
+    let response = app
+        .call(None, TestRequest::get().uri("/v1/patterns/tb303/random"))
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn public_patterns_are_listed_newest_first_with_their_owner() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let user_id = Uuid::new_v4();
+    app.repository.add_user(user_id, "acid_fan");
+    let first = app.create_pattern(user_id, true).await;
+    app.create_pattern(user_id, false).await;
+    let second = app.create_pattern(user_id, true).await;
+
+    // Act
+    let response = app
+        .call(
+            None,
+            TestRequest::get().uri("/v1/patterns/tb303/public?limit=1"),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["data"][0]["pattern_id"], second.to_string());
+    assert_eq!(body["data"][0]["username"], "acid_fan");
+    assert_ne!(body["data"][0]["pattern_id"], first.to_string());
+}
+
+#[tokio::test]
+async fn public_patterns_reject_invalid_pagination() {
+    // Arrange
+    let app = InMemoryApp::new();
+
+    for query in ["limit=0", "limit=101", "offset=-1", "order=sideways"] {
+        // Act
+        let response = app
+            .call(
+                None,
+                TestRequest::get().uri(&format!("/v1/patterns/tb303/public?{query}")),
+            )
+            .await;
+
+        // Assert
+        assert_eq!(400, response.status().as_u16(), "{query} was accepted");
+    }
+}
+
+#[tokio::test]
+async fn only_the_owner_can_delete_a_pattern() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let owner_id = Uuid::new_v4();
+    let editor_id = Uuid::new_v4();
+    let pattern_id = app.create_pattern(owner_id, false).await;
+    app.repository
+        .add_collaborator(pattern_id, editor_id, CollaboratorRole::Editor);
+    let uri = format!("/v1/patterns/tb303/{pattern_id}");
+
+    // Act
+    let by_editor = app
+        .call(Some(editor_id), TestRequest::delete().uri(&uri))
+        .await;
+    let by_owner = app
+        .call(Some(owner_id), TestRequest::delete().uri(&uri))
+        .await;
+    let again = app
+        .call(Some(owner_id), TestRequest::delete().uri(&uri))
+        .await;
+
+    // Assert
+    assert_eq!(403, by_editor.status().as_u16());
+    assert_eq!(204, by_owner.status().as_u16());
+    assert_eq!(404, again.status().as_u16());
+    assert_eq!(app.repository.pattern_count(), 0);
+}
+
+#[tokio::test]
+async fn deleted_patterns_are_not_served_from_the_cache() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let owner_id = Uuid::new_v4();
+    let pattern_id = app.create_pattern(owner_id, true).await;
+    let uri = format!("/v1/patterns/tb303/{pattern_id}");
+    app.call(None, TestRequest::get().uri(&uri)).await;
+    assert!(app.cache.get(pattern_id).is_some());
+
+    // Act
+    app.call(Some(owner_id), TestRequest::delete().uri(&uri))
+        .await;
+    let response = app.call(None, TestRequest::get().uri(&uri)).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn unchanged_patterns_are_answered_with_304() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let pattern_id = app.create_pattern(Uuid::new_v4(), true).await;
+    let uri = format!("/v1/patterns/tb303/{pattern_id}");
+    let first = app.call(None, TestRequest::get().uri(&uri)).await;
+    let etag = first.headers().get("ETag").unwrap().clone();
+
+    // Act
+    let second = app
+        .call(
+            None,
+            TestRequest::get()
+                .uri(&uri)
+                .insert_header(("If-None-Match", etag.clone())),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(
+        first.headers().get("Cache-Control").unwrap(),
+        "public, max-age=60"
+    );
+    assert_eq!(first.headers().get("Vary").unwrap(), "Authorization");
+    assert!(first.headers().get("Last-Modified").is_some());
+    assert_eq!(304, second.status().as_u16());
+    assert_eq!(second.headers().get("ETag").unwrap(), etag);
+    assert_eq!(second.headers().get("Vary").unwrap(), "Authorization");
+}
+
+#[tokio::test]
+async fn private_patterns_are_never_cached() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let owner_id = Uuid::new_v4();
+    let pattern_id = app.create_pattern(owner_id, false).await;
+
+    // Act
+    let pattern = fetch_pattern_cached(
+        &app.cache,
+        app.repository.as_ref(),
+        app.storage.as_ref(),
+        pattern_id,
+        Some(owner_id.into()),
+    )
+    .await;
+
+    // Assert
+    assert_ok!(pattern);
+    assert!(app.cache.get(pattern_id).is_none());
+}
+
+#[tokio::test]
+async fn public_pattern_pages_change_their_etag_with_the_listing() {
+    // Arrange
+    let app = InMemoryApp::new();
+    app.create_pattern(Uuid::new_v4(), true).await;
+    let uri = "/v1/patterns/tb303/public";
+    let first = app.call(None, TestRequest::get().uri(uri)).await;
+    let etag = first.headers().get("ETag").unwrap().clone();
+    app.create_pattern(Uuid::new_v4(), true).await;
+
+    // Act
+    let second = app
+        .call(
+            None,
+            TestRequest::get()
+                .uri(uri)
+                .insert_header(("If-None-Match", etag.clone())),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(
+        first.headers().get("Cache-Control").unwrap(),
+        "public, max-age=60"
+    );
+    assert!(first.headers().get("Last-Modified").is_none());
+    assert_eq!(200, second.status().as_u16());
+    assert_ne!(second.headers().get("ETag").unwrap(), etag);
+}
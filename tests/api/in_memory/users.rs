@@ -0,0 +1,23 @@
+use crate::in_memory::InMemoryApp;
+use actix_web::test::{read_body_json, TestRequest};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn get_me_returns_the_signed_in_user() {
+    // Arrange
+    let app = InMemoryApp::new();
+    let user_id = Uuid::new_v4();
+    app.repository.add_user(user_id, "acid_fan");
+
+    // Act
+    let response = app
+        .call(Some(user_id), TestRequest::get().uri("/v1/users/me"))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = read_body_json(response).await;
+    assert_eq!(body["user_id"], user_id.to_string());
+    assert_eq!(body["username"], "acid_fan");
+    assert!(body["avatar_url"].is_null());
+}
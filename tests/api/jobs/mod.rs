@@ -0,0 +1,2 @@
+mod queue;
+mod schedules;
@@ -0,0 +1,243 @@
+use crate::helpers::{spawn_app, TestApp};
+use acid::jobs::{enqueue_job, run_next_job, schedule_job, Job, JobOutcome};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn create_released_upload(app: &TestApp) -> String {
+    let user_id = Uuid::new_v4();
+    let key = format!("avatars/{}/{}", user_id, Uuid::new_v4());
+
+    sqlx::query!(
+        r#"
+        INSERT INTO uploads (key, user_id, upload_type, content_type, content_length)
+        VALUES ($1, $2, 'avatar', 'image/png', 3)
+        "#,
+        key,
+        user_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to create test upload");
+    app.s3_mock.insert(&key, vec![1, 2, 3], "image/png");
+    app.s3_mock
+        .insert(&format!("{key}-64.webp"), vec![1, 2, 3], "image/webp");
+
+    key
+}
+
+async fn enqueue(app: &TestApp, job: Job) -> Uuid {
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    let job_id = enqueue_job(&mut transaction, &job).await.unwrap();
+    transaction.commit().await.unwrap();
+    job_id
+}
+
+async fn insert_raw_job(app: &TestApp, payload: serde_json::Value, max_attempts: i32) -> Uuid {
+    let job_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO jobs (job_id, kind, payload, max_attempts)
+        VALUES ($1, 'unknown', $2, $3)
+        "#,
+        job_id,
+        payload,
+        max_attempts
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    job_id
+}
+
+struct JobRow {
+    status: String,
+    attempts: i32,
+    run_at: chrono::DateTime<Utc>,
+    last_error: Option<String>,
+    died_at: Option<chrono::DateTime<Utc>>,
+}
+
+async fn fetch_job(app: &TestApp, job_id: Uuid) -> Option<JobRow> {
+    sqlx::query_as!(
+        JobRow,
+        r#"SELECT status, attempts, run_at, last_error, died_at FROM jobs WHERE job_id = $1"#,
+        job_id
+    )
+    .fetch_optional(&app.db_pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn delete_upload_jobs_delete_the_upload_and_its_variants() {
+    // Arrange
+    let app = spawn_app().await;
+    let key = create_released_upload(&app).await;
+    let job_id = enqueue(&app, Job::DeleteUpload { key: key.clone() }).await;
+
+    // Act
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert!(!app.s3_mock.contains(&key));
+    assert!(!app.s3_mock.contains(&format!("{key}-64.webp")));
+    let uploads = sqlx::query_scalar!("SELECT COUNT(*) FROM uploads WHERE key = $1", key)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(uploads, Some(0));
+    assert!(
+        fetch_job(&app, job_id).await.is_none(),
+        "Finished jobs are removed"
+    );
+}
+
+#[tokio::test]
+async fn delete_upload_jobs_keep_uploads_that_were_claimed_again() {
+    // Arrange
+    let app = spawn_app().await;
+    let key = create_released_upload(&app).await;
+    enqueue(&app, Job::DeleteUpload { key: key.clone() }).await;
+    sqlx::query!("UPDATE uploads SET claimed_at = NOW() WHERE key = $1", key)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert!(app.s3_mock.contains(&key));
+}
+
+#[tokio::test]
+async fn jobs_enqueued_in_a_rolled_back_transaction_never_run() {
+    // Arrange
+    let app = spawn_app().await;
+    let key = create_released_upload(&app).await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    enqueue_job(&mut transaction, &Job::DeleteUpload { key: key.clone() })
+        .await
+        .unwrap();
+
+    // Act
+    transaction.rollback().await.unwrap();
+    let outcome = run_next_job(&app.job_context()).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome, JobOutcome::EmptyQueue);
+    assert!(app.s3_mock.contains(&key));
+}
+
+#[tokio::test]
+async fn scheduled_jobs_wait_until_they_are_due() {
+    // Arrange
+    let app = spawn_app().await;
+    let key = create_released_upload(&app).await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    let job_id = schedule_job(
+        &mut transaction,
+        &Job::DeleteUpload { key: key.clone() },
+        Utc::now() + Duration::hours(1),
+    )
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    // Act - Part 1
+    app.run_pending_jobs().await;
+
+    // Assert - Part 1
+    assert!(app.s3_mock.contains(&key));
+    assert!(fetch_job(&app, job_id).await.is_some());
+
+    // Act - Part 2
+    sqlx::query!("UPDATE jobs SET run_at = NOW() WHERE job_id = $1", job_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.run_pending_jobs().await;
+
+    // Assert - Part 2
+    assert!(!app.s3_mock.contains(&key));
+}
+
+#[tokio::test]
+async fn failed_jobs_are_retried_later() {
+    // Arrange
+    let app = spawn_app().await;
+    let job_id = insert_raw_job(&app, json!({ "kind": "send_newsletter" }), 5).await;
+
+    // Act
+    let outcome = run_next_job(&app.job_context()).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome, JobOutcome::Failed);
+    let job = fetch_job(&app, job_id).await.unwrap();
+    assert_eq!(job.status, "pending");
+    assert_eq!(job.attempts, 1);
+    assert!(job.run_at > Utc::now());
+    assert!(job
+        .last_error
+        .is_some_and(|e| e.contains("Failed to decode job")));
+    assert_eq!(
+        run_next_job(&app.job_context()).await.unwrap(),
+        JobOutcome::EmptyQueue,
+        "Retried before it was due"
+    );
+}
+
+#[tokio::test]
+async fn jobs_are_dead_lettered_after_the_last_attempt() {
+    // Arrange
+    let app = spawn_app().await;
+    let job_id = insert_raw_job(&app, json!({ "kind": "send_newsletter" }), 2).await;
+
+    // Act
+    for _ in 0..2 {
+        sqlx::query!("UPDATE jobs SET run_at = NOW() WHERE job_id = $1", job_id)
+            .execute(&app.db_pool)
+            .await
+            .unwrap();
+        run_next_job(&app.job_context()).await.unwrap();
+    }
+
+    // Assert
+    let job = fetch_job(&app, job_id).await.unwrap();
+    assert_eq!(job.status, "dead");
+    assert_eq!(job.attempts, 2);
+    assert!(job.died_at.is_some());
+    assert_eq!(
+        run_next_job(&app.job_context()).await.unwrap(),
+        JobOutcome::EmptyQueue,
+        "Dead jobs are not run again"
+    );
+}
+
+#[tokio::test]
+async fn locked_jobs_are_skipped_by_other_workers() {
+    // Arrange
+    let app = spawn_app().await;
+    let key = create_released_upload(&app).await;
+    let job_id = enqueue(&app, Job::DeleteUpload { key: key.clone() }).await;
+    let mut other_worker = app.db_pool.begin().await.unwrap();
+    sqlx::query!(
+        "SELECT job_id FROM jobs WHERE job_id = $1 FOR UPDATE",
+        job_id
+    )
+    .fetch_one(&mut *other_worker)
+    .await
+    .unwrap();
+
+    // Act
+    let outcome = run_next_job(&app.job_context()).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome, JobOutcome::EmptyQueue);
+    other_worker.rollback().await.unwrap();
+    assert_eq!(
+        run_next_job(&app.job_context()).await.unwrap(),
+        JobOutcome::Succeeded
+    );
+}
@@ -0,0 +1,97 @@
+use crate::helpers::spawn_app;
+use acid::jobs::{enqueue_due_schedules, Job, Schedule};
+use chrono::{Duration, Utc};
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+fn sweep_every_hour() -> Schedule {
+    Schedule {
+        name: "sweep_orphaned_uploads",
+        every: StdDuration::from_secs(60 * 60),
+        job: Job::SweepOrphanedUploads,
+    }
+}
+
+#[tokio::test]
+async fn schedules_are_enqueued_once_per_interval() {
+    // Arrange
+    let app = spawn_app().await;
+    let schedules = vec![sweep_every_hour()];
+
+    // Act
+    let first = enqueue_due_schedules(&app.db_pool, &schedules)
+        .await
+        .unwrap();
+    let second = enqueue_due_schedules(&app.db_pool, &schedules)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(first, 1);
+    assert_eq!(second, 0);
+    let jobs = sqlx::query_scalar!("SELECT kind FROM jobs")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(jobs, vec!["sweep_orphaned_uploads"]);
+    let next_run_at = sqlx::query_scalar!(
+        "SELECT next_run_at FROM job_schedules WHERE name = 'sweep_orphaned_uploads'"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert!(next_run_at > Utc::now());
+    assert!(next_run_at <= Utc::now() + Duration::hours(1));
+}
+
+#[tokio::test]
+async fn due_schedules_are_enqueued_again() {
+    // Arrange
+    let app = spawn_app().await;
+    let schedules = vec![sweep_every_hour()];
+    enqueue_due_schedules(&app.db_pool, &schedules)
+        .await
+        .unwrap();
+
+    // Act
+    sqlx::query!("UPDATE job_schedules SET next_run_at = NOW()")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let enqueued = enqueue_due_schedules(&app.db_pool, &schedules)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(enqueued, 1);
+}
+
+#[tokio::test]
+async fn the_scheduled_sweep_deletes_orphaned_uploads() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let key = format!("avatars/{}/{}", user_id, Uuid::new_v4());
+    sqlx::query!(
+        r#"
+        INSERT INTO uploads (key, user_id, upload_type, content_type, content_length, created_at)
+        VALUES ($1, $2, 'avatar', 'image/png', 3, $3)
+        "#,
+        key,
+        user_id,
+        Utc::now() - Duration::seconds(app.uploads.orphan_ttl_secs as i64 + 60)
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+    app.s3_mock.insert(&key, vec![1, 2, 3], "image/png");
+    enqueue_due_schedules(&app.db_pool, &[sweep_every_hour()])
+        .await
+        .unwrap();
+
+    // Act
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert!(!app.s3_mock.contains(&key));
+}
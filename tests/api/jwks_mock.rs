@@ -0,0 +1,37 @@
+use actix_web::{web, App, HttpResponse, HttpServer};
+use serde_json::json;
+use std::net::TcpListener;
+
+/// In-process stand-in for the JWKS document of a Cognito user pool.
+pub struct MockJwks;
+
+impl MockJwks {
+    /// Serves a set with one key on a random port and returns its URL.
+    pub fn start() -> String {
+        Self::start_with(json!({
+            "keys": [{ "kid": "test-key", "kty": "RSA", "alg": "RS256", "n": "sXch", "e": "AQAB" }]
+        }))
+    }
+
+    pub fn start_with(jwk_set: serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock JWKS.");
+        let port = listener.local_addr().unwrap().port();
+        let server = HttpServer::new(move || {
+            let jwk_set = jwk_set.clone();
+            App::new().route(
+                "/.well-known/jwks.json",
+                web::get().to(move || {
+                    let jwk_set = jwk_set.clone();
+                    async move { HttpResponse::Ok().json(jwk_set) }
+                }),
+            )
+        })
+        .workers(1)
+        .listen(listener)
+        .expect("Failed to listen on mock JWKS port.")
+        .run();
+        tokio::spawn(server);
+
+        format!("http://127.0.0.1:{port}/.well-known/jwks.json")
+    }
+}
@@ -1,6 +1,24 @@
+mod admin;
+mod cors;
+mod embeds;
+mod feeds;
+mod files;
+mod fixtures;
 mod health_check;
 mod helpers;
+mod idempotency;
+mod in_memory;
+mod jobs;
+mod jwks_mock;
+mod metrics;
 mod patterns;
+mod problems;
+mod rate_limits;
+mod s3_mock;
+mod shutdown;
+mod telemetry;
 mod test_data;
 mod uploads;
 mod users;
+mod webhook_receiver;
+mod webhooks;
@@ -0,0 +1,157 @@
+use crate::helpers::{spawn_app, spawn_app_with, TestApp};
+use crate::jwks_mock::MockJwks;
+use acid::fixtures::load_fixture_file;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn scrape(app: &TestApp, token: Option<&str>) -> reqwest::Response {
+    let request = reqwest::Client::new().get(format!("{}/metrics", &app.address));
+    let request = match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn get_metrics(app: &TestApp) -> String {
+    let response = scrape(app, Some(app.metrics.token.expose_secret())).await;
+    assert_eq!(200, response.status().as_u16());
+    response.text().await.unwrap()
+}
+
+/// Reads the sample whose name and labels are exactly `series`.
+fn sample(metrics: &str, series: &str) -> Option<f64> {
+    metrics.lines().find_map(|line| {
+        let value = line.strip_prefix(series)?.strip_prefix(' ')?;
+        Some(value.parse().unwrap())
+    })
+}
+
+#[tokio::test]
+async fn metrics_are_served_in_the_prometheus_text_format() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = scrape(&app, Some(app.metrics.token.expose_secret())).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers()["Content-Type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain; version=0.0.4"));
+    let body = response.text().await.unwrap();
+    assert!(body.contains("# TYPE acid_db_pool_connections gauge"));
+    assert!(sample(&body, r#"acid_db_pool_connections{state="idle"}"#).is_some());
+}
+
+#[tokio::test]
+async fn requests_record_how_long_they_wait_for_a_connection() {
+    // Arrange
+    let app = spawn_app().await;
+    let waits = |body: &str| sample(body, "acid_db_pool_acquire_duration_seconds_count");
+    let before = waits(&get_metrics(&app).await).unwrap_or(0.0);
+
+    // Act
+    app.api_client
+        .get(format!("{}/v1/patterns/tb303/public", &app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    let after = waits(&get_metrics(&app).await).unwrap();
+    assert!(after > before);
+}
+
+#[tokio::test]
+async fn metrics_are_only_served_to_scrapers_with_the_token() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for token in [None, Some("not-the-token")] {
+        // Act
+        let response = scrape(&app, token).await;
+
+        // Assert
+        assert_eq!(401, response.status().as_u16(), "token: {token:?}");
+        assert_eq!(
+            response.headers()["Content-Type"],
+            "application/problem+json"
+        );
+    }
+}
+
+#[tokio::test]
+async fn requests_are_counted_by_route_template() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_id = Uuid::new_v4();
+
+    // Act
+    let response = app.get_pattern_tb303(&pattern_id, None).await;
+    let metrics = get_metrics(&app).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let series = r#"acid_http_requests_total{method="GET",route="/v1/patterns/tb303/{pattern_id}",status="404"}"#;
+    assert!(sample(&metrics, series).unwrap() >= 1.0, "{metrics}");
+    assert!(!metrics.contains(&pattern_id.to_string()));
+    let queries = r#"acid_db_query_duration_seconds_count{operation="fetch_pattern",outcome="ok"}"#;
+    assert!(sample(&metrics, queries).unwrap() >= 1.0);
+}
+
+#[tokio::test]
+async fn archive_gauges_count_the_patterns_in_the_database() {
+    // Arrange
+    let app = spawn_app_with(|c| c.metrics.refresh_interval_secs = 1).await;
+    load_fixture_file(&app.db_pool, app.storage.as_ref(), "fixtures/archive.yaml")
+        .await
+        .expect("Failed to load fixtures");
+    let (patterns, public_patterns): (i64, i64) =
+        sqlx::query_as("SELECT COUNT(*), COUNT(*) FILTER (WHERE is_public) FROM patterns_tb303")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+
+    // Act
+    // The gauges are counted in the background, not on each scrape.
+    let mut metrics = get_metrics(&app).await;
+    for _ in 0..50 {
+        if sample(&metrics, "acid_patterns") == Some(patterns as f64) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        metrics = get_metrics(&app).await;
+    }
+
+    // Assert
+    assert!(patterns > 0);
+    assert_eq!(sample(&metrics, "acid_patterns"), Some(patterns as f64));
+    assert_eq!(
+        sample(&metrics, "acid_public_patterns"),
+        Some(public_patterns as f64)
+    );
+}
+
+#[tokio::test]
+async fn failed_jwks_fetches_are_counted() {
+    // Arrange
+    let jwks_url = MockJwks::start_with(json!({ "keys": [] }));
+    let app = spawn_app_with(|c| c.cognito.jwks_url = Some(jwks_url)).await;
+    let before = sample(&get_metrics(&app).await, "acid_jwks_fetch_failures_total").unwrap();
+
+    // Act
+    reqwest::Client::new()
+        .get(format!("{}/health/ready", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let after = sample(&get_metrics(&app).await, "acid_jwks_fetch_failures_total").unwrap();
+    assert!(after >= before + 1.0);
+}
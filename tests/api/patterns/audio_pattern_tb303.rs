@@ -0,0 +1,231 @@
+use crate::helpers::{spawn_app, test_wav};
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_returns_401_without_auth() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            &Uuid::new_v4(),
+            json!({ "audio_key": "pattern-audio/x/y" }).to_string(),
+            None,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_attaches_clip_to_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app.upload_test_audio(&token).await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            pattern_id,
+            json!({ "audio_key": audio_key }).to_string(),
+            Some(token.clone()),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["content_type"], "audio/wav");
+    assert_eq!(body["duration_ms"], 1000);
+
+    let pattern: serde_json::Value = app
+        .get_pattern_tb303(pattern_id, Some(token))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(pattern["audio_url"].as_str().unwrap().ends_with(&audio_key));
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_returns_403_for_unowned_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app.upload_test_audio(&token).await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            pattern_id,
+            json!({ "audio_key": audio_key }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_returns_400_for_clips_that_are_too_long() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app
+        .upload_test_file(
+            &token,
+            "pattern_audio",
+            "audio/wav",
+            test_wav(8000, 8000 * 61),
+        )
+        .await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            pattern_id,
+            json!({ "audio_key": audio_key }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_returns_400_for_files_that_are_not_audio() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app
+        .upload_test_file(&token, "pattern_audio", "audio/mpeg", vec![0u8; 1024])
+        .await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            pattern_id,
+            json!({ "audio_key": audio_key }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_audio_deletes_the_replaced_clip() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let first = app.upload_test_audio(&token).await;
+    app.put_pattern_tb303_audio(
+        pattern_id,
+        json!({ "audio_key": first }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+    let second = app.upload_test_audio(&token).await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_audio(
+            pattern_id,
+            json!({ "audio_key": second }).to_string(),
+            Some(token),
+        )
+        .await;
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&first));
+    assert!(app.s3_mock.contains(&second));
+}
+
+#[tokio::test]
+async fn delete_pattern_tb303_audio_removes_clip() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app.upload_test_audio(&token).await;
+    app.put_pattern_tb303_audio(
+        pattern_id,
+        json!({ "audio_key": audio_key }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+
+    // Act
+    let response = app
+        .delete_pattern_tb303_audio(pattern_id, Some(token.clone()))
+        .await;
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&audio_key));
+    let pattern: serde_json::Value = app
+        .get_pattern_tb303(pattern_id, Some(token))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(pattern["audio_url"].is_null());
+}
+
+#[tokio::test]
+async fn delete_pattern_tb303_deletes_attached_audio() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app.upload_test_audio(&token).await;
+    app.put_pattern_tb303_audio(
+        pattern_id,
+        json!({ "audio_key": audio_key }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+
+    // Act
+    let response = app.delete_pattern_tb303(pattern_id, Some(token)).await;
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&audio_key));
+    let uploads = sqlx::query_scalar!("SELECT COUNT(*) FROM uploads WHERE key = $1", audio_key)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(uploads, Some(0));
+}
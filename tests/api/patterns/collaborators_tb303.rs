@@ -0,0 +1,276 @@
+use crate::helpers::spawn_app;
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn post_collaborator_tb303_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = json!({"username": "someone", "role": "editor"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(&Uuid::new_v4(), body, None)
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_adds_collaborator_by_username() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    let collaborator_id = Uuid::new_v4();
+    app.create_test_user(&collaborator_id).await;
+    let body = json!({"username": collaborator_id.to_string(), "role": "editor"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["user_id"], collaborator_id.to_string());
+    assert_eq!(json["role"], "editor");
+
+    let saved = sqlx::query!(
+        "SELECT role FROM pattern_collaborators_tb303 WHERE pattern_id = $1 AND user_id = $2",
+        pattern_id,
+        collaborator_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch saved collaborator");
+    assert_eq!(saved.role, "editor");
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_updates_role_of_existing_collaborator() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    let collaborator_id = Uuid::new_v4();
+    app.create_test_collaborator(pattern_id, &collaborator_id, "editor")
+        .await;
+    let body = json!({"username": collaborator_id.to_string(), "role": "viewer"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let roles = sqlx::query!(
+        "SELECT role FROM pattern_collaborators_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch collaborators");
+    assert_eq!(roles.len(), 1);
+    assert_eq!(roles[0].role, "viewer");
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_returns_404_for_unknown_username() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let body = json!({"username": "nobody-by-this-name", "role": "viewer"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_returns_400_when_inviting_the_owner() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let body = json!({"username": app.get_test_username().await, "role": "editor"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_returns_403_for_editors() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+
+    let owner_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&owner_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "editor")
+        .await;
+
+    let other_id = Uuid::new_v4();
+    app.create_test_user(&other_id).await;
+    let body = json!({"username": other_id.to_string(), "role": "editor"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn post_collaborator_tb303_returns_400_for_invalid_role() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let body = json!({"username": "someone", "role": "owner"}).to_string();
+
+    // Act
+    let response = app
+        .post_collaborator_tb303(pattern_id, body, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn list_collaborators_tb303_returns_collaborators_to_owner() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &Uuid::new_v4(), "editor")
+        .await;
+    app.create_test_collaborator(pattern_id, &Uuid::new_v4(), "viewer")
+        .await;
+
+    // Act
+    let response = app.list_collaborators_tb303(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn list_collaborators_tb303_returns_404_for_unrelated_user() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.list_collaborators_tb303(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn delete_collaborator_tb303_removes_collaborator() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let collaborator_id = Uuid::new_v4();
+    app.create_test_collaborator(pattern_id, &collaborator_id, "viewer")
+        .await;
+
+    // Act
+    let response = app
+        .delete_collaborator_tb303(pattern_id, &collaborator_id, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+
+    let remaining = sqlx::query!(
+        "SELECT user_id FROM pattern_collaborators_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .expect("Failed to fetch collaborators");
+    assert!(remaining.is_empty());
+}
+
+#[tokio::test]
+async fn delete_collaborator_tb303_lets_collaborator_leave() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "viewer")
+        .await;
+
+    // Act
+    let response = app
+        .delete_collaborator_tb303(pattern_id, &user_id, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn delete_collaborator_tb303_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .delete_collaborator_tb303(&Uuid::new_v4(), &Uuid::new_v4(), None)
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
@@ -0,0 +1,173 @@
+use crate::helpers::spawn_app;
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn put_pattern_tb303_cover_returns_401_without_auth() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_cover(
+            &Uuid::new_v4(),
+            json!({ "cover_key": "pattern-covers/x/y" }).to_string(),
+            None,
+        )
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_cover_attaches_processed_cover() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let cover_key = app.upload_test_image(&token, "pattern_cover").await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_cover(
+            pattern_id,
+            json!({ "cover_key": cover_key }).to_string(),
+            Some(token.clone()),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["variants"].as_array().unwrap().len(), 3);
+    assert!(app.s3_mock.contains(&format!("{cover_key}-256.webp")));
+
+    let pattern: serde_json::Value = app
+        .get_pattern_tb303(pattern_id, Some(token))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(pattern["cover_url"]
+        .as_str()
+        .unwrap()
+        .ends_with(&format!("{cover_key}-original.webp")));
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_cover_rejects_audio_uploads() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let audio_key = app.upload_test_audio(&token).await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_cover(
+            pattern_id,
+            json!({ "cover_key": audio_key }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_cover_returns_403_for_unowned_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let cover_key = app.upload_test_image(&token, "pattern_cover").await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_cover(
+            pattern_id,
+            json!({ "cover_key": cover_key }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_cover_deletes_the_replaced_cover_and_its_variants() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let first = app.upload_test_image(&token, "pattern_cover").await;
+    app.put_pattern_tb303_cover(
+        pattern_id,
+        json!({ "cover_key": first }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+    let second = app.upload_test_image(&token, "pattern_cover").await;
+
+    // Act
+    let response = app
+        .put_pattern_tb303_cover(
+            pattern_id,
+            json!({ "cover_key": second }).to_string(),
+            Some(token),
+        )
+        .await;
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&format!("{first}-original.webp")));
+    assert!(!app.s3_mock.contains(&format!("{first}-256.webp")));
+    assert!(app.s3_mock.contains(&format!("{second}-original.webp")));
+}
+
+#[tokio::test]
+async fn delete_pattern_tb303_cover_removes_cover() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let cover_key = app.upload_test_image(&token, "pattern_cover").await;
+    app.put_pattern_tb303_cover(
+        pattern_id,
+        json!({ "cover_key": cover_key }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+
+    // Act
+    let response = app
+        .delete_pattern_tb303_cover(pattern_id, Some(token.clone()))
+        .await;
+    app.run_pending_jobs().await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&cover_key));
+    let pattern: serde_json::Value = app
+        .get_pattern_tb303(pattern_id, Some(token))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(pattern["cover_url"].is_null());
+}
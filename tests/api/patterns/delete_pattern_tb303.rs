@@ -44,6 +44,26 @@ async fn delete_pattern_tb303_returns_403_for_unowned_pattern() {
     assert_eq!(403, response.status().as_u16());
 }
 
+#[tokio::test]
+async fn delete_pattern_tb303_returns_403_for_editor() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "editor")
+        .await;
+
+    // Act
+    let response = app.delete_pattern_tb303(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
 #[tokio::test]
 async fn delete_pattern_tb303_returns_204_for_owned_pattern() {
     // Arrange
@@ -99,6 +99,29 @@ async fn get_pattern_tb303_returns_404_for_existing_unowned_private_pattern() {
     assert_eq!(404, response.status().as_u16());
 }
 
+#[tokio::test]
+async fn get_pattern_tb303_returns_200_for_private_pattern_shared_with_viewer() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "viewer")
+        .await;
+
+    // Act
+    let response = app.get_pattern_tb303(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["title"], "Pattern 1");
+}
+
 #[tokio::test]
 async fn get_pattern_tb303_returns_200_for_existing_owned_public_pattern() {
     // Arrange
@@ -194,3 +217,28 @@ async fn get_pattern_tb303_fails_if_there_is_a_fatal_database_error() {
     // assert
     assert_eq!(response.status().as_u16(), 500);
 }
+
+#[tokio::test]
+async fn get_pattern_tb303_does_not_serve_cached_patterns_made_private_elsewhere() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    let cached = app.get_pattern_tb303(pattern_id, None).await;
+    // As `acidctl` or another instance would, without touching this cache.
+    sqlx::query!(
+        "UPDATE patterns_tb303 SET is_public = false, updated_at = NOW() WHERE pattern_id = $1",
+        pattern_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_pattern_tb303(pattern_id, None).await;
+
+    // Assert
+    assert_eq!(200, cached.status().as_u16());
+    assert_eq!(404, response.status().as_u16());
+}
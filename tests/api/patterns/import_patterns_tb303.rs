@@ -0,0 +1,180 @@
+use crate::helpers::spawn_app;
+use crate::test_data::get_valid_tb303_pattern_data;
+use serde_json::json;
+use uuid::Uuid;
+
+fn archive(patterns: Vec<serde_json::Value>) -> String {
+    json!({ "version": 1, "patterns": patterns }).to_string()
+}
+
+fn valid_pattern(name: &str) -> serde_json::Value {
+    let mut pattern: serde_json::Value =
+        serde_json::from_str(&get_valid_tb303_pattern_data(None)).unwrap();
+    pattern["name"] = json!(name);
+    pattern
+}
+
+#[tokio::test]
+async fn import_tb303_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_import_tb303(archive(vec![valid_pattern("Imported")]), None, None)
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn import_tb303_rejects_unsupported_archive_versions() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let body = json!({ "version": 99, "patterns": [] }).to_string();
+
+    // Act
+    let response = app.post_import_tb303(body, None, Some(token)).await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn import_tb303_creates_patterns_with_their_metadata() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    let pattern_id = Uuid::new_v4();
+    let mut first = valid_pattern("Imported 1");
+    first["id"] = json!(pattern_id);
+    first["created_at"] = json!("2020-01-01T00:00:00Z");
+    let second = valid_pattern("Imported 2");
+
+    // Act
+    let response = app
+        .post_import_tb303(archive(vec![first, second]), None, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["created"], 2);
+    assert_eq!(json["results"][0]["status"], "created");
+    assert_eq!(json["results"][0]["pattern_id"], pattern_id.to_string());
+
+    let saved = sqlx::query!(
+        "SELECT user_id, created_at FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch imported pattern.");
+    assert_eq!(saved.user_id, user_id);
+    assert_eq!(saved.created_at.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+}
+
+#[tokio::test]
+async fn import_tb303_reports_duplicates_of_existing_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    app.post_patterns_tb303(get_valid_tb303_pattern_data(None), Some(token.clone()))
+        .await;
+    let existing = valid_pattern("Pattern 1");
+
+    // Act
+    let response = app
+        .post_import_tb303(archive(vec![existing]), None, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["created"], 0);
+    assert_eq!(json["duplicates"], 1);
+    assert_eq!(json["results"][0]["status"], "duplicate");
+}
+
+#[tokio::test]
+async fn import_tb303_rejects_the_whole_archive_when_a_pattern_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    let mut invalid = valid_pattern("Invalid");
+    invalid["bars"] = json!([]);
+    let valid = valid_pattern("Valid");
+
+    // Act
+    let response = app
+        .post_import_tb303(archive(vec![valid, invalid]), None, Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["status"], "fail");
+    assert_eq!(json["invalid"], 1);
+    assert_eq!(json["results"][1]["status"], "invalid");
+    assert_eq!(
+        json["results"][1]["error"],
+        "Pattern must contain at least one step."
+    );
+    assert_eq!(
+        json["results"][1]["errors"][0]["pointer"],
+        "/patterns/1/bars"
+    );
+
+    let count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM patterns_tb303 WHERE user_id = $1 AND name = 'Valid'",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(count, Some(0));
+}
+
+#[tokio::test]
+async fn import_tb303_dry_run_does_not_write_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let response = app
+        .post_import_tb303(
+            archive(vec![valid_pattern("Dry run")]),
+            Some(true),
+            Some(token),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["dry_run"], true);
+    assert_eq!(json["created"], 1);
+
+    let count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM patterns_tb303 WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(count, Some(0));
+}
@@ -107,6 +107,11 @@ async fn list_public_patterns_tb303_returns_correct_response_shape() {
     assert!(record.get("username").is_some());
     assert!(record["username"].is_string());
     assert!(record.get("avatar_url").is_some());
+    assert!(record["cover_url"].is_null());
+    assert!(record["preview_url"].as_str().unwrap().contains(&format!(
+        "/v1/patterns/tb303/{}/preview.svg?v=",
+        record["pattern_id"].as_str().unwrap()
+    )));
 }
 
 #[tokio::test]
@@ -204,3 +209,55 @@ async fn list_public_patterns_tb303_returns_400_for_invalid_order() {
         .await;
     assert_eq!(400, response.status().as_u16());
 }
+
+#[tokio::test]
+async fn list_public_patterns_tb303_is_compressed_when_asked() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 3, Some(true)).await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/v1/patterns/tb303/public", &app.address))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(response.headers()["Content-Encoding"], "gzip");
+    assert!(response.headers()["Vary"]
+        .to_str()
+        .unwrap()
+        .to_ascii_lowercase()
+        .contains("accept-encoding"));
+}
+
+#[tokio::test]
+async fn list_public_patterns_tb303_returns_304_for_a_current_etag() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 2, Some(true)).await;
+    let response = app.list_public_patterns_tb303(None, None, None).await;
+    let etag = response.headers()["ETag"].clone();
+    assert!(response.headers()["Cache-Control"]
+        .to_str()
+        .unwrap()
+        .contains("max-age=60"));
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/v1/patterns/tb303/public", &app.address))
+        .header("If-None-Match", etag)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(304, response.status().as_u16());
+}
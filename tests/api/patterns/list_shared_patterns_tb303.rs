@@ -0,0 +1,69 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn list_shared_patterns_tb303_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.list_shared_patterns_tb303(None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/problem+json"
+    );
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "unauthorized");
+}
+
+#[tokio::test]
+async fn list_shared_patterns_tb303_returns_patterns_shared_with_user() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+
+    let owner_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&owner_id, 3, Some(false)).await;
+    app.create_test_collaborator(&pattern_ids[0], &user_id, "editor")
+        .await;
+    app.create_test_collaborator(&pattern_ids[1], &user_id, "viewer")
+        .await;
+
+    // Act
+    let response = app.list_shared_patterns_tb303(Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    let patterns = json.as_array().unwrap();
+    assert_eq!(patterns.len(), 2);
+    assert!(patterns
+        .iter()
+        .all(|p| p["username"] == owner_id.to_string()));
+    assert!(patterns
+        .iter()
+        .all(|p| p["pattern_id"] != pattern_ids[2].to_string()));
+}
+
+#[tokio::test]
+async fn list_shared_patterns_tb303_does_not_include_owned_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 2, Some(false)).await;
+
+    // Act
+    let response = app.list_shared_patterns_tb303(Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert!(json.as_array().unwrap().is_empty());
+}
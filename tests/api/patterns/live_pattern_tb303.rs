@@ -0,0 +1,190 @@
+use crate::helpers::{spawn_app, TestApp};
+use crate::webhook_receiver::WebhookReceiver;
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn live_pattern_tb303_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_pattern_tb303_live(pattern_id, None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_returns_401_for_invalid_query_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app
+        .get_pattern_tb303_live(pattern_id, Some("not-a-token".to_string()))
+        .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_returns_404_for_unshared_private_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_pattern_tb303_live(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_returns_400_for_non_websocket_requests() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_pattern_tb303_live(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+async fn share_pattern(app: &TestApp, pattern_id: &Uuid, user_id: &Uuid, role: &str) {
+    sqlx::query(
+        "INSERT INTO pattern_collaborators_tb303 (pattern_id, user_id, role) VALUES ($1, $2, $3)",
+    )
+    .bind(pattern_id)
+    .bind(user_id)
+    .bind(role)
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to share test pattern");
+}
+
+fn knob_edit(base_version: i64, value: i32) -> serde_json::Value {
+    json!({
+        "type": "knob_edit",
+        "base_version": base_version,
+        "knob": "cut_off_freq",
+        "value": value
+    })
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_rejects_edits_from_viewers() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    share_pattern(&app, pattern_id, &user_id, "viewer").await;
+    let mut live = app.connect_pattern_tb303_live(pattern_id, &token).await;
+    let welcome = live.receive("welcome").await;
+    assert_eq!(welcome["can_edit"], false);
+
+    // Act
+    live.send(knob_edit(welcome["version"].as_i64().unwrap(), 120))
+        .await;
+
+    // Assert
+    let error = live.receive("error").await;
+    assert_eq!(
+        error["message"],
+        "You don't have permission to edit this pattern"
+    );
+    let cut_off_freq: Option<i32> =
+        sqlx::query_scalar("SELECT cut_off_freq FROM patterns_tb303 WHERE pattern_id = $1")
+            .bind(pattern_id)
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert_ne!(cut_off_freq, Some(120));
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_stops_edits_once_the_role_is_revoked() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    share_pattern(&app, pattern_id, &user_id, "editor").await;
+    let mut live = app.connect_pattern_tb303_live(pattern_id, &token).await;
+    let welcome = live.receive("welcome").await;
+    live.send(knob_edit(welcome["version"].as_i64().unwrap(), 120))
+        .await;
+    let edited = live.receive("knob_edited").await;
+
+    // Act
+    sqlx::query("DELETE FROM pattern_collaborators_tb303 WHERE pattern_id = $1")
+        .bind(pattern_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    live.send(knob_edit(edited["version"].as_i64().unwrap(), 200))
+        .await;
+
+    // Assert
+    let error = live.receive("error").await;
+    assert_eq!(
+        error["message"],
+        "You don't have permission to edit this pattern"
+    );
+}
+
+#[tokio::test]
+async fn live_pattern_tb303_edits_notify_webhooks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    let mut live = app.connect_pattern_tb303_live(pattern_id, &token).await;
+    let welcome = live.receive("welcome").await;
+
+    // Act
+    live.send(knob_edit(welcome["version"].as_i64().unwrap(), 120))
+        .await;
+    live.receive("knob_edited").await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].event, "pattern.updated");
+    assert_eq!(
+        received[0].json()["data"]["pattern"]["id"],
+        pattern_id.to_string()
+    );
+}
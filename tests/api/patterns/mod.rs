@@ -1,7 +1,14 @@
+mod audio_pattern_tb303;
+mod collaborators_tb303;
+mod cover_pattern_tb303;
 mod delete_pattern_tb303;
 mod get_pattern_tb303;
 mod get_patterns_tb303_random;
+mod import_patterns_tb303;
 mod list_patterns_tb303;
 mod list_public_patterns_tb303;
+mod list_shared_patterns_tb303;
+mod live_pattern_tb303;
 mod post_patterns_tb303;
+mod preview_pattern_tb303;
 mod put_pattern_tb303;
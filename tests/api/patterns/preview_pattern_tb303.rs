@@ -0,0 +1,110 @@
+use crate::helpers::{spawn_app, TestApp};
+use uuid::Uuid;
+
+/// Gives the pattern a bar with a single accented note on the first step.
+async fn add_test_bar(app: &TestApp, pattern_id: &Uuid) {
+    let bar_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"INSERT INTO bars_tb303 (bar_id, pattern_id, number) VALUES ($1, $2, 1)"#,
+        bar_id,
+        pattern_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to create test bar");
+
+    sqlx::query!(
+        r#"
+        INSERT INTO steps_tb303 (step_id, bar_id, number, note, time, accent)
+        VALUES ($1, $2, 1, 'C', 'note', true)
+        "#,
+        Uuid::new_v4(),
+        bar_id
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to create test step");
+}
+
+#[tokio::test]
+async fn get_pattern_tb303_preview_renders_public_pattern_as_svg() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    add_test_bar(&app, pattern_id).await;
+
+    // Act
+    let response = app.get_pattern_tb303_preview(pattern_id, None).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "image/svg+xml"
+    );
+    assert_eq!(
+        response.headers().get("X-Content-Type-Options").unwrap(),
+        "nosniff"
+    );
+    assert!(response
+        .headers()
+        .get("Cache-Control")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("public"));
+    let body = response.text().await.unwrap();
+    assert!(body.starts_with("<svg"));
+    assert!(body.contains("#ff5a1f"));
+}
+
+#[tokio::test]
+async fn get_pattern_tb303_preview_returns_404_for_private_pattern_without_auth() {
+    // Arrange
+    let app = spawn_app().await;
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_pattern_tb303_preview(pattern_id, None).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_pattern_tb303_preview_returns_404_for_missing_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_pattern_tb303_preview(&Uuid::new_v4(), None).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_pattern_tb303_preview_is_private_for_owner_of_private_pattern() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+
+    // Act
+    let response = app.get_pattern_tb303_preview(pattern_id, Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        response.headers().get("Cache-Control").unwrap(),
+        "private, no-store"
+    );
+}
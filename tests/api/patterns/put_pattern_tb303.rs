@@ -97,6 +97,82 @@ async fn put_pattern_tb303_returns_404_for_pattern_not_owned_by_user() {
     assert_eq!(404, response.status().as_u16());
 }
 
+#[tokio::test]
+async fn put_pattern_tb303_allows_editor_to_update_pattern_they_do_not_own() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+
+    let owner_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&owner_id, 1, Some(false)).await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "editor")
+        .await;
+    let body = get_valid_tb303_pattern_data(Some(false));
+
+    // Act
+    let response = app.put_pattern_tb303(pattern_id, body, Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let saved = sqlx::query!(
+        "SELECT user_id, title FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Failed to fetch updated pattern");
+
+    assert_eq!(saved.user_id, owner_id);
+    assert_eq!(saved.title, Some("Stakker humanoid".to_string()));
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_returns_403_when_editor_changes_visibility() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "editor")
+        .await;
+    let body = get_valid_tb303_pattern_data(Some(true));
+
+    // Act
+    let response = app.put_pattern_tb303(pattern_id, body, Some(token)).await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn put_pattern_tb303_returns_403_for_viewer() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    let pattern_id = pattern_ids.first().expect("No patterns created");
+    app.create_test_collaborator(pattern_id, &user_id, "viewer")
+        .await;
+    let body = get_valid_tb303_pattern_data(None);
+
+    // Act
+    let response = app.put_pattern_tb303(pattern_id, body, Some(token)).await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
 #[tokio::test]
 async fn put_pattern_tb303_returns_400_for_invalid_data() {
     // Arrange
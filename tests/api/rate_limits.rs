@@ -0,0 +1,221 @@
+use crate::helpers::{spawn_app_with, TestApp};
+use acid::configuration::{RateLimit, RateLimitBackend, Settings};
+use acid::rate_limiting::{prune_rate_limit_windows, Budget, PostgresRateLimiter, RateLimiter};
+use serde_json::json;
+
+const ONE_PER_MINUTE: RateLimit = RateLimit {
+    requests: 1,
+    period_secs: 60,
+};
+
+async fn spawn_app_with_read_limit(requests: u32, backend: RateLimitBackend) -> TestApp {
+    spawn_app_with(|c: &mut Settings| {
+        c.rate_limits.backend = backend;
+        c.rate_limits.expensive_reads = RateLimit {
+            requests,
+            period_secs: 60,
+        };
+    })
+    .await
+}
+
+async fn get(app: &TestApp, path: &str, token: Option<&str>) -> reqwest::Response {
+    let request = app.api_client.get(format!("{}{}", &app.address, path));
+    let request = match token {
+        Some(token) => request.header("Authorization", format!("Bearer {token}")),
+        None => request,
+    };
+    request.send().await.expect("Failed to execute request.")
+}
+
+fn header(response: &reqwest::Response, name: &str) -> Option<u64> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap().parse().unwrap())
+}
+
+#[tokio::test]
+async fn expensive_reads_over_the_limit_get_429() {
+    // Arrange
+    let app = spawn_app_with_read_limit(2, RateLimitBackend::Memory).await;
+
+    // Act
+    let mut responses = Vec::new();
+    for _ in 0..3 {
+        responses.push(get(&app, "/v1/patterns/tb303/public", None).await);
+    }
+
+    // Assert
+    let statuses: Vec<_> = responses.iter().map(|r| r.status().as_u16()).collect();
+    assert_eq!(statuses, [200, 200, 429]);
+    let remaining: Vec<_> = responses
+        .iter()
+        .map(|r| header(r, "RateLimit-Remaining"))
+        .collect();
+    assert_eq!(remaining, [Some(1), Some(0), Some(0)]);
+    assert!(responses
+        .iter()
+        .all(|r| header(r, "RateLimit-Limit") == Some(2)));
+
+    let refused = responses.pop().unwrap();
+    let retry_after = header(&refused, "Retry-After").unwrap();
+    assert!((1..=60).contains(&retry_after));
+    assert_eq!(header(&refused, "RateLimit-Reset"), Some(retry_after));
+    assert_eq!(
+        refused.headers()["Content-Type"],
+        "application/problem+json"
+    );
+    let body: serde_json::Value = refused.json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "type": "about:blank",
+            "title": "Too Many Requests",
+            "status": 429,
+            "code": "rate_limited",
+            "detail": "Too many requests, try again later"
+        })
+    );
+}
+
+#[tokio::test]
+async fn routes_outside_the_budgets_are_not_limited() {
+    // Arrange
+    let app = spawn_app_with_read_limit(1, RateLimitBackend::Memory).await;
+    get(&app, "/v1/patterns/tb303/public", None).await;
+
+    // Act
+    let response = get(&app, "/health/live", None).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers().get("RateLimit-Limit").is_none());
+}
+
+#[tokio::test]
+async fn made_up_tokens_do_not_get_a_budget_of_their_own() {
+    // Arrange
+    let app = spawn_app_with_read_limit(1, RateLimitBackend::Memory).await;
+    get(&app, "/v1/patterns/tb303/public", Some("first.made.up")).await;
+
+    // Act
+    let response = get(&app, "/v1/patterns/tb303/public", Some("second.made.up")).await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn presigns_over_the_limit_get_429() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limits.presigns = ONE_PER_MINUTE).await;
+    let token = app.get_test_user_token().await;
+    let body = json!({
+        "upload_type": "avatar",
+        "content_type": "image/png",
+        "content_length": 1024
+    });
+
+    // Act
+    let first = app
+        .post_presign(body.to_string(), Some(token.clone()))
+        .await;
+    let second = app.post_presign(body.to_string(), Some(token)).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(429, second.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_postgres_backend_counts_in_the_database() {
+    // Arrange
+    let app = spawn_app_with_read_limit(1, RateLimitBackend::Postgres).await;
+
+    // Act
+    let first = get(&app, "/v1/patterns/tb303/public", None).await;
+    let second = get(&app, "/v1/patterns/tb303/public", None).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_eq!(429, second.status().as_u16());
+    let (budget, count): (String, i32) = sqlx::query_as("SELECT budget, count FROM rate_limits")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(budget, "expensive_reads");
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn postgres_limiters_share_budgets_across_instances() {
+    // Arrange
+    let app = spawn_app_with_read_limit(1, RateLimitBackend::Postgres).await;
+    let first_instance = PostgresRateLimiter::new(app.db_pool.clone());
+    let second_instance = PostgresRateLimiter::new(app.db_pool.clone());
+
+    // Act
+    let first = first_instance
+        .hit(Budget::Writes, "user:1", ONE_PER_MINUTE)
+        .await
+        .unwrap();
+    let second = second_instance
+        .hit(Budget::Writes, "user:1", ONE_PER_MINUTE)
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(first.allowed);
+    assert!(!second.allowed);
+}
+
+#[tokio::test]
+async fn pruning_deletes_only_windows_that_ran_out() {
+    // Arrange
+    let app = spawn_app_with_read_limit(1, RateLimitBackend::Postgres).await;
+    let limiter = PostgresRateLimiter::new(app.db_pool.clone());
+    let expired = RateLimit {
+        requests: 1,
+        period_secs: 0,
+    };
+    limiter.hit(Budget::Writes, "ip:1", expired).await.unwrap();
+    limiter
+        .hit(Budget::Writes, "ip:2", ONE_PER_MINUTE)
+        .await
+        .unwrap();
+
+    // Act
+    let pruned = prune_rate_limit_windows(&app.db_pool).await.unwrap();
+
+    // Assert
+    assert_eq!(pruned, 1);
+    let clients: Vec<String> = sqlx::query_scalar("SELECT client FROM rate_limits")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(clients, ["ip:2"]);
+}
+
+#[tokio::test]
+async fn clients_cannot_pick_their_own_forwarded_ip() {
+    // Arrange
+    let app = spawn_app_with(|c: &mut Settings| {
+        c.rate_limits.trusted_proxy_hops = 1;
+        c.rate_limits.expensive_reads = ONE_PER_MINUTE;
+    })
+    .await;
+    let get_as = |made_up: &'static str| {
+        app.api_client
+            .get(format!("{}/v1/patterns/tb303/public", &app.address))
+            .header("X-Forwarded-For", format!("{made_up}, 198.51.100.1"))
+            .send()
+    };
+    get_as("203.0.113.1").await.unwrap();
+
+    // Act
+    let response = get_as("203.0.113.2").await.unwrap();
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+}
@@ -0,0 +1,136 @@
+use actix_web::{dev::Server, web, App, HttpResponse, HttpServer};
+use std::collections::{BTreeMap, HashMap};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+pub struct StoredObject {
+    pub body: Vec<u8>,
+    pub content_type: String,
+}
+
+/// In-process stand-in for the subset of the S3 API the application uses,
+/// addressed path-style as `/{bucket}/{key}`. Signatures are not checked.
+#[derive(Clone, Default)]
+pub struct MockS3 {
+    objects: Arc<Mutex<BTreeMap<String, StoredObject>>>,
+}
+
+impl MockS3 {
+    /// Starts the mock on a random port and returns it with its base URL.
+    pub fn start() -> (MockS3, String) {
+        let mock = MockS3::default();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock S3.");
+        let port = listener.local_addr().unwrap().port();
+        let server = mock.server(listener);
+        tokio::spawn(server);
+
+        (mock, format!("http://127.0.0.1:{port}"))
+    }
+
+    fn server(&self, listener: TcpListener) -> Server {
+        let data = web::Data::new(self.clone());
+        HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .app_data(web::PayloadConfig::new(64 * 1024 * 1024))
+                .route("/{bucket}", web::head().to(HttpResponse::Ok))
+                .route("/{bucket}", web::get().to(list_objects))
+                .route("/{bucket}/", web::head().to(HttpResponse::Ok))
+                .route("/{bucket}/", web::get().to(list_objects))
+                .route("/{bucket}/{key:.*}", web::put().to(put_object))
+                .route("/{bucket}/{key:.*}", web::head().to(head_object))
+                .route("/{bucket}/{key:.*}", web::get().to(get_object))
+                .route("/{bucket}/{key:.*}", web::delete().to(delete_object))
+        })
+        .workers(1)
+        .listen(listener)
+        .expect("Failed to listen on mock S3 port.")
+        .run()
+    }
+
+    pub fn insert(&self, key: &str, body: Vec<u8>, content_type: &str) {
+        self.objects.lock().unwrap().insert(
+            key.to_string(),
+            StoredObject {
+                body,
+                content_type: content_type.to_string(),
+            },
+        );
+    }
+
+    pub fn get(&self, key: &str) -> Option<StoredObject> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.objects.lock().unwrap().contains_key(key)
+    }
+}
+
+async fn put_object(
+    mock: web::Data<MockS3>,
+    path: web::Path<(String, String)>,
+    request: actix_web::HttpRequest,
+    body: web::Bytes,
+) -> HttpResponse {
+    let content_type = request
+        .headers()
+        .get("Content-Type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+    mock.insert(&path.1, body.to_vec(), content_type);
+    HttpResponse::Ok().finish()
+}
+
+async fn head_object(mock: web::Data<MockS3>, path: web::Path<(String, String)>) -> HttpResponse {
+    match mock.get(&path.1) {
+        Some(object) => HttpResponse::Ok()
+            .content_type(object.content_type)
+            .body(object.body),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn get_object(mock: web::Data<MockS3>, path: web::Path<(String, String)>) -> HttpResponse {
+    match mock.get(&path.1) {
+        Some(object) => HttpResponse::Ok()
+            .content_type(object.content_type)
+            .body(object.body),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn delete_object(mock: web::Data<MockS3>, path: web::Path<(String, String)>) -> HttpResponse {
+    mock.objects.lock().unwrap().remove(&path.1);
+    HttpResponse::NoContent().finish()
+}
+
+async fn list_objects(
+    mock: web::Data<MockS3>,
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let prefix = query.get("prefix").cloned().unwrap_or_default();
+    let contents: String = mock
+        .objects
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(key, _)| key.starts_with(&prefix))
+        .map(|(key, object)| {
+            format!(
+                "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified><Size>{}</Size></Contents>",
+                key,
+                object.body.len()
+            )
+        })
+        .collect();
+
+    HttpResponse::Ok().content_type("application/xml").body(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Name>{}</Name><Prefix>{}</Prefix><MaxKeys>1000</MaxKeys><IsTruncated>false</IsTruncated>{}</ListBucketResult>"#,
+        path.into_inner(),
+        prefix,
+        contents
+    ))
+}
@@ -0,0 +1,133 @@
+use crate::helpers::{spawn_app, spawn_app_with};
+use acid::domain::WebhookEvent;
+use acid::jobs::run_worker_until_stopped;
+use acid::webhook_dispatcher::run_dispatcher_until_stopped;
+use acid::webhooks::enqueue_pattern_event;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn stopping_lets_requests_in_flight_finish() {
+    // Arrange
+    let app = spawn_app().await;
+    // Holds the listing up in the database until the lock is released
+    let mut lock = app.db_pool.begin().await.unwrap();
+    sqlx::query("LOCK TABLE patterns_tb303 IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+    let in_flight = tokio::spawn(
+        app.api_client
+            .get(format!("{}/v1/patterns/tb303/public", &app.address))
+            .send(),
+    );
+    loop {
+        let waiting: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pg_stat_activity WHERE wait_event_type = 'Lock' AND datname = current_database()",
+        )
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+        if waiting > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // Act
+    let stopped = tokio::spawn(app.server.stop(true));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    lock.rollback().await.unwrap();
+
+    // Assert
+    let response = in_flight
+        .await
+        .unwrap()
+        .expect("The request in flight was dropped.");
+    assert_eq!(200, response.status().as_u16());
+    stopped.await.unwrap();
+    let after = app
+        .api_client
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await;
+    assert!(after.is_err());
+}
+
+#[tokio::test]
+async fn the_job_worker_stops_when_asked() {
+    // Arrange
+    let app = spawn_app().await;
+    let shutdown = CancellationToken::new();
+    let worker = tokio::spawn(run_worker_until_stopped(
+        app.configuration.clone(),
+        shutdown.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Act
+    shutdown.cancel();
+
+    // Assert
+    let stopped = tokio::time::timeout(Duration::from_secs(5), worker)
+        .await
+        .expect("The job worker did not stop.");
+    assert!(stopped.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn the_webhook_dispatcher_finishes_its_delivery_before_stopping() {
+    // Arrange
+    let app = spawn_app_with(|c| c.webhooks.timeout_secs = 1).await;
+    // Accepts connections but never answers.
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", silent.local_addr().unwrap());
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    app.create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    enqueue_pattern_event(
+        &mut transaction,
+        WebhookEvent::PatternUpdated,
+        pattern_ids[0],
+        true,
+    )
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+    let shutdown = CancellationToken::new();
+    let dispatcher = tokio::spawn(run_dispatcher_until_stopped(
+        app.configuration.clone(),
+        shutdown.clone(),
+    ));
+    // Waits for the delivery to be claimed and on its way.
+    loop {
+        let attempts: i32 = sqlx::query_scalar("SELECT attempts FROM webhook_deliveries")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+        if attempts > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // Act
+    shutdown.cancel();
+
+    // Assert
+    let stopped = tokio::time::timeout(Duration::from_secs(5), dispatcher)
+        .await
+        .expect("The webhook dispatcher did not stop.");
+    assert!(stopped.unwrap().is_ok());
+    // The attempt ran out its timeout and was recorded, rather than being
+    // left claimed.
+    let last_error: Option<String> =
+        sqlx::query_scalar("SELECT last_error FROM webhook_deliveries")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap();
+    assert!(last_error.is_some());
+}
@@ -0,0 +1,94 @@
+use acid::configuration::TracingSettings;
+use acid::telemetry::{get_subscriber, get_tracer, get_tracer_provider};
+use actix_web::{web, App, HttpResponse, HttpServer};
+use opentelemetry::global;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// In-process stand-in for an OpenTelemetry collector's OTLP/HTTP receiver.
+struct MockCollector {
+    requests: Arc<Mutex<Vec<web::Bytes>>>,
+}
+
+impl MockCollector {
+    /// Accepts exports on a random port and returns the traces endpoint.
+    fn start() -> (Self, String) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock collector.");
+        let port = listener.local_addr().unwrap().port();
+        let received = requests.clone();
+        let server = HttpServer::new(move || {
+            let received = received.clone();
+            App::new().route(
+                "/v1/traces",
+                web::post().to(move |body: web::Bytes| {
+                    received.lock().unwrap().push(body);
+                    async { HttpResponse::Ok().finish() }
+                }),
+            )
+        })
+        .workers(1)
+        .listen(listener)
+        .expect("Failed to listen on mock collector port.")
+        .run();
+        tokio::spawn(server);
+
+        (
+            Self { requests },
+            format!("http://127.0.0.1:{port}/v1/traces"),
+        )
+    }
+
+    fn received(&self) -> Vec<u8> {
+        self.requests.lock().unwrap().concat()
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[test]
+fn no_spans_are_exported_by_default() {
+    let provider = get_tracer_provider("test", &TracingSettings::default()).unwrap();
+
+    assert!(provider.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_are_exported_to_the_collector_under_the_incoming_trace() {
+    // Arrange
+    let (collector, endpoint) = MockCollector::start();
+    let settings = TracingSettings {
+        otlp_endpoint: Some(endpoint),
+    };
+    let provider = get_tracer_provider("test", &settings).unwrap().unwrap();
+    let tracer = get_tracer(Some(&provider), "test");
+    let subscriber = get_subscriber("test".into(), "info".into(), std::io::sink, tracer);
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let headers = HashMap::from([(
+        "traceparent".to_string(),
+        format!("00-{trace_id}-00f067aa0ba902b7-01"),
+    )]);
+
+    // Act
+    tracing::subscriber::with_default(subscriber, || {
+        let parent = global::get_text_map_propagator(|propagator| propagator.extract(&headers));
+        let span = tracing::info_span!("Saving new pattern in the database");
+        span.set_parent(parent).unwrap();
+        span.in_scope(|| tracing::info!("Pattern saved"));
+    });
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap()
+        .expect("Failed to flush spans");
+
+    // Assert
+    let received = collector.received();
+    assert!(contains(&received, b"Saving new pattern in the database"));
+    assert!(contains(&received, &hex::decode(trace_id).unwrap()));
+}
@@ -1 +1,3 @@
 mod presign;
+mod process;
+mod sweeper;
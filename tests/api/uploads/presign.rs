@@ -48,6 +48,32 @@ async fn presign_returns_400_for_invalid_content_type() {
     assert_eq!(response.status().as_u16(), 400);
 }
 
+#[tokio::test]
+async fn presign_returns_400_for_content_type_not_accepted_by_upload_type() {
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+
+    for (upload_type, content_type) in [("avatar", "audio/mpeg"), ("pattern_audio", "image/png")] {
+        let body = json!({
+            "upload_type": upload_type,
+            "content_type": content_type,
+            "content_length": 1024
+        });
+
+        let response = app
+            .post_presign(body.to_string(), Some(token.clone()))
+            .await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Expected 400 for {} with content_type: {}",
+            upload_type,
+            content_type
+        );
+    }
+}
+
 #[tokio::test]
 async fn presign_returns_200_for_valid_avatar_request() {
     let app = spawn_app().await;
@@ -0,0 +1,51 @@
+use crate::helpers::{spawn_app, test_png};
+use acid::domain::UploadType;
+use acid::routes::{process_image_upload, verify_upload};
+use claims::assert_some;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn processing_leaves_the_upload_claimable() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let key = format!("avatars/{}/{}", user_id, Uuid::new_v4());
+    let image = test_png(300, 200);
+    sqlx::query!(
+        r#"
+        INSERT INTO uploads (key, user_id, upload_type, content_type, content_length)
+        VALUES ($1, $2, 'avatar', 'image/png', $3)
+        "#,
+        key,
+        user_id,
+        image.len() as i64
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to create test upload");
+    app.s3_mock.insert(&key, image, "image/png");
+
+    // Act
+    let sizes = process_image_upload(
+        app.storage.as_ref(),
+        &key,
+        UploadType::Avatar,
+        "image/png".to_string(),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(sizes, vec![64, 256, 1024]);
+    assert!(app.s3_mock.contains(&format!("{key}-original.webp")));
+    // The claim never happened, so a retry verifies the upload again.
+    assert_some!(verify_upload(
+        &app.db_pool,
+        app.storage.as_ref(),
+        user_id,
+        &key,
+        UploadType::Avatar
+    )
+    .await
+    .unwrap());
+}
@@ -0,0 +1,111 @@
+use crate::helpers::{spawn_app, TestApp};
+use acid::domain::UploadType;
+use acid::routes::verify_upload;
+use acid::upload_sweeper::sweep_orphaned_uploads;
+use chrono::{Duration, Utc};
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+async fn create_upload(app: &TestApp, age: Duration, claimed: bool) -> String {
+    create_user_upload(app, Uuid::new_v4(), age, claimed).await
+}
+
+async fn create_user_upload(app: &TestApp, user_id: Uuid, age: Duration, claimed: bool) -> String {
+    let key = format!("avatars/{}/{}", user_id, Uuid::new_v4());
+    let created_at = Utc::now() - age;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO uploads (key, user_id, upload_type, content_type, content_length, created_at, claimed_at)
+        VALUES ($1, $2, 'avatar', 'image/png', 3, $3, $4)
+        "#,
+        key,
+        user_id,
+        created_at,
+        claimed.then_some(created_at)
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to create test upload");
+    app.s3_mock.insert(&key, vec![1, 2, 3], "image/png");
+
+    key
+}
+
+#[tokio::test]
+async fn sweeper_deletes_old_unclaimed_uploads() {
+    // Arrange
+    let app = spawn_app().await;
+    let orphan = create_upload(&app, Duration::hours(2), false).await;
+
+    // Act
+    let swept = sweep_orphaned_uploads(
+        &app.db_pool,
+        app.storage.as_ref(),
+        StdDuration::from_secs(3600),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(swept, 1);
+    assert!(!app.s3_mock.contains(&orphan));
+    let remaining = sqlx::query_scalar!("SELECT COUNT(*) FROM uploads")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, Some(0));
+}
+
+#[tokio::test]
+async fn sweeper_keeps_claimed_and_recent_uploads() {
+    // Arrange
+    let app = spawn_app().await;
+    let claimed = create_upload(&app, Duration::hours(2), true).await;
+    let recent = create_upload(&app, Duration::minutes(5), false).await;
+
+    // Act
+    let swept = sweep_orphaned_uploads(
+        &app.db_pool,
+        app.storage.as_ref(),
+        StdDuration::from_secs(3600),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(swept, 0);
+    assert!(app.s3_mock.contains(&claimed));
+    assert!(app.s3_mock.contains(&recent));
+}
+
+#[tokio::test]
+async fn sweeper_skips_uploads_being_claimed() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let key = create_user_upload(&app, user_id, Duration::hours(2), false).await;
+    let mut claim = app.db_pool.begin().await.unwrap();
+    verify_upload(
+        &mut *claim,
+        app.storage.as_ref(),
+        user_id,
+        &key,
+        UploadType::Avatar,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    let swept = sweep_orphaned_uploads(
+        &app.db_pool,
+        app.storage.as_ref(),
+        StdDuration::from_secs(3600),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(swept, 0);
+    assert!(app.s3_mock.contains(&key));
+}
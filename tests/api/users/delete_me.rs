@@ -0,0 +1,135 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn delete_me_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.delete_user_me(None, None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn delete_me_removes_the_user_their_patterns_and_files() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 2, Some(true)).await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+
+    // Act
+    let response = app.delete_user_me(None, Some(token)).await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert!(!app.s3_mock.contains(&avatar_key));
+
+    let users = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE user_id = $1", user_id)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(users, Some(0));
+
+    let patterns = sqlx::query_scalar!("SELECT COUNT(*) FROM patterns_tb303")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(patterns, Some(0));
+
+    let deletion = sqlx::query!(
+        "SELECT pattern_disposition, deleted_patterns, deleted_files FROM account_deletions WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("Account deletion was not logged.");
+    assert_eq!(deletion.pattern_disposition, "delete");
+    assert_eq!(deletion.deleted_patterns, 2);
+    assert_eq!(deletion.deleted_files, 1);
+}
+
+#[tokio::test]
+async fn delete_me_reassigns_every_pattern_when_asked() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 2, Some(true)).await;
+    let private = app.create_test_patterns(&user_id, 1, Some(false)).await;
+
+    // Act
+    let response = app.delete_user_me(Some("reassign"), Some(token)).await;
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+
+    let owners = sqlx::query_scalar!("SELECT user_id FROM patterns_tb303")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(owners.len(), 3);
+    assert!(owners.iter().all(|owner| owner.is_nil()));
+
+    let is_public = sqlx::query_scalar!(
+        "SELECT is_public FROM patterns_tb303 WHERE pattern_id = $1",
+        private[0]
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .expect("The private pattern was deleted.");
+    assert_eq!(is_public, Some(false));
+}
+
+#[tokio::test]
+async fn delete_me_is_idempotent() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    // Act
+    let first = app.delete_user_me(None, Some(token.clone())).await;
+    let second = app.delete_user_me(None, Some(token)).await;
+
+    // Assert
+    assert_eq!(204, first.status().as_u16());
+    assert_eq!(204, second.status().as_u16());
+
+    let deletions = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM account_deletions WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(deletions, Some(1));
+}
+
+#[tokio::test]
+async fn deleting_a_user_cascades_to_their_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = uuid::Uuid::new_v4();
+    app.create_test_patterns(&user_id, 2, Some(false)).await;
+
+    // Act
+    sqlx::query!("DELETE FROM users WHERE user_id = $1", user_id)
+        .execute(&app.db_pool)
+        .await
+        .expect("Deleting a user with patterns must not be blocked.");
+
+    // Assert
+    let patterns = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM patterns_tb303 WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(patterns, Some(0));
+}
@@ -0,0 +1,142 @@
+use crate::helpers::spawn_app;
+use crate::test_data::get_valid_tb303_pattern_data;
+use acid::authentication::UserId;
+use acid::routes::export_me;
+use acid::storage::ObjectStorage;
+use actix_web::dev::Service;
+use actix_web::web::{self, Data};
+use actix_web::{test, App, HttpMessage};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn export_me_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_user_me_export(None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn export_me_returns_all_of_the_users_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    app.post_patterns_tb303(
+        get_valid_tb303_pattern_data(Some(false)),
+        Some(token.clone()),
+    )
+    .await;
+    app.post_patterns_tb303(
+        get_valid_tb303_pattern_data(Some(true)),
+        Some(token.clone()),
+    )
+    .await;
+    app.create_test_patterns(&Uuid::new_v4(), 2, Some(true))
+        .await;
+
+    // Act
+    let response = app.get_user_me_export(Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(response
+        .headers()
+        .get("Content-Disposition")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("attachment"));
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["format"], "acidarchive.patterns");
+    assert_eq!(json["version"], 1);
+
+    let patterns = json["patterns"].as_array().unwrap();
+    assert_eq!(patterns.len(), 2);
+    assert!(patterns
+        .iter()
+        .all(|p| !p["bars"].as_array().unwrap().is_empty()));
+    assert!(patterns.iter().any(|p| p["is_public"] == true));
+}
+
+#[tokio::test]
+async fn export_me_output_can_be_imported_as_duplicates() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_user(&user_id).await;
+
+    app.post_patterns_tb303(get_valid_tb303_pattern_data(None), Some(token.clone()))
+        .await;
+    let export = app
+        .get_user_me_export(Some(token.clone()))
+        .await
+        .text()
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.post_import_tb303(export, None, Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["duplicates"], 1);
+    assert_eq!(json["created"], 0);
+}
+
+#[tokio::test]
+async fn export_me_leaves_out_patterns_deleted_while_streaming() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 3, Some(false)).await;
+    let service = test::init_service(
+        App::new()
+            .wrap_fn(move |req, srv| {
+                req.extensions_mut().insert(UserId::from(user_id));
+                srv.call(req)
+            })
+            .app_data(Data::new(app.db_pool.clone()))
+            .app_data(Data::<dyn ObjectStorage>::from(app.storage.clone()))
+            .route("/export", web::get().to(export_me)),
+    )
+    .await;
+    // The patterns are listed when the response starts and read as its
+    // body is streamed.
+    let response = test::call_service(
+        &service,
+        test::TestRequest::get().uri("/export").to_request(),
+    )
+    .await;
+    sqlx::query!(
+        "DELETE FROM patterns_tb303 WHERE pattern_id = $1",
+        pattern_ids[1]
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let json: serde_json::Value = test::read_body_json(response).await;
+
+    // Assert
+    let exported: Vec<String> = json["patterns"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        exported,
+        vec![pattern_ids[0].to_string(), pattern_ids[2].to_string()]
+    );
+}
@@ -54,8 +54,8 @@ async fn get_me_returns_urls_after_patch() {
     let token = app.get_test_user_token().await;
     let user_id = app.get_test_user_id().await;
 
-    let avatar_key = app.get_presign_key(&token, "avatar").await;
-    let banner_key = app.get_presign_key(&token, "banner").await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+    let banner_key = app.upload_test_image(&token, "banner").await;
 
     let patch_body = json!({
         "avatar_key": avatar_key,
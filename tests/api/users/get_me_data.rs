@@ -0,0 +1,75 @@
+use crate::helpers::spawn_app;
+use acid::domain::WebhookEvent;
+use acid::webhooks::enqueue_profile_event;
+
+#[tokio::test]
+async fn get_me_data_returns_401_for_unauthorized_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_user_me_data(None).await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_me_data_returns_profile_files_and_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    app.create_test_patterns(&user_id, 2, Some(false)).await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+
+    // Act
+    let response = app.get_user_me_data(Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(json["profile"]["user_id"], user_id.to_string());
+    assert_eq!(json["patterns"].as_array().unwrap().len(), 2);
+    assert_eq!(json["files"][0]["key"], avatar_key);
+    assert_eq!(json["files"][0]["size"], 1024);
+}
+
+#[tokio::test]
+async fn get_me_data_includes_webhooks_and_their_deliveries_without_secrets() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let user_id = app.get_test_user_id().await;
+    let webhook_id = app
+        .create_test_webhook(
+            &user_id,
+            "https://hooks.example.com/acid",
+            &[WebhookEvent::ProfileUpdated.as_ref()],
+            false,
+        )
+        .await;
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    enqueue_profile_event(&mut transaction, user_id)
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    // Act
+    let response = app.get_user_me_data(Some(token)).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    let webhook = &json["webhooks"][0];
+    assert_eq!(webhook["id"], webhook_id.to_string());
+    assert_eq!(webhook["url"], "https://hooks.example.com/acid");
+    assert_eq!(webhook["events"][0], "profile.updated");
+    assert!(webhook.get("secret").is_none());
+    let deliveries = json["webhook_deliveries"].as_array().unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0]["webhook_id"], webhook_id.to_string());
+    assert_eq!(deliveries[0]["event"], "profile.updated");
+}
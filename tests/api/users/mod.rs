@@ -1,2 +1,5 @@
+mod delete_me;
+mod export_me;
 mod get_me;
+mod get_me_data;
 mod patch_me;
@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, test_png};
 use serde_json::json;
 
 #[tokio::test]
@@ -28,7 +28,7 @@ async fn patch_me_sets_avatar_url() {
     let token = app.get_test_user_token().await;
     let user_id = app.get_test_user_id().await;
 
-    let avatar_key = app.get_presign_key(&token, "avatar").await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
     let body = json!({
         "avatar_key": avatar_key
     });
@@ -48,7 +48,7 @@ async fn patch_me_sets_banner_url() {
     let token = app.get_test_user_token().await;
     let user_id = app.get_test_user_id().await;
 
-    let banner_key = app.get_presign_key(&token, "banner").await;
+    let banner_key = app.upload_test_image(&token, "banner").await;
     let body = json!({
         "banner_key": banner_key
     });
@@ -68,8 +68,8 @@ async fn patch_me_sets_both_urls() {
     let token = app.get_test_user_token().await;
     let user_id = app.get_test_user_id().await;
 
-    let avatar_key = app.get_presign_key(&token, "avatar").await;
-    let banner_key = app.get_presign_key(&token, "banner").await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+    let banner_key = app.upload_test_image(&token, "banner").await;
     let body = json!({ "avatar_key": avatar_key, "banner_key": banner_key });
 
     let response = app.patch_user_me(body.to_string(), Some(token)).await;
@@ -93,7 +93,7 @@ async fn patch_me_partial_update_does_not_overwrite_other_fields() {
     let user_id = app.get_test_user_id().await;
 
     // Set avatar first
-    let avatar_key = app.get_presign_key(&token, "avatar").await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
     app.patch_user_me(
         json!({ "avatar_key": avatar_key }).to_string(),
         Some(token.clone()),
@@ -101,7 +101,7 @@ async fn patch_me_partial_update_does_not_overwrite_other_fields() {
     .await;
 
     // Then set banner
-    let banner_key = app.get_presign_key(&token, "banner").await;
+    let banner_key = app.upload_test_image(&token, "banner").await;
     let response = app
         .patch_user_me(json!({ "banner_key": banner_key }).to_string(), Some(token))
         .await;
@@ -118,3 +118,169 @@ async fn patch_me_partial_update_does_not_overwrite_other_fields() {
         .unwrap()
         .contains(&format!("banners/{}/", user_id)));
 }
+
+#[tokio::test]
+async fn patch_me_returns_400_when_nothing_was_uploaded() {
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+
+    let body = json!({
+        "upload_type": "avatar",
+        "content_type": "image/png",
+        "content_length": 1024
+    });
+    let response = app
+        .post_presign(body.to_string(), Some(token.clone()))
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+
+    let response = app
+        .patch_user_me(
+            json!({ "avatar_key": body["key"] }).to_string(),
+            Some(token),
+        )
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn patch_me_returns_400_when_upload_size_does_not_match() {
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+    app.s3_mock
+        .insert(&avatar_key, vec![0u8; 20 * 1024 * 1024], "image/png");
+
+    let response = app
+        .patch_user_me(json!({ "avatar_key": avatar_key }).to_string(), Some(token))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn patch_me_returns_400_when_upload_content_type_does_not_match() {
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+    app.s3_mock
+        .insert(&avatar_key, vec![0u8; 1024], "application/x-msdownload");
+
+    let response = app
+        .patch_user_me(json!({ "avatar_key": avatar_key }).to_string(), Some(token))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn patch_me_deletes_the_replaced_avatar() {
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+
+    let first = app.upload_test_image(&token, "avatar").await;
+    app.patch_user_me(
+        json!({ "avatar_key": first }).to_string(),
+        Some(token.clone()),
+    )
+    .await;
+
+    let second = app.upload_test_image(&token, "avatar").await;
+    let response = app
+        .patch_user_me(json!({ "avatar_key": second }).to_string(), Some(token))
+        .await;
+
+    app.run_pending_jobs().await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!app.s3_mock.contains(&format!("{}-original.webp", first)));
+    assert!(!app.s3_mock.contains(&format!("{}-64.webp", first)));
+    assert!(app.s3_mock.contains(&format!("{}-original.webp", second)));
+}
+
+#[tokio::test]
+async fn patch_me_stores_webp_variants_of_the_avatar() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let avatar_key = app.upload_test_image(&token, "avatar").await;
+
+    // Act
+    let response = app
+        .patch_user_me(json!({ "avatar_key": avatar_key }).to_string(), Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let sizes: Vec<i64> = body["avatar_variants"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|variant| variant["size"].as_i64().unwrap())
+        .collect();
+    assert_eq!(sizes, vec![64, 256, 1024]);
+
+    let original = app
+        .s3_mock
+        .get(&format!("{}-original.webp", avatar_key))
+        .unwrap();
+    assert_eq!(original.content_type, "image/webp");
+    assert!(body["avatar_url"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("{}-original.webp", avatar_key)));
+    assert!(!app.s3_mock.contains(&avatar_key));
+    for size in sizes {
+        let variant = app
+            .s3_mock
+            .get(&format!("{}-{}.webp", avatar_key, size))
+            .unwrap();
+        assert_eq!(variant.content_type, "image/webp");
+    }
+}
+
+#[tokio::test]
+async fn patch_me_returns_400_when_upload_is_not_an_image() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let avatar_key = app
+        .upload_test_file(&token, "avatar", "image/png", vec![0u8; 1024])
+        .await;
+
+    // Act
+    let response = app
+        .patch_user_me(
+            json!({ "avatar_key": avatar_key }).to_string(),
+            Some(token.clone()),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let user = app.get_user_me(Some(token)).await;
+    let body: serde_json::Value = user.json().await.unwrap();
+    assert!(body["avatar_url"].is_null());
+}
+
+#[tokio::test]
+async fn patch_me_returns_400_when_image_format_does_not_match_content_type() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.get_test_user_token().await;
+    let avatar_key = app
+        .upload_test_file(&token, "avatar", "image/jpeg", test_png(64, 64))
+        .await;
+
+    // Act
+    let response = app
+        .patch_user_me(json!({ "avatar_key": avatar_key }).to_string(), Some(token))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,83 @@
+use actix_web::{dev::Server, web, App, HttpRequest, HttpResponse, HttpServer};
+use std::collections::VecDeque;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+pub struct ReceivedWebhook {
+    pub signature: String,
+    pub event: String,
+    pub delivery_id: String,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedWebhook {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("Webhook body is not JSON")
+    }
+}
+
+/// In-process HTTP endpoint that records every webhook POSTed to it. It
+/// answers with the queued status codes in order, then with 200.
+#[derive(Clone, Default)]
+pub struct WebhookReceiver {
+    received: Arc<Mutex<Vec<ReceivedWebhook>>>,
+    statuses: Arc<Mutex<VecDeque<u16>>>,
+}
+
+impl WebhookReceiver {
+    /// Starts the receiver on a random port and returns it with its URL.
+    pub fn start() -> (WebhookReceiver, String) {
+        let receiver = WebhookReceiver::default();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind webhook receiver.");
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(receiver.server(listener));
+
+        (receiver, format!("http://127.0.0.1:{port}/hook"))
+    }
+
+    fn server(&self, listener: TcpListener) -> Server {
+        let data = web::Data::new(self.clone());
+        HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .route("/hook", web::post().to(receive))
+        })
+        .workers(1)
+        .listen(listener)
+        .expect("Failed to listen on webhook receiver port.")
+        .run()
+    }
+
+    pub fn respond_with(&self, status: u16) {
+        self.statuses.lock().unwrap().push_back(status);
+    }
+
+    pub fn received(&self) -> Vec<ReceivedWebhook> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+async fn receive(
+    receiver: web::Data<WebhookReceiver>,
+    request: HttpRequest,
+    body: web::Bytes,
+) -> HttpResponse {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    receiver.received.lock().unwrap().push(ReceivedWebhook {
+        signature: header("X-Acid-Signature"),
+        event: header("X-Acid-Event"),
+        delivery_id: header("X-Acid-Delivery"),
+        body: body.to_vec(),
+    });
+
+    let status = receiver.statuses.lock().unwrap().pop_front().unwrap_or(200);
+    HttpResponse::build(actix_web::http::StatusCode::from_u16(status).unwrap()).finish()
+}
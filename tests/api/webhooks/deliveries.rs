@@ -0,0 +1,413 @@
+use crate::helpers::{spawn_app, TestApp};
+use crate::webhook_receiver::WebhookReceiver;
+use acid::configuration::WebhookSettings;
+use acid::domain::WebhookEvent;
+use acid::webhook_dispatcher::{dispatch_next_delivery, DispatchOutcome, MAX_ATTEMPTS};
+use acid::webhooks::{
+    enqueue_pattern_event, enqueue_profile_event, signature_header, webhook_secret,
+};
+use uuid::Uuid;
+
+async fn enqueue(app: &TestApp, event: WebhookEvent, pattern_id: Uuid, was_public: bool) {
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    enqueue_pattern_event(&mut transaction, event, pattern_id, was_public)
+        .await
+        .expect("Failed to queue pattern event");
+    transaction.commit().await.unwrap();
+}
+
+struct Delivery {
+    status: String,
+    attempts: i32,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+}
+
+async fn deliveries(app: &TestApp, webhook_id: Uuid) -> Vec<Delivery> {
+    sqlx::query_as!(
+        Delivery,
+        r#"
+        SELECT status, attempts, last_status_code, last_error
+        FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY created_at
+        "#,
+        webhook_id
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap()
+}
+
+async fn make_deliveries_due(app: &TestApp) {
+    sqlx::query!(r#"UPDATE webhook_deliveries SET next_attempt_at = NOW()"#)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn pattern_events_are_delivered_signed() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+
+    // Act
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    let webhook = &received[0];
+    assert_eq!(webhook.event, "pattern.updated");
+
+    let timestamp: i64 = webhook
+        .signature
+        .strip_prefix("t=")
+        .and_then(|rest| rest.split(',').next())
+        .and_then(|t| t.parse().ok())
+        .expect("Signature has no timestamp");
+    let secret = webhook_secret(&app.hmac_secret, webhook_id);
+    assert_eq!(
+        webhook.signature,
+        signature_header(&secret, timestamp, &webhook.body)
+    );
+
+    let payload = webhook.json();
+    assert_eq!(payload["event"], "pattern.updated");
+    assert_eq!(payload["data"]["pattern"]["id"], pattern_ids[0].to_string());
+    assert_eq!(payload["data"]["pattern"]["name"], "Pattern 1");
+    assert_eq!(payload["data"]["pattern"]["username"], user_id.to_string());
+
+    let deliveries = deliveries(&app, webhook_id).await;
+    assert_eq!(deliveries[0].status, "succeeded");
+    assert_eq!(deliveries[0].attempts, 1);
+    assert_eq!(deliveries[0].last_status_code, Some(200));
+    assert_eq!(webhook.delivery_id.len(), 36);
+}
+
+#[tokio::test]
+async fn events_only_reach_webhooks_subscribed_to_them() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    app.create_test_webhook(&user_id, &url, &["pattern.deleted"], false)
+        .await;
+
+    // Act
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    assert!(receiver.received().is_empty());
+}
+
+#[tokio::test]
+async fn webhooks_only_receive_events_about_their_owners_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    app.create_test_webhook(&Uuid::new_v4(), &url, &["pattern.updated"], false)
+        .await;
+
+    // Act
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    assert!(receiver.received().is_empty());
+}
+
+#[tokio::test]
+async fn global_webhooks_only_receive_events_about_public_patterns() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let public_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(true))
+        .await;
+    let private_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    app.create_test_webhook(&Uuid::new_v4(), &url, &["pattern.updated"], true)
+        .await;
+
+    // Act
+    enqueue(&app, WebhookEvent::PatternUpdated, private_ids[0], false).await;
+    enqueue(&app, WebhookEvent::PatternUpdated, public_ids[0], true).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].json()["data"]["pattern"]["id"],
+        public_ids[0].to_string()
+    );
+}
+
+#[tokio::test]
+async fn global_webhooks_hear_about_patterns_that_were_just_unpublished() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let pattern_ids = app
+        .create_test_patterns(&Uuid::new_v4(), 1, Some(false))
+        .await;
+    app.create_test_webhook(&Uuid::new_v4(), &url, &["pattern.unpublished"], true)
+        .await;
+
+    // Act
+    enqueue(&app, WebhookEvent::PatternUnpublished, pattern_ids[0], true).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let received = receiver.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].json()["data"]["pattern"]["is_public"], false);
+}
+
+#[tokio::test]
+async fn profile_events_reach_own_and_global_webhooks() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let user_id = Uuid::new_v4();
+    app.create_test_user(&user_id).await;
+    app.create_test_webhook(&user_id, &url, &["profile.updated"], false)
+        .await;
+    app.create_test_webhook(&Uuid::new_v4(), &url, &["profile.updated"], true)
+        .await;
+    app.create_test_webhook(&Uuid::new_v4(), &url, &["profile.updated"], false)
+        .await;
+
+    // Act
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    enqueue_profile_event(&mut transaction, user_id)
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let received = receiver.received();
+    assert_eq!(received.len(), 2);
+    for webhook in received {
+        assert_eq!(
+            webhook.json()["data"]["user"]["user_id"],
+            user_id.to_string()
+        );
+    }
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_later() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    receiver.respond_with(500);
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+
+    // Act - Part 1
+    app.dispatch_pending_webhooks().await;
+
+    // Assert - Part 1
+    let delivery = &deliveries(&app, webhook_id).await[0];
+    assert_eq!(delivery.status, "pending");
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.last_status_code, Some(500));
+    assert!(delivery.last_error.is_some());
+
+    // Act - Part 2
+    app.dispatch_pending_webhooks().await;
+
+    // Assert - Part 2
+    assert_eq!(receiver.received().len(), 1, "Retried before it was due");
+
+    // Act - Part 3
+    make_deliveries_due(&app).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert - Part 3
+    let received = receiver.received();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].json()["id"], received[1].json()["id"]);
+    let delivery = &deliveries(&app, webhook_id).await[0];
+    assert_eq!(delivery.status, "succeeded");
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_error, None);
+}
+
+#[tokio::test]
+async fn deliveries_fail_after_the_last_attempt() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    receiver.respond_with(503);
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries SET attempts = $1"#,
+        MAX_ATTEMPTS - 1
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    app.dispatch_pending_webhooks().await;
+    make_deliveries_due(&app).await;
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    assert_eq!(receiver.received().len(), 1);
+    let delivery = &deliveries(&app, webhook_id).await[0];
+    assert_eq!(delivery.status, "failed");
+    assert_eq!(delivery.attempts, MAX_ATTEMPTS);
+}
+
+#[tokio::test]
+async fn unreachable_receivers_are_recorded_in_the_log() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let webhook_id = app
+        .create_test_webhook(
+            &user_id,
+            &format!("http://127.0.0.1:{port}/hook"),
+            &["pattern.updated"],
+            false,
+        )
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+
+    // Act
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    let delivery = &deliveries(&app, webhook_id).await[0];
+    assert_eq!(delivery.status, "pending");
+    assert_eq!(delivery.last_status_code, None);
+    assert!(delivery
+        .last_error
+        .as_ref()
+        .is_some_and(|e| e.contains("Failed to send")));
+}
+
+#[tokio::test]
+async fn private_addresses_are_refused_unless_allowed() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+    let settings = WebhookSettings {
+        allow_private_networks: false,
+        ..app.webhooks.clone()
+    };
+
+    // Act
+    let outcome = dispatch_next_delivery(&app.db_pool, &settings, &app.hmac_secret)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(outcome, DispatchOutcome::Attempted);
+    assert!(receiver.received().is_empty());
+    let delivery = &deliveries(&app, webhook_id).await[0];
+    assert!(delivery
+        .last_error
+        .as_ref()
+        .is_some_and(|e| e.contains("private address")));
+}
+
+#[tokio::test]
+async fn deliveries_of_deleted_webhooks_are_dropped() {
+    // Arrange
+    let app = spawn_app().await;
+    let (receiver, url) = WebhookReceiver::start();
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+
+    // Act
+    sqlx::query!(r#"DELETE FROM webhooks WHERE webhook_id = $1"#, webhook_id)
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    app.dispatch_pending_webhooks().await;
+
+    // Assert
+    assert!(receiver.received().is_empty());
+}
+
+#[tokio::test]
+async fn deliveries_are_claimed_rather_than_locked_while_they_are_sent() {
+    // Arrange
+    let app = spawn_app().await;
+    // Accepts connections but never answers.
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", silent.local_addr().unwrap());
+    let user_id = Uuid::new_v4();
+    let pattern_ids = app.create_test_patterns(&user_id, 1, Some(true)).await;
+    let webhook_id = app
+        .create_test_webhook(&user_id, &url, &["pattern.updated"], false)
+        .await;
+    enqueue(&app, WebhookEvent::PatternUpdated, pattern_ids[0], true).await;
+
+    // Act
+    let sending = tokio::spawn({
+        let pool = app.db_pool.clone();
+        let settings = app.webhooks.clone();
+        let hmac_secret = app.hmac_secret.clone();
+        async move { dispatch_next_delivery(&pool, &settings, &hmac_secret).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // Assert
+    let second = dispatch_next_delivery(&app.db_pool, &app.webhooks, &app.hmac_secret)
+        .await
+        .unwrap();
+    assert_eq!(second, DispatchOutcome::EmptyQueue);
+    let locked = sqlx::query!(
+        r#"SELECT delivery_id FROM webhook_deliveries WHERE webhook_id = $1 FOR UPDATE NOWAIT"#,
+        webhook_id
+    )
+    .fetch_one(&app.db_pool)
+    .await;
+    assert!(locked.is_ok(), "The delivery is locked: {locked:?}");
+    assert_eq!(deliveries(&app, webhook_id).await[0].attempts, 1);
+
+    sending.abort();
+}
@@ -0,0 +1,2 @@
+mod deliveries;
+mod manage;